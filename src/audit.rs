@@ -0,0 +1,124 @@
+use crate::plan::PlannedWrite;
+use crate::Error;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A write made to a Google API as recorded in the audit log.
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    timestamp: DateTime<Utc>,
+    /// The local user that ran scma-gsync
+    actor: &'a str,
+    /// The calendar ID or the contact group name written to
+    resource: &'a str,
+    #[serde(flatten)]
+    write: &'a PlannedWrite,
+    /// A summary of the entity before the write (e.g. the previous event summary)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<&'a str>,
+    /// A summary of the entity after the write (e.g. the new event summary)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<&'a str>,
+}
+
+/// An append-only JSON Lines log of the writes made to the Google APIs.
+///
+/// Unlike the plan, only writes that were made are logged.  Nothing is logged on a dry run or for
+/// a write that failed.  The log is never truncated so that it accumulates the history of every
+/// run.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+    actor: String,
+}
+
+impl AuditLog {
+    /// Opens the log for appending.  Creates the log if it does not exist.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("unable to open audit log `{}`", path.display()))
+            .map_err(Error::file)?;
+        let actor = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        Ok(Self {
+            file: Mutex::new(file),
+            actor,
+        })
+    }
+
+    /// Appends an entry for the write.
+    pub fn append(
+        &self,
+        resource: &str,
+        write: &PlannedWrite,
+        before: Option<&str>,
+        after: Option<&str>,
+    ) -> Result<(), Error> {
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            actor: &self.actor,
+            resource,
+            write,
+            before,
+            after,
+        };
+        let mut line = serde_json::to_string(&entry).map_err(Error::file)?;
+        line.push('\n');
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(line.as_bytes())
+            .context("unable to write audit log")
+            .map_err(Error::file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn append() {
+        let path = std::env::temp_dir().join(format!("scma-gsync-audit-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        for _ in 0..2 {
+            let log = AuditLog::open(&path).unwrap();
+            log.append(
+                "primary",
+                &PlannedWrite::EventPatch {
+                    event_id: "00527".into(),
+                    summary: "SCMA: Joshua Tree".into(),
+                },
+                Some("SCMA: Joshua Tree (old)"),
+                Some("SCMA: Joshua Tree"),
+            )
+            .unwrap();
+        }
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let entries: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["op"], "event-patch");
+        assert_eq!(entries[0]["resource"], "primary");
+        assert_eq!(entries[0]["event_id"], "00527");
+        assert_eq!(entries[0]["before"], "SCMA: Joshua Tree (old)");
+        assert_eq!(entries[0]["after"], "SCMA: Joshua Tree");
+    }
+}
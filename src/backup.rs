@@ -0,0 +1,112 @@
+use crate::fs::write_atomic;
+use crate::output::{CalendarBackup, ContactsBackup};
+use crate::Error;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use std::path::{Path, PathBuf};
+
+/// A snapshot of what scma-gsync writes to Google for inspecting and recovering from a bad sync.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub created: DateTime<Utc>,
+    #[serde(default)]
+    pub calendars: Vec<CalendarBackup>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contacts: Option<ContactsBackup>,
+}
+
+impl Backup {
+    pub fn new(calendars: Vec<CalendarBackup>, contacts: Option<ContactsBackup>) -> Self {
+        Self {
+            created: Utc::now(),
+            calendars,
+            contacts,
+        }
+    }
+
+    /// Returns the timestamped file name of the backup (e.g.
+    /// `scma-gsync-backup-20240102T030405Z.json`).
+    pub fn file_name(&self) -> String {
+        format!(
+            "scma-gsync-backup-{}.json",
+            self.created.format("%Y%m%dT%H%M%SZ")
+        )
+    }
+
+    /// Writes the backup as JSON to the timestamped file in the directory.
+    ///
+    /// Returns the path of the file.  Creates the directory if it does not exist.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, Error> {
+        std::fs::create_dir_all(dir)
+            .context(format!(
+                "unable to create backup directory `{}`",
+                dir.display()
+            ))
+            .map_err(Error::file)?;
+        let path = dir.join(self.file_name());
+        let backup = serde_json::to_string_pretty(self).map_err(Error::file)?;
+        write_atomic(&path, &(backup + "\n"))
+            .context(format!("unable to write backup file `{}`", path.display()))
+            .map_err(Error::file)?;
+
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let backup = std::fs::read_to_string(path)
+            .context(format!("unable to read backup file `{}`", path.display()))
+            .map_err(Error::file)?;
+        serde_json::from_str(&backup)
+            .context(format!("unable to parse backup file `{}`", path.display()))
+            .map_err(Error::file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    #[test]
+    fn save_load() {
+        let dir = std::env::temp_dir().join(format!("scma-gsync-backup-{}", std::process::id()));
+        let calendar: CalendarBackup = serde_json::from_str(
+            r#"{
+                "calendar_id": "primary",
+                "events": [{"id": "scma00527", "summary": "SCMA: Joshua Tree", "status": "confirmed"}],
+                "acl": [{"role": "reader", "scope": {"type": "user", "value": "jane@example.com"}}]
+            }"#,
+        )
+        .unwrap();
+        let mut backup = Backup::new(vec![calendar], None);
+        backup.created = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+
+        let path = backup.save(&dir).unwrap();
+        let loaded = Backup::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            path.file_name().unwrap(),
+            "scma-gsync-backup-20240102T030405Z.json"
+        );
+        assert_eq!(loaded.created, backup.created);
+        assert_eq!(loaded.calendars.len(), 1);
+        assert_eq!(loaded.calendars[0].calendar_id, "primary");
+        assert_eq!(
+            loaded.calendars[0].events[0].summary.as_deref(),
+            Some("SCMA: Joshua Tree")
+        );
+        assert_eq!(
+            loaded.calendars[0].acl[0]
+                .scope
+                .as_ref()
+                .and_then(|scope| scope.value.as_deref()),
+            Some("jane@example.com")
+        );
+        assert!(loaded.contacts.is_none());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::model::{id_sort_key, Event};
+use crate::output::strip_html;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+/// The fraction of description words that must change for the change to be reported
+const DESCRIPTION_CHANGE_THRESHOLD: f64 = 0.1;
+
+/// The state of an event as of the previous run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSnapshot {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub location: String,
+    pub description: String,
+}
+
+impl From<&Event> for EventSnapshot {
+    fn from(event: &Event) -> Self {
+        Self {
+            id: event.id.clone(),
+            title: event.title.clone(),
+            url: event.url.clone(),
+            start_date: event.start_date,
+            end_date: event.end_date,
+            location: event.location.clone(),
+            description: event.description.clone(),
+        }
+    }
+}
+
+/// A change to an event between runs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Change {
+    New,
+    Rescheduled {
+        old_start_date: NaiveDate,
+        old_end_date: NaiveDate,
+    },
+    LocationChanged {
+        old_location: String,
+    },
+    DescriptionChanged,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ChangelogEntry {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub location: String,
+    pub change: Change,
+}
+
+impl ChangelogEntry {
+    /// Returns the kind of change (e.g. "Rescheduled").
+    pub fn label(&self) -> &'static str {
+        match self.change {
+            Change::New => "New",
+            Change::Rescheduled { .. } => "Rescheduled",
+            Change::LocationChanged { .. } => "Location changed",
+            Change::DescriptionChanged => "Description changed",
+            Change::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Returns the dates of the event or what changed (e.g. the old and new dates).
+    pub fn detail(&self) -> String {
+        let dates = format!("{}/{}", self.start_date, self.end_date);
+        match &self.change {
+            Change::Rescheduled {
+                old_start_date,
+                old_end_date,
+            } => format!("{old_start_date}/{old_end_date} -> {dates}"),
+            Change::LocationChanged { old_location } => {
+                format!("{old_location} -> {}", self.location)
+            }
+            Change::New | Change::DescriptionChanged | Change::Cancelled => dates,
+        }
+    }
+}
+
+impl fmt::Display for ChangelogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}) {}",
+            self.label(),
+            self.title,
+            self.detail(),
+            self.url
+        )
+    }
+}
+
+/// The changes to the SCMA events since the previous run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Changelog(pub Vec<ChangelogEntry>);
+
+impl Changelog {
+    /// Determines the changes from the previous events to the current events.
+    ///
+    /// Previous events that are missing from the current events are reported as cancelled unless
+    /// they ended before `today` (i.e. they dropped off the list of upcoming events).  An event
+    /// may have more than one change (e.g. rescheduled and moved).
+    pub fn between(previous: &[EventSnapshot], current: &[Event], today: NaiveDate) -> Self {
+        let previous_by_id: HashMap<&str, &EventSnapshot> = previous
+            .iter()
+            .map(|snapshot| (snapshot.id.as_str(), snapshot))
+            .collect();
+        let current_ids: HashMap<&str, &Event> = current
+            .iter()
+            .map(|event| (event.id.as_str(), event))
+            .collect();
+
+        let mut entries = Vec::new();
+        for event in current {
+            let entry = |change| ChangelogEntry {
+                id: event.id.clone(),
+                title: event.title.clone(),
+                url: event.url.clone(),
+                start_date: event.start_date,
+                end_date: event.end_date,
+                location: event.location.clone(),
+                change,
+            };
+
+            let Some(previous) = previous_by_id.get(event.id.as_str()) else {
+                entries.push(entry(Change::New));
+                continue;
+            };
+
+            if previous.start_date != event.start_date || previous.end_date != event.end_date {
+                entries.push(entry(Change::Rescheduled {
+                    old_start_date: previous.start_date,
+                    old_end_date: previous.end_date,
+                }));
+            }
+            if previous.location.trim() != event.location.trim() {
+                entries.push(entry(Change::LocationChanged {
+                    old_location: previous.location.clone(),
+                }));
+            }
+            if description_change(&previous.description, &event.description)
+                > DESCRIPTION_CHANGE_THRESHOLD
+            {
+                entries.push(entry(Change::DescriptionChanged));
+            }
+        }
+
+        for snapshot in previous {
+            if !current_ids.contains_key(snapshot.id.as_str()) && snapshot.end_date >= today {
+                entries.push(ChangelogEntry {
+                    id: snapshot.id.clone(),
+                    title: snapshot.title.clone(),
+                    url: snapshot.url.clone(),
+                    start_date: snapshot.start_date,
+                    end_date: snapshot.end_date,
+                    location: snapshot.location.clone(),
+                    change: Change::Cancelled,
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            a.start_date
+                .cmp(&b.start_date)
+                .then_with(|| id_sort_key(&a.id).cmp(&id_sort_key(&b.id)))
+        });
+
+        Self(entries)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_human(&self) -> String {
+        let mut buffer = String::new();
+
+        writeln!(buffer, "Changelog").unwrap();
+        if self.is_empty() {
+            write!(buffer, "  No changes").unwrap();
+        }
+        for (i, entry) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(buffer).unwrap();
+            }
+            write!(buffer, "  {entry}").unwrap();
+        }
+
+        buffer
+    }
+}
+
+/// Returns the fraction of words that differ between two HTML descriptions.
+fn description_change(previous: &str, current: &str) -> f64 {
+    let previous = words(previous);
+    let current = words(current);
+    let total = previous.len() + current.len();
+
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    for word in &previous {
+        *counts.entry(word).or_default() += 1;
+    }
+    for word in &current {
+        *counts.entry(word).or_default() -= 1;
+    }
+
+    if total == 0 {
+        return 0.0;
+    }
+    let changed: i64 = counts.values().map(|count| count.abs()).sum();
+
+    changed as f64 / total as f64
+}
+
+/// Returns the lowercase words of an HTML description ignoring tags.
+fn words(html: &str) -> Vec<String> {
+    strip_html(html)
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::event;
+
+    #[test]
+    fn between() {
+        let description = "<p>Meet at the campground at 8am.  Bring a rope and a rack.</p>";
+        let trip = |id: &str, start_date: &str| Event {
+            title: format!("Event {id}"),
+            url: format!("https://example.com/{id}"),
+            location: "Joshua Tree".into(),
+            description: description.into(),
+            ..event(id, start_date)
+        };
+        let previous: Vec<EventSnapshot> = [
+            trip("1", "2022-01-14"),
+            trip("2", "2022-01-15"),
+            trip("3", "2022-01-16"),
+            trip("4", "2022-01-17"),
+            trip("5", "2022-01-01"),
+        ]
+        .iter()
+        .map(EventSnapshot::from)
+        .collect();
+        let current = vec![
+            Event {
+                description: description.replace("  ", " "),
+                ..trip("1", "2022-01-14")
+            },
+            trip("2", "2022-01-22"),
+            Event {
+                location: "Red Rocks".into(),
+                ..trip("3", "2022-01-16")
+            },
+            trip("6", "2022-01-18"),
+        ];
+
+        let changelog = Changelog::between(&previous, &current, "2022-01-10".parse().unwrap());
+        insta::assert_snapshot!(changelog.to_human());
+    }
+
+    #[test]
+    fn description_change() {
+        let previous = "<p>Meet at the campground at 8am.</p>";
+        assert_eq!(super::description_change(previous, previous), 0.0);
+        assert_eq!(
+            super::description_change(previous, "<div>Meet at the campground at 8am.</div>"),
+            0.0
+        );
+        assert!(super::description_change(previous, "<p>Meet at the trailhead at 9am.</p>") > 0.1);
+        assert_eq!(
+            super::description_change("<p>Ming &amp; LeRoy</p>", "<p>Ming & LeRoy</p>"),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::output::is_rate_limited;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An error of the library API.
+///
+/// The variant identifies the subsystem that failed.  The message is that of the underlying error.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Unable to log in to the SCMA website (e.g. a bad username or password)
+    #[error(transparent)]
+    Login(BoxError),
+    /// Unable to resolve the SCMA credentials (e.g. from the OS keyring or a prompt)
+    #[error(transparent)]
+    Credentials(BoxError),
+    /// Unable to fetch from or submit to the SCMA website
+    #[error(transparent)]
+    Web(BoxError),
+    /// Unable to parse SCMA website content
+    #[error(transparent)]
+    Parse(BoxError),
+    /// Unable to authenticate with Google
+    #[error(transparent)]
+    Auth(BoxError),
+    /// A Google API rate limit or quota was exceeded even after retrying
+    #[error(transparent)]
+    Quota(BoxError),
+    /// A Google API request failed
+    #[error(transparent)]
+    Google(BoxError),
+    /// Unable to email an invitation or text a reminder
+    #[error(transparent)]
+    Notify(BoxError),
+    /// Unable to read or write a local file (e.g. the state file)
+    #[error(transparent)]
+    File(BoxError),
+}
+
+impl Error {
+    pub(crate) fn login(err: impl Into<BoxError>) -> Self {
+        Self::Login(err.into())
+    }
+
+    pub(crate) fn credentials(err: impl Into<BoxError>) -> Self {
+        Self::Credentials(err.into())
+    }
+
+    pub(crate) fn web(err: impl Into<BoxError>) -> Self {
+        Self::Web(err.into())
+    }
+
+    pub(crate) fn parse(err: impl Into<BoxError>) -> Self {
+        Self::Parse(err.into())
+    }
+
+    pub(crate) fn auth(err: impl Into<BoxError>) -> Self {
+        Self::Auth(err.into())
+    }
+
+    pub(crate) fn google(err: impl Into<BoxError>) -> Self {
+        Self::Google(err.into())
+    }
+
+    pub(crate) fn notify(err: impl Into<BoxError>) -> Self {
+        Self::Notify(err.into())
+    }
+
+    pub(crate) fn file(err: impl Into<BoxError>) -> Self {
+        Self::File(err.into())
+    }
+}
+
+/// Classifies Google API errors as auth, quota, or other failures.
+impl From<google_calendar3::Error> for Error {
+    fn from(err: google_calendar3::Error) -> Self {
+        use google_calendar3::Error as GoogleError;
+
+        let rate_limited = match &err {
+            GoogleError::BadRequest(error) => error["error"]["code"]
+                .as_u64()
+                .and_then(|code| u16::try_from(code).ok())
+                .is_some_and(|code| is_rate_limited(code, Some(error))),
+            GoogleError::Failure(response) => is_rate_limited(response.status().as_u16(), None),
+            _ => false,
+        };
+
+        match err {
+            GoogleError::MissingToken(_) | GoogleError::MissingAPIKey => Self::Auth(err.into()),
+            _ if rate_limited => Self::Quota(err.into()),
+            _ => Self::Google(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_google_error() {
+        let error = |code: u16, reason: &str| {
+            google_calendar3::Error::BadRequest(serde_json::json!({
+                "error": {
+                    "code": code,
+                    "errors": [{"reason": reason}],
+                }
+            }))
+        };
+
+        assert!(matches!(
+            Error::from(error(403, "rateLimitExceeded")),
+            Error::Quota(_)
+        ));
+        assert!(matches!(Error::from(error(429, "")), Error::Quota(_)));
+        assert!(matches!(
+            Error::from(error(403, "forbidden")),
+            Error::Google(_)
+        ));
+        assert!(matches!(
+            Error::from(google_calendar3::Error::MissingAPIKey),
+            Error::Auth(_)
+        ));
+    }
+}
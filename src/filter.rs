@@ -0,0 +1,482 @@
+use crate::model::{id_sort_key, Event, MemberStatus, User};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Selects which SCMA events are synced.
+///
+/// The filter is applied to the SCMA event list before any event details are fetched or any
+/// Google Calendar operations are determined.  This ensures that excluded events are treated the
+/// same as events that do not exist on the SCMA website.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    /// SCMA event IDs that are never synced
+    exclude_ids: HashSet<String>,
+    /// The only SCMA event IDs that are synced if not empty
+    include_ids: HashSet<String>,
+    /// Lowercase keywords of which the title must contain one if not empty
+    title_keywords: Vec<String>,
+    /// Lowercase keywords of which the location must contain one if not empty
+    location_keywords: Vec<String>,
+    /// Events ending before this date are out of scope
+    since: Option<NaiveDate>,
+    /// Events starting after this date are out of scope
+    until: Option<NaiveDate>,
+    /// Removes events listed more than once on the SCMA website
+    dedup: bool,
+    /// The duplicates removed by apply().  Shared by clones.
+    duplicates: Arc<Mutex<Vec<DuplicateEvent>>>,
+}
+
+/// An event removed by the dedup pass.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DuplicateEvent {
+    /// The SCMA event ID of the removed duplicate
+    pub id: String,
+    /// The SCMA event ID of the event that was kept
+    pub kept_id: String,
+    pub title: String,
+    pub url: String,
+}
+
+impl EventFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Excludes the given SCMA event IDs.
+    pub fn exclude_ids<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude_ids
+            .extend(ids.into_iter().map(|id| id.into().trim().to_string()));
+        self
+    }
+
+    /// Includes only the given SCMA event IDs.
+    pub fn include_ids<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.include_ids
+            .extend(ids.into_iter().map(|id| id.into().trim().to_string()));
+        self
+    }
+
+    /// Includes only events whose title contains one of the given keywords (case-insensitive).
+    pub fn match_title<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.title_keywords.extend(
+            keywords
+                .into_iter()
+                .map(|keyword| keyword.as_ref().to_lowercase()),
+        );
+        self
+    }
+
+    /// Includes only events whose location contains one of the given keywords (case-insensitive).
+    pub fn match_location<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.location_keywords.extend(
+            keywords
+                .into_iter()
+                .map(|keyword| keyword.as_ref().to_lowercase()),
+        );
+        self
+    }
+
+    /// Returns true if only some of the events in the date window are selected (e.g. by ID or
+    /// keyword).
+    ///
+    /// The events that are not selected are out of scope but still exist so they must not be
+    /// treated as removed (e.g. as orphans).
+    pub fn is_partial(&self) -> bool {
+        !self.include_ids.is_empty()
+            || !self.title_keywords.is_empty()
+            || !self.location_keywords.is_empty()
+    }
+
+    /// Excludes events that end before the given date.
+    ///
+    /// Events before this date are out of scope.  They are neither synced nor reconciled.
+    pub fn since(mut self, date: NaiveDate) -> Self {
+        self.since = Some(date);
+        self
+    }
+
+    /// Returns the first end date in scope, if any.
+    pub fn since_date(&self) -> Option<NaiveDate> {
+        self.since
+    }
+
+    /// Excludes events that start after the given date.
+    ///
+    /// Events beyond this date are out of scope.  They are neither synced nor reconciled.
+    pub fn until(mut self, date: NaiveDate) -> Self {
+        self.until = Some(date);
+        self
+    }
+
+    /// Returns the last start date in scope, if any.
+    pub fn until_date(&self) -> Option<NaiveDate> {
+        self.until
+    }
+
+    /// Removes duplicate events.
+    ///
+    /// Events are duplicates if they have the same normalized title and the same start and end
+    /// dates.  The event with the lowest SCMA event ID is kept.
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Returns true if the event should be synced.
+    pub fn is_match(&self, event: &Event) -> bool {
+        !self.exclude_ids.contains(&event.id) && self.is_selected(event) && self.is_in_window(event)
+    }
+
+    /// Returns true if the event is selected by ID and keyword.
+    fn is_selected(&self, event: &Event) -> bool {
+        (self.include_ids.is_empty() || self.include_ids.contains(&event.id))
+            && contains_any(&event.title, &self.title_keywords)
+            && contains_any(&event.location, &self.location_keywords)
+    }
+
+    /// Returns true if the event is within the date window.
+    pub fn is_in_window(&self, event: &Event) -> bool {
+        self.since.is_none_or(|since| event.end_date >= since)
+            && self.until.is_none_or(|until| event.start_date <= until)
+    }
+
+    /// Returns the duplicates removed by apply() for the run summary.
+    pub fn duplicates(&self) -> Vec<DuplicateEvent> {
+        self.duplicates.lock().unwrap().clone()
+    }
+
+    /// Removes events that should not be synced.
+    ///
+    /// The removed duplicates are recorded (see duplicates()).
+    pub fn apply(&self, events: Vec<Event>) -> Vec<Event> {
+        let (events, excluded): (Vec<_>, Vec<_>) =
+            events.into_iter().partition(|event| self.is_match(event));
+
+        if !excluded.is_empty() {
+            info!(
+                count = excluded.len(),
+                excluded = ?excluded.iter().map(|event| event.id.as_str()).collect::<Vec<_>>(),
+                "Excluding events"
+            );
+        }
+
+        if self.dedup {
+            let (events, duplicates) = dedup_events(events);
+            for duplicate in &duplicates {
+                warn!(
+                    %duplicate.id,
+                    %duplicate.kept_id,
+                    %duplicate.title,
+                    %duplicate.url,
+                    "Removing duplicate event, consider deleting it from the SCMA website"
+                );
+            }
+            self.duplicates.lock().unwrap().extend(duplicates);
+            events
+        } else {
+            events
+        }
+    }
+}
+
+/// Returns true if the text contains one of the lowercase keywords (case-insensitive) or if there
+/// are no keywords.
+fn contains_any(text: &str, keywords: &[String]) -> bool {
+    let text = text.to_lowercase();
+    keywords.is_empty() || keywords.iter().any(|keyword| text.contains(keyword))
+}
+
+/// Selects which SCMA users are synced.
+///
+/// The filter is applied to the SCMA users before any profile photos are fetched or any Google
+/// operations are determined.  Users that are not selected are treated as if they do not exist
+/// (e.g. they are removed from the calendar ACL).
+#[derive(Clone, Debug, Default)]
+pub struct UserFilter {
+    /// The only member statuses that are synced if not empty
+    member_statuses: Vec<MemberStatus>,
+    /// Excludes users that have resigned their membership
+    exclude_resigned: bool,
+}
+
+impl UserFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Includes only users with one of the given member statuses.
+    pub fn member_statuses<I>(mut self, statuses: I) -> Self
+    where
+        I: IntoIterator<Item = MemberStatus>,
+    {
+        self.member_statuses.extend(statuses);
+        self
+    }
+
+    /// Excludes users that have resigned their membership (see User::is_resigned).
+    pub fn exclude_resigned(mut self, exclude_resigned: bool) -> Self {
+        self.exclude_resigned = exclude_resigned;
+        self
+    }
+
+    /// Returns true if the user should be synced.
+    pub fn is_match(&self, user: &User) -> bool {
+        (self.member_statuses.is_empty() || self.member_statuses.contains(&user.member_status))
+            && !(self.exclude_resigned && user.is_resigned())
+    }
+
+    /// Removes users that should not be synced.
+    pub fn apply(&self, users: Vec<User>) -> Vec<User> {
+        let (users, excluded): (Vec<_>, Vec<_>) =
+            users.into_iter().partition(|user| self.is_match(user));
+
+        if !excluded.is_empty() {
+            info!(count = excluded.len(), "Excluding users");
+        }
+
+        users
+    }
+}
+
+/// Lowercases and collapses everything but alphanumerics so that trivial differences in
+/// punctuation, case, or whitespace don't prevent a match.
+fn normalize_title(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn dedup_events(events: Vec<Event>) -> (Vec<Event>, Vec<DuplicateEvent>) {
+    type Key = (String, NaiveDate, NaiveDate);
+
+    let key = |event: &Event| -> Key {
+        (
+            normalize_title(&event.title),
+            event.start_date,
+            event.end_date,
+        )
+    };
+
+    // The lowest ID for each key
+    let mut kept_ids: HashMap<Key, &Event> = HashMap::new();
+    for event in &events {
+        kept_ids
+            .entry(key(event))
+            .and_modify(|kept| {
+                if id_sort_key(&event.id) < id_sort_key(&kept.id) {
+                    *kept = event;
+                }
+            })
+            .or_insert(event);
+    }
+    let kept_ids: HashMap<Key, String> = kept_ids
+        .into_iter()
+        .map(|(key, event)| (key, event.id.clone()))
+        .collect();
+
+    let mut duplicates = Vec::new();
+    let events = events
+        .into_iter()
+        .filter(|event| {
+            let kept_id = &kept_ids[&key(event)];
+            if *kept_id == event.id {
+                true
+            } else {
+                duplicates.push(DuplicateEvent {
+                    id: event.id.clone(),
+                    kept_id: kept_id.clone(),
+                    title: event.title.clone(),
+                    url: event.url.clone(),
+                });
+                false
+            }
+        })
+        .collect();
+
+    (events, duplicates)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::event;
+
+    #[test]
+    fn exclude_ids() {
+        let filter = EventFilter::new().exclude_ids(["2", " 4 "]);
+        let events = vec![
+            event("1", "2022-01-14"),
+            event("2", "2022-01-14"),
+            event("3", "2022-01-14"),
+            event("4", "2022-01-14"),
+        ];
+        let actual: Vec<String> = filter
+            .apply(events)
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        assert_eq!(actual, vec!["1", "3"]);
+    }
+
+    #[test]
+    fn include_ids() {
+        let filter = EventFilter::new()
+            .include_ids(["2", "3"])
+            .exclude_ids(["3"]);
+        let events = vec![
+            event("1", "2022-01-14"),
+            event("2", "2022-01-14"),
+            event("3", "2022-01-14"),
+        ];
+        let actual: Vec<String> = filter
+            .apply(events)
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        assert_eq!(actual, vec!["2"]);
+        assert!(filter.is_partial());
+        assert!(!EventFilter::new().exclude_ids(["3"]).is_partial());
+    }
+
+    #[test]
+    fn match_title_and_location() {
+        let filter = EventFilter::new()
+            .match_title(["joshua", "Tahquitz"])
+            .match_location(["CA"]);
+        let mut events = vec![
+            event("1", "2022-01-14"),
+            event("2", "2022-01-14"),
+            event("3", "2022-01-14"),
+            event("4", "2022-01-14"),
+        ];
+        events[0].title = "Joshua Tree, Hidden Valley".into();
+        events[0].location = "Joshua Tree, CA".into();
+        events[1].title = "Tahquitz".into();
+        events[1].location = "Idyllwild, CA".into();
+        events[2].title = "Joshua Tree".into();
+        events[2].location = "Red Rocks, NV".into();
+        events[3].title = "Board Meeting".into();
+        events[3].location = "Pasadena, CA".into();
+        let actual: Vec<String> = filter
+            .apply(events)
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        assert_eq!(actual, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn user_filter() {
+        let user = |id: &str, member_status: MemberStatus, resigned: Option<&str>| User {
+            id: id.into(),
+            member_status,
+            resignedmembership: resigned.map(Into::into),
+            ..Default::default()
+        };
+        let users = vec![
+            user("1", MemberStatus::RM, None),
+            user("2", MemberStatus::Student, None),
+            user("3", MemberStatus::HM, Some("2023-01-01")),
+            user("4", MemberStatus::AM, Some("")),
+        ];
+        let filter = UserFilter::new()
+            .member_statuses([MemberStatus::RM, MemberStatus::HM, MemberStatus::AM])
+            .exclude_resigned(true);
+        let actual: Vec<String> = filter
+            .apply(users)
+            .into_iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(actual, vec!["1", "4"]);
+    }
+
+    #[test]
+    fn until() {
+        let filter = EventFilter::new().until("2022-03-31".parse().unwrap());
+        let events = vec![
+            event("1", "2022-01-14"),
+            event("2", "2022-03-31"),
+            event("3", "2022-04-01"),
+        ];
+        let actual: Vec<String> = filter
+            .apply(events)
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        assert_eq!(actual, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn since() {
+        let filter = EventFilter::new()
+            .since("2022-02-01".parse().unwrap())
+            .until("2022-03-31".parse().unwrap());
+        let mut in_progress = event("2", "2022-01-31");
+        in_progress.end_date = "2022-02-01".parse().unwrap();
+        let events = vec![
+            event("1", "2022-01-31"),
+            in_progress,
+            event("3", "2022-02-01"),
+            event("4", "2022-04-01"),
+        ];
+        let actual: Vec<String> = filter
+            .apply(events)
+            .into_iter()
+            .map(|event| event.id)
+            .collect();
+        assert_eq!(actual, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut events = vec![
+            event("12", "2022-01-14"),
+            event("11", "2022-01-14"),
+            event("13", "2022-01-15"),
+            event("14", "2022-01-14"),
+        ];
+        events[0].title = "Joshua Tree [G]".into();
+        events[1].title = "joshua  tree (G)".into();
+        events[2].title = "Joshua Tree [G]".into();
+        events[3].title = "Joshua Tree [S1]".into();
+
+        let filter = EventFilter::new().dedup(true);
+        let events = filter.apply(events);
+        let actual: Vec<String> = events.into_iter().map(|event| event.id).collect();
+        assert_eq!(actual, vec!["11", "13", "14"]);
+        assert_eq!(
+            filter.duplicates(),
+            vec![DuplicateEvent {
+                id: "12".into(),
+                kept_id: "11".into(),
+                title: "Joshua Tree [G]".into(),
+                url: "a url".into(),
+            }]
+        );
+    }
+}
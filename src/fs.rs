@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+
+/// Writes the file by writing a temporary file alongside it then renaming it.
+///
+/// A failure mid-write leaves any existing file intact.
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let result =
+        std::fs::write(&temp_path, content).and_then(|()| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn write_atomic() {
+        let dir = std::env::temp_dir().join(format!("scma-gsync-fs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.yml");
+
+        std::fs::write(&path, "old").unwrap();
+        super::write_atomic(&path, "new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert!(!dir.join("events.yml.tmp").exists());
+
+        // A failed write leaves the existing file intact
+        assert!(super::write_atomic(&dir, "new").is_err());
+        assert!(!dir.with_extension("tmp").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
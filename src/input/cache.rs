@@ -0,0 +1,122 @@
+use crate::Error;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use std::path::PathBuf;
+
+/// A disk cache of SCMA event detail pages keyed by URL.
+///
+/// A cached page is revalidated with the ETag or Last-Modified of the response it came from so
+/// that it is only downloaded again if it has changed.  If a TTL is set, a cached page younger
+/// than the TTL is used without revalidating it.
+#[derive(Clone, Debug)]
+pub struct PageCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+/// A cached page and the validators of the response it came from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CachedPage {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched: DateTime<Utc>,
+    pub body: String,
+}
+
+impl PageCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: None,
+        }
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:x}.json", Sha256::digest(url.as_bytes())))
+    }
+
+    /// Returns the cached page of the URL if any.
+    ///
+    /// An unreadable cache entry is treated as a cache miss.
+    pub(crate) fn get(&self, url: &str) -> Option<CachedPage> {
+        let path = self.path(url);
+        let json = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str::<CachedPage>(&json) {
+            Ok(page) if page.url == url => Some(page),
+            Ok(_) => None,
+            Err(e) => {
+                warn!(path=%path.display(), %e, "Ignoring unreadable cached page");
+                None
+            }
+        }
+    }
+
+    pub(crate) fn put(&self, page: &CachedPage) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.dir).map_err(Error::file)?;
+        let json = serde_json::to_string(page).map_err(Error::file)?;
+        std::fs::write(self.path(&page.url), json).map_err(Error::file)
+    }
+
+    /// Returns true if the cached page can be used without revalidating it.
+    pub(crate) fn is_fresh(&self, page: &CachedPage, now: DateTime<Utc>) -> bool {
+        self.ttl.is_some_and(|ttl| now - page.fetched < ttl)
+    }
+
+    /// Returns true if the cached page can be revalidated or used without revalidating it.
+    pub(crate) fn is_useful(&self, page: &CachedPage) -> bool {
+        self.ttl.is_some() || page.etag.is_some() || page.last_modified.is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn page(fetched: DateTime<Utc>) -> CachedPage {
+        CachedPage {
+            url: "https://www.rockclimbing.org/index.php/event-list/events-list/event/00527".into(),
+            etag: Some("\"abc\"".into()),
+            last_modified: None,
+            fetched,
+            body: "<html></html>".into(),
+        }
+    }
+
+    #[test]
+    fn get_put() {
+        let dir = std::env::temp_dir().join(format!("scma-gsync-cache-{}", std::process::id()));
+        let cache = PageCache::new(&dir);
+        let page = page(Utc::now());
+
+        assert_eq!(cache.get(&page.url), None);
+        cache.put(&page).unwrap();
+        assert_eq!(cache.get(&page.url), Some(page));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn is_fresh() {
+        let now = Utc::now();
+        let page = page(now - Duration::hours(2));
+
+        assert!(!PageCache::new("cache").is_fresh(&page, now));
+        assert!(!PageCache::new("cache")
+            .ttl(Duration::hours(1))
+            .is_fresh(&page, now));
+        assert!(PageCache::new("cache")
+            .ttl(Duration::hours(3))
+            .is_fresh(&page, now));
+    }
+}
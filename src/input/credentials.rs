@@ -0,0 +1,159 @@
+use crate::Error;
+
+use anyhow::Context;
+use tracing::{debug, info};
+
+const KEYRING_SERVICE: &str = "scma-gsync";
+/// The keyring entry that stores the SCMA username.  The password is stored in an entry keyed by
+/// the username.
+const KEYRING_USERNAME_ENTRY: &str = "scma-username";
+
+/// Credentials for the SCMA website.
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /// Resolves the SCMA credentials.
+    ///
+    /// Credentials are resolved from the first available source:
+    ///
+    /// 1. The given username and password (e.g. from command line options or environment
+    ///    variables)
+    /// 2. The OS keyring
+    /// 3. The prompt (e.g. an interactive prompt).  The prompt is given the username if known.
+    pub fn resolve(
+        username: &str,
+        password: &str,
+        prompt: impl FnOnce(&str) -> anyhow::Result<Self>,
+    ) -> Result<Self, Error> {
+        resolve(username, password, keyring_get, prompt)
+    }
+
+    /// Stores the credentials in the OS keyring for subsequent runs.
+    pub fn store_in_keyring(&self) -> Result<(), Error> {
+        keyring_set(KEYRING_USERNAME_ENTRY, &self.username)?;
+        keyring_set(&self.username, &self.password)?;
+        info!(username=%self.username, "Stored SCMA credentials in OS keyring");
+
+        Ok(())
+    }
+
+    fn from_keyring(username: &str, keyring_get: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let username = if username.is_empty() {
+            keyring_get(KEYRING_USERNAME_ENTRY)?
+        } else {
+            username.to_string()
+        };
+        let password = keyring_get(&username)?;
+
+        Some(Self { username, password })
+    }
+}
+
+fn resolve(
+    username: &str,
+    password: &str,
+    keyring_get: impl Fn(&str) -> Option<String>,
+    prompt: impl FnOnce(&str) -> anyhow::Result<Credentials>,
+) -> Result<Credentials, Error> {
+    if !username.is_empty() && !password.is_empty() {
+        return Ok(Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+    }
+
+    if let Some(credentials) = Credentials::from_keyring(username, keyring_get) {
+        info!(username=%credentials.username, "Using SCMA credentials from OS keyring");
+        return Ok(credentials);
+    }
+
+    prompt(username).map_err(Error::credentials)
+}
+
+/// Returns the value of the OS keyring entry or None if not found.
+pub fn keyring_get(user: &str) -> Option<String> {
+    match keyring::Entry::new(KEYRING_SERVICE, user).and_then(|entry| entry.get_password()) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            debug!(%user, error=%e, "Unable to get entry from OS keyring");
+            None
+        }
+    }
+}
+
+/// Sets the value of the OS keyring entry.
+pub fn keyring_set(user: &str, value: &str) -> Result<(), Error> {
+    keyring::Entry::new(KEYRING_SERVICE, user)
+        .and_then(|entry| entry.set_password(value))
+        .with_context(|| format!("unable to store `{user}` in OS keyring"))
+        .map_err(Error::credentials)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    fn resolve_with(
+        username: &str,
+        password: &str,
+        keyring: &[(&str, &str)],
+    ) -> Result<(String, String), String> {
+        let keyring: HashMap<&str, &str> = keyring.iter().copied().collect();
+        let keyring_get = |user: &str| keyring.get(user).map(|value| value.to_string());
+        let prompt = |username: &str| -> anyhow::Result<Credentials> {
+            Err(anyhow::anyhow!("prompted for `{username}`"))
+        };
+        super::resolve(username, password, keyring_get, prompt)
+            .map(|credentials| (credentials.username, credentials.password))
+            .map_err(|err| err.to_string())
+    }
+
+    fn ok(username: &str, password: &str) -> Result<(String, String), String> {
+        Ok((username.to_string(), password.to_string()))
+    }
+
+    #[test]
+    fn resolve() {
+        let keyring = [
+            (KEYRING_USERNAME_ENTRY, "jdoe"),
+            ("jdoe", "keyring secret"),
+            ("jane", "jane secret"),
+        ];
+
+        // The given (e.g. environment) credentials take precedence over the keyring
+        assert_eq!(
+            resolve_with("john", "secret", &keyring),
+            ok("john", "secret")
+        );
+        // The keyring password of the given username
+        assert_eq!(
+            resolve_with("jane", "", &keyring),
+            ok("jane", "jane secret")
+        );
+        // The keyring username and password
+        assert_eq!(resolve_with("", "", &keyring), ok("jdoe", "keyring secret"));
+        assert_eq!(
+            resolve_with("", "secret", &keyring),
+            ok("jdoe", "keyring secret")
+        );
+
+        // A keyring miss falls back to the prompt
+        assert_eq!(
+            resolve_with("john", "", &keyring),
+            Err("prompted for `john`".to_string())
+        );
+        assert_eq!(
+            resolve_with("", "", &[]),
+            Err("prompted for ``".to_string())
+        );
+        assert_eq!(
+            resolve_with("", "", &[(KEYRING_USERNAME_ENTRY, "jdoe")]),
+            Err("prompted for ``".to_string())
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::Error;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use std::path::PathBuf;
+
+/// Replaces a secret in a recorded exchange
+const REDACTED: &str = "REDACTED";
+
+/// Fixture files of the HTTP exchanges with the SCMA website keyed by method and URL.
+///
+/// In record mode, each exchange is saved to the fixture directory with any secrets (the password
+/// and the session cookies) redacted.  In replay mode, each exchange is answered from the fixture
+/// directory without touching the network.  A missing fixture is an error.
+#[derive(Clone, Debug)]
+pub struct Fixtures {
+    dir: PathBuf,
+    mode: FixtureMode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FixtureMode {
+    Record,
+    Replay,
+}
+
+/// A recorded request and its response.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    /// The body of the response.  Hex encoded if binary (e.g. a photo).
+    pub body: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+impl Fixtures {
+    pub fn record(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: FixtureMode::Record,
+        }
+    }
+
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: FixtureMode::Replay,
+        }
+    }
+
+    pub(crate) fn is_replay(&self) -> bool {
+        self.mode == FixtureMode::Replay
+    }
+
+    fn path(&self, method: &str, url: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{:x}.json",
+            Sha256::digest(format!("{method} {url}").as_bytes())
+        ))
+    }
+
+    /// Returns the recorded exchange of the request.
+    pub(crate) fn get(&self, method: &str, url: &str) -> Result<Exchange, Error> {
+        let path = self.path(method, url);
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| {
+                format!(
+                    "no fixture for `{method} {url}` in `{}`",
+                    self.dir.display()
+                )
+            })
+            .map_err(Error::file)?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("unable to parse fixture `{}`", path.display()))
+            .map_err(Error::file)
+    }
+
+    /// Records the exchange with the given secrets redacted.
+    pub(crate) fn put(&self, exchange: &Exchange, secrets: &[String]) -> Result<(), Error> {
+        let exchange = exchange.redact(secrets);
+        std::fs::create_dir_all(&self.dir).map_err(Error::file)?;
+        let json = serde_json::to_string_pretty(&exchange).map_err(Error::file)?;
+        std::fs::write(self.path(&exchange.method, &exchange.url), json).map_err(Error::file)
+    }
+}
+
+impl Exchange {
+    pub(crate) fn text(method: &str, url: &str, status: u16, body: &str) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            status,
+            body: body.to_string(),
+            binary: false,
+        }
+    }
+
+    pub(crate) fn bytes(method: &str, url: &str, status: u16, body: &[u8]) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            status,
+            body: body.iter().map(|byte| format!("{byte:02x}")).collect(),
+            binary: true,
+        }
+    }
+
+    /// Returns the body of the response decoding it if binary.
+    pub(crate) fn body_bytes(&self) -> Result<Vec<u8>, Error> {
+        if !self.binary {
+            return Ok(self.body.as_bytes().to_vec());
+        }
+
+        (0..self.body.len())
+            .step_by(2)
+            .map(|i| {
+                self.body
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| Error::file(anyhow!("invalid fixture body for `{}`", self.url)))
+            })
+            .collect()
+    }
+
+    fn redact(&self, secrets: &[String]) -> Self {
+        let mut exchange = self.clone();
+        for secret in secrets.iter().filter(|secret| !secret.is_empty()) {
+            exchange.url = exchange.url.replace(secret.as_str(), REDACTED);
+            if !exchange.binary {
+                exchange.body = exchange.body.replace(secret.as_str(), REDACTED);
+            }
+        }
+        exchange
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_replay() {
+        let dir = std::env::temp_dir().join(format!("scma-gsync-fixtures-{}", std::process::id()));
+        let url = "https://www.rockclimbing.org/index.php/event-list/events-list/event/00527";
+        let secrets = ["hunter2".to_string(), "abc123".to_string()];
+
+        let exchange = Exchange::text("GET", url, 200, "<html>hunter2 abc123</html>");
+        Fixtures::record(&dir).put(&exchange, &secrets).unwrap();
+        let photo = Exchange::bytes("GET", "https://example.com/photo.jpg", 200, &[0, 0xff]);
+        Fixtures::record(&dir).put(&photo, &secrets).unwrap();
+
+        let fixtures = Fixtures::replay(&dir);
+        assert_eq!(
+            fixtures.get("GET", url).unwrap().body,
+            "<html>REDACTED REDACTED</html>"
+        );
+        assert!(fixtures.get("POST", url).is_err());
+        assert_eq!(
+            fixtures
+                .get("GET", "https://example.com/photo.jpg")
+                .unwrap()
+                .body_bytes()
+                .unwrap(),
+            vec![0, 0xff]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use crate::filter::EventFilter;
+use crate::input::{EventSource, UserSource};
+use crate::model::{Event, User};
+use crate::Error;
+
+use async_trait::async_trait;
+
+/// Reads events or users from JSON (e.g. as written by the JSON output).
+pub struct JsonInput {
+    json: String,
+}
+
+impl JsonInput {
+    pub fn new(json: impl Into<String>) -> Self {
+        Self { json: json.into() }
+    }
+}
+
+#[async_trait]
+impl EventSource for JsonInput {
+    async fn read_events(&self, filter: &EventFilter) -> Result<Vec<Event>, Error> {
+        let events = serde_json::from_str(&self.json).map_err(Error::parse)?;
+        Ok(filter.apply(events))
+    }
+}
+
+#[async_trait]
+impl UserSource for JsonInput {
+    async fn read_users(&self) -> Result<Vec<User>, Error> {
+        serde_json::from_str(&self.json).map_err(Error::parse)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_users() {
+        let json = JsonInput::new(
+            r#"[
+  {
+    "id": "42",
+    "name": "Jane Doe",
+    "member_status": "RM",
+    "trip_leader_status": "S1",
+    "position": null,
+    "address": "",
+    "city": "",
+    "state": "",
+    "zipcode": "",
+    "phone": null,
+    "email": "jane@example.com",
+    "climbingtypes": null,
+    "lead": null,
+    "follow": null,
+    "favoriteclimbs": null,
+    "referredby": null,
+    "dob": null,
+    "applicantdate": null,
+    "membersince": null,
+    "resignedmembership": null,
+    "sex": null,
+    "register_date": "",
+    "lastvisit_date": "",
+    "timestamp": null
+  }
+]"#,
+        );
+
+        let users = json.read_users().await.unwrap();
+        let emails: Vec<&str> = users.iter().map(|user| user.email.as_str()).collect();
+        assert_eq!(emails, ["jane@example.com"]);
+    }
+}
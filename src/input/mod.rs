@@ -1,3 +1,32 @@
+mod cache;
+mod credentials;
+mod fixture;
+mod json;
 mod web;
+mod yaml;
 
+pub use cache::PageCache;
+pub use credentials::{keyring_get, keyring_set, Credentials};
+pub use fixture::Fixtures;
+pub use json::JsonInput;
 pub use web::Web;
+pub use yaml::YamlInput;
+
+use crate::filter::EventFilter;
+use crate::model::{Event, User};
+use crate::Error;
+
+use async_trait::async_trait;
+
+/// A source of SCMA events (e.g. the SCMA website or a YAML file).
+#[async_trait]
+pub trait EventSource: Send + Sync {
+    /// Reads the events selected by the filter including any event details.
+    async fn read_events(&self, filter: &EventFilter) -> Result<Vec<Event>, Error>;
+}
+
+/// A source of SCMA users (e.g. the SCMA website or a YAML file).
+#[async_trait]
+pub trait UserSource: Send + Sync {
+    async fn read_users(&self) -> Result<Vec<User>, Error>;
+}
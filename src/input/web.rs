@@ -1,135 +1,657 @@
-use crate::model::{Attendee, Comment, DateSelect, Event, User};
+use crate::filter::EventFilter;
+use crate::input::cache::{CachedPage, PageCache};
+use crate::input::fixture::{Exchange, Fixtures};
+use crate::input::{EventSource, UserSource};
+use crate::model::{
+    sort_events, sort_users, Attendee, Comment, DateSelect, Event, EventTime, Frequency,
+    Recurrence, User, DEFAULT_TIMEZONE,
+};
+use crate::output::{backoff, jitter, strip_html};
+use crate::Error;
 
 use anyhow::{anyhow, Context};
-use chrono::{DateTime, Utc};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
 use futures::{stream, StreamExt, TryStreamExt};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
 use select::document::Document;
-use select::predicate::{And, Attr, Class, Name};
+use select::predicate::{And, Attr, Class, Descendant, Name};
 use tap::prelude::*;
-use tracing::info;
+use tokio::sync::Mutex;
+use tracing::{debug, info, trace, warn};
 
 use std::convert::TryFrom;
+use std::future::Future;
+use std::sync::Arc;
 
 const SITE_URL: &str = "https://www.rockclimbing.org";
 const LOGIN_URL: &str = "https://www.rockclimbing.org/index.php/component/comprofiler/login";
 const EVENTS_URL: &str =
     "https://www.rockclimbing.org/index.php/event-list/events-list?format=json";
 const USERS_URL: &str = "https://www.rockclimbing.org/index.php?option=com_jsondumper";
+/// The Community Builder profile page of a user by user ID
+const PROFILE_URL: &str =
+    "https://www.rockclimbing.org/index.php/component/comprofiler/userprofile/";
 const CONCURRENT_REQUESTS: usize = 3;
+/// The default number of times to retry a transient page fetch failure
+const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Fails a request that hangs instead of stalling the run
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+/// The Komento (comment system) component of SCMA event comments
+const KOMENTO_COMPONENT: &str = "com_ohanah";
+/// The Komento AJAX method that adds a comment
+const KOMENTO_ADD_COMMENT: &str = "site.views.komento.addcomment";
+/// Only present together in pages served to a logged out user (the username and password fields
+/// of the login form).  A password field alone (e.g. of a change password form) is not enough.
+const LOGGED_OUT_MARKERS: [&str; 2] = [r#"name="username""#, r#"name="passwd""#];
+/// The name of the registration form field for the number of tickets (attendee plus guests)
+const RSVP_COUNT_FIELD: &str = "number_of_tickets";
+/// The duration of a timed event without an end time
+const DEFAULT_EVENT_HOURS: i64 = 2;
+/// Words before a time that qualify something other than the event (e.g. "parking after 6 PM")
+const TIME_QUALIFIERS: &[&str] = &["after", "before", "until", "by", "than"];
 
 pub struct Web {
     dates: DateSelect,
     client: reqwest::Client,
+    /// The cookie store of the client.  Holds the session cookie.
+    cookies: Arc<Jar>,
+    username: String,
+    password: String,
+    /// Incremented on each login.  Prevents concurrent requests that detect the same expired
+    /// session from each logging in again.
+    session: Mutex<u64>,
+    /// Caches the event detail pages if set
+    page_cache: Option<PageCache>,
+    /// The number of times to retry a transient page fetch failure
+    max_retries: u32,
+    /// Records or replays the HTTP exchanges if set
+    fixtures: Option<Fixtures>,
+    /// The timezone of SCMA events.  Determines whether DateSelect::Since is in the past.
+    timezone: Tz,
 }
 
 impl Web {
-    pub async fn new(
+    pub async fn new(username: &str, password: &str, dates: DateSelect) -> Result<Web, Error> {
+        Self::with_session(username, password, dates, None).await
+    }
+
+    /// Resumes the session of the given cookies (as returned by `session_cookies`) if it is still
+    /// valid.  Otherwise, logs in.
+    pub async fn with_session(
         username: &str,
         password: &str,
         dates: DateSelect,
-    ) -> Result<Web, Box<dyn std::error::Error>> {
-        let client = Self::create_client()?;
+        session_cookies: Option<&str>,
+    ) -> Result<Web, Error> {
+        let cookies = Arc::new(Jar::default());
+        let client = Self::create_client(cookies.clone())?;
 
-        let web = Self { dates, client };
+        let web = Self {
+            dates,
+            client,
+            cookies,
+            username: username.to_string(),
+            password: password.to_string(),
+            session: Mutex::new(0),
+            page_cache: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            fixtures: None,
+            timezone: DEFAULT_TIMEZONE,
+        };
 
-        web.login(username, password).await?;
+        match session_cookies {
+            Some(session_cookies) if web.resume(session_cookies).await? => {
+                info!("Resumed SCMA session");
+            }
+            _ => web.login().await?,
+        }
 
         Ok(web)
     }
 
-    pub async fn read(&self) -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    /// Replays the HTTP exchanges recorded in the fixtures instead of using the SCMA website.
+    ///
+    /// Does not log in.  The fixtures must be in replay mode.
+    pub fn replay(dates: DateSelect, fixtures: Fixtures) -> Result<Web, Error> {
+        let cookies = Arc::new(Jar::default());
+        let client = Self::create_client(cookies.clone())?;
+
+        Ok(Self {
+            dates,
+            client,
+            cookies,
+            username: String::new(),
+            password: String::new(),
+            session: Mutex::new(0),
+            page_cache: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            fixtures: Some(fixtures),
+            timezone: DEFAULT_TIMEZONE,
+        })
+    }
+
+    /// Returns the cookies of the session for resuming it with `with_session`.
+    pub fn session_cookies(&self) -> Option<String> {
+        let url = reqwest::Url::parse(SITE_URL).ok()?;
+        let cookies = self.cookies.cookies(&url)?;
+        cookies.to_str().ok().map(str::to_string)
+    }
+
+    /// Restores the session cookies and returns true if the session is still valid.
+    async fn resume(&self, session_cookies: &str) -> Result<bool, Error> {
+        let url = reqwest::Url::parse(SITE_URL).map_err(Error::web)?;
+        for cookie in session_cookies.split(';').map(str::trim) {
+            if !cookie.is_empty() {
+                self.cookies
+                    .add_cookie_str(&format!("{cookie}; Path=/"), &url);
+            }
+        }
+
+        let rsp = self.fetch_response_once(SITE_URL, None).await?;
+        Ok(!rsp.page.is_logged_out())
+    }
+
+    /// Enables the disk cache of the event detail pages.
+    pub fn set_page_cache(&mut self, cache: PageCache) {
+        self.page_cache = Some(cache);
+    }
+
+    /// Records the HTTP exchanges to (or replays them from) the fixtures.
+    pub fn set_fixtures(&mut self, fixtures: Fixtures) {
+        self.fixtures = Some(fixtures);
+    }
+
+    /// Sets the timezone of SCMA events.  Defaults to DEFAULT_TIMEZONE.
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = timezone;
+    }
+
+    /// Returns the fixtures if replaying.
+    fn replaying(&self) -> Option<&Fixtures> {
+        self.fixtures
+            .as_ref()
+            .filter(|fixtures| fixtures.is_replay())
+    }
+
+    /// Records the exchange if recording.
+    ///
+    /// The password and the session cookies are redacted.  A failure to record is logged.
+    fn record(&self, exchange: &Exchange) {
+        let Some(fixtures) = self
+            .fixtures
+            .as_ref()
+            .filter(|fixtures| !fixtures.is_replay())
+        else {
+            return;
+        };
+
+        let mut secrets = vec![self.password.clone()];
+        if let Some(session_cookies) = self.session_cookies() {
+            secrets.extend(
+                session_cookies
+                    .split(';')
+                    .filter_map(|cookie| cookie.split_once('='))
+                    .map(|(_, value)| value.trim().to_string()),
+            );
+        }
+        if let Err(e) = fixtures.put(exchange, &secrets) {
+            warn!(url=%exchange.url, %e, "Unable to record fixture");
+        }
+    }
+
+    /// Sets the number of times to retry a page fetch that fails with a timeout, a connection
+    /// error, or a 5xx or 429 response.
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    pub async fn read(&self) -> Result<Vec<Event>, Error> {
         let events = self.fetch_events().await?;
         let events = self.fetch_events_details(events).await?;
         Ok(events)
     }
 
-    pub async fn fetch_events(&self) -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    pub async fn fetch_events(&self) -> Result<Vec<Event>, Error> {
         let events_url = match self.dates {
             DateSelect::All => EVENTS_URL.to_string(),
             DateSelect::NotPast => [EVENTS_URL, "&filterEvents=notpast"].join(""),
+            // The SCMA website only filters out past events so later dates are filtered locally
+            // (see EventFilter::since)
+            DateSelect::Since(date)
+                if date >= Utc::now().with_timezone(&self.timezone).date_naive() =>
+            {
+                [EVENTS_URL, "&filterEvents=notpast"].join("")
+            }
+            DateSelect::Since(_) => EVENTS_URL.to_string(),
         };
 
         info!(url=%events_url, "Fetching event list page");
-        let events_page = Page::from_url(&self.client, &events_url).await?;
+        let events_page = self.fetch_page(&events_url).await?;
         let events = EventList::try_from(events_page)?.into_inner();
 
         Ok(events)
     }
 
-    fn create_client() -> Result<reqwest::Client, Box<dyn std::error::Error>> {
-        Ok(reqwest::Client::builder()
-            .cookie_store(true)
+    fn create_client(cookies: Arc<Jar>) -> Result<reqwest::Client, Error> {
+        reqwest::Client::builder()
+            .cookie_provider(cookies)
+            .timeout(REQUEST_TIMEOUT)
             .user_agent(format!(
                 "{} {} {}",
                 env!("CARGO_PKG_NAME"),
                 env!("CARGO_PKG_VERSION"),
                 env!("CARGO_PKG_REPOSITORY")
             ))
-            .build()?)
+            .build()
+            .map_err(Error::web)
     }
 
-    async fn login(&self, username: &str, password: &str) -> anyhow::Result<()> {
+    async fn login(&self) -> Result<(), Error> {
         let url = LOGIN_URL;
 
+        if self.replaying().is_some() {
+            return Err(Error::login(anyhow!(
+                "unable to login to {} while replaying fixtures",
+                SITE_URL
+            )));
+        }
+
         info!(%url, "Logging in");
 
-        let login_params = [("username", username), ("passwd", password)];
+        let login_params = [
+            ("username", self.username.as_str()),
+            ("passwd", self.password.as_str()),
+        ];
         let rsp = self
             .client
             .post(url)
             .form(&login_params)
             .send()
             .await
-            .with_context(|| format!("unable to login to {} due to bad request", SITE_URL))?;
+            .with_context(|| format!("unable to login to {} due to bad request", SITE_URL))
+            .map_err(Error::login)?;
 
         if !rsp.status().is_success() {
-            Err(anyhow!(
+            Err(Error::login(anyhow!(
                 "unable to login to {} due to bad response",
                 SITE_URL
-            ))
+            )))
         } else if rsp.url().path() != "/" {
-            Err(anyhow!(
+            Err(Error::login(anyhow!(
                 "unable to login to {} due to bad username or password",
                 SITE_URL
-            ))
+            )))
         } else {
             Ok(())
         }
     }
 
-    async fn fetch_events_details(
-        &self,
-        events: Vec<Event>,
-    ) -> Result<Vec<Event>, Box<dyn std::error::Error>> {
+    pub async fn fetch_events_details(&self, events: Vec<Event>) -> Result<Vec<Event>, Error> {
         let events = stream::iter(events)
             .map(|event| self.fetch_event_details(event))
             .buffer_unordered(CONCURRENT_REQUESTS)
             .try_collect::<Vec<_>>()
             .await?
-            .tap_mut(|events| events.sort_by_key(|event| event.start_date));
+            .tap_mut(|events| sort_events(events));
 
         Ok(events)
     }
 
-    pub async fn fetch_event_details(
-        &self,
-        event: Event,
-    ) -> Result<Event, Box<dyn std::error::Error>> {
+    pub async fn fetch_event_details(&self, event: Event) -> Result<Event, Error> {
         info!(%event.id, %event, url=%event.url, "Fetching event");
-        let event_page = Page::from_url(&self.client, &event.url).await?;
+        let event_page = self.fetch_page_cached(&event.url).await?;
         let timestamp = Utc::now();
         let event = Event::try_from((event, event_page, timestamp))?;
         Ok(event)
     }
 
-    pub async fn fetch_users(&self) -> Result<Vec<User>, Box<dyn std::error::Error>> {
+    /// Fetches the name of the logged in user.
+    ///
+    /// The name is scraped from the comment form of the given event page.  This is the name used
+    /// in the event attendee list.
+    pub async fn fetch_account_name(&self, event: &Event) -> Result<String, Error> {
+        info!(%event.id, %event, url=%event.url, "Fetching account name");
+        let page = self.fetch_page(&event.url).await?;
+        account_name(&page).ok_or_else(|| {
+            Error::parse(anyhow!(
+                "unable to determine the name of the logged in user"
+            ))
+        })
+    }
+
+    /// Registers the logged in user for the event with the given number of tickets (attendee
+    /// plus guests).
+    ///
+    /// Submits the registration form linked from the event page.
+    pub async fn rsvp(&self, event: &Event, count: u8) -> Result<(), Error> {
+        info!(%event.id, %event, url=%event.url, "Fetching event");
+        let page = self.fetch_page(&event.url).await?;
+        let registration_url = registration_url(&page).ok_or_else(|| {
+            Error::web(anyhow!("registration is not open for event `{}`", event.id))
+        })?;
+
+        info!(%event.id, %event, url=%registration_url, "Fetching registration form");
+        let page = self.fetch_page(&registration_url).await?;
+        let mut form = Form::find(&page, RSVP_COUNT_FIELD).ok_or_else(|| {
+            Error::parse(anyhow!(
+                "unable to find the registration form for event `{}`",
+                event.id
+            ))
+        })?;
+        form.set(RSVP_COUNT_FIELD, &count.to_string());
+
+        info!(%event.id, %event, %count, url=%form.action, "Submitting registration");
+        self.submit(&registration_url, &form).await
+    }
+
+    /// Posts a comment to the event as the logged in user.
+    ///
+    /// Uses the same AJAX request as the comment form on the event page.
+    pub async fn comment(&self, event: &Event, message: &str) -> Result<(), Error> {
+        info!(%event.id, %event, url=%event.url, "Fetching event");
+        let page = self.fetch_page(&event.url).await?;
+        let komento = Komento::try_from(&page)
+            .with_context(|| format!("unable to find the comment form for event `{}`", event.id))
+            .map_err(Error::parse)?;
+
+        let params = [
+            ("namespace", KOMENTO_ADD_COMMENT),
+            ("format", "ajax"),
+            ("no_html", "1"),
+            ("tmpl", "component"),
+            (komento.token.as_str(), "1"),
+            ("component", KOMENTO_COMPONENT),
+            ("cid", &event.id),
+            ("comment", message),
+            ("parent_id", "0"),
+            ("depth", "0"),
+            ("subscribe", "0"),
+            ("pageItemId", &komento.page_item_id),
+            ("contentLink", &event.url),
+        ];
+
+        info!(%event.id, %event, url=%komento.base_url, "Posting comment");
+        let (status, text) = self
+            .post_form(&komento.base_url, &params)
+            .await
+            .with_context(|| {
+                format!(
+                    "unable to post comment to {} due to bad request",
+                    komento.base_url
+                )
+            })
+            .map_err(Error::web)?;
+        if !status.is_success() {
+            return Err(Error::web(anyhow!(
+                "unable to post comment to {} due to bad response ({})",
+                komento.base_url,
+                status
+            )));
+        }
+
+        // Komento responds with a list of commands for the client.  A rejection indicates failure
+        // (e.g. a comment that is too short or flood control).
+        trace!(%text, "addcomment");
+        if text.contains(r#""type":"reject""#) {
+            return Err(Error::web(anyhow!(
+                "comment rejected for event `{}`: {text}",
+                event.id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Fetches a page, logging in again if the session has expired.
+    ///
+    /// An expired session is detected by the login form in the response.
+    async fn fetch_page(&self, url: &str) -> Result<Page, Error> {
+        Ok(self.fetch_response(url, None).await?.page)
+    }
+
+    /// Fetches the page using the page cache if enabled.
+    async fn fetch_page_cached(&self, url: &str) -> Result<Page, Error> {
+        let Some(ref cache) = self.page_cache else {
+            return self.fetch_page(url).await;
+        };
+
+        let now = Utc::now();
+        let cached = cache.get(url);
+        if let Some(ref cached) = cached {
+            if cache.is_fresh(cached, now) {
+                debug!(%url, "Using cached page");
+                return Ok(Page(cached.body.clone()));
+            }
+        }
+
+        let rsp = self.fetch_response(url, cached.as_ref()).await?;
+        if rsp.not_modified {
+            debug!(%url, "Using cached page, not modified");
+        }
+
+        let cached = CachedPage {
+            url: url.to_string(),
+            etag: rsp.etag,
+            last_modified: rsp.last_modified,
+            fetched: now,
+            body: rsp.page.0.clone(),
+        };
+        if cache.is_useful(&cached) {
+            if let Err(e) = cache.put(&cached) {
+                warn!(%url, %e, "Unable to cache page");
+            }
+        }
+
+        Ok(rsp.page)
+    }
+
+    /// Fetches the page logging in again if the session has expired.
+    ///
+    /// Makes a conditional request if given a cached page.
+    async fn fetch_response(
+        &self,
+        url: &str,
+        cached: Option<&CachedPage>,
+    ) -> Result<PageResponse, Error> {
+        let session = *self.session.lock().await;
+        fetch_with_relogin(
+            url,
+            || self.fetch_response_once(url, cached),
+            || self.relogin(url, session),
+        )
+        .await
+    }
+
+    /// Logs in again unless another request already did since the session was read.
+    async fn relogin(&self, url: &str, session: u64) -> Result<(), Error> {
+        let mut current_session = self.session.lock().await;
+        // Another request may have already logged in again
+        if *current_session == session {
+            warn!(%url, "SCMA session expired, logging in again");
+            self.login().await?;
+            *current_session += 1;
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_response_once(
+        &self,
+        url: &str,
+        cached: Option<&CachedPage>,
+    ) -> Result<PageResponse, Error> {
+        if let Some(fixtures) = self.replaying() {
+            let exchange = fixtures.get("GET", url)?;
+            return Ok(PageResponse {
+                page: Page(exchange.body),
+                etag: None,
+                last_modified: None,
+                not_modified: false,
+            });
+        }
+
+        let mut retries = 0;
+        loop {
+            match PageResponse::from_url(&self.client, url, cached).await {
+                Ok(rsp) => {
+                    self.record(&Exchange::text("GET", url, 200, &rsp.page.0));
+                    return Ok(rsp);
+                }
+                Err(e) if is_transient(&e) && retries < self.max_retries => {
+                    let delay = backoff(retries) + jitter();
+                    retries += 1;
+                    warn!(%url, %e, retry=%retries, ?delay, "Unable to fetch page, retrying");
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    let attempts = retries + 1;
+                    return Err(Error::web(anyhow!(
+                        "unable to fetch page `{url}` after {attempts} attempt(s): {e}"
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Submits the form found on the page at the given URL.
+    async fn submit(&self, page_url: &str, form: &Form) -> Result<(), Error> {
+        self.submit_inner(page_url, form).await.map_err(Error::web)
+    }
+
+    async fn submit_inner(&self, page_url: &str, form: &Form) -> anyhow::Result<()> {
+        let url = reqwest::Url::parse(page_url)?
+            .join(&form.action)
+            .with_context(|| format!("invalid form action `{}`", form.action))?;
+        let (status, _) = self
+            .post_form(url.as_str(), &form.fields)
+            .await
+            .with_context(|| format!("unable to submit form to {url} due to bad request"))?;
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "unable to submit form to {url} due to bad response ({status})"
+            ))
+        }
+    }
+
+    /// Posts the form and returns the status and the body of the response.
+    ///
+    /// The form fields are not recorded as they may contain secrets (e.g. a form token).
+    async fn post_form<T: serde::Serialize + ?Sized>(
+        &self,
+        url: &str,
+        form: &T,
+    ) -> anyhow::Result<(StatusCode, String)> {
+        if let Some(fixtures) = self.replaying() {
+            let exchange = fixtures.get("POST", url)?;
+            return Ok((StatusCode::from_u16(exchange.status)?, exchange.body));
+        }
+
+        let rsp = self.client.post(url).form(form).send().await?;
+        let status = rsp.status();
+        let text = rsp.text().await?;
+        self.record(&Exchange::text("POST", url, status.as_u16(), &text));
+
+        Ok((status, text))
+    }
+
+    pub async fn fetch_users(&self) -> Result<Vec<User>, Error> {
         let url = USERS_URL;
 
         info!(url=%url, "Fetching users");
-        let page = Page::from_url(&self.client, url).await?;
+        let page = self.fetch_page(url).await?;
         let users = Users::try_from(page)?;
 
-        Ok(users.0)
+        Ok(users.0.tap_mut(|users| sort_users(users)))
+    }
+
+    /// Fetches the profile photos of the users.
+    pub async fn fetch_users_photos(&self, users: &mut [User]) -> Result<(), Error> {
+        info!(count=%users.len(), "Fetching user photos");
+        stream::iter(users.iter_mut())
+            .map(|user| async move {
+                user.photo = self.fetch_user_photo(user).await?;
+                Ok::<_, Error>(())
+            })
+            .buffer_unordered(CONCURRENT_REQUESTS)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fetches the profile photo of the user.
+    ///
+    /// Returns None if the user has not uploaded a photo.
+    pub async fn fetch_user_photo(&self, user: &User) -> Result<Option<Vec<u8>>, Error> {
+        if user.id.is_empty() {
+            return Ok(None);
+        }
+
+        let url = format!("{PROFILE_URL}{}", user.id);
+        debug!(%url, user=%user.name_email(), "Fetching user profile");
+        let page = self.fetch_page(&url).await?;
+        let Some(photo_url) = profile_photo_url(&page) else {
+            return Ok(None);
+        };
+        let photo_url = reqwest::Url::parse(&url)
+            .and_then(|url| url.join(&photo_url))
+            .map_err(Error::parse)?;
+
+        if let Some(fixtures) = self.replaying() {
+            return fixtures
+                .get("GET", photo_url.as_str())?
+                .body_bytes()
+                .map(Some);
+        }
+
+        let photo = self
+            .client
+            .get(photo_url.clone())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| Error::web(anyhow!("unable to fetch photo `{photo_url}`: {e}")))?
+            .bytes()
+            .await
+            .map_err(|e| Error::web(anyhow!("unable to fetch photo `{photo_url}`: {e}")))?;
+        self.record(&Exchange::bytes("GET", photo_url.as_str(), 200, &photo));
+
+        Ok(Some(photo.to_vec()))
+    }
+}
+
+/// Returns the URL of the uploaded photo on a Community Builder profile page.
+///
+/// The placeholder shown for users without a photo is ignored.
+fn profile_photo_url(page: &Page) -> Option<String> {
+    let document = Document::from(page.as_ref());
+    let src = document
+        .find(And(Name("img"), Class("cbFullPict")))
+        .next()?
+        .attr("src")?;
+
+    (!src.contains("nophoto")).then(|| src.to_string())
+}
+
+#[async_trait]
+impl EventSource for Web {
+    async fn read_events(&self, filter: &EventFilter) -> Result<Vec<Event>, Error> {
+        let events = filter.apply(self.fetch_events().await?);
+        self.fetch_events_details(events).await
+    }
+}
+
+#[async_trait]
+impl UserSource for Web {
+    async fn read_users(&self) -> Result<Vec<User>, Error> {
+        self.fetch_users().await
     }
 }
 
@@ -144,19 +666,106 @@ impl AsRef<str> for Page {
 }
 
 impl Page {
+    /// Returns true if the page was served to a logged out user.
+    fn is_logged_out(&self) -> bool {
+        LOGGED_OUT_MARKERS
+            .iter()
+            .all(|marker| self.0.contains(marker))
+    }
+}
+
+/// Fetches a page.  If the page was served to a logged out user, logs in and fetches it once more.
+async fn fetch_with_relogin<F, FFut, L, LFut>(
+    url: &str,
+    fetch: F,
+    login: L,
+) -> Result<PageResponse, Error>
+where
+    F: Fn() -> FFut,
+    FFut: Future<Output = Result<PageResponse, Error>>,
+    L: FnOnce() -> LFut,
+    LFut: Future<Output = Result<(), Error>>,
+{
+    let rsp = fetch().await?;
+    if !rsp.page.is_logged_out() {
+        return Ok(rsp);
+    }
+
+    login().await?;
+
+    let rsp = fetch().await?;
+    if rsp.page.is_logged_out() {
+        Err(Error::web(anyhow!(
+            "unable to fetch page `{url}`: logged out after logging in again"
+        )))
+    } else {
+        Ok(rsp)
+    }
+}
+
+/// Returns true if the fetch failure may succeed if retried.
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_timeout()
+        || e.is_connect()
+        || e.status().is_some_and(|status| {
+            status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+        })
+}
+
+/// A fetched page and its cache validators
+struct PageResponse {
+    page: Page,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// The page is the cached page
+    not_modified: bool,
+}
+
+impl PageResponse {
     async fn from_url(
         client: &reqwest::Client,
         url: &str,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let rsp = client.get(url).send().await?;
-        let text = rsp.text().await?;
+        cached: Option<&CachedPage>,
+    ) -> reqwest::Result<Self> {
+        let mut req = client.get(url);
+        if let Some(cached) = cached {
+            if let Some(ref etag) = cached.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(ref last_modified) = cached.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let rsp = req.send().await?.error_for_status()?;
 
-        Ok(Self(text))
+        let header = |name| {
+            rsp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        match cached {
+            Some(cached) if rsp.status() == StatusCode::NOT_MODIFIED => Ok(Self {
+                page: Page(cached.body.clone()),
+                etag: etag.or_else(|| cached.etag.clone()),
+                last_modified: last_modified.or_else(|| cached.last_modified.clone()),
+                not_modified: true,
+            }),
+            _ => Ok(Self {
+                page: Page(rsp.text().await?),
+                etag,
+                last_modified,
+                not_modified: false,
+            }),
+        }
     }
 }
 
 impl TryFrom<(Event, Page, DateTime<Utc>)> for Event {
-    type Error = Box<dyn std::error::Error>;
+    type Error = Error;
 
     fn try_from(event_page_timestamp: (Event, Page, DateTime<Utc>)) -> Result<Self, Self::Error> {
         let (event_item, page, timestamp) = event_page_timestamp;
@@ -166,6 +775,10 @@ impl TryFrom<(Event, Page, DateTime<Utc>)> for Event {
         let url = event_item.url;
         let start_date = event_item.start_date;
         let end_date = event_item.end_date;
+        let time = event_item.time;
+        let recurrence = event_item.recurrence;
+        let recurring_parent = event_item.recurring_parent;
+        let category = event_item.category;
         let location = event_item.location;
         let description = event_item.description;
 
@@ -225,6 +838,7 @@ impl TryFrom<(Event, Page, DateTime<Utc>)> for Event {
                     name,
                     count,
                     comment,
+                    email: None,
                 }
             })
             .collect();
@@ -242,6 +856,10 @@ impl TryFrom<(Event, Page, DateTime<Utc>)> for Event {
             url,
             start_date,
             end_date,
+            time,
+            recurrence,
+            recurring_parent,
+            category,
             location,
             description,
             comments,
@@ -253,7 +871,136 @@ impl TryFrom<(Event, Page, DateTime<Utc>)> for Event {
     }
 }
 
-use serde::Serialize;
+/// Returns the absolute URL of the registration page linked from an event page.
+fn registration_url(page: &Page) -> Option<String> {
+    let href = Document::from(page.as_ref())
+        .find(Descendant(Class("ohanah-registration-link"), Name("a")))
+        .find_map(|node| node.attr("href").map(str::to_string))?;
+
+    reqwest::Url::parse(SITE_URL)
+        .and_then(|url| url.join(&href))
+        .ok()
+        .map(String::from)
+}
+
+/// An HTML form populated with its default values.
+#[derive(Debug, PartialEq, Eq)]
+struct Form {
+    action: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Form {
+    /// Finds the first form on the page that contains the named field.
+    fn find(page: &Page, field: &str) -> Option<Self> {
+        Document::from(page.as_ref())
+            .find(Name("form"))
+            .map(|form| {
+                let mut fields = Vec::new();
+                for node in form.find(Attr("name", ())) {
+                    let name = node.attr("name").unwrap_or_default().to_string();
+                    let value = match node.name() {
+                        Some("input") => {
+                            let checkable = matches!(node.attr("type"), Some("checkbox" | "radio"));
+                            let skip = matches!(node.attr("type"), Some("submit" | "button"))
+                                || (checkable && node.attr("checked").is_none());
+                            if skip {
+                                continue;
+                            }
+                            node.attr("value").unwrap_or_default().to_string()
+                        }
+                        Some("textarea") => node.text(),
+                        Some("select") => {
+                            let mut options = node.find(Name("option"));
+                            let selected = node
+                                .find(And(Name("option"), Attr("selected", ())))
+                                .next()
+                                .or_else(|| options.next());
+                            selected
+                                .map(|option| {
+                                    option
+                                        .attr("value")
+                                        .map(str::to_string)
+                                        .unwrap_or_else(|| option.text())
+                                })
+                                .unwrap_or_default()
+                        }
+                        _ => continue,
+                    };
+                    fields.push((name, value));
+                }
+
+                Self {
+                    action: form.attr("action").unwrap_or_default().to_string(),
+                    fields,
+                }
+            })
+            .find(|form| form.fields.iter().any(|(name, _)| name == field))
+    }
+
+    /// Sets the value of a field, adding the field if it does not exist.
+    fn set(&mut self, name: &str, value: &str) {
+        match self.fields.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.fields.push((name.to_string(), value.to_string())),
+        }
+    }
+}
+
+/// The Komento (comment system) configuration of an event page.
+#[derive(Debug, PartialEq, Eq)]
+struct Komento {
+    /// The URL of AJAX requests
+    base_url: String,
+    /// The anti-CSRF token.  Sent as the name of a parameter.
+    token: String,
+    page_item_id: String,
+}
+
+impl TryFrom<&Page> for Komento {
+    type Error = anyhow::Error;
+
+    fn try_from(page: &Page) -> Result<Self, Self::Error> {
+        let text = page.as_ref();
+
+        let base_url = text
+            .split_once("baseUrl: '")
+            .and_then(|(_, rest)| rest.split_once('\''))
+            .map(|(base_url, _)| base_url.to_string())
+            .ok_or_else(|| anyhow!("unable to find Komento base URL"))?;
+
+        let token = text
+            .split_once("ajax: {")
+            .and_then(|(_, rest)| rest.split_once("data: {"))
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(token, _)| token.to_string())
+            .filter(|token| token.chars().all(|c| c.is_ascii_alphanumeric()))
+            .ok_or_else(|| anyhow!("unable to find Komento token"))?;
+
+        let page_item_id = Document::from(text)
+            .find(And(Name("input"), Attr("name", "pageItemId")))
+            .find_map(|node| node.attr("value").map(str::to_string))
+            .ok_or_else(|| anyhow!("unable to find Komento page item ID"))?;
+
+        Ok(Self {
+            base_url,
+            token,
+            page_item_id,
+        })
+    }
+}
+
+/// Returns the name of the logged in user from the comment form of an event page.
+fn account_name(page: &Page) -> Option<String> {
+    Document::from(page.as_ref())
+        .find(Descendant(Class("kmt-form-author"), Class("kmt-author")))
+        .next()
+        .map(|node| node.text().trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+use serde::{Deserialize, Serialize};
 #[derive(Serialize)]
 pub struct EventList(Vec<Event>);
 
@@ -264,19 +1011,84 @@ impl EventList {
 }
 
 impl TryFrom<Page> for EventList {
-    type Error = Box<dyn std::error::Error>;
+    type Error = Error;
 
     fn try_from(page: Page) -> Result<Self, Self::Error> {
-        let events = serde_json::from_str::<Vec<Event>>(page.as_ref())?.tap_mut(|events| {
-            events
-                .iter_mut()
-                .for_each(|event| event.url = [SITE_URL, &event.url].join(""))
-        });
+        let recurrences =
+            serde_json::from_str::<Vec<RecurrenceItem>>(page.as_ref()).map_err(Error::parse)?;
+        let events = serde_json::from_str::<Vec<Event>>(page.as_ref())
+            .map_err(Error::parse)?
+            .tap_mut(|events| {
+                events
+                    .iter_mut()
+                    .zip(recurrences)
+                    .for_each(|(event, recurrence)| {
+                        event.url = [SITE_URL, &event.url].join("");
+                        event.time = parse_event_time(event);
+                        event.recurrence = recurrence.recurrence();
+                        event.recurring_parent = recurrence.recurring_parent();
+                    })
+            });
 
         Ok(Self(events))
     }
 }
 
+/// The recurrence fields of an SCMA JSON event
+///
+/// The SCMA website (Ohanah) creates an event for each occurrence of a recurring event.  The first
+/// event has the recurrence rule.  The others reference the first event.
+#[derive(Deserialize)]
+struct RecurrenceItem {
+    #[serde(rename = "isRecurring", default)]
+    is_recurring: String,
+    #[serde(rename = "everyNumber", default)]
+    every_number: String,
+    #[serde(rename = "everyWhat", default)]
+    every_what: String,
+    #[serde(rename = "endOnDate", default)]
+    end_on_date: String,
+    #[serde(rename = "endAfterNumber", default)]
+    end_after_number: String,
+    #[serde(rename = "recurringParent", default)]
+    recurring_parent: String,
+}
+
+impl RecurrenceItem {
+    fn recurrence(&self) -> Option<Recurrence> {
+        if self.is_recurring != "1" {
+            return None;
+        }
+        let frequency = match self.every_what.as_str() {
+            "day" => Frequency::Daily,
+            "week" => Frequency::Weekly,
+            "month" => Frequency::Monthly,
+            "year" => Frequency::Yearly,
+            _ => return None,
+        };
+        let interval = self.every_number.parse().ok().filter(|n| *n > 0)?;
+        let count = self.end_after_number.parse().ok().filter(|n| *n > 0);
+        // Unset dates are 0000-00-00 or the Unix epoch
+        let until = self
+            .end_on_date
+            .parse::<NaiveDate>()
+            .ok()
+            .filter(|date| date.year() > 1970);
+
+        Some(Recurrence {
+            frequency,
+            interval,
+            until,
+            count,
+        })
+    }
+
+    fn recurring_parent(&self) -> Option<String> {
+        let id = self.recurring_parent.trim();
+        (!id.is_empty() && id != "0").then(|| id.to_string())
+    }
+}
+
 impl FromIterator<Event> for EventList {
     fn from_iter<I: IntoIterator<Item = Event>>(iter: I) -> Self {
         Self(Vec::from_iter(iter))
@@ -287,16 +1099,15 @@ impl FromIterator<Event> for EventList {
 pub struct Users(Vec<User>);
 
 impl TryFrom<Page> for Users {
-    type Error = Box<dyn std::error::Error>;
+    type Error = Error;
 
     fn try_from(page: Page) -> Result<Self, Self::Error> {
-        use serde::Deserialize;
         #[derive(Deserialize)]
         struct Data {
             users: Vec<User>,
         }
 
-        let mut data: Data = serde_json::from_str::<Data>(page.as_ref())?;
+        let mut data: Data = serde_json::from_str::<Data>(page.as_ref()).map_err(Error::parse)?;
         data.users.iter_mut().for_each(|user| {
             user.phone = user.phone.as_ref().map(normalize_phone_number);
             user.email = normalize_email(&user.email);
@@ -306,6 +1117,173 @@ impl TryFrom<Page> for Users {
     }
 }
 
+/// Returns the time of a single-day event parsed from its description.
+///
+/// The first time (e.g. "7:30 PM") or time range (e.g. "7-9:30pm" or "8:00 a.m. to 1:00 p.m.")
+/// with an AM/PM is used.  A time without an end time lasts DEFAULT_EVENT_HOURS.  Multi-day events
+/// and events without a time in the description are all-day.
+fn parse_event_time(event: &Event) -> Option<EventTime> {
+    if event.start_date != event.end_date {
+        return None;
+    }
+
+    let chars: Vec<char> = strip_html(&event.description)
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    (0..chars.len()).find_map(|i| {
+        let is_boundary = i == 0
+            || (!matches!(chars[i - 1], '0'..='9' | ':' | '/' | '.' | '-' | '$')
+                && !chars[i - 1].is_alphabetic());
+        if !is_boundary || is_qualified(&chars[..i]) {
+            return None;
+        }
+        parse_time_range(&chars, i)
+    })
+}
+
+/// Returns true if the text before a time ends with a TIME_QUALIFIERS word.
+fn is_qualified(before: &[char]) -> bool {
+    let before: String = before.iter().collect();
+    let word = before
+        .trim_end()
+        .rsplit(|c: char| !c.is_alphabetic())
+        .next()
+        .unwrap_or_default();
+    TIME_QUALIFIERS.contains(&word)
+}
+
+/// A time in 12-hour format
+#[derive(Clone, Copy)]
+struct ClockTime {
+    hour: u32,
+    minute: u32,
+    pm: Option<bool>,
+}
+
+impl ClockTime {
+    fn to_naive_time(self, pm: bool) -> Option<NaiveTime> {
+        NaiveTime::from_hms_opt(self.hour % 12 + if pm { 12 } else { 0 }, self.minute, 0)
+    }
+}
+
+/// Parses a time range (or a single time) starting at index i.
+///
+/// A time without an AM/PM takes the AM/PM of the other time of the range such that the range
+/// does not end before it starts (e.g. "11-1pm" is 11 AM to 1 PM).
+fn parse_time_range(chars: &[char], i: usize) -> Option<EventTime> {
+    let (start, i) = parse_clock_time(chars, i)?;
+    let end = parse_time_separator(chars, i).and_then(|i| parse_clock_time(chars, i));
+
+    let (start, end) = match (start.pm, end) {
+        (Some(pm), Some((end, _))) => {
+            let start = start.to_naive_time(pm)?;
+            let end = match end.pm {
+                Some(end_pm) => end.to_naive_time(end_pm)?,
+                None => end
+                    .to_naive_time(pm)
+                    .filter(|end| *end > start)
+                    .or_else(|| end.to_naive_time(!pm))?,
+            };
+            (start, end)
+        }
+        (None, Some((end, _))) => {
+            let end_pm = end.pm?;
+            let end = end.to_naive_time(end_pm)?;
+            let start = start
+                .to_naive_time(end_pm)
+                .filter(|start| *start < end)
+                .or_else(|| start.to_naive_time(!end_pm))?;
+            (start, end)
+        }
+        (Some(pm), None) => {
+            let start = start.to_naive_time(pm)?;
+            (start, start + Duration::hours(DEFAULT_EVENT_HOURS))
+        }
+        (None, None) => return None,
+    };
+
+    Some(EventTime { start, end })
+}
+
+/// Parses a time (e.g. "7", "7:30", "7:30 PM", or "7:30p.m.") starting at index i.
+///
+/// Returns the time and the index after it.
+fn parse_clock_time(chars: &[char], mut i: usize) -> Option<(ClockTime, usize)> {
+    let digit = |i: usize| chars.get(i).and_then(|c| c.to_digit(10));
+
+    let mut hour = digit(i)?;
+    i += 1;
+    if let Some(d) = digit(i) {
+        hour = hour * 10 + d;
+        i += 1;
+    }
+    if digit(i).is_some() || !(1..=12).contains(&hour) {
+        return None;
+    }
+
+    let mut minute = 0;
+    if chars.get(i) == Some(&':') {
+        minute = digit(i + 1)? * 10 + digit(i + 2)?;
+        i += 3;
+        if digit(i).is_some() || minute >= 60 {
+            return None;
+        }
+    }
+
+    let mut j = skip_spaces(chars, i);
+    let pm = match chars.get(j) {
+        Some(&c @ ('a' | 'p')) => {
+            let meridiem_end = match (chars.get(j + 1), chars.get(j + 2), chars.get(j + 3)) {
+                (Some('m'), _, _) => Some(j + 2),
+                (Some('.'), Some('m'), Some('.')) => Some(j + 4),
+                (Some('.'), Some('m'), _) => Some(j + 3),
+                _ => None,
+            };
+            meridiem_end
+                .filter(|&end| !chars.get(end).is_some_and(char::is_ascii_alphabetic))
+                .map(|end| {
+                    j = end;
+                    c == 'p'
+                })
+        }
+        _ => None,
+    };
+    if pm.is_some() {
+        i = j;
+    }
+
+    Some((ClockTime { hour, minute, pm }, i))
+}
+
+/// Parses a time range separator (e.g. "-" or " to ") starting at index i.
+///
+/// Returns the index after it.
+fn parse_time_separator(chars: &[char], i: usize) -> Option<usize> {
+    let i = skip_spaces(chars, i);
+    let rest: String = chars[i..].iter().take(6).collect();
+    let len = ["-", "–", "—", "to", "until"]
+        .iter()
+        .find(|separator| {
+            rest.starts_with(*separator)
+                && !rest[separator.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic())
+        })?
+        .chars()
+        .count();
+
+    Some(skip_spaces(chars, i + len))
+}
+
+fn skip_spaces(chars: &[char], mut i: usize) -> usize {
+    while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
 fn normalize_phone_number<S>(phone_number: S) -> String
 where
     S: AsRef<str>,
@@ -325,6 +1303,7 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::model::event;
 
     use chrono::TimeZone;
 
@@ -338,6 +1317,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn profile_photo_url() {
+        let page = Page(
+            r#"<div class="cbProfile"><img src="/images/comprofiler/527_5f1a.jpg" class="cbImgPict cbFullPict img-thumbnail" alt="John Doe"></div>"#
+                .to_string(),
+        );
+        assert_eq!(
+            super::profile_photo_url(&page),
+            Some("/images/comprofiler/527_5f1a.jpg".to_string())
+        );
+
+        let page = Page(
+            r#"<img src="/components/com_comprofiler/plugin/templates/default/images/avatar/nophoto_n.png" class="cbImgPict cbFullPict">"#
+                .to_string(),
+        );
+        assert_eq!(super::profile_photo_url(&page), None);
+    }
+
     fn path_to_input(filename: &str) -> PathBuf {
         [env!("CARGO_MANIFEST_DIR"), "test", "inputs", filename]
             .iter()
@@ -349,22 +1346,149 @@ mod test {
         let path = path_to_input("event-527.html");
         let page = Page::from_file(path).unwrap();
         let event_item = Event {
-            id: "527".into(),
-            title: "a title".into(),
-            url: "a url".into(),
-            start_date: "2022-01-14".parse().unwrap(),
             end_date: "2022-01-17".parse().unwrap(),
-            location: "a location".into(),
-            description: "a description".into(),
-            comments: None,
-            attendees: None,
-            timestamp: None,
+            ..event("527", "2022-01-14")
         };
         let timestamp = Utc.timestamp_opt(0, 0).unwrap();
         let event = Event::try_from((event_item, page, timestamp)).unwrap();
         insta::assert_yaml_snapshot!(event);
     }
 
+    #[test]
+    fn is_logged_out() {
+        let path = path_to_input("event-527.html");
+        let page = Page::from_file(path).unwrap();
+        assert!(!page.is_logged_out());
+
+        let path = path_to_input("login.html");
+        let page = Page::from_file(path).unwrap();
+        assert!(page.is_logged_out());
+
+        // A change password form on a page served to a logged in user
+        let page = Page(
+            r#"<form id="cbcheckedadminForm">
+                <input type="password" name="passwd" autocomplete="current-password">
+                <input type="password" name="password" autocomplete="new-password">
+                <input type="password" name="password__verify" autocomplete="new-password">
+            </form>"#
+                .into(),
+        );
+        assert!(!page.is_logged_out());
+    }
+
+    #[test]
+    fn fetch_with_relogin() {
+        let login_page = std::fs::read_to_string(path_to_input("login.html")).unwrap();
+        let event_page = std::fs::read_to_string(path_to_input("event-527.html")).unwrap();
+        let response = |page: &str| PageResponse {
+            page: Page(page.to_string()),
+            etag: None,
+            last_modified: None,
+            not_modified: false,
+        };
+        let fetch_with_relogin = |pages: Vec<&str>| {
+            let pages = std::sync::Mutex::new(pages);
+            let logins = std::cell::Cell::new(0);
+            let fetch = || std::future::ready(Ok(response(pages.lock().unwrap().remove(0))));
+            let login = || {
+                logins.set(logins.get() + 1);
+                std::future::ready(Ok(()))
+            };
+            let rsp = futures::executor::block_on(super::fetch_with_relogin("a url", fetch, login));
+            (
+                rsp.map(|rsp| rsp.page.0),
+                logins.get(),
+                pages.into_inner().unwrap().len(),
+            )
+        };
+
+        // Logged in
+        let (rsp, logins, unfetched) = fetch_with_relogin(vec![&event_page]);
+        assert_eq!(rsp.unwrap(), event_page);
+        assert_eq!((logins, unfetched), (0, 0));
+
+        // Session expired
+        let (rsp, logins, unfetched) = fetch_with_relogin(vec![&login_page, &event_page]);
+        assert_eq!(rsp.unwrap(), event_page);
+        assert_eq!((logins, unfetched), (1, 0));
+
+        // Still logged out after logging in again.  Logs in once.
+        let (rsp, logins, unfetched) =
+            fetch_with_relogin(vec![&login_page, &login_page, &event_page]);
+        assert!(rsp.is_err());
+        assert_eq!((logins, unfetched), (1, 1));
+    }
+
+    #[test]
+    fn account_name() {
+        let path = path_to_input("event-527.html");
+        let page = Page::from_file(path).unwrap();
+        assert_eq!(super::account_name(&page), Some("Rob Donnelly".to_string()));
+    }
+
+    #[test]
+    fn registration_url() {
+        let path = path_to_input("event-527.html");
+        let page = Page::from_file(path).unwrap();
+        assert_eq!(
+            super::registration_url(&page).as_deref(),
+            Some("https://www.rockclimbing.org/index.php/event-list/events-list/joshua-tree-g-36/registration")
+        );
+    }
+
+    #[test]
+    fn komento() {
+        let path = path_to_input("event-527.html");
+        let page = Page::from_file(path).unwrap();
+        assert_eq!(
+            Komento::try_from(&page).unwrap(),
+            Komento {
+                base_url:
+                    "https://www.rockclimbing.org/index.php?option=com_komento&lang=none&Itemid=661"
+                        .into(),
+                token: "746559fc77b6fb6fe64a8e2ce485f82a".into(),
+                page_item_id: "661".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn form() {
+        let page = Page(
+            r#"
+            <form action="/search"><input name="q" value=""></form>
+            <form action="/register" method="post">
+              <input type="hidden" name="_token" value="abc">
+              <select name="number_of_tickets">
+                <option value="1">1</option>
+                <option value="2" selected>2</option>
+              </select>
+              <input type="checkbox" name="newsletter" value="1">
+              <textarea name="comment">hi</textarea>
+              <input type="submit" name="save" value="Register">
+            </form>
+            "#
+            .into(),
+        );
+        let mut form = Form::find(&page, "number_of_tickets").unwrap();
+        assert_eq!(form.action, "/register");
+        form.set("number_of_tickets", "3");
+        let fields: Vec<(&str, &str)> = form
+            .fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("_token", "abc"),
+                ("number_of_tickets", "3"),
+                ("comment", "hi")
+            ]
+        );
+        assert!(Form::find(&page, "missing").is_none());
+    }
+
     #[test]
     fn parse_event_list_json() {
         let path = path_to_input("events-list.json");
@@ -383,6 +1507,98 @@ mod test {
         insta::assert_yaml_snapshot!(users);
     }
 
+    #[test]
+    fn parse_event_time() {
+        let event_time = |description: &str| {
+            let event = Event {
+                description: description.into(),
+                ..event("527", "2022-01-14")
+            };
+            super::parse_event_time(&event).map(|time| {
+                format!(
+                    "{}-{}",
+                    time.start.format("%H:%M"),
+                    time.end.format("%H:%M")
+                )
+            })
+        };
+
+        assert_eq!(
+            event_time("<p>The meeting begins at 7:30 PM, with the presentation</p>"),
+            Some("19:30-21:30".into())
+        );
+        assert_eq!(
+            event_time("Coffee and pastries 8-9am, Idyllwild Town Hall."),
+            Some("08:00-09:00".into())
+        );
+        assert_eq!(event_time("Time: 3PM - 7PM"), Some("15:00-19:00".into()));
+        assert_eq!(
+            event_time("Time: 8:00 a.m. until 4:00 p.m. (meet at the parking lot)"),
+            Some("08:00-16:00".into())
+        );
+        assert_eq!(
+            event_time("When: Sunday, June 4th, 2017 @ 8:00 am – 1:00 pm"),
+            Some("08:00-13:00".into())
+        );
+        assert_eq!(
+            event_time("Climbing from 8:00 AM to 2:00, followed by a potluck"),
+            Some("08:00-14:00".into())
+        );
+        assert_eq!(
+            event_time("Held on March 10th from 7-9:30pm."),
+            Some("19:00-21:30".into())
+        );
+        assert_eq!(
+            event_time("Doors open at 6:30 and show at 7 pm.&nbsp; Parking after 6:00 pm"),
+            Some("19:00-21:00".into())
+        );
+        assert_eq!(event_time("Free parking in the structure after 6 PM"), None);
+        assert_eq!(event_time("Meet 03-27-19 at 10 in the lot"), None);
+        assert_eq!(event_time("7 amazing routes"), None);
+        assert_eq!(event_time("no times here"), None);
+    }
+
+    #[test]
+    fn parse_event_time_multi_day() {
+        let event = Event {
+            end_date: "2022-01-17".parse().unwrap(),
+            description: "Meet at 7:30 AM".into(),
+            ..event("527", "2022-01-14")
+        };
+        assert_eq!(super::parse_event_time(&event), None);
+    }
+
+    #[test]
+    fn recurrence_item() {
+        let item = |json: &str| serde_json::from_str::<RecurrenceItem>(json).unwrap();
+
+        let parent = item(
+            r#"{"isRecurring": "1", "everyNumber": "1", "everyWhat": "week", "endOnDate": "2022-08-30", "endAfterNumber": "0", "recurringParent": "0"}"#,
+        );
+        assert_eq!(
+            parent.recurrence(),
+            Some(Recurrence {
+                frequency: Frequency::Weekly,
+                interval: 1,
+                until: Some("2022-08-30".parse().unwrap()),
+                count: None,
+            })
+        );
+        assert_eq!(parent.recurring_parent(), None);
+
+        let child = item(
+            r#"{"isRecurring": "0", "everyNumber": "0", "everyWhat": "month", "endOnDate": "1970-01-01", "endAfterNumber": "0", "recurringParent": "527"}"#,
+        );
+        assert_eq!(child.recurrence(), None);
+        assert_eq!(child.recurring_parent(), Some("527".to_string()));
+
+        let count = item(
+            r#"{"isRecurring": "1", "everyNumber": "2", "everyWhat": "month", "endOnDate": "1970-01-01", "endAfterNumber": "6", "recurringParent": "0"}"#,
+        );
+        assert_eq!(count.recurrence().unwrap().count, Some(6));
+        assert_eq!(count.recurrence().unwrap().until, None);
+    }
+
     #[test]
     fn normalize_phone_number() {
         let phone_numbers = vec![
@@ -0,0 +1,64 @@
+use crate::filter::EventFilter;
+use crate::input::{EventSource, UserSource};
+use crate::model::{Event, User};
+use crate::Error;
+
+use async_trait::async_trait;
+
+/// Reads events or users from YAML (e.g. as written by the YAML output).
+pub struct YamlInput {
+    yaml: String,
+}
+
+impl YamlInput {
+    pub fn new(yaml: impl Into<String>) -> Self {
+        Self { yaml: yaml.into() }
+    }
+}
+
+#[async_trait]
+impl EventSource for YamlInput {
+    async fn read_events(&self, filter: &EventFilter) -> Result<Vec<Event>, Error> {
+        let events = serde_yaml::from_str(&self.yaml).map_err(Error::parse)?;
+        Ok(filter.apply(events))
+    }
+}
+
+#[async_trait]
+impl UserSource for YamlInput {
+    async fn read_users(&self) -> Result<Vec<User>, Error> {
+        serde_yaml::from_str(&self.yaml).map_err(Error::parse)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_events() {
+        let yaml = YamlInput::new(
+            r#"
+- id: "1"
+  title: Joshua Tree
+  url: https://www.rockclimbing.org/1
+  start_date: 2022-01-14
+  end_date: 2022-01-16
+  location: Joshua Tree
+  description: ""
+- id: "2"
+  title: Red Rocks
+  url: https://www.rockclimbing.org/2
+  start_date: 2022-02-11
+  end_date: 2022-02-13
+  location: Red Rocks
+  description: ""
+"#,
+        );
+        let filter = EventFilter::new().exclude_ids(["1"]);
+
+        let events = yaml.read_events(&filter).await.unwrap();
+        let ids: Vec<&str> = events.iter().map(|event| event.id.as_str()).collect();
+        assert_eq!(ids, ["2"]);
+    }
+}
@@ -4,10 +4,51 @@ use hyper_util::client::legacy::connect::HttpConnector;
 // For hyper connections
 pub(crate) type Connector = HttpsConnector<HttpConnector>;
 
+mod audit;
+mod backup;
+mod changelog;
+mod error;
+mod filter;
+mod fs;
 mod input;
+mod lock;
 mod model;
 mod output;
+mod plan;
+mod redact;
+mod schedule;
+mod secret;
+mod state;
+mod summary;
 
-pub use input::Web;
-pub use model::{DateSelect, Event};
-pub use output::{GAuth, GCal, GPpl};
+pub use audit::AuditLog;
+pub use backup::Backup;
+pub use changelog::{Change, Changelog, ChangelogEntry, EventSnapshot};
+pub use error::Error;
+pub use filter::{DuplicateEvent, EventFilter, UserFilter};
+pub use fs::write_atomic;
+pub use input::{
+    keyring_get, keyring_set, Credentials, EventSource, Fixtures, JsonInput, PageCache, UserSource,
+    Web, YamlInput,
+};
+pub use lock::RunLock;
+pub use model::{
+    sort_events, sort_users, DateSelect, Event, EventTime, Frequency, MemberStatus, Recurrence,
+    User, DEFAULT_TIMEZONE,
+};
+pub use output::{
+    events_to_csv, events_to_ics, events_to_markdown, forget_invitee, forget_recipient,
+    users_to_csv, users_to_vcf, AclReportEntry, AclReportStatus, Archive, CachedEvent,
+    CalendarBackup, CalendarRoute, CalendarSelect, ContactDiscrepancy, ContactDrift, ContactField,
+    ContactsBackup, DescriptionInclude, DescriptionTemplate, DiscordWebhook, EventCache,
+    EventColor, EventDrift, EventField, EventOptions, EventReminder, EventSink, GAuth, GCal, GPpl,
+    GSheets, Inviter, Lang, Mailchimp, MembershipRule, OrphanAction, PushedState, Reminder,
+    ReminderMethod, Secret, SlackWebhook, SmtpConfig, StaleContactAction, TelegramBot, TokenStore,
+    TwilioConfig, UserSink, DEFAULT_SUMMARY_FORMAT,
+};
+pub use plan::{Plan, PlannedWrite};
+pub use redact::{Redact, RedactedUser};
+pub use schedule::Schedule;
+pub use secret::SecretRef;
+pub use state::State;
+pub use summary::{Phase, PhaseTiming, Summary};
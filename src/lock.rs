@@ -0,0 +1,105 @@
+use crate::Error;
+
+use anyhow::{anyhow, Context};
+use tracing::info;
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// An exclusive lock that prevents overlapping runs (e.g. a cron run that starts while the
+/// previous run is still going).
+///
+/// Uses an OS file lock that is released when the process exits, even if it crashes, so a lock
+/// left behind by a dead process is stale and is taken over.  The lock file holds the process
+/// ID of the holder for diagnostics.  The lock is released when dropped.
+#[derive(Debug)]
+pub struct RunLock {
+    path: PathBuf,
+    file: File,
+}
+
+impl RunLock {
+    /// Acquires the lock or returns an error if another run holds it.
+    pub fn acquire(path: &Path) -> Result<Self, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("unable to open lock file `{}`", path.display()))
+            .map_err(Error::file)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut holder = String::new();
+                let _ = file.read_to_string(&mut holder);
+                return Err(Error::file(anyhow!(
+                    "another run (process {}) holds the lock file `{}`",
+                    holder.trim(),
+                    path.display()
+                )));
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(Error::file(
+                    anyhow::Error::new(e)
+                        .context(format!("unable to lock lock file `{}`", path.display())),
+                ))
+            }
+        }
+
+        let mut stale = String::new();
+        let _ = file.read_to_string(&mut stale);
+        if !stale.trim().is_empty() {
+            info!(lock_file=?path, process=%stale.trim(), "Took over stale lock file");
+        }
+
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| write!(file, "{}", std::process::id()))
+            .and_then(|()| file.flush())
+            .with_context(|| format!("unable to write lock file `{}`", path.display()))
+            .map_err(Error::file)?;
+        info!(lock_file=?path, "Acquired lock file");
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        // Clears the process ID so that the lock file is not reported as stale.  The OS lock is
+        // released when the file is closed.
+        let _ = self.file.set_len(0);
+        info!(lock_file=?self.path, "Released lock file");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn acquire() {
+        let path = std::env::temp_dir().join(format!("scma-gsync-{}.lock", std::process::id()));
+
+        let lock = RunLock::acquire(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        assert!(RunLock::acquire(&path).is_err());
+
+        drop(lock);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        let lock = RunLock::acquire(&path).unwrap();
+
+        drop(lock);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,40 +1,399 @@
-use scma_gsync::{DateSelect, Event, GAuth, GCal, GPpl, Web};
+use scma_gsync::{
+    events_to_csv, events_to_ics, events_to_markdown, forget_invitee, forget_recipient,
+    keyring_get, keyring_set, sort_events, sort_users, users_to_csv, users_to_vcf, write_atomic,
+    Archive, AuditLog, Backup, CalendarRoute, CalendarSelect, Changelog, Credentials, DateSelect,
+    DescriptionInclude, DescriptionTemplate, DiscordWebhook, Error, Event, EventFilter,
+    EventOptions, EventReminder, EventSink, EventSnapshot, EventSource, Fixtures, GAuth, GCal,
+    GPpl, GSheets, Inviter, JsonInput, Lang, Mailchimp, MemberStatus, MembershipRule, OrphanAction,
+    PageCache, Phase, Plan, Redact, RedactedUser, Reminder, RunLock, Schedule, Secret, SecretRef,
+    SlackWebhook, SmtpConfig, StaleContactAction, State, Summary, TelegramBot, TokenStore,
+    TwilioConfig, User, UserFilter, UserSink, UserSource, Web, YamlInput, DEFAULT_SUMMARY_FORMAT,
+};
 
 use anyhow::Context;
-use clap::{Parser, ValueEnum};
+use async_trait::async_trait;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use chrono_tz::Tz;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{generate, Shell};
 use futures::{stream, StreamExt, TryStreamExt};
-use tracing::info;
+use indexmap::IndexMap;
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
 use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 const CONCURRENT_REQUESTS: usize = 3;
+const DAEMON_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60);
+const DAEMON_MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+const HEALTHCHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// The env file loaded from the current directory if --env-file is not given
+const DEFAULT_ENV_FILE: &str = ".env";
+
+#[derive(Clone, Subcommand)]
+enum Command {
+    /// Operates on SCMA events
+    #[command(subcommand)]
+    Events(EventsCommand),
+    /// Operates on SCMA users
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// Writes the Google Calendar events and ACL and the Google Contacts group members to a
+    /// timestamped backup file
+    Backup(BackupArgs),
+    /// Restores the Google Calendar events and ACL and the Google Contacts group members from a
+    /// backup file
+    Restore(RestoreArgs),
+    /// Writes shell completions or the man page to stdout
+    Completions(CompletionsArgs),
+}
+
+#[derive(Clone, Subcommand)]
+enum EventsCommand {
+    /// Syncs the SCMA events to Google Calendar
+    Sync(InputArgs),
+    /// Patches only the descriptions of existing Google Calendar events whose attendees or
+    /// comments have changed
+    ///
+    /// Re-fetches the event detail pages.  Events are never inserted.
+    Refresh,
+    /// Writes the SCMA events to a file
+    Export(ExportArgs),
+    /// Registers for an SCMA event
+    Rsvp(RsvpArgs),
+    /// Posts a comment to an SCMA event
+    Comment(CommentArgs),
+    /// Reports synced Google Calendar events that were edited in Google Calendar as YAML
+    DriftReport(OutputArgs),
+}
+
+#[derive(Clone, Subcommand)]
+enum UsersCommand {
+    /// Syncs the SCMA users to the Google Calendar ACL as readers
+    SyncAcl(InputArgs),
+    /// Syncs the SCMA users to a Google Contacts group
+    SyncContacts(InputArgs),
+    /// Writes the SCMA users to a file
+    Export(UserExportArgs),
+    /// Writes the SCMA member roster to a Google Sheets spreadsheet tab
+    SyncSheet(SheetArgs),
+    /// Upserts the SCMA users into a Mailchimp audience tagged by member and trip leader status
+    SyncMailchimp(MailchimpArgs),
+    /// Reports the Google Calendar ACL cross-referenced against the SCMA users as YAML
+    AclReport(ReportArgs),
+    /// Reports Google Contacts that differ from the SCMA users as CSV
+    ContactsReport(ReportArgs),
+    /// Removes a member from the Google Calendar ACL, the Google Contacts group, and local state
+    PurgeMember(PurgeMemberArgs),
+    /// Reports synced Google Contacts that were edited in Google Contacts as YAML
+    DriftReport(OutputArgs),
+}
+
+#[derive(Clone, Args)]
+struct InputArgs {
+    /// Reads from a YAML or JSON file (`-` for stdin) instead of the SCMA website.
+    #[arg(long = "ifile")]
+    input_file: Option<PipeFile>,
+    /// The format of the --ifile file.
+    #[arg(value_enum, long = "iformat", default_value = "yaml")]
+    input_format: InputFormat,
+}
+
+#[derive(Clone, Args)]
+struct OutputArgs {
+    /// The file to write to (`-` for stdout).
+    #[arg(long = "ofile", default_value = "-")]
+    output_file: PipeFile,
+}
+
+#[derive(Clone, Args)]
+struct ExportArgs {
+    #[arg(value_enum, long, default_value = "yaml")]
+    format: ExportFormat,
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Clone, Args)]
+struct UserExportArgs {
+    #[arg(value_enum, long, default_value = "yaml")]
+    format: UserExportFormat,
+    /// Omits personal information from YAML and JSON exports (e.g. for sharing beyond the board).
+    #[arg(value_enum, long, default_value = "none")]
+    redact: RedactLevel,
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Clone, Args)]
+struct ReportArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Clone, Args)]
+struct SheetArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// The ID of the spreadsheet (from its URL).
+    ///
+    /// Example: --spreadsheet-id 1BxiMVs0XRA5nFMdKvBdBZjgmUUqptlbs74OgvE2upms
+    #[arg(long)]
+    spreadsheet_id: String,
+    /// The name of the sheet (tab) to write the roster to.  Created if it does not exist.  Its
+    /// contents are replaced.
+    #[arg(long, default_value = "Roster")]
+    sheet: String,
+}
+
+#[derive(Clone, Args)]
+struct MailchimpArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// The Mailchimp API key (e.g. abc123-us6).
+    #[arg(long, env = "MAILCHIMP_API_KEY", hide_env_values = true)]
+    mailchimp_api_key: String,
+    /// The ID of the Mailchimp audience (list) to sync to.
+    #[arg(long)]
+    mailchimp_list_id: String,
+}
+
+#[derive(Clone, Args)]
+struct PurgeMemberArgs {
+    /// The email or SCMA user ID of the member.
+    ///
+    /// A user ID is resolved to an email via the SCMA users (or --ifile).
+    member: String,
+    #[command(flatten)]
+    input: InputArgs,
+    /// Does not remove the member from the Google Calendar ACL.
+    #[arg(long)]
+    skip_acl: bool,
+    /// Does not remove the member from the Google Contacts group.
+    #[arg(long)]
+    skip_contacts: bool,
+}
+
+#[derive(Clone, Args)]
+struct BackupArgs {
+    /// The directory to write the backup file to.
+    #[arg(long = "odir", default_value = ".")]
+    output_dir: PathBuf,
+    /// Does not back up the Google Contacts group.
+    #[arg(long)]
+    skip_contacts: bool,
+}
+
+#[derive(Clone, Args)]
+struct RestoreArgs {
+    /// The backup file written by the backup command.
+    backup_file: PathBuf,
+    /// Does not restore the Google Contacts group.
+    #[arg(long)]
+    skip_contacts: bool,
+}
+
+#[derive(Clone, Args)]
+struct RsvpArgs {
+    /// The SCMA event ID to register for.
+    #[arg(long)]
+    event_id: String,
+
+    /// The number of tickets to register for (yourself plus guests).
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..))]
+    count: u8,
+}
+
+#[derive(Clone, Args)]
+struct CompletionsArgs {
+    /// The shell to write completions for or `man` for the man page.
+    #[arg(value_enum)]
+    target: CompletionTarget,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum CompletionTarget {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+    #[clap(name = "powershell")]
+    PowerShell,
+    /// A roff man page
+    Man,
+}
+
+#[derive(Clone, Args)]
+struct CommentArgs {
+    /// The SCMA event ID to comment on.
+    #[arg(long)]
+    event_id: String,
+
+    /// The comment to post.
+    #[arg(long)]
+    message: String,
+}
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
-enum DataType {
-    Events,
-    Users,
+enum ExportFormat {
+    Yaml,
+    /// An iCalendar (.ics) file of all-day events
+    Ics,
+    /// A CSV file with a column per field and a header row
+    Csv,
+    Json,
+    /// A Markdown document with a heading per event for newsletters or a wiki
+    Markdown,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
-enum InputType {
-    Web,
+enum UserExportFormat {
     Yaml,
+    /// A CSV file with a column per field and a header row
+    Csv,
+    Json,
+    /// A vCard (.vcf) file of contacts
+    Vcf,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
-enum OutputType {
-    #[clap(name = "gcal")]
+enum InputFormat {
+    Yaml,
+    Json,
+}
+
+/// Where events are written to
+enum EventOutput {
     GCal,
-    #[clap(name = "gppl")]
-    GPpl,
+    Yaml(PipeFile),
+    Ics(PipeFile),
+    Csv(PipeFile),
+    Json(PipeFile),
+    Markdown(PipeFile),
+}
+
+/// Where users are written to
+enum UserOutput {
+    Acl,
+    Contacts,
+    Sheet {
+        spreadsheet_id: String,
+        sheet: String,
+    },
+    Mailchimp {
+        api_key: String,
+        list_id: String,
+    },
+    Yaml(PipeFile, Redact),
+    Csv(PipeFile),
+    Json(PipeFile, Redact),
+    Vcf(PipeFile),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum DescriptionSection {
+    /// The attendee names and guest counts
+    Attendees,
+    /// The comments attendees leave when they RSVP
+    AttendeeComments,
+    /// The event comment thread
+    Comments,
+    /// The number of attendees (including guests) and comments in place of omitted attendees and
+    /// comments
+    Counts,
+    /// Omits all optional sections
+    None,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum RedactLevel {
+    /// Omit nothing
+    None,
+    /// Omit the contact details (phone, address, date of birth, and sex)
+    Contact,
+    /// Omit everything but the name, member status, trip leader status, and position
+    Full,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Orphans {
+    /// Leave orphaned events on the calendar as is
+    Ignore,
+    /// Prefix the summary of orphaned events with a cancelled marker
+    Cancel,
+    /// Prefix the summary of orphaned events with a cancelled marker and set their status to
+    /// cancelled.  Google Calendar hides cancelled events.
+    CancelStatus,
+    /// Delete orphaned events from the calendar
+    Delete,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum StaleContacts {
+    /// Leave stale contacts in the group as is
+    Ignore,
+    /// Delete stale contacts
+    Delete,
+    /// Move stale contacts from the group to the group given by --alumni-group
+    Move,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Language {
+    /// English
+    En,
+    /// Spanish
+    Es,
+}
+
+impl From<Language> for Lang {
+    fn from(language: Language) -> Self {
+        match language {
+            Language::En => Lang::En,
+            Language::Es => Lang::Es,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum SummaryFormat {
+    Human,
+    Json,
+    None,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per line with the fields `timestamp`, `level`, `target`, `message`, and
+    /// the fields of the event
+    Json,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum PlanFormat {
     Yaml,
+    Json,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
 enum AuthType {
     #[clap(name = "oauth")]
     OAuth,
+    #[clap(name = "oauth-device")]
+    OAuthDevice,
     ServiceAccount,
     Infer,
 }
@@ -69,51 +428,474 @@ impl From<Boolean> for bool {
     }
 }
 
-#[derive(Parser)]
+/// Parses a number of days from a string like `90d`, `12w`, or `90`.
+fn parse_days(s: &str) -> Result<Days, String> {
+    let s = s.trim();
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, 'd')) => (&s[..i], 1),
+        Some((i, 'w')) => (&s[..i], 7),
+        _ => (s, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid number of days `{s}`, expected e.g. `90d` or `12w`"))?;
+
+    let days = number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("invalid number of days `{s}`, too large"))?;
+
+    Ok(Days::new(days))
+}
+
+/// Returns today's date in the timezone.
+fn today(timezone: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&timezone).date_naive()
+}
+
+/// Returns the date the number of days after today.  Errors if the date is out of range.
+fn days_after_today(timezone: Tz, days: Days) -> anyhow::Result<NaiveDate> {
+    today(timezone)
+        .checked_add_days(days)
+        .ok_or_else(|| anyhow::anyhow!("date {days:?} after today is out of range"))
+}
+
+/// Returns the date the number of days before today.  Errors if the date is out of range.
+fn days_before_today(timezone: Tz, days: Days) -> anyhow::Result<NaiveDate> {
+    today(timezone)
+        .checked_sub_days(days)
+        .ok_or_else(|| anyhow::anyhow!("date {days:?} before today is out of range"))
+}
+
+/// A date given as is or as a number of days relative to today
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum DateArg {
+    Date(NaiveDate),
+    Days(Days),
+}
+
+/// Parses a date from a string like `2023-01-01` or a number of days like `90d` or `12w`.
+fn parse_date(s: &str) -> Result<DateArg, String> {
+    match s.trim().parse() {
+        Ok(date) => Ok(DateArg::Date(date)),
+        Err(_) => parse_days(s).map(DateArg::Days).map_err(|_| {
+            format!("invalid date `{s}`, expected e.g. `2023-01-01`, `90d`, or `12w`")
+        }),
+    }
+}
+
+/// Parses a duration from a string like `30m`, `12h`, or `1d`.
+fn parse_ttl(s: &str) -> Result<chrono::Duration, String> {
+    let s = s.trim();
+    let err = || format!("invalid age `{s}`, expected e.g. `30m`, `12h`, or `1d`");
+    let (number, unit) = match s.char_indices().last() {
+        Some((i, unit)) => (&s[..i], unit),
+        None => return Err(err()),
+    };
+    let number: i64 = number.parse().map_err(|_| err())?;
+
+    match unit {
+        'm' => Ok(chrono::Duration::minutes(number)),
+        'h' => Ok(chrono::Duration::hours(number)),
+        'd' => Ok(chrono::Duration::days(number)),
+        _ => Err(err()),
+    }
+}
+
+#[derive(Clone, Parser)]
 #[command(about, version, author)]
 struct Cli {
     /// Disables Google API methods that create, modify, or delete.
-    #[arg(short = 'n', long)]
+    #[arg(short = 'n', long, global = true)]
     dry_run: bool,
 
-    /// The data type to operate on.
-    #[arg(value_enum, default_value = "events")]
-    data_type: DataType,
+    /// Writes the Google API writes of the run (event inserts, patches, cancels, and deletes; ACL
+    /// inserts and deletes; and contact creates, updates, deletes, moves, and photo updates) to
+    /// the given file.
+    ///
+    /// Use `-` for stdout.  Combine with --dry-run to review exactly what a real run would do
+    /// without making any changes.
+    #[arg(long, global = true)]
+    plan_file: Option<PipeFile>,
+
+    /// Appends the Google API writes that were made to the given JSON Lines file.
+    ///
+    /// Each line records the time, the local user, the calendar or contact group, the write (as in
+    /// --plan-file), and summaries of the entity before and after the write.  Nothing is logged on
+    /// a dry run or for a failed write.
+    #[arg(long, global = true)]
+    audit_log: Option<PathBuf>,
 
-    #[arg(value_enum, short, long, default_value = "web")]
-    input: InputType,
-    /// The name of the input file to use for the yaml input.
-    #[arg(long = "ifile", default_value = "-")]
-    input_file: PipeFile,
+    /// The format of the plan (see --plan-file).
+    #[arg(value_enum, long, default_value = "yaml", global = true)]
+    plan_format: PlanFormat,
 
-    #[arg(value_enum, short, long, default_value = "gcal")]
-    output: OutputType,
-    /// The name of the output file to use for the yaml output.
-    #[arg(long = "ofile", default_value = "-")]
-    output_file: PipeFile,
+    /// Path to an env file to load environment variables (e.g. SCMA_USERNAME) from.
+    ///
+    /// Uses dotenv conventions (`KEY=value` per line).  Variables already set in the environment
+    /// take precedence.  If not specified, `.env` in the current directory is loaded if it
+    /// exists.
+    #[arg(long, global = true)]
+    env_file: Option<String>,
+
+    /// Path to a YAML config file that provides values for options.
+    ///
+    /// Keys are long option names (e.g. `calendar`, `calendar-owner`, `exclude-event-id`) and
+    /// values are strings, numbers, booleans (for flags), or lists (for repeatable options).
+    /// Subcommand options (e.g. `spreadsheet-id`) only apply to their subcommand.  Options given on
+    /// the command line or by environment variables take precedence over the config file.
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    /// The profile of the config file to use (e.g. `prod` or `staging`).
+    ///
+    /// The options of the profile (under `profiles.<name>` in the config file) take precedence
+    /// over the top-level options of the config file.  Allows one installation to manage multiple
+    /// calendars (e.g. a test calendar alongside the live one) with different secret files, token
+    /// files, calendar names, and contact groups.
+    #[arg(long, requires = "config", env = "SCMA_GSYNC_PROFILE", global = true)]
+    profile: Option<String>,
+
+    /// The IANA timezone of SCMA events (e.g. America/Los_Angeles).
+    ///
+    /// Used for the times of timed events, the dates of comments, the "last updated" timestamps,
+    /// and the current date (e.g. for --since and --horizon and for skipping past events).
+    #[arg(long, default_value = "America/Los_Angeles", global = true)]
+    timezone: Tz,
+
+    /// The format of the log written to stderr.
+    ///
+    /// Use `json` for ingestion by log aggregators (e.g. Loki or CloudWatch).
+    #[arg(value_enum, long, default_value = "text", global = true)]
+    log_format: LogFormat,
+
+    /// The format of the end-of-run summary printed to stderr.
+    ///
+    /// The summary includes the duration of each phase of the run (e.g. login, event detail
+    /// fetches, event writes), the number of writes by operation (e.g. event inserts, ACL
+    /// deletes, contact creates), the number of events synced and skipped as unchanged, the
+    /// number of Google API calls and retries, and the errors.
+    #[arg(value_enum, long, default_value = "human", global = true)]
+    summary_format: SummaryFormat,
+
+    /// Continues with the remaining writes if a write (e.g. an event patch or an ACL insert) fails.
+    ///
+    /// The run exits with an error that lists the failed writes at the end.
+    #[arg(long, global = true)]
+    continue_on_error: bool,
+
+    /// Writes the end-of-run summary to the given file instead of stderr.
+    #[arg(long, global = true)]
+    summary_file: Option<String>,
+
+    /// Writes Prometheus metrics of the run (e.g. the run duration, events synced, ACL inserts and
+    /// deletes, and API retries) to the given file.
+    ///
+    /// Intended for the node_exporter textfile collector (e.g.
+    /// `/var/lib/node_exporter/scma-gsync.prom`).  Written whether or not the run succeeds.
+    #[arg(long, global = true)]
+    metrics_file: Option<String>,
+
+    /// Pushes Prometheus metrics of the run to the Pushgateway at the given URL.
+    ///
+    /// Example: --metrics-push-url http://pushgateway:9091
+    #[arg(long, global = true)]
+    metrics_push_url: Option<String>,
+
+    /// The job name of the metrics pushed to the Pushgateway (see --metrics-push-url).
+    #[arg(long, default_value = "scma-gsync", global = true)]
+    metrics_job: String,
+
+    /// The healthcheck URL to ping at the start and end of each run (e.g. a healthchecks.io
+    /// check).
+    ///
+    /// Pings `<url>/start` at the start of the run, `<url>` on success, and `<url>/fail` (with the
+    /// error) on failure so that a failed or missed run triggers an alert.
+    ///
+    /// Example: --healthcheck-url https://hc-ping.com/<uuid>
+    #[arg(long, env = "HEALTHCHECK_URL", global = true)]
+    healthcheck_url: Option<String>,
+
+    /// Writes a changelog of the SCMA event changes (new, rescheduled, moved, cancelled, and
+    /// significantly changed descriptions) since the previous run to the given file.
+    ///
+    /// Use `-` for stdout.  The events of each run are recorded in the state file (see
+    /// --state-file) for comparison on the next run.  The first run reports all events as new.
+    #[arg(long, global = true)]
+    changelog_file: Option<String>,
+    /// Posts the SCMA event changes (see --changelog-file) to a Slack channel via the given
+    /// incoming webhook URL.
+    ///
+    /// Nothing is posted if there are no changes.  The first run reports all events as new.
+    #[arg(long, env = "SLACK_WEBHOOK_URL", hide_env_values = true, global = true)]
+    slack_webhook_url: Option<String>,
+    /// Posts the SCMA event changes (see --changelog-file) to a Discord channel via the given
+    /// webhook URL.
+    ///
+    /// Nothing is posted if there are no changes.  The first run reports all events as new.
+    #[arg(
+        long,
+        env = "DISCORD_WEBHOOK_URL",
+        hide_env_values = true,
+        global = true
+    )]
+    discord_webhook_url: Option<String>,
+    /// Posts the SCMA event changes (see --changelog-file) to a Telegram chat via the bot with the
+    /// given token.
+    ///
+    /// The bot must be a member of the chat (see --telegram-chat-id).  Nothing is posted if there
+    /// are no changes.  The first run reports all events as new.
+    #[arg(
+        long,
+        env = "TELEGRAM_BOT_TOKEN",
+        hide_env_values = true,
+        requires = "telegram_chat_id",
+        global = true
+    )]
+    telegram_bot_token: Option<String>,
+    /// The Telegram chat to post the SCMA event changes to (e.g. `-1001234567890` or
+    /// `@channelname`).
+    #[arg(
+        long,
+        env = "TELEGRAM_CHAT_ID",
+        requires = "telegram_bot_token",
+        global = true
+    )]
+    telegram_chat_id: Option<String>,
+
+    /// Path to the YAML file used to persist state (e.g. the events of the previous run) between
+    /// runs.
+    ///
+    /// This file is fully managed (created, written, and read) by the application.
+    #[arg(long, default_value = "scma-gsync-state.yml", global = true)]
+    state_file: String,
+    /// Upserts the SCMA events (including attendees and comments) or users read by each run into
+    /// the given SQLite database for a queryable historical archive.
+    ///
+    /// The database and its tables are created if they do not exist.  Rows are never deleted.
+    #[arg(long, global = true)]
+    archive_db: Option<String>,
+
+    /// Path to a lock file that prevents overlapping runs (e.g. a cron run that starts while the
+    /// previous run is still going).
+    ///
+    /// The run fails if another run holds the lock.  The lock is released when the process exits
+    /// so a lock file left behind by a crashed run does not block later runs.  With --daemon, the
+    /// lock is held for the lifetime of the daemon.
+    #[arg(long, global = true)]
+    lock_file: Option<String>,
+
+    /// Keeps running and performs the sync on the schedule given by --schedule.
+    ///
+    /// A failed run is retried with exponential backoff (starting at 1 minute) until it succeeds
+    /// or the next scheduled run.
+    #[arg(help_heading = "Daemon Options")]
+    #[arg(long, global = true)]
+    daemon: bool,
+
+    /// The schedule of daemon runs in cron format (minute hour day-of-month month day-of-week),
+    /// in the timezone given by --timezone.
+    #[arg(help_heading = "Daemon Options")]
+    #[arg(long, default_value = "0 3 * * *", global = true)]
+    schedule: Schedule,
+
+    #[command(subcommand)]
+    command: Command,
 
     /// Username for the SCMA website (https://rockclimbing.org).
+    ///
+    /// If the username or password is not provided, the credentials are read from the OS keyring.
+    /// If not found in the OS keyring, the credentials are prompted for interactively with the
+    /// option to store them in the OS keyring.
     #[arg(help_heading = "Web Input Options")]
-    #[arg(short, long, default_value = "", env = "SCMA_USERNAME")]
+    #[arg(short, long, default_value = "", env = "SCMA_USERNAME", global = true)]
     username: String,
     /// Password for the SCMA website (https://rockclimbing.org).
     #[arg(help_heading = "Web Input Options")]
-    #[arg(short, long, default_value = "", env = "SCMA_PASSWORD")]
+    #[arg(short, long, default_value = "", env = "SCMA_PASSWORD", global = true)]
     password: String,
     /// Includes past events.
     ///
     /// Without this option, only in-progress and future events will be sync'd.  With this option,
     /// all events (past, in-progress, and future) will be sync'd.
     #[arg(help_heading = "Web Input Options")]
-    #[arg(long)]
+    #[arg(long, global = true)]
     all: bool,
+    /// Persists the SCMA session cookie in the given file to resume the session on the next run
+    /// instead of logging in again.
+    ///
+    /// The session is only resumed if it is still valid.  This file is fully managed (created,
+    /// written, and read) by the application.  Keep it private as it grants access to the SCMA
+    /// account.
+    #[arg(help_heading = "Web Input Options")]
+    #[arg(long, global = true)]
+    session_file: Option<String>,
+    /// The number of times to retry an SCMA page fetch that fails with a timeout, a connection
+    /// error, or a server error.
+    ///
+    /// Retries are made with exponential backoff starting at 1 second.
+    #[arg(help_heading = "Web Input Options")]
+    #[arg(long, default_value = "3", global = true)]
+    web_retries: u32,
+    /// Caches the SCMA event detail pages in the given directory.
+    ///
+    /// A cached page is revalidated with the SCMA website (ETag or Last-Modified) and only
+    /// downloaded again if it has changed.
+    #[arg(help_heading = "Web Input Options")]
+    #[arg(long, global = true)]
+    page_cache_dir: Option<String>,
+    /// Uses a cached event detail page without revalidating it if it is younger than the given age
+    /// (e.g. `30m`, `12h`, or `1d`).
+    #[arg(help_heading = "Web Input Options")]
+    #[arg(long, value_parser = parse_ttl, requires = "page_cache_dir", global = true)]
+    page_cache_ttl: Option<chrono::Duration>,
+    /// Records the HTTP exchanges with the SCMA website to fixture files in the given directory.
+    ///
+    /// The password and the session cookies are redacted.  The fixtures can be replayed with
+    /// --replay for offline testing or attached to a bug report.  Exchanges with the Google APIs
+    /// are not recorded.
+    #[arg(help_heading = "Web Input Options")]
+    #[arg(long, value_name = "DIR", conflicts_with = "replay", global = true)]
+    record: Option<String>,
+    /// Replays the HTTP exchanges with the SCMA website from the fixture files (see --record) in
+    /// the given directory instead of using the SCMA website.
+    ///
+    /// Does not log in.  A request without a fixture fails.
+    #[arg(help_heading = "Web Input Options")]
+    #[arg(long, value_name = "DIR", global = true)]
+    replay: Option<String>,
+
+    /// Excludes an event from the sync by SCMA event ID.
+    ///
+    /// Use multiple times to exclude multiple events.  Excluded events are treated as if they do
+    /// not exist on the SCMA website.
+    ///
+    /// Example: --exclude-event-id 527 --exclude-event-id 531
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long = "exclude-event-id", global = true)]
+    exclude_event_ids: Vec<String>,
+
+    /// Syncs only the event with the given SCMA event ID.
+    ///
+    /// Use multiple times to sync multiple events.  The other events are left as is (e.g. they are
+    /// not treated as orphans).
+    ///
+    /// Example: --only-event-id 527 --only-event-id 531
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long = "only-event-id", value_name = "EVENT_ID", global = true)]
+    only_event_ids: Vec<String>,
+
+    /// Syncs only the events whose title contains the given keyword (case-insensitive).
+    ///
+    /// Use multiple times to match any of multiple keywords.  The other events are left as is (e.g.
+    /// they are not treated as orphans).
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, value_name = "KEYWORD", global = true)]
+    match_title: Vec<String>,
+
+    /// Syncs only the events whose location contains the given keyword (case-insensitive).
+    ///
+    /// Use multiple times to match any of multiple keywords.  The other events are left as is (e.g.
+    /// they are not treated as orphans).
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, value_name = "KEYWORD", global = true)]
+    match_location: Vec<String>,
+
+    /// A YAML file containing a list of SCMA event IDs to exclude from the sync.
+    ///
+    /// Combined with any --exclude-event-id options.
+    ///
+    /// Example contents:
+    ///
+    ///  [ "527", "531" ]
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, global = true)]
+    exclude_events_file: Option<String>,
+
+    /// Removes events that the SCMA website lists more than once.
+    ///
+    /// Events are duplicates if they have the same title (ignoring case, whitespace, and
+    /// punctuation) and the same start and end dates.  The event with the lowest SCMA event ID is
+    /// kept.  Duplicates are logged so they can be cleaned up on the SCMA website.
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, global = true)]
+    dedup: bool,
+
+    /// Limits the sync to events starting within the given number of days from today.
+    ///
+    /// Events beyond the horizon are out of scope.  They are neither synced nor reconciled.
+    ///
+    /// Accepts days (e.g. `90d` or `90`) or weeks (e.g. `12w`).
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, value_parser = parse_days, global = true)]
+    horizon: Option<Days>,
+
+    /// Limits the sync to events ending on or after the given date.
+    ///
+    /// Accepts a date (e.g. `2023-01-01`) or a number of days or weeks before today (e.g. `90d` or
+    /// `12w`).  Past events are fetched from the SCMA website if the date is in the past (see
+    /// --all).  Events before the date are neither synced nor reconciled.  Use with --until to
+    /// backfill past events in slices.
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, value_parser = parse_date, global = true)]
+    since: Option<DateArg>,
+
+    /// Limits the sync to events starting on or before the given date.
+    ///
+    /// Accepts a date (e.g. `2023-06-30`) or a number of days or weeks after today (e.g. `90d` or
+    /// `12w`).  Events after the date are neither synced nor reconciled.
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, value_parser = parse_date, conflicts_with = "horizon", global = true)]
+    until: Option<DateArg>,
+
+    /// Syncs only the events the SCMA user (see --username) is going to.
+    ///
+    /// Intended for syncing to a personal calendar (e.g. `--auth-type oauth --calendar "SCMA
+    /// (Mine)"`).  Cannot be used with `--ifile`.
+    #[arg(help_heading = "Event Selection Options")]
+    #[arg(long, global = true)]
+    only_mine: bool,
+
+    /// Syncs only the users with the given member statuses.
+    ///
+    /// Users that are not selected are treated as if they do not exist (e.g. they are removed from
+    /// the calendar ACL).
+    ///
+    /// Example: --member-status RM,HM,AM
+    #[arg(help_heading = "User Selection Options")]
+    #[arg(
+        long = "member-status",
+        value_name = "STATUS",
+        value_delimiter = ',',
+        global = true
+    )]
+    member_statuses: Vec<MemberStatus>,
+
+    /// Excludes the users that have resigned their membership.
+    ///
+    /// Excluded users are treated as if they do not exist (e.g. they are removed from the calendar
+    /// ACL).
+    #[arg(help_heading = "User Selection Options")]
+    #[arg(long, global = true)]
+    exclude_resigned: bool,
+
+    /// Includes the users that have opted out of communication or have a blocked account.
+    ///
+    /// By default, these users are not added to the calendar ACL, contacts are not created for
+    /// them, and they are not sent email invitations or SMS reminders.  Their existing ACL rules
+    /// and contacts are kept.
+    #[arg(help_heading = "User Selection Options")]
+    #[arg(long, global = true)]
+    include_optedout: bool,
 
     /// The authentication type to use for the Google APIs.
     ///
     /// The Google Calendar output infers `--auth-type service-account`.  The Google People output
     /// infers `--auth-type oauth`.
+    ///
+    /// `--auth-type oauth-device` authorizes by entering a code on another device instead of a
+    /// browser redirect (e.g. on a headless server).
     #[arg(help_heading = "Google Authentication Options")]
-    #[arg(value_enum, long, default_value = "infer")]
+    #[arg(value_enum, long, default_value = "infer", global = true)]
     auth_type: AuthType,
 
     /// Path to the JSON file that contains the client secret.
@@ -121,37 +903,130 @@ struct Cli {
     /// This file is downloaded by the user from the Google API console
     /// (https://console.developers.google.com).
     ///
-    /// This is used for both the `--auth-type oauth` and `--auth-type service-account`.
+    /// This is used for the `--auth-type oauth`, `--auth-type oauth-device`, and `--auth-type
+    /// service-account`.
+    ///
+    /// May also reference a secret in a cloud secret manager: `gcp-sm://<project>/<secret>` (GCP
+    /// Secret Manager), `aws-ssm://<name>` (AWS SSM Parameter Store), or `aws-sm://<id>` (AWS
+    /// Secrets Manager).  The secret is fetched using the `gcloud` or `aws` CLI.
     ///
-    /// The `--auth-type oauth` JSON looks like: `{"installed":{"client_id": ... }}`.
+    /// The `--auth-type oauth` and `--auth-type oauth-device` JSON looks like: `{"installed":{"client_id": ... }}`.
     ///
     /// The `--auth-type service-account` JSON looks like: `{"type": "service_account", "project_id": ...}`.
     #[arg(help_heading = "Google Authentication Options")]
     #[arg(
         long = "secret-file",
         default_value = "secret.json",
-        env = "GOOGLE_CLIENT_SECRET_PATH"
+        env = "GOOGLE_CLIENT_SECRET_PATH",
+        global = true
     )]
     client_secret_json_path: String,
 
+    /// The JSON of the OAuth client secret.
+    ///
+    /// Takes precedence over `--secret-file` for the `--auth-type oauth` and `--auth-type
+    /// oauth-device`.  Intended for CI and containers where mounting a secret file is awkward.
+    #[arg(help_heading = "Google Authentication Options")]
+    #[arg(
+        long = "secret-json",
+        env = "GOOGLE_CLIENT_SECRET_JSON",
+        hide_env_values = true,
+        global = true
+    )]
+    client_secret_json: Option<String>,
+
+    /// The JSON of the service account key.
+    ///
+    /// Takes precedence over `--secret-file` for the `--auth-type service-account`.  Intended for
+    /// CI and containers where mounting a secret file is awkward.
+    #[arg(help_heading = "Google Authentication Options")]
+    #[arg(
+        long = "service-account-json",
+        env = "GOOGLE_SERVICE_ACCOUNT_JSON",
+        hide_env_values = true,
+        global = true
+    )]
+    service_account_json: Option<String>,
+
     /// Path to the JSON file used to persist the OAuth tokens.
     ///
     /// This file is fully managed (created, written, and read) by the application.
     ///
-    /// This is used for the oauth --auth-type only.
+    /// This is used for the oauth and oauth-device --auth-type only.
     #[arg(help_heading = "Google Authentication Options")]
     #[arg(
         long = "token-file",
         default_value = "token.json",
-        env = "GOOGLE_OAUTH_TOKEN_PATH"
+        env = "GOOGLE_OAUTH_TOKEN_PATH",
+        global = true
     )]
     oauth_token_json_path: String,
 
+    /// Stores the OAuth tokens and the SCMA session in the OS keyring instead of in plaintext
+    /// files.
+    ///
+    /// The `--token-file` and `--session-file` paths are used as the names of the keyring entries
+    /// instead.
+    #[arg(help_heading = "Google Authentication Options")]
+    #[arg(long, global = true)]
+    keyring_storage: bool,
+
+    /// The Google Workspace user for the service account to act as.
+    ///
+    /// Requires domain-wide delegation of the service account.  Allows syncing directly to the
+    /// calendar and contacts of the user instead of sharing a calendar owned by the service
+    /// account.
+    ///
+    /// This is used for the service-account --auth-type only.
+    ///
+    /// Example: --impersonate user@example.org
+    #[arg(help_heading = "Google Authentication Options")]
+    #[arg(long, global = true)]
+    impersonate: Option<String>,
+    /// Overrides the root URL of the Google Calendar API (default: https://www.googleapis.com/).
+    ///
+    /// For testing against a local mock server or emulator.  Plain HTTP is allowed.
+    ///
+    /// Example: --calendar-root-url http://localhost:8080/
+    #[arg(help_heading = "Google Authentication Options")]
+    #[arg(long, env = "GOOGLE_CALENDAR_ROOT_URL", global = true)]
+    calendar_root_url: Option<String>,
+    /// Overrides the root URL of the Google People API (default: https://people.googleapis.com/).
+    ///
+    /// For testing against a local mock server or emulator.  Plain HTTP is allowed.
+    ///
+    /// Example: --people-root-url http://localhost:8080/
+    #[arg(help_heading = "Google Authentication Options")]
+    #[arg(long, env = "GOOGLE_PEOPLE_ROOT_URL", global = true)]
+    people_root_url: Option<String>,
+
     /// The name of the Google Calendar to sync to.
     #[arg(help_heading = "Google Calendar Options")]
-    #[arg(short, long, default_value = "SCMA")]
+    #[arg(short, long, default_value = "SCMA", global = true)]
     calendar: String,
 
+    /// The ID of the Google Calendar to sync to (e.g. abc123@group.calendar.google.com).
+    ///
+    /// Bypasses the lookup of the calendar by name.  Use if multiple calendars share the name or
+    /// the calendar may be renamed.  The calendar must exist.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, conflicts_with = "calendar", global = true)]
+    calendar_id: Option<String>,
+
+    /// A YAML file containing a map of calendar names to the rules of the events synced to them.
+    ///
+    /// Events are synced to the first calendar (in file order) with a matching rule and otherwise
+    /// to the --calendar calendar.  An event matches a rule if it matches any of its categories
+    /// (SCMA category IDs), locations, or keywords (in the title).  Each calendar is created and
+    /// has its ACL synced independently.  Cannot be used with --incremental or --local-state.
+    ///
+    /// Example contents:
+    ///
+    ///  { "SCMA Admin": { "keywords": ["board meeting"] }, "SCMA Trips": { "categories": ["2"] } }
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    calendar_routes_file: Option<String>,
+
     /// Add a user (by email address) as a co-owner of the calendar.
     ///
     /// Use multiple times to specify multiple owners. Useful when using service account
@@ -159,12 +1034,55 @@ struct Cli {
     ///
     /// Example: --calendar-owner owner1@example.com --calendar-owner owner2@example.com
     #[arg(help_heading = "Google Calendar Options")]
-    #[arg(long = "calendar-owner")]
+    #[arg(long = "calendar-owner", global = true)]
     calendar_owners: Vec<String>,
 
+    /// Grants read access to a Google Group (by email address) during the ACL sync.
+    ///
+    /// Use multiple times to specify multiple groups.  The reader rules of other groups are
+    /// deleted.  Group rules are left unchanged if not specified.
+    ///
+    /// Example: --acl-group members@example.com
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long = "acl-group", global = true)]
+    acl_groups: Vec<String>,
+
+    /// Grants read access to the --acl-group groups only instead of also to each user.
+    ///
+    /// The reader rules of users are deleted.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, requires = "acl_groups", global = true)]
+    no_acl_users: bool,
+
+    /// Grants read access to everyone in a Google Workspace domain during the ACL sync.
+    ///
+    /// The reader rules of other domains are deleted.  Domain rules are left unchanged if not
+    /// specified.
+    ///
+    /// Example: --share-domain example.org
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    share_domain: Option<String>,
+
+    /// Makes the calendar public by granting everyone read access during the ACL sync.
+    ///
+    /// The HTML and ICS subscription URLs of the calendar are logged.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    public: bool,
+
+    /// Only inserts the missing readers during the ACL sync.
+    ///
+    /// The extra ACL rules are reported but not deleted.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    acl_no_delete: bool,
+
     /// A map of email aliases to account for email aliases resolution done by Goolge Calendar.
     ///
-    /// A YAML file containing a map of SCMA email addresses to Google email aliases.
+    /// A YAML file containing a map of SCMA email addresses to Google email aliases.  Also used to
+    /// match SCMA members to Google Contacts so that a contact stored under the alias is not
+    /// duplicated.
     ///
     /// Google Calendar resolves email address aliases.  For example, we might do an acl.insert for
     /// the email user-alias@example.com.  Behind the scenes, Google Calendar resolves the alias
@@ -178,219 +1096,2440 @@ struct Cli {
     ///  { "user-alias@example.com": "user@example.com", "scma-member-email-address@example.com":
     ///  "google-resolved-email-address@example.com" }
     #[arg(help_heading = "Google Calendar Options")]
-    #[arg(long)]
+    #[arg(long, global = true)]
     email_aliases_file: Option<String>,
 
-    /// Disables sending an email notification on ACL insert
+    /// The format of the event summary.
+    ///
+    /// The placeholders {title}, {location}, {id}, {start_date}, {end_date}, and {url} are replaced
+    /// with the SCMA event fields.
+    ///
+    /// Example: --event-summary-format "{title} @ {location}"
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, default_value = DEFAULT_SUMMARY_FORMAT, global = true)]
+    event_summary_format: String,
+
+    /// Appends the number of attendees to the event summary (e.g. "SCMA: Joshua Tree (12 going)").
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    summary_attendee_count: bool,
+
+    /// Adds the SCMA attendees of each event as guests of the calendar event.
+    ///
+    /// Attendees are matched to SCMA users by name to get their email.  Guests see the event on
+    /// their own calendar (i.e. they are invited).  Requires OAuth authentication or a service
+    /// account with domain-wide delegation.  Cannot be used with --ifile.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    attendee_guests: bool,
+
+    /// A YAML file containing a map of title keywords to event summary prefixes.
+    ///
+    /// Keywords are matched case-insensitively against the SCMA event title.  The first matching
+    /// keyword (in file order) wins.
+    ///
+    /// Example contents:
+    ///
+    ///  { "campout": "🏕", "meeting": "📋", "climb": "🧗" }
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    title_markers_file: Option<String>,
+
+    /// A YAML file containing a map of SCMA category IDs to event colors.
+    ///
+    /// The category ID of each event is the `category` field of the events YAML.  The colors are
+    /// lavender, sage, grape, flamingo, banana, tangerine, peacock, graphite, blueberry, basil,
+    /// and tomato.  Events of unmapped categories use the calendar color.
+    ///
+    /// Example contents:
+    ///
+    ///  { "2": "basil", "4": "blueberry" }
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    category_colors_file: Option<String>,
+
+    /// Overrides the reminders of the synced events.
+    ///
+    /// Reminders are `<method>:<time>` where the method is popup or email and the time before the
+    /// start of the event is in minutes or has a unit (m, h, d, or w).  Google Calendar reminders
+    /// are per user and only apply to the account that syncs the calendar and event guests.
+    /// Uses the default reminders of the calendar if not specified.
+    ///
+    /// Example: --event-reminders popup:1d,email:2d
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, value_delimiter = ',', global = true)]
+    event_reminders: Vec<EventReminder>,
+
+    /// Sets the default reminders of the calendar.
+    ///
+    /// Uses the same format as --event-reminders.  Google Calendar reminders are per user and the
+    /// default reminders are those of the account that syncs the calendar.  Left unchanged if not
+    /// specified.
+    ///
+    /// Example: --default-reminders popup:1d
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, value_delimiter = ',', global = true)]
+    default_reminders: Vec<EventReminder>,
+
+    /// Sets whether attendees other than the organizer can invite others to synced events.
+    ///
+    /// Left unchanged if not specified.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(value_enum, long, global = true)]
+    guests_can_invite_others: Option<Boolean>,
+
+    /// Sets whether attendees other than the organizer can modify synced events.
+    ///
+    /// Left unchanged if not specified.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(value_enum, long, global = true)]
+    guests_can_modify: Option<Boolean>,
+
+    /// Sets whether attendees other than the organizer can see who the event's attendees are.
+    ///
+    /// Left unchanged if not specified.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(value_enum, long, global = true)]
+    guests_can_see_other_guests: Option<Boolean>,
+
+    /// The optional sections to include in the event description.
+    ///
+    /// The link to the SCMA event and the SCMA event description are always included.  Omit
+    /// sections to keep attendee names or comments private from everyone with calendar access.
+    /// Include counts to show only the number of attendees and comments instead.
+    ///
+    /// Example: --description-include attendees
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(
+        value_enum,
+        long,
+        value_delimiter = ',',
+        default_value = "attendees,attendee-comments,comments",
+        global = true
+    )]
+    description_include: Vec<DescriptionSection>,
+
+    /// A Tera template file that replaces the layout of the event description.
+    ///
+    /// The SCMA event is available as `event` with the same fields as the events YAML (e.g.
+    /// `event.title`, `event.url`, `event.description`, `event.attendees`, and `event.comments`).
+    /// The sync footer is appended to the rendered template.  Overrides --description-include.
+    ///
+    /// Example contents:
+    ///
+    ///  <a href="{{ event.url }}">{{ event.title }}</a>{{ event.description }}
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    description_template: Option<String>,
+
+    /// The language of the event description headings, the sync footer, and the attendee count.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(value_enum, long, default_value = "en", global = true)]
+    lang: Language,
+
+    /// Fetches only the calendar events changed since the previous run.
+    ///
+    /// The existing calendar events are cached in the state file (see --state-file) along with a
+    /// sync token for fetching the changes.  Without this option, the calendar events are listed
+    /// on every run.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, global = true)]
+    incremental: bool,
+
+    /// Diffs the calendar events and ACL against what was last pushed instead of reading them
+    /// from the calendar.
+    ///
+    /// What was pushed is recorded in the state file (see --state-file).  The calendar is read
+    /// once to initialize the record.  Reduces API calls but changes made in Google Calendar
+    /// (e.g. a manually deleted event) are not seen.  Delete the record from the state file to
+    /// re-read the calendar.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(long, conflicts_with = "incremental", global = true)]
+    local_state: bool,
+
+    /// What to do with synced calendar events that are no longer synced from SCMA.
+    ///
+    /// Events that were deleted on SCMA, that are excluded (e.g. by --exclude-event-id or
+    /// --only-mine), or that are duplicates are orphaned.  Only events within the sync window are
+    /// considered.  Calendar events that were not created by scma-gsync are never touched.
+    #[arg(help_heading = "Google Calendar Options")]
+    #[arg(value_enum, long, default_value = "ignore", global = true)]
+    orphans: Orphans,
+
+    /// Disables sending an email notification on ACL insert
     #[arg(help_heading = "Google Calendar Options")]
-    #[arg(value_enum, long, default_value = "false")]
+    #[arg(value_enum, long, default_value = "false", global = true)]
     notify_acl_insert: Boolean,
 
     /// The name of the Google People ContactGroup to sync to.
     #[arg(help_heading = "Google People Options")]
-    #[arg(long, default_value = "SCMA")]
+    #[arg(long, default_value = "SCMA", global = true)]
     group: String,
+
+    /// Disables syncing the SCMA profile photos of members to Google Contacts.
+    ///
+    /// Photos are only synced when reading from the SCMA website.  A photo is only uploaded if it
+    /// changed since it was last uploaded.
+    #[arg(help_heading = "Google People Options")]
+    #[arg(long, global = true)]
+    no_contact_photos: bool,
+
+    /// What to do with contacts in the group that are no longer SCMA members.
+    ///
+    /// Contacts are matched to SCMA members by email.
+    #[arg(help_heading = "Google People Options")]
+    #[arg(value_enum, long, default_value = "ignore", global = true)]
+    stale_contacts: StaleContacts,
+
+    /// Deletes contacts in the group that are no longer SCMA members.
+    ///
+    /// Same as --stale-contacts delete.  Nothing is deleted if more than --max-contact-deletes
+    /// contacts would be deleted.
+    #[arg(help_heading = "Google People Options")]
+    #[arg(long, conflicts_with = "stale_contacts", global = true)]
+    delete_stale_contacts: bool,
+
+    /// The maximum number of stale contacts to delete.
+    ///
+    /// If more contacts would be deleted (e.g. because of an incomplete SCMA roster), none are
+    /// deleted and the sync fails.  With --dry-run, a warning is logged instead.
+    #[arg(help_heading = "Google People Options")]
+    #[arg(long, default_value = "25", global = true)]
+    max_contact_deletes: usize,
+
+    /// The name of the Google People ContactGroup to move stale contacts to for --stale-contacts
+    /// move.
+    #[arg(help_heading = "Google People Options")]
+    #[arg(long, default_value = "SCMA Alumni", global = true)]
+    alumni_group: String,
+
+    /// A YAML file containing a map of additional contact group names to membership rules.
+    ///
+    /// Each contact group is synced with the members that match its rule.  Contact groups are
+    /// created if they do not exist.  Rules:
+    ///
+    /// * trip-leader -- members with any trip leader status
+    ///
+    /// * !trip-leader-status [G, S1, S2] -- members with one of the trip leader statuses
+    ///
+    /// * position -- members with a position (e.g. board members)
+    ///
+    /// Example contents:
+    ///
+    ///  { "SCMA Trip Leaders": trip-leader, "SCMA Board": position }
+    #[arg(help_heading = "Google People Options")]
+    #[arg(long, global = true)]
+    contact_groups_file: Option<String>,
+
+    /// Emails attendees a personal invitation (ICS attachment) for each event they are going to.
+    ///
+    /// Attendees are matched to SCMA users by name.  Each attendee is invited once per event.  If
+    /// the dates of an event change, a revised invitation is sent to all attendees.  Past events
+    /// are skipped.  Cannot be used with `--ifile`.
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, requires_all = ["smtp_host", "invite_from"], global = true)]
+    email_invites: bool,
+
+    /// The SMTP server to send invitations through.  Connects using STARTTLS.
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, env = "SMTP_HOST", global = true)]
+    smtp_host: Option<String>,
+
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, default_value = "587", env = "SMTP_PORT", global = true)]
+    smtp_port: u16,
+
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, default_value = "", env = "SMTP_USERNAME", global = true)]
+    smtp_username: String,
+
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, default_value = "", env = "SMTP_PASSWORD", global = true)]
+    smtp_password: String,
+
+    /// The sender and organizer of invitations.
+    ///
+    /// Example: --invite-from "SCMA <scma@example.com>"
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, env = "INVITE_FROM", global = true)]
+    invite_from: Option<String>,
+
+    /// Path to the YAML file used to record sent invitations.
+    ///
+    /// This file is fully managed (created, written, and read) by the application.
+    #[arg(help_heading = "Email Invitation Options")]
+    #[arg(long, default_value = "invites-sent.yml", global = true)]
+    invites_sent_file: String,
+
+    /// Texts opted-in attendees a reminder before each event they are going to.
+    ///
+    /// Reminders are sent via Twilio to the phone number on the SCMA website.  Each attendee is
+    /// reminded once per event.  Because reminders are only sent while the event is within the
+    /// lead time, this is intended for frequent (e.g. hourly) scheduled runs.  Cannot be used with
+    /// `--ifile`.
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(
+        long,
+        requires_all = ["twilio_account_sid", "twilio_auth_token", "twilio_from", "sms_opt_in_file"],
+        global = true
+    )]
+    sms_reminders: bool,
+
+    /// The number of hours before the start of an event to send reminders.
+    ///
+    /// Timed events start at their start time and all-day events at midnight (see --timezone).
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(long, default_value = "12", global = true)]
+    sms_reminder_lead: u32,
+
+    /// A YAML file containing a list of the SCMA emails of users that opted in to SMS reminders.
+    ///
+    /// Example contents:
+    ///
+    ///  [ "user1@example.com", "user2@example.com" ]
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(long, global = true)]
+    sms_opt_in_file: Option<String>,
+
+    /// Path to the YAML file used to record sent reminders.
+    ///
+    /// This file is fully managed (created, written, and read) by the application.
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(long, default_value = "sms-sent.yml", global = true)]
+    sms_sent_file: String,
+
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(long, env = "TWILIO_ACCOUNT_SID", global = true)]
+    twilio_account_sid: Option<String>,
+
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(long, env = "TWILIO_AUTH_TOKEN", global = true)]
+    twilio_auth_token: Option<String>,
+
+    /// The Twilio phone number to send reminders from (e.g. +15555550100).
+    #[arg(help_heading = "SMS Reminder Options")]
+    #[arg(long, env = "TWILIO_FROM", global = true)]
+    twilio_from: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The env file may provide values for options (e.g. SCMA_USERNAME) and for logging (e.g.
+    // RUST_LOG) so it must be loaded before the final parse and before logging is initialized.  It
+    // is loaded before the runtime starts its worker threads since setting environment variables
+    // is not thread-safe.
+    let env_file = load_env_file(env_file_arg(std::env::args_os()).as_deref())?;
+
+    tokio::runtime::Runtime::new()?.block_on(start(env_file))
+}
+
+async fn start(env_file: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let args = parse_args()?;
+    if let Command::Completions(ref completions) = args.command {
+        return write_completions(completions.target);
+    }
+
+    let filter = EnvFilter::from_default_env().add_directive("info".parse()?);
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(filter);
+    match args.log_format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .init(),
+    }
+
+    if let Some(env_file) = env_file {
+        info!(?env_file, "Loaded environment variables from env file");
+    }
+
+    rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .unwrap();
+
+    let _lock = match args.lock_file {
+        Some(ref path) => Some(RunLock::acquire(Path::new(path))?),
+        None => None,
+    };
+
+    if args.daemon {
+        daemon(args).await
+    } else {
+        run(args).await
+    }
+}
+
+/// Writes the shell completions or the man page to stdout.
+fn write_completions(target: CompletionTarget) -> Result<(), Box<dyn std::error::Error>> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut stdout = std::io::stdout();
+
+    match target {
+        CompletionTarget::Bash => generate(Shell::Bash, &mut command, name, &mut stdout),
+        CompletionTarget::Zsh => generate(Shell::Zsh, &mut command, name, &mut stdout),
+        CompletionTarget::Fish => generate(Shell::Fish, &mut command, name, &mut stdout),
+        CompletionTarget::Elvish => generate(Shell::Elvish, &mut command, name, &mut stdout),
+        CompletionTarget::PowerShell => {
+            generate(Shell::PowerShell, &mut command, name, &mut stdout)
+        }
+        CompletionTarget::Man => clap_mangen::Man::new(command).render(&mut stdout)?,
+    }
+
+    Ok(())
+}
+
+/// Runs the sync on the schedule until the process is terminated.
+async fn daemon(args: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    info!(schedule=%args.schedule, "Starting daemon");
+
+    let mut run_number = 0;
+    loop {
+        let Some(until_next) = args.schedule.until_next(&args.timezone) else {
+            return Err(format!("schedule `{}` never runs", args.schedule).into());
+        };
+        info!(next=%(Utc::now().with_timezone(&args.timezone) + until_next), "Waiting for next scheduled run");
+        tokio::time::sleep(until_next).await;
+
+        let mut backoff = DAEMON_INITIAL_BACKOFF;
+        loop {
+            run_number += 1;
+            info!(run=%run_number, "Starting run");
+            match run(args.clone()).await {
+                Ok(()) => {
+                    info!(run=%run_number, "Finished run");
+                    break;
+                }
+                Err(err) => error!(run=%run_number, %err, "Run failed"),
+            }
+
+            // Give up on retries once the next scheduled run is due
+            let until_next = args.schedule.until_next(&args.timezone).unwrap_or_default();
+            if backoff >= until_next {
+                break;
+            }
+            info!(run=%run_number, ?backoff, "Retrying failed run");
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(DAEMON_MAX_BACKOFF);
+        }
+    }
+}
+
+/// Performs a single sync and prints the run summary.
+async fn run(args: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let summary = Summary::new();
+    if let Some(ref audit_log) = args.audit_log {
+        summary.plan().set_audit_log(AuditLog::open(audit_log)?);
+    }
+    let summary_format = args.summary_format;
+    let summary_file = args.summary_file.clone();
+    let plan_file = args.plan_file.clone();
+    let plan_format = args.plan_format;
+    let metrics_file = args.metrics_file.clone();
+    let metrics_push_url = args.metrics_push_url.clone();
+    let metrics_job = args.metrics_job.clone();
+    let healthcheck_url = args.healthcheck_url.clone();
+
+    if let Some(ref url) = healthcheck_url {
+        ping_healthcheck(url, "/start", String::new()).await;
+    }
+
+    let result = match args.command.clone() {
+        Command::Events(EventsCommand::Sync(input)) => {
+            process_events(args, &input, EventOutput::GCal, &summary).await
+        }
+        Command::Events(EventsCommand::Refresh) => refresh_events(args, &summary).await,
+        Command::Events(EventsCommand::Export(export)) => {
+            let output = match export.format {
+                ExportFormat::Yaml => EventOutput::Yaml(export.output.output_file),
+                ExportFormat::Ics => EventOutput::Ics(export.output.output_file),
+                ExportFormat::Csv => EventOutput::Csv(export.output.output_file),
+                ExportFormat::Json => EventOutput::Json(export.output.output_file),
+                ExportFormat::Markdown => EventOutput::Markdown(export.output.output_file),
+            };
+            process_events(args, &export.input, output, &summary).await
+        }
+        Command::Events(EventsCommand::Rsvp(rsvp)) => process_rsvp(args, &rsvp, &summary).await,
+        Command::Events(EventsCommand::Comment(comment)) => {
+            process_comment(args, &comment, &summary).await
+        }
+        Command::Events(EventsCommand::DriftReport(output)) => {
+            process_event_drift(args, &output, &summary).await
+        }
+        Command::Users(UsersCommand::SyncAcl(input)) => {
+            process_users(args, &input, UserOutput::Acl, &summary).await
+        }
+        Command::Users(UsersCommand::SyncContacts(input)) => {
+            process_users(args, &input, UserOutput::Contacts, &summary).await
+        }
+        Command::Users(UsersCommand::Export(export)) => {
+            let redact = match export.redact {
+                RedactLevel::None => Redact::None,
+                RedactLevel::Contact => Redact::Contact,
+                RedactLevel::Full => Redact::Full,
+            };
+            let output = match export.format {
+                UserExportFormat::Yaml => UserOutput::Yaml(export.output.output_file, redact),
+                UserExportFormat::Csv => UserOutput::Csv(export.output.output_file),
+                UserExportFormat::Json => UserOutput::Json(export.output.output_file, redact),
+                UserExportFormat::Vcf => UserOutput::Vcf(export.output.output_file),
+            };
+            process_users(args, &export.input, output, &summary).await
+        }
+        Command::Users(UsersCommand::AclReport(report)) => {
+            process_acl(args, &report, &summary).await
+        }
+        Command::Users(UsersCommand::SyncMailchimp(mailchimp)) => {
+            let output = UserOutput::Mailchimp {
+                api_key: mailchimp.mailchimp_api_key,
+                list_id: mailchimp.mailchimp_list_id,
+            };
+            process_users(args, &mailchimp.input, output, &summary).await
+        }
+        Command::Users(UsersCommand::SyncSheet(sheet)) => {
+            let output = UserOutput::Sheet {
+                spreadsheet_id: sheet.spreadsheet_id,
+                sheet: sheet.sheet,
+            };
+            process_users(args, &sheet.input, output, &summary).await
+        }
+        Command::Users(UsersCommand::ContactsReport(report)) => {
+            process_contacts(args, &report, &summary).await
+        }
+        Command::Users(UsersCommand::PurgeMember(purge)) => {
+            process_purge_member(args, &purge, &summary).await
+        }
+        Command::Users(UsersCommand::DriftReport(output)) => {
+            process_contact_drift(args, &output, &summary).await
+        }
+        Command::Backup(backup) => process_backup(args, &backup, &summary).await,
+        Command::Restore(restore) => process_restore(args, &restore, &summary).await,
+        Command::Completions(_) => unreachable!("Handled by main"),
+    };
+
+    let result = match (result, &plan_file) {
+        (Ok(()), Some(plan_file)) => {
+            write_plan(&summary.plan(), plan_format, plan_file).map_err(|err| {
+                error!(%err, "Unable to write plan");
+                err.into()
+            })
+        }
+        (result, _) => result,
+    };
+
+    let failures = summary.plan().failures();
+    for failure in &failures {
+        summary.record_error(failure);
+    }
+    if let Err(ref err) = result {
+        summary.record_error(err);
+    }
+    let result = match result {
+        Ok(()) if !failures.is_empty() => Err(format!(
+            "{} writes failed:\n  {}",
+            failures.len(),
+            failures.join("\n  ")
+        )
+        .into()),
+        result => result,
+    };
+    let result = match write_summary(&summary, summary_format, summary_file.as_deref()) {
+        Ok(()) => result,
+        Err(err) => {
+            error!(%err, "Unable to write summary");
+            result.and(Err(err.into()))
+        }
+    };
+
+    if metrics_file.is_some() || metrics_push_url.is_some() {
+        let metrics = summary.to_prometheus(result.is_ok());
+        if let Err(err) = write_metrics(
+            &metrics,
+            metrics_file.as_deref(),
+            metrics_push_url.as_deref(),
+            &metrics_job,
+        )
+        .await
+        {
+            error!(%err, "Unable to write metrics");
+        }
+    }
+
+    if let Some(ref url) = healthcheck_url {
+        match result {
+            Ok(()) => ping_healthcheck(url, "", String::new()).await,
+            Err(ref err) => ping_healthcheck(url, "/fail", err.to_string()).await,
+        }
+    }
+
+    result
+}
+
+/// Pings the endpoint of the healthcheck URL with the body (e.g. the error of a failed run).
+///
+/// A failed ping is logged but does not fail the run.
+async fn ping_healthcheck(url: &str, endpoint: &str, body: String) {
+    let url = format!("{}{endpoint}", url.trim_end_matches('/'));
+    info!(%url, "Pinging healthcheck");
+    let result = reqwest::Client::new()
+        .post(&url)
+        .timeout(HEALTHCHECK_TIMEOUT)
+        .body(body)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+    if let Err(err) = result {
+        warn!(%url, %err, "Unable to ping healthcheck");
+    }
+}
+
+/// Writes the planned changes to the file (or stdout).
+fn write_plan(plan: &Plan, format: PlanFormat, file: &PipeFile) -> anyhow::Result<()> {
+    let plan = match format {
+        PlanFormat::Yaml => plan.to_yaml()?,
+        PlanFormat::Json => plan.to_json()? + "\n",
+    };
+    info!(output=?file, "Writing plan");
+    write_output(file, &plan)
+}
+
+/// Writes the run summary to the file or stderr.
+fn write_summary(
+    summary: &Summary,
+    format: SummaryFormat,
+    file: Option<&str>,
+) -> anyhow::Result<()> {
+    let summary_text = match format {
+        SummaryFormat::Human => summary.to_human(),
+        SummaryFormat::Json => summary.to_json()?,
+        SummaryFormat::None => return Ok(()),
+    };
+    match file {
+        Some(path) => write_atomic(Path::new(path), &(summary_text + "\n"))
+            .context(format!("unable to write summary file `{path}`")),
+        None => {
+            eprintln!("{summary_text}");
+            Ok(())
+        }
+    }
+}
+
+/// Writes the metrics to the file and/or pushes them to the Pushgateway.
+async fn write_metrics(
+    metrics: &str,
+    file: Option<&str>,
+    push_url: Option<&str>,
+    job: &str,
+) -> anyhow::Result<()> {
+    if let Some(path) = file {
+        info!(output=%path, "Writing metrics");
+        write_atomic(Path::new(path), metrics)
+            .context(format!("unable to write metrics file `{path}`"))?;
+    }
+
+    if let Some(url) = push_url {
+        let url = format!("{}/metrics/job/{job}", url.trim_end_matches('/'));
+        info!(%url, "Pushing metrics");
+        reqwest::Client::new()
+            .put(&url)
+            .body(metrics.to_string())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context(format!("unable to push metrics to `{url}`"))?;
+    }
+
+    Ok(())
+}
+
+/// Parses the command line merged with the config file (see --config).
+fn parse_args() -> anyhow::Result<Cli> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    // The config file may provide required options (e.g. --spreadsheet-id) so the command line is
+    // validated after the config file is merged
+    let Ok(matches) = Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(&args)
+    else {
+        return Ok(Cli::parse_from(args));
+    };
+    let Some(path) = matches.get_one::<String>("config") else {
+        return Ok(Cli::parse_from(args));
+    };
+
+    let config =
+        std::fs::read_to_string(path).context(format!("unable to read config file `{path}`"))?;
+    let config: serde_yaml::Mapping =
+        serde_yaml::from_str(&config).context(format!("unable to parse config file `{path}`"))?;
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    let config =
+        profile_config(config, profile).context(format!("invalid config file `{path}`"))?;
+    let (config_args, subcommand_args) =
+        config_args(&config, &matches).context(format!("invalid config file `{path}`"))?;
+
+    // The config args are inserted after the program name and before the command line args.  The
+    // subcommand args are appended after the subcommand.
+    let mut args = args.into_iter();
+    let args: Vec<OsString> = args
+        .next()
+        .into_iter()
+        .chain(config_args.into_iter().map(OsString::from))
+        .chain(args)
+        .chain(subcommand_args.into_iter().map(OsString::from))
+        .collect();
+
+    Ok(Cli::parse_from(args))
+}
+
+/// Returns the config file options with the options of the profile applied.
+///
+/// The profiles are given by the `profiles` key as a map of profile names to options.
+fn profile_config(
+    mut config: serde_yaml::Mapping,
+    profile: Option<&str>,
+) -> anyhow::Result<serde_yaml::Mapping> {
+    let profiles = match config.remove("profiles") {
+        None => serde_yaml::Mapping::new(),
+        Some(serde_yaml::Value::Mapping(profiles)) => profiles,
+        Some(value) => anyhow::bail!("invalid profiles `{value:?}`, expected a map of profiles"),
+    };
+    let Some(profile) = profile else {
+        return Ok(config);
+    };
+
+    match profiles.get(profile) {
+        Some(serde_yaml::Value::Mapping(options)) => {
+            config.extend(options.clone());
+            Ok(config)
+        }
+        Some(value) => anyhow::bail!("invalid profile `{profile}` `{value:?}`, expected options"),
+        None => anyhow::bail!("unknown profile `{profile}`"),
+    }
+}
+
+/// Converts the config file into command line args.
+///
+/// Returns the args of the top-level options and the args of the options of the subcommand given
+/// on the command line (e.g. --spreadsheet-id of `users sync-sheet`).  Options of other
+/// subcommands are skipped so that one config file serves every subcommand.  Options already given
+/// on the command line or by environment variables are skipped so that they take precedence.
+fn config_args(
+    config: &serde_yaml::Mapping,
+    matches: &ArgMatches,
+) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let command = Cli::command();
+    let mut subcommand = &command;
+    let mut subcommand_matches = matches;
+    while let Some((name, sub_matches)) = subcommand_matches.subcommand() {
+        let Some(sub) = subcommand.find_subcommand(name) else {
+            break;
+        };
+        subcommand = sub;
+        subcommand_matches = sub_matches;
+    }
+
+    let mut top_args = Vec::new();
+    let mut subcommand_args = Vec::new();
+
+    for (key, value) in config {
+        let key = key
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("invalid key `{key:?}`, expected an option name"))?;
+        let find = |command: &clap::Command| {
+            command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(key))
+                .cloned()
+        };
+        let (arg, matches, args) = if let Some(arg) = find(&command) {
+            (arg, matches, &mut top_args)
+        } else if let Some(arg) = find(subcommand) {
+            (arg, subcommand_matches, &mut subcommand_args)
+        } else if is_subcommand_option(&command, key) {
+            continue;
+        } else {
+            anyhow::bail!("unknown option `{key}`");
+        };
+        if matches!(
+            matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+
+        let values = match value {
+            serde_yaml::Value::Sequence(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                serde_yaml::Value::Bool(value) if !arg.get_action().takes_values() => {
+                    if *value {
+                        args.push(format!("--{key}"));
+                    }
+                    continue;
+                }
+                serde_yaml::Value::Bool(value) => value.to_string(),
+                serde_yaml::Value::Number(value) => value.to_string(),
+                serde_yaml::Value::String(value) => value.clone(),
+                value => anyhow::bail!("invalid value `{value:?}` for option `{key}`"),
+            };
+            args.push(format!("--{key}={value}"));
+        }
+    }
+
+    Ok((top_args, subcommand_args))
+}
+
+/// Returns true if the option is an option of any subcommand.
+fn is_subcommand_option(command: &clap::Command, key: &str) -> bool {
+    command.get_subcommands().any(|subcommand| {
+        subcommand
+            .get_arguments()
+            .any(|arg| arg.get_long() == Some(key))
+            || is_subcommand_option(subcommand, key)
+    })
+}
+
+/// Returns the --env-file option without validating the command line.
+///
+/// The env file may provide required options (e.g. MAILCHIMP_API_KEY) so the command line is
+/// validated after the env file is loaded.
+fn env_file_arg(args: impl IntoIterator<Item = OsString>) -> Option<String> {
+    Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok()?
+        .get_one::<String>("env_file")
+        .cloned()
+}
+
+/// Loads environment variables from an env file.
+///
+/// Variables already set in the environment take precedence.  If no path is given, `.env` in the
+/// current directory is loaded if it exists.  Parent directories are not searched.
+fn load_env_file(path: Option<&str>) -> anyhow::Result<Option<PathBuf>> {
+    match path {
+        Some(path) => {
+            dotenvy::from_filename(path)
+                .with_context(|| format!("unable to load env file `{path}`"))?;
+            Ok(Some(PathBuf::from(path)))
+        }
+        None => match dotenvy::from_path(DEFAULT_ENV_FILE) {
+            Ok(()) => Ok(Some(PathBuf::from(DEFAULT_ENV_FILE))),
+            Err(e) if e.not_found() => Ok(None),
+            Err(e) => Err(e).context("unable to load env file `.env`"),
+        },
+    }
+}
+
+/// Prompts for the SCMA credentials with hidden password input.
+///
+/// After a successful prompt, the user is offered to store the credentials in the OS keyring for
+/// subsequent runs.  Fails if stdin is not a terminal.
+fn prompt_credentials(username: &str) -> anyhow::Result<Credentials> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "SCMA username and password are required, use --username and --password or set SCMA_USERNAME and SCMA_PASSWORD"
+        );
+    }
+
+    let username = if username.is_empty() {
+        prompt_line("SCMA username: ")?
+    } else {
+        username.to_string()
+    };
+    let password = rpassword::prompt_password(format!("SCMA password for {username}: "))
+        .context("unable to read SCMA password")?;
+    let credentials = Credentials { username, password };
+
+    if prompt_yes_no("Store SCMA credentials in the OS keyring?")? {
+        credentials.store_in_keyring()?;
+    }
+
+    Ok(credentials)
+}
+
+fn prompt_line(prompt: &str) -> anyhow::Result<String> {
+    eprint!("{prompt}");
+    std::io::stderr().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context("unable to read from stdin")?;
+
+    Ok(line.trim().to_string())
+}
+
+fn prompt_yes_no(prompt: &str) -> anyhow::Result<bool> {
+    let answer = prompt_line(&format!("{prompt} [y/N] "))?;
+
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Returns the JSON if given.  Otherwise, returns the secret file or fetches the secret if the
+/// secret file is a secret manager URI (e.g. `gcp-sm://project/secret`).
+async fn secret_from_args(args: &Cli, json: &Option<String>) -> Result<Secret, Error> {
+    if let Some(json) = json {
+        return Ok(Secret::Json(json.clone()));
+    }
+
+    match SecretRef::parse(&args.client_secret_json_path)? {
+        Some(secret) => Ok(Secret::Json(secret.fetch().await?)),
+        None => Ok(Secret::File(args.client_secret_json_path.clone())),
+    }
+}
+
+async fn auth_from_args(args: &Cli, infer_type: AuthType) -> Result<GAuth, Error> {
+    let auth_type = match args.auth_type {
+        AuthType::Infer => infer_type,
+        AuthType::OAuth | AuthType::OAuthDevice | AuthType::ServiceAccount => args.auth_type,
+    };
+
+    let token_store = if args.keyring_storage {
+        TokenStore::Keyring(args.oauth_token_json_path.clone())
+    } else {
+        TokenStore::File(args.oauth_token_json_path.clone())
+    };
+
+    let mut auth = match auth_type {
+        AuthType::OAuth => {
+            let secret = secret_from_args(args, &args.client_secret_json).await?;
+            GAuth::with_oauth(&secret, &token_store).await?
+        }
+        AuthType::OAuthDevice => {
+            let secret = secret_from_args(args, &args.client_secret_json).await?;
+            GAuth::with_device_flow(&secret, &token_store).await?
+        }
+        AuthType::ServiceAccount => {
+            let secret = secret_from_args(args, &args.service_account_json).await?;
+            GAuth::with_service_account(&secret, args.impersonate.as_deref()).await?
+        }
+        AuthType::Infer => unreachable!("Due to match above"),
+    };
+    if let Some(ref url) = args.calendar_root_url {
+        auth.set_calendar_root_url(url);
+    }
+    if let Some(ref url) = args.people_root_url {
+        auth.set_people_root_url(url);
+    }
+
+    Ok(auth)
+}
+
+async fn web_from_args(
+    args: &Cli,
+    dates: DateSelect,
+    summary: &Summary,
+) -> Result<Web, Box<dyn std::error::Error>> {
+    if let Some(ref dir) = args.replay {
+        info!(fixtures=%dir, "Replaying SCMA website fixtures");
+        let mut web = Web::replay(dates, Fixtures::replay(dir))?;
+        web.set_timezone(args.timezone);
+        return Ok(web);
+    }
+
+    let credentials = Credentials::resolve(&args.username, &args.password, prompt_credentials)?;
+    let session_cookies = match args.session_file {
+        None => None,
+        Some(ref path) if args.keyring_storage => keyring_get(path),
+        Some(ref path) => match std::fs::read_to_string(path) {
+            Ok(session_cookies) => Some(session_cookies),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => Err(e).context(format!("unable to read session file `{path}`"))?,
+        },
+    };
+    let mut web = summary
+        .time(
+            Phase::Login,
+            Web::with_session(
+                &credentials.username,
+                &credentials.password,
+                dates,
+                session_cookies.as_deref(),
+            ),
+        )
+        .await?;
+    if let (Some(path), Some(session_cookies)) = (&args.session_file, web.session_cookies()) {
+        if args.keyring_storage {
+            keyring_set(path, &session_cookies)?;
+        } else {
+            write_atomic(Path::new(path), &session_cookies)
+                .context(format!("unable to write session file `{path}`"))?;
+        }
+    }
+    web.set_max_retries(args.web_retries);
+    web.set_timezone(args.timezone);
+    if let Some(ref dir) = args.record {
+        web.set_fixtures(Fixtures::record(dir));
+    }
+    if let Some(ref dir) = args.page_cache_dir {
+        let cache = PageCache::new(dir);
+        let cache = match args.page_cache_ttl {
+            Some(ttl) => cache.ttl(ttl),
+            None => cache,
+        };
+        web.set_page_cache(cache);
+    }
+
+    Ok(web)
+}
+
+/// Authenticates with Google and finds (or creates) the calendar.
+async fn gcal_from_args_with_auth(
+    args: &Cli,
+    calendar_owners: &[String],
+    summary: &Summary,
+) -> Result<GCal, Box<dyn std::error::Error>> {
+    let calendar = match args.calendar_id {
+        Some(ref calendar_id) => CalendarSelect::Id(calendar_id.clone()),
+        None => CalendarSelect::Name(args.calendar.clone()),
+    };
+
+    summary
+        .time(Phase::GoogleAuth, async {
+            let auth = auth_from_args(args, AuthType::ServiceAccount).await?;
+            gcal_from_args(args, auth, &calendar, calendar_owners, summary.plan()).await
+        })
+        .await
+}
+
+/// Authenticates with Google and finds (or creates) the calendar and the routed calendars.
+async fn calendars_from_args_with_auth(
+    args: &Cli,
+    calendar_owners: &[String],
+    summary: &Summary,
+) -> Result<Calendars, Box<dyn std::error::Error>> {
+    let routes = calendar_routes_from_args(args)?;
+    if !routes.is_empty() && (args.incremental || args.local_state) {
+        return Err(
+            "--calendar-routes-file cannot be used with --incremental or --local-state".into(),
+        );
+    }
+
+    let default = gcal_from_args_with_auth(args, calendar_owners, summary).await?;
+    let mut routed = Vec::with_capacity(routes.len());
+    for (calendar_name, route) in routes {
+        let calendar = CalendarSelect::Name(calendar_name);
+        let gcal = summary
+            .time(Phase::GoogleAuth, async {
+                let auth = auth_from_args(args, AuthType::ServiceAccount).await?;
+                gcal_from_args(args, auth, &calendar, calendar_owners, summary.plan()).await
+            })
+            .await?;
+        routed.push((route, gcal));
+    }
+
+    Ok(Calendars { default, routed })
+}
+
+fn calendar_routes_from_args(args: &Cli) -> anyhow::Result<IndexMap<String, CalendarRoute>> {
+    let calendar_routes = match args.calendar_routes_file {
+        None => IndexMap::new(),
+        Some(ref path) => {
+            let calendar_routes = std::fs::read_to_string(path)
+                .context(format!("unable to read calendar routes file `{path}`"))?;
+            serde_yaml::from_str(&calendar_routes)
+                .context(format!("unable to parse calendar routes file `{path}`"))?
+        }
+    };
+
+    Ok(calendar_routes)
+}
+
+async fn gcal_from_args(
+    args: &Cli,
+    auth: GAuth,
+    calendar: &CalendarSelect,
+    calendar_owners: &[String],
+    plan: Arc<Plan>,
+) -> Result<GCal, Box<dyn std::error::Error>> {
+    let title_markers = match args.title_markers_file {
+        None => IndexMap::new(),
+        Some(ref path) => {
+            let title_markers = std::fs::read_to_string(path)
+                .context(format!("unable to read title markers file `{path}`"))?;
+            serde_yaml::from_str(&title_markers)
+                .context(format!("unable to parse title markers file `{path}`"))?
+        }
+    };
+
+    let category_colors = match args.category_colors_file {
+        None => IndexMap::new(),
+        Some(ref path) => {
+            let category_colors = std::fs::read_to_string(path)
+                .context(format!("unable to read category colors file `{path}`"))?;
+            serde_yaml::from_str(&category_colors)
+                .context(format!("unable to parse category colors file `{path}`"))?
+        }
+    };
+
+    let description_template = match args.description_template {
+        None => None,
+        Some(ref path) => {
+            let template = std::fs::read_to_string(path)
+                .context(format!("unable to read description template file `{path}`"))?;
+            let template = DescriptionTemplate::new(&template).context(format!(
+                "unable to parse description template file `{path}`"
+            ))?;
+            Some(template)
+        }
+    };
+
+    let event_options = EventOptions {
+        summary_format: Some(args.event_summary_format.clone()),
+        attendee_count: args.summary_attendee_count,
+        attendee_guests: args.attendee_guests,
+        title_markers,
+        category_colors,
+        guests_can_invite_others: args.guests_can_invite_others.map(bool::from),
+        guests_can_modify: args.guests_can_modify.map(bool::from),
+        guests_can_see_other_guests: args.guests_can_see_other_guests.map(bool::from),
+        description_include: DescriptionInclude {
+            attendees: args
+                .description_include
+                .contains(&DescriptionSection::Attendees),
+            attendee_comments: args
+                .description_include
+                .contains(&DescriptionSection::AttendeeComments),
+            comments: args
+                .description_include
+                .contains(&DescriptionSection::Comments),
+            counts: args
+                .description_include
+                .contains(&DescriptionSection::Counts),
+        },
+        description_template,
+        reminders: (!args.event_reminders.is_empty()).then(|| args.event_reminders.clone()),
+        lang: args.lang.into(),
+        timezone: Some(args.timezone),
+    };
+
+    let mut gcal = GCal::new(
+        calendar,
+        calendar_owners,
+        auth,
+        args.dry_run,
+        args.notify_acl_insert.into(),
+        event_options,
+        plan,
+    )
+    .await?;
+    gcal.set_acl_groups(args.acl_groups.clone(), !args.no_acl_users);
+    gcal.set_acl_domain(args.share_domain.clone());
+    gcal.set_acl_public(args.public);
+    gcal.set_acl_no_delete(args.acl_no_delete);
+    gcal.set_acl_include_optedout(args.include_optedout);
+    gcal.set_continue_on_error(args.continue_on_error);
+
+    Ok(gcal)
+}
+
+fn event_filter_from_args(args: &Cli) -> anyhow::Result<EventFilter> {
+    let exclude_ids = match args.exclude_events_file {
+        None => Vec::new(),
+        Some(ref path) => {
+            let exclude_ids = std::fs::read_to_string(path)
+                .context(format!("unable to read event exclusions file `{path}`"))?;
+            let exclude_ids: Vec<serde_yaml::Value> = serde_yaml::from_str(&exclude_ids)
+                .context(format!("unable to parse event exclusions file `{path}`"))?;
+            exclude_ids
+                .into_iter()
+                .map(|id| match id {
+                    serde_yaml::Value::String(id) => Ok(id),
+                    serde_yaml::Value::Number(id) => Ok(id.to_string()),
+                    _ => Err(anyhow::anyhow!(
+                        "unable to parse event exclusions file `{path}`: expected a list of event IDs"
+                    )),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        }
+    };
+
+    let filter = EventFilter::new()
+        .exclude_ids(exclude_ids)
+        .exclude_ids(args.exclude_event_ids.iter().cloned())
+        .include_ids(args.only_event_ids.iter().cloned())
+        .match_title(&args.match_title)
+        .match_location(&args.match_location)
+        .dedup(args.dedup);
+
+    let filter = match args.horizon {
+        Some(horizon) => {
+            let until = days_after_today(args.timezone, horizon).context("invalid --horizon")?;
+            info!(%until, "Limiting sync to events starting on or before horizon");
+            filter.until(until)
+        }
+        None => filter,
+    };
+    let filter = match since_from_args(args)? {
+        Some(since) => {
+            info!(%since, "Limiting sync to events ending on or after date");
+            filter.since(since)
+        }
+        None => filter,
+    };
+    let filter = match args.until {
+        Some(until) => {
+            let until = match until {
+                DateArg::Date(date) => date,
+                DateArg::Days(days) => {
+                    days_after_today(args.timezone, days).context("invalid --until")?
+                }
+            };
+            info!(%until, "Limiting sync to events starting on or before date");
+            filter.until(until)
+        }
+        None => filter,
+    };
+
+    Ok(filter)
+}
+
+/// Reads the input file or stdin.
+fn read_input(file: &PipeFile) -> anyhow::Result<String> {
+    match file {
+        PipeFile::Pipe => {
+            let stdin = std::io::stdin();
+            if stdin.is_terminal() {
+                anyhow::bail!("unable to read stdin: expected YAML piped in, not a terminal");
+            }
+            std::io::read_to_string(stdin).context("unable to read stdin")
+        }
+        PipeFile::File(path) => {
+            std::fs::read_to_string(path).context(format!("unable to read input file `{path}`"))
+        }
+    }
+}
+
+/// Writes the output file or stdout.
+fn write_output(file: &PipeFile, content: &str) -> anyhow::Result<()> {
+    match file {
+        PipeFile::Pipe => {
+            print!("{content}");
+            Ok(())
+        }
+        PipeFile::File(path) => write_atomic(Path::new(path), content)
+            .context(format!("unable to write output file `{path}`")),
+    }
+}
+
+fn dates_from_args(args: &Cli) -> anyhow::Result<DateSelect> {
+    let dates = match since_from_args(args)? {
+        Some(since) => DateSelect::Since(since),
+        None if args.all => DateSelect::All,
+        None => DateSelect::NotPast,
+    };
+
+    Ok(dates)
+}
+
+/// Returns the `--since` date, if any.
+fn since_from_args(args: &Cli) -> anyhow::Result<Option<NaiveDate>> {
+    let since = match args.since {
+        None => None,
+        Some(DateArg::Date(date)) => Some(date),
+        Some(DateArg::Days(days)) => {
+            Some(days_before_today(args.timezone, days).context("invalid --since")?)
+        }
+    };
+
+    Ok(since)
+}
+
+async fn process_events(
+    args: Cli,
+    input: &InputArgs,
+    output: EventOutput,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dates = dates_from_args(&args)?;
+    let filter = event_filter_from_args(&args)?;
+
+    if input.input_file.is_some() {
+        if args.email_invites {
+            return Err("--email-invites cannot be used with --ifile".into());
+        }
+        if args.sms_reminders {
+            return Err("--sms-reminders cannot be used with --ifile".into());
+        }
+        if args.only_mine {
+            return Err("--only-mine cannot be used with --ifile".into());
+        }
+        if args.attendee_guests {
+            return Err("--attendee-guests cannot be used with --ifile".into());
+        }
+    }
+
+    // Log in to the SCMA website and authenticate with Google concurrently
+    let (web, sink) = tokio::try_join!(
+        web_from_input(&args, input, dates, summary),
+        event_sink(&args, output, &filter, summary),
+    )?;
+    let file = file_from_input(input, "events")?;
+    let source: &dyn EventSource = match (&web, &file) {
+        (Some(web), _) => web,
+        (None, Some(file)) => file.events(),
+        (None, None) => unreachable!("Due to web_from_input"),
+    };
+
+    let mut events = summary
+        .time(Phase::EventRead, source.read_events(&filter))
+        .await?;
+    summary.record_duplicates(filter.duplicates());
+    write_changelog(&args, &filter, &events).await?;
+    write_archive(&args, |archive, seen| archive.write_events(&events, seen))?;
+    if let Some(ref web) = web {
+        if let Some(attendee) = attendee_from_args(&args, web, &events, summary).await? {
+            events.retain(|event| event.is_attending(&attendee));
+        }
+    }
+    sort_events(&mut events);
+
+    let users = match web {
+        Some(ref web) if args.attendee_guests || args.email_invites || args.sms_reminders => {
+            summary.time(Phase::UserFetch, web.fetch_users()).await?
+        }
+        _ => Vec::new(),
+    };
+    if args.attendee_guests {
+        events
+            .iter_mut()
+            .for_each(|event| event.set_attendee_emails(&users));
+    }
+
+    sink.write_events(&events).await?;
+
+    notify_attendees(&args, &users, &events, summary).await?;
+
+    Ok(())
+}
+
+/// Logs in to the SCMA website unless reading from `--ifile`.
+async fn web_from_input(
+    args: &Cli,
+    input: &InputArgs,
+    dates: DateSelect,
+    summary: &Summary,
+) -> Result<Option<Web>, Box<dyn std::error::Error>> {
+    match input.input_file {
+        None => Ok(Some(web_from_args(args, dates, summary).await?)),
+        Some(_) => Ok(None),
+    }
+}
+
+/// Events or users read from `--ifile`
+enum FileInput {
+    Yaml(YamlInput),
+    Json(JsonInput),
+}
+
+impl FileInput {
+    fn events(&self) -> &dyn EventSource {
+        match self {
+            Self::Yaml(yaml) => yaml,
+            Self::Json(json) => json,
+        }
+    }
+
+    fn users(&self) -> &dyn UserSource {
+        match self {
+            Self::Yaml(yaml) => yaml,
+            Self::Json(json) => json,
+        }
+    }
+}
+
+/// Reads `--ifile` if given.
+fn file_from_input(input: &InputArgs, what: &str) -> anyhow::Result<Option<FileInput>> {
+    match input.input_file {
+        None => Ok(None),
+        Some(ref input_file) => {
+            info!(input=?input_file, "Reading {what}");
+            let content = read_input(input_file)?;
+            Ok(Some(match input.input_format {
+                InputFormat::Yaml => FileInput::Yaml(YamlInput::new(content)),
+                InputFormat::Json => FileInput::Json(JsonInput::new(content)),
+            }))
+        }
+    }
+}
+
+async fn event_sink<'a>(
+    args: &'a Cli,
+    output: EventOutput,
+    filter: &EventFilter,
+    summary: &'a Summary,
+) -> Result<Box<dyn EventSink + 'a>, Box<dyn std::error::Error>> {
+    let sink: Box<dyn EventSink + 'a> = match output {
+        EventOutput::GCal => {
+            let mut calendars =
+                calendars_from_args_with_auth(args, &args.calendar_owners, summary).await?;
+            load_gcal_state(args, &mut calendars.default)?;
+            if !args.default_reminders.is_empty() {
+                for gcal in calendars.all() {
+                    gcal.set_default_reminders(&args.default_reminders).await?;
+                }
+            }
+            Box::new(CalendarSink {
+                args,
+                calendars,
+                filter: filter.clone(),
+                summary,
+            })
+        }
+        EventOutput::Yaml(file) => Box::new(FileSink::new(file, FileFormat::Yaml, args)),
+        EventOutput::Ics(file) => Box::new(FileSink::new(file, FileFormat::Ics, args)),
+        EventOutput::Csv(file) => Box::new(FileSink::new(file, FileFormat::Csv, args)),
+        EventOutput::Json(file) => Box::new(FileSink::new(file, FileFormat::Json, args)),
+        EventOutput::Markdown(file) => Box::new(FileSink::new(file, FileFormat::Markdown, args)),
+    };
+
+    Ok(sink)
+}
+
+/// Writes to Google Calendar using the state file and handling orphaned events as directed by
+/// the options.
+struct CalendarSink<'a> {
+    args: &'a Cli,
+    calendars: Calendars,
+    filter: EventFilter,
+    summary: &'a Summary,
+}
+
+#[async_trait]
+impl EventSink for CalendarSink<'_> {
+    async fn write_events(&self, events: &[Event]) -> Result<(), Error> {
+        for (gcal, events) in self.calendars.partition(events) {
+            self.summary
+                .time(Phase::EventWrites, gcal.write_events(&events))
+                .await?;
+            self.summary.record_events_synced(events.len());
+            reconcile_orphans(self.args, gcal, &events, &self.filter, self.summary).await?;
+        }
+        save_gcal_state(self.args, &self.calendars.default).map_err(|err| Error::File(err.into()))
+    }
+}
+
+/// Syncs the calendar readers after applying the email aliases.
+#[async_trait]
+impl UserSink for CalendarSink<'_> {
+    async fn write_users(&self, users: Vec<User>) -> Result<(), Error> {
+        let email_aliases =
+            acl_email_aliases_from_args(self.args).map_err(|err| Error::File(err.into()))?;
+        info!(?email_aliases, "Applying email aliases");
+        let users: Vec<User> = users
+            .into_iter()
+            .map(|mut user| {
+                if let Some(alias) = email_aliases.get(&user.email) {
+                    user.email = alias.clone();
+                }
+                user
+            })
+            .collect();
+
+        for gcal in self.calendars.all() {
+            self.summary
+                .time(Phase::AclSync, gcal.write_users(users.clone()))
+                .await?;
+        }
+        save_learned_aliases(self.args, &self.calendars).map_err(|err| Error::File(err.into()))?;
+        save_gcal_state(self.args, &self.calendars.default).map_err(|err| Error::File(err.into()))
+    }
+}
+
+/// The calendars to sync to.
+///
+/// Events are routed to the first calendar with a matching route (see --calendar-routes-file) and
+/// otherwise to the default calendar.
+struct Calendars {
+    default: GCal,
+    routed: Vec<(CalendarRoute, GCal)>,
+}
+
+impl Calendars {
+    fn all(&self) -> impl Iterator<Item = &GCal> {
+        std::iter::once(&self.default).chain(self.routed.iter().map(|(_, gcal)| gcal))
+    }
+
+    /// Returns the index of the calendar of the event in all().
+    fn route_index(&self, event: &Event) -> usize {
+        self.routed
+            .iter()
+            .position(|(route, _)| route.matches(event))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// Returns the calendar of the event.
+    fn route(&self, event: &Event) -> &GCal {
+        match self.route_index(event) {
+            0 => &self.default,
+            index => &self.routed[index - 1].1,
+        }
+    }
+
+    /// Returns the events of each calendar.
+    ///
+    /// Every calendar is included even if it has no events so that its orphans are reconciled.
+    fn partition(&self, events: &[Event]) -> Vec<(&GCal, Vec<Event>)> {
+        let mut partitions: Vec<(&GCal, Vec<Event>)> =
+            self.all().map(|gcal| (gcal, Vec::new())).collect();
+        for event in events {
+            partitions[self.route_index(event)].1.push(event.clone());
+        }
+
+        partitions
+    }
+}
+
+/// Writes to Google Contacts.
+struct ContactsSink<'a> {
+    gppl: GPpl,
+    summary: &'a Summary,
+}
+
+#[async_trait]
+impl UserSink for ContactsSink<'_> {
+    async fn write_users(&self, users: Vec<User>) -> Result<(), Error> {
+        self.summary
+            .time(Phase::ContactSync, self.gppl.write_users(users))
+            .await
+    }
+}
+
+/// The format of a file written by a `FileSink`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum FileFormat {
+    Yaml,
+    Ics,
+    Csv,
+    Json,
+    Vcf,
+    Markdown,
+}
+
+/// Writes to a file (or stdout).
+struct FileSink {
+    file: PipeFile,
+    format: FileFormat,
+    /// The iCalendar calendar name
+    calendar: String,
+    /// The personal information omitted from YAML and JSON users
+    redact: Redact,
+    /// The timezone of the iCalendar event times and Markdown comment dates
+    timezone: Tz,
+}
+
+impl FileSink {
+    fn new(file: PipeFile, format: FileFormat, args: &Cli) -> Self {
+        Self {
+            file,
+            format,
+            calendar: args.calendar.clone(),
+            redact: Redact::None,
+            timezone: args.timezone,
+        }
+    }
+
+    fn redact(mut self, redact: Redact) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    fn write(&self, content: &str) -> Result<(), Error> {
+        write_output(&self.file, content).map_err(|err| Error::File(err.into()))
+    }
+}
+
+#[async_trait]
+impl EventSink for FileSink {
+    async fn write_events(&self, events: &[Event]) -> Result<(), Error> {
+        info!(output=?self.file, "Writing events");
+        match self.format {
+            FileFormat::Yaml => {
+                self.write(&serde_yaml::to_string(events).map_err(|err| Error::File(err.into()))?)
+            }
+            FileFormat::Ics => self.write(&events_to_ics(events, &self.calendar, self.timezone)),
+            FileFormat::Csv => self.write(&events_to_csv(events)?),
+            FileFormat::Json => self.write(
+                &serde_json::to_string_pretty(events).map_err(|err| Error::File(err.into()))?,
+            ),
+            FileFormat::Markdown => self.write(&events_to_markdown(events, self.timezone)),
+            FileFormat::Vcf => unreachable!("Events are not exported as vCard"),
+        }
+    }
+}
+
+#[async_trait]
+impl UserSink for FileSink {
+    async fn write_users(&self, mut users: Vec<User>) -> Result<(), Error> {
+        sort_users(&mut users);
+
+        info!(output=?self.file, "Writing users");
+        let redacted: Vec<RedactedUser> = users
+            .iter()
+            .map(|user| RedactedUser::new(user, self.redact))
+            .collect();
+        match self.format {
+            FileFormat::Yaml => self
+                .write(&serde_yaml::to_string(&redacted).map_err(|err| Error::File(err.into()))?),
+            FileFormat::Csv => self.write(&users_to_csv(&users)?),
+            FileFormat::Json => self.write(
+                &serde_json::to_string_pretty(&redacted).map_err(|err| Error::File(err.into()))?,
+            ),
+            FileFormat::Vcf => self.write(&users_to_vcf(&users)),
+            FileFormat::Ics => unreachable!("Users are not exported as iCalendar"),
+            FileFormat::Markdown => unreachable!("Users are not exported as Markdown"),
+        }
+    }
+}
+
+/// Enables incremental sync for `--incremental` or diffing against the pushed state for
+/// `--local-state` using the state file.
+fn load_gcal_state(args: &Cli, gcal: &mut GCal) -> anyhow::Result<()> {
+    if args.incremental || args.local_state {
+        let state = State::load(Path::new(&args.state_file))?;
+        if args.incremental {
+            gcal.set_event_cache(state.calendar.unwrap_or_default());
+        }
+        if args.local_state {
+            gcal.set_pushed_state(state.pushed.unwrap_or_default());
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the calendar event cache and the pushed state in the state file for the next run.
+fn save_gcal_state(args: &Cli, gcal: &GCal) -> anyhow::Result<()> {
+    let cache = gcal.event_cache();
+    let pushed = gcal.pushed_state();
+    if args.dry_run || (cache.is_none() && pushed.is_none()) {
+        return Ok(());
+    }
+
+    let state_file = Path::new(&args.state_file);
+    let mut state = State::load(state_file)?;
+    if cache.is_some() {
+        state.calendar = cache;
+    }
+    if pushed.is_some() {
+        state.pushed = pushed;
+    }
+    state.save(state_file)?;
+
+    Ok(())
+}
+
+/// Cancels or deletes calendar events that were not synced as directed by `--orphans`.
+async fn reconcile_orphans(
+    args: &Cli,
+    gcal: &GCal,
+    events: &[Event],
+    filter: &EventFilter,
+    summary: &Summary,
+) -> Result<(), Error> {
+    let action = match args.orphans {
+        Orphans::Ignore => return Ok(()),
+        Orphans::Cancel => OrphanAction::Cancel,
+        Orphans::CancelStatus => OrphanAction::CancelStatus,
+        Orphans::Delete => OrphanAction::Delete,
+    };
+    if filter.is_partial() {
+        info!("Not reconciling orphaned events of a partial event selection");
+        return Ok(());
+    }
+    let since = filter
+        .since_date()
+        .or_else(|| (!args.all).then(|| today(args.timezone)));
+
+    summary
+        .time(
+            Phase::EventWrites,
+            gcal.events_reconcile(events, since, filter.until_date(), action),
+        )
+        .await?;
+
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let filter = EnvFilter::from_default_env().add_directive("info".parse()?);
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(filter)
-        .init();
+/// Sends email invitations and SMS reminders to attendees as enabled.
+async fn notify_attendees(
+    args: &Cli,
+    users: &[User],
+    events: &[Event],
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Attendees without a user are not notified
+    let users: Vec<User> = users
+        .iter()
+        .filter(|user| args.include_optedout || !user.is_optedout_or_blocked())
+        .cloned()
+        .collect();
+    if args.email_invites {
+        send_invites(args, &users, events, summary).await?;
+    }
+    if args.sms_reminders {
+        send_reminders(args, &users, events, summary).await?;
+    }
 
-    let args = Cli::parse();
+    Ok(())
+}
 
-    rustls::crypto::aws_lc_rs::default_provider()
-        .install_default()
-        .unwrap();
+async fn send_invites(
+    args: &Cli,
+    users: &[User],
+    events: &[Event],
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = SmtpConfig {
+        host: args.smtp_host.clone().unwrap_or_default(),
+        port: args.smtp_port,
+        username: args.smtp_username.clone(),
+        password: args.smtp_password.clone(),
+        from: args.invite_from.clone().unwrap_or_default(),
+    };
+    let mut inviter = Inviter::new(config, Path::new(&args.invites_sent_file), args.dry_run)?;
+    inviter.set_timezone(args.timezone);
 
-    match args.data_type {
-        DataType::Events => process_events(args).await,
-        DataType::Users => process_users(args).await,
+    let mut result = Ok(());
+    for event in events {
+        result = summary
+            .time(Phase::InviteSend, inviter.invite(event, users))
+            .await;
+        if result.is_err() {
+            break;
+        }
     }
+
+    // Record the invitations sent before any failure to avoid re-sending them
+    inviter.save()?;
+
+    Ok(result?)
 }
 
-async fn auth_from_args(args: &Cli, infer_type: AuthType) -> anyhow::Result<GAuth> {
-    let auth_type = match args.auth_type {
-        AuthType::Infer => infer_type,
-        AuthType::OAuth | AuthType::ServiceAccount => args.auth_type,
+async fn send_reminders(
+    args: &Cli,
+    users: &[User],
+    events: &[Event],
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let opt_in: Vec<String> = match args.sms_opt_in_file {
+        None => Vec::new(),
+        Some(ref path) => {
+            let opt_in = std::fs::read_to_string(path)
+                .context(format!("unable to read SMS opt-in file `{path}`"))?;
+            serde_yaml::from_str(&opt_in)
+                .context(format!("unable to parse SMS opt-in file `{path}`"))?
+        }
+    };
+    let config = TwilioConfig {
+        account_sid: args.twilio_account_sid.clone().unwrap_or_default(),
+        auth_token: args.twilio_auth_token.clone().unwrap_or_default(),
+        from: args.twilio_from.clone().unwrap_or_default(),
     };
+    let mut reminder = Reminder::new(
+        config,
+        chrono::Duration::hours(args.sms_reminder_lead.into()),
+        opt_in,
+        Path::new(&args.sms_sent_file),
+        args.dry_run,
+    )?;
+    reminder.set_timezone(args.timezone);
 
-    match auth_type {
-        AuthType::OAuth => {
-            GAuth::with_oauth(&args.client_secret_json_path, &args.oauth_token_json_path).await
+    let mut result = Ok(());
+    for event in events {
+        result = summary
+            .time(Phase::SmsSend, reminder.remind(event, users))
+            .await;
+        if result.is_err() {
+            break;
         }
-        AuthType::ServiceAccount => {
-            GAuth::with_service_account(&args.client_secret_json_path).await
+    }
+
+    // Record the reminders sent before any failure to avoid re-sending them
+    reminder.save()?;
+
+    Ok(result?)
+}
+
+/// Writes the changelog of event changes since the previous run, posts it to the configured
+/// webhooks (Slack, Discord, and Telegram), and records the current events.
+async fn write_changelog(args: &Cli, filter: &EventFilter, events: &[Event]) -> anyhow::Result<()> {
+    if args.changelog_file.is_none()
+        && args.slack_webhook_url.is_none()
+        && args.discord_webhook_url.is_none()
+        && args.telegram_bot_token.is_none()
+    {
+        return Ok(());
+    }
+    if filter.is_partial() {
+        // The unselected events would be reported as removed
+        info!("Not writing changelog of a partial event selection");
+        return Ok(());
+    }
+
+    let state_file = Path::new(&args.state_file);
+    let mut state = State::load(state_file)?;
+    let changelog = Changelog::between(&state.events, events, today(args.timezone));
+    if let Some(ref changelog_file) = args.changelog_file {
+        info!(changes=%changelog.0.len(), output=%changelog_file, "Writing changelog");
+        match changelog_file.as_str() {
+            "-" => println!("{}", changelog.to_human()),
+            path => write_atomic(Path::new(path), &(changelog.to_human() + "\n"))
+                .context(format!("unable to write changelog file `{path}`"))?,
         }
-        AuthType::Infer => unreachable!("Due to match above"),
     }
+    if let Some(ref url) = args.slack_webhook_url {
+        SlackWebhook::new(url, args.dry_run)
+            .post(&changelog)
+            .await?;
+    }
+    if let Some(ref url) = args.discord_webhook_url {
+        DiscordWebhook::new(url, args.dry_run)
+            .post(&changelog)
+            .await?;
+    }
+    if let (Some(ref token), Some(ref chat_id)) = (&args.telegram_bot_token, &args.telegram_chat_id)
+    {
+        TelegramBot::new(token, chat_id, args.dry_run)
+            .post(&changelog)
+            .await?;
+    }
+
+    if !args.dry_run {
+        state.events = events.iter().map(EventSnapshot::from).collect();
+        state.save(state_file)?;
+    }
+
+    Ok(())
 }
 
-async fn process_events(args: Cli) -> Result<(), Box<dyn std::error::Error>> {
-    let dates = if args.all {
-        DateSelect::All
-    } else {
-        DateSelect::NotPast
-    };
-
-    match (args.input, args.output) {
-        (InputType::Web, OutputType::GCal) => {
-            // Handle this case specially to maximize concurrency
-            //
-            // I've found it difficult to do this in a more general fashion.
-            let auth = auth_from_args(&args, AuthType::ServiceAccount).await?;
-
-            let ((web, events), gcal) = tokio::try_join!(
-                web_events(&args.username, &args.password, dates),
-                GCal::new(
-                    &args.calendar,
-                    &args.calendar_owners,
-                    auth,
-                    args.dry_run,
-                    args.notify_acl_insert.into()
-                ),
-            )?;
-
-            stream::iter(events)
-                .map(|event| scma_to_gcal(event, &web, &gcal))
-                .buffer_unordered(CONCURRENT_REQUESTS)
-                .try_collect::<Vec<_>>()
+/// Writes to the `--archive-db` database if given.
+fn write_archive(
+    args: &Cli,
+    write: impl FnOnce(&mut Archive, DateTime<Utc>) -> Result<(), Error>,
+) -> anyhow::Result<()> {
+    let Some(ref path) = args.archive_db else {
+        return Ok(());
+    };
+    if args.dry_run {
+        info!(archive=%path, "Dry run, not archiving");
+        return Ok(());
+    }
+
+    let mut archive = Archive::open(Path::new(path))?;
+    write(&mut archive, Utc::now())?;
+
+    Ok(())
+}
+
+async fn refresh_events(args: Cli, summary: &Summary) -> Result<(), Box<dyn std::error::Error>> {
+    let dates = dates_from_args(&args)?;
+    let filter = event_filter_from_args(&args)?;
+
+    let ((web, events), calendars) = tokio::try_join!(
+        web_events(&args, dates, &filter, summary),
+        calendars_from_args_with_auth(&args, &[], summary),
+    )?;
+    summary.record_duplicates(filter.duplicates());
+    let attendee = attendee_from_args(&args, &web, &events, summary).await?;
+
+    stream::iter(events)
+        .map(|event| {
+            let gcal = calendars.route(&event);
+            scma_to_gcal_description(event, &web, gcal, attendee.as_deref(), summary)
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    Ok(())
+}
+
+/// Reads the users including their profile photos if `photos` and reading from the SCMA website.
+async fn read_users(
+    args: &Cli,
+    input: &InputArgs,
+    photos: bool,
+    summary: &Summary,
+) -> Result<Vec<User>, Box<dyn std::error::Error>> {
+    let web = web_from_input(args, input, DateSelect::NotPast, summary).await?;
+    let file = file_from_input(input, "users")?;
+    let source: &dyn UserSource = match (&web, &file) {
+        (Some(web), _) => web,
+        (None, Some(file)) => file.users(),
+        (None, None) => unreachable!("Due to web_from_input"),
+    };
+
+    let users = summary.time(Phase::UserFetch, source.read_users()).await?;
+    let mut users = user_filter_from_args(args).apply(users);
+    if let (true, Some(web)) = (photos, &web) {
+        summary
+            .time(Phase::UserFetch, web.fetch_users_photos(&mut users))
+            .await?;
+    }
+
+    Ok(users)
+}
+
+fn user_filter_from_args(args: &Cli) -> UserFilter {
+    UserFilter::new()
+        .member_statuses(args.member_statuses.iter().cloned())
+        .exclude_resigned(args.exclude_resigned)
+}
+
+fn email_aliases_from_args(args: &Cli) -> anyhow::Result<HashMap<String, String>> {
+    let email_aliases = match args.email_aliases_file {
+        None => HashMap::new(),
+        Some(ref path) => {
+            let email_aliases = std::fs::read_to_string(path)
+                .context(format!("unable to read email aliases file `{path}`"))?;
+            serde_yaml::from_str(&email_aliases)
+                .context(format!("unable to parse email aliases file `{path}`"))?
+        }
+    };
+
+    Ok(email_aliases)
+}
+
+/// Returns the email aliases of the calendar ACL sync.
+///
+/// Combines the email aliases learned from previous ACL syncs with the email aliases file.  The
+/// learned aliases are also applied to the aliases of the file.
+fn acl_email_aliases_from_args(args: &Cli) -> anyhow::Result<HashMap<String, String>> {
+    let learned = State::load(Path::new(&args.state_file))?.email_aliases;
+    let mut email_aliases: HashMap<String, String> = learned.clone().into_iter().collect();
+    for (email, alias) in email_aliases_from_args(args)? {
+        let alias = learned.get(&alias).cloned().unwrap_or(alias);
+        email_aliases.insert(email, alias);
+    }
+
+    Ok(email_aliases)
+}
+
+/// Records the email aliases learned from the ACL inserts in the state file for the next run.
+fn save_learned_aliases(args: &Cli, calendars: &Calendars) -> anyhow::Result<()> {
+    let learned: Vec<(String, String)> = calendars
+        .all()
+        .flat_map(|gcal| gcal.learned_aliases())
+        .collect();
+    if args.dry_run || learned.is_empty() {
+        return Ok(());
+    }
+
+    let state_file = Path::new(&args.state_file);
+    let mut state = State::load(state_file)?;
+    state.email_aliases.extend(learned);
+    state.save(state_file)?;
+
+    Ok(())
+}
+
+fn contact_groups_from_args(args: &Cli) -> anyhow::Result<IndexMap<String, MembershipRule>> {
+    let contact_groups = match args.contact_groups_file {
+        None => IndexMap::new(),
+        Some(ref path) => {
+            let contact_groups = std::fs::read_to_string(path)
+                .context(format!("unable to read contact groups file `{path}`"))?;
+            serde_yaml::from_str(&contact_groups)
+                .context(format!("unable to parse contact groups file `{path}`"))?
+        }
+    };
+
+    Ok(contact_groups)
+}
+
+/// Returns the user emails with email aliases applied.
+fn user_emails<'a>(users: &'a [User], email_aliases: &'a HashMap<String, String>) -> Vec<&'a str> {
+    info!(?email_aliases, "Applying email aliases");
+    users
+        .iter()
+        .map(|user| user.email.as_str())
+        .map(|email| email_aliases.get(email).map(AsRef::as_ref).unwrap_or(email))
+        .collect()
+}
+
+async fn process_users(
+    args: Cli,
+    input: &InputArgs,
+    output: UserOutput,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let photos = matches!(output, UserOutput::Contacts) && !args.no_contact_photos;
+    let (users, sink) = tokio::try_join!(
+        read_users(&args, input, photos, summary),
+        user_sink(&args, output, summary),
+    )?;
+    write_archive(&args, |archive, seen| archive.write_users(&users, seen))?;
+    sink.write_users(users).await?;
+
+    Ok(())
+}
+
+async fn user_sink<'a>(
+    args: &'a Cli,
+    output: UserOutput,
+    summary: &'a Summary,
+) -> Result<Box<dyn UserSink + 'a>, Box<dyn std::error::Error>> {
+    let sink: Box<dyn UserSink + 'a> = match output {
+        UserOutput::Acl => {
+            let mut calendars =
+                calendars_from_args_with_auth(args, &args.calendar_owners, summary).await?;
+            load_gcal_state(args, &mut calendars.default)?;
+            Box::new(CalendarSink {
+                args,
+                calendars,
+                filter: EventFilter::new(),
+                summary,
+            })
+        }
+        UserOutput::Contacts => {
+            let mut gppl = summary
+                .time(Phase::GoogleAuth, async {
+                    let auth = auth_from_args(args, AuthType::OAuth).await?;
+                    GPpl::new(&args.group, auth, args.dry_run, summary.plan()).await
+                })
                 .await?;
+            gppl.set_stale_contact_action(match args.stale_contacts {
+                _ if args.delete_stale_contacts => StaleContactAction::Delete,
+                StaleContacts::Ignore => StaleContactAction::Ignore,
+                StaleContacts::Delete => StaleContactAction::Delete,
+                StaleContacts::Move => StaleContactAction::Move(args.alumni_group.clone()),
+            });
+            gppl.set_max_contact_deletes(args.max_contact_deletes);
+            gppl.set_continue_on_error(args.continue_on_error);
+            gppl.set_include_optedout(args.include_optedout);
+            gppl.set_email_aliases(email_aliases_from_args(args)?);
+            gppl.set_timezone(args.timezone);
+            for (group_name, rule) in contact_groups_from_args(args)? {
+                gppl.add_rule_group(&group_name, rule).await?;
+            }
+            Box::new(ContactsSink { gppl, summary })
         }
-        _ => {
-            let events = match args.input {
-                InputType::Web => {
-                    Web::new(&args.username, &args.password, dates)
-                        .await?
-                        .read()
-                        .await?
-                }
-                InputType::Yaml => {
-                    info!(input=?args.input_file, "Reading events");
-                    let events_yaml = match args.input_file {
-                        PipeFile::Pipe => todo!(),
-                        PipeFile::File(ref path) => std::fs::read_to_string(path)?,
-                    };
-                    serde_yaml::from_str(&events_yaml)?
-                }
-            };
+        UserOutput::Sheet {
+            spreadsheet_id,
+            sheet,
+        } => {
+            let gsheets = summary
+                .time(Phase::GoogleAuth, async {
+                    let auth = auth_from_args(args, AuthType::OAuth).await?;
+                    GSheets::new(&spreadsheet_id, &sheet, auth, args.dry_run, summary.plan()).await
+                })
+                .await?;
+            Box::new(gsheets)
+        }
+        UserOutput::Mailchimp { api_key, list_id } => {
+            let mut mailchimp = Mailchimp::new(&api_key, &list_id, args.dry_run, summary.plan())?;
+            mailchimp.set_continue_on_error(args.continue_on_error);
+            Box::new(mailchimp)
+        }
+        UserOutput::Yaml(file, redact) => {
+            Box::new(FileSink::new(file, FileFormat::Yaml, args).redact(redact))
+        }
+        UserOutput::Csv(file) => Box::new(FileSink::new(file, FileFormat::Csv, args)),
+        UserOutput::Json(file, redact) => {
+            Box::new(FileSink::new(file, FileFormat::Json, args).redact(redact))
+        }
+        UserOutput::Vcf(file) => Box::new(FileSink::new(file, FileFormat::Vcf, args)),
+    };
+
+    Ok(sink)
+}
+
+async fn process_acl(
+    args: Cli,
+    report: &ReportArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let users = read_users(&args, &report.input, false, summary).await?;
+    let email_aliases = acl_email_aliases_from_args(&args)?;
+    let emails = user_emails(&users, &email_aliases);
+
+    // The report is read-only so never create the calendar
+    let args = Cli {
+        dry_run: true,
+        ..args
+    };
+    let entries = gcal_from_args_with_auth(&args, &[], summary)
+        .await?
+        .acl_report(&emails)
+        .await?;
+
+    let output_file = &report.output.output_file;
+    info!(output=?output_file, "Writing ACL report");
+    write_output(output_file, &serde_yaml::to_string(&entries)?)?;
+
+    Ok(())
+}
+
+async fn process_contacts(
+    args: Cli,
+    report: &ReportArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let users = read_users(&args, &report.input, false, summary).await?;
+
+    let mut gppl = summary
+        .time(Phase::GoogleAuth, async {
+            let auth = auth_from_args(&args, AuthType::OAuth).await?;
+            // The report is read-only so never create the contact group
+            GPpl::new(&args.group, auth, true, summary.plan()).await
+        })
+        .await?;
+    gppl.set_email_aliases(email_aliases_from_args(&args)?);
+    let entries = gppl.people_report(&users).await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for entry in &entries {
+        writer.serialize(entry)?;
+    }
+    let csv = String::from_utf8(writer.into_inner()?)?;
+
+    let output_file = &report.output.output_file;
+    info!(output=?output_file, "Writing contacts report");
+    write_output(output_file, &csv)?;
+
+    Ok(())
+}
+
+/// Reports the synced events of all calendars that were edited in Google Calendar.
+async fn process_event_drift(
+    args: Cli,
+    output: &OutputArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The report is read-only so never create the calendars
+    let args = Cli {
+        dry_run: true,
+        ..args
+    };
+    let calendars = calendars_from_args_with_auth(&args, &[], summary).await?;
+    let mut entries = Vec::new();
+    for gcal in calendars.all() {
+        entries.append(&mut gcal.drift_report().await?);
+    }
+    for entry in &entries {
+        warn!(calendar_id=%entry.calendar_id, event_id=%entry.event_id, summary=%entry.summary, fields=?entry.fields, "Event edited in Google Calendar");
+    }
+
+    let output_file = &output.output_file;
+    info!(output=?output_file, "Writing event drift report");
+    write_output(output_file, &serde_yaml::to_string(&entries)?)?;
 
-            match args.output {
-                OutputType::GCal => {
-                    let auth = auth_from_args(&args, AuthType::ServiceAccount).await?;
-                    GCal::new(
-                        &args.calendar,
-                        &args.calendar_owners,
+    Ok(())
+}
+
+/// Reports the synced contacts that were edited in Google Contacts.
+async fn process_contact_drift(
+    args: Cli,
+    output: &OutputArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let gppl = summary
+        .time(Phase::GoogleAuth, async {
+            let auth = auth_from_args(&args, AuthType::OAuth).await?;
+            // The report is read-only so never create the contact group
+            GPpl::new(&args.group, auth, true, summary.plan()).await
+        })
+        .await?;
+    let entries = gppl.drift_report().await?;
+    for entry in &entries {
+        warn!(name=%entry.name, email=%entry.email, fields=?entry.fields, "Contact edited in Google Contacts");
+    }
+
+    let output_file = &output.output_file;
+    info!(output=?output_file, "Writing contact drift report");
+    write_output(output_file, &serde_yaml::to_string(&entries)?)?;
+
+    Ok(())
+}
+
+/// Backs up the calendars and the contact group.
+async fn process_backup(
+    args: Cli,
+    backup: &BackupArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The backup is read-only so never create the calendars
+    let args = Cli {
+        dry_run: true,
+        ..args
+    };
+    let calendars = calendars_from_args_with_auth(&args, &[], summary).await?;
+    let mut calendar_backups = Vec::new();
+    for gcal in calendars.all() {
+        calendar_backups.push(gcal.backup().await?);
+    }
+
+    let contacts_backup = if backup.skip_contacts {
+        None
+    } else {
+        let gppl = summary
+            .time(Phase::GoogleAuth, async {
+                let auth = auth_from_args(&args, AuthType::OAuth).await?;
+                // The backup is read-only so never create the contact group
+                GPpl::new(&args.group, auth, true, summary.plan()).await
+            })
+            .await?;
+        Some(gppl.backup().await?)
+    };
+
+    let path = Backup::new(calendar_backups, contacts_backup).save(&backup.output_dir)?;
+    info!(output=%path.display(), "Wrote backup");
+
+    Ok(())
+}
+
+/// Restores the calendars and the contact group of a backup.
+///
+/// Each calendar is restored by its ID and the contact group by its name as backed up (i.e.
+/// --calendar and --group are ignored).
+async fn process_restore(
+    args: Cli,
+    restore: &RestoreArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let backup = Backup::load(&restore.backup_file)?;
+    info!(created=%backup.created, calendars=%backup.calendars.len(), "Restoring backup");
+
+    for calendar_backup in &backup.calendars {
+        let calendar = CalendarSelect::Id(calendar_backup.calendar_id.clone());
+        let gcal = summary
+            .time(Phase::GoogleAuth, async {
+                let auth = auth_from_args(&args, AuthType::ServiceAccount).await?;
+                gcal_from_args(&args, auth, &calendar, &[], summary.plan()).await
+            })
+            .await?;
+        gcal.restore(calendar_backup).await?;
+    }
+
+    match backup.contacts {
+        Some(ref contacts_backup) if !restore.skip_contacts => {
+            let mut gppl = summary
+                .time(Phase::GoogleAuth, async {
+                    let auth = auth_from_args(&args, AuthType::OAuth).await?;
+                    GPpl::new(
+                        &contacts_backup.group_name,
                         auth,
                         args.dry_run,
-                        args.notify_acl_insert.into(),
+                        summary.plan(),
                     )
-                    .await?
-                    .write(&events)
-                    .await?;
-                }
-                OutputType::Yaml => {
-                    info!(output=?args.output_file, "Writing events");
-                    match args.output_file {
-                        PipeFile::Pipe => println!("{}", serde_yaml::to_string(&events)?),
-                        PipeFile::File(_) => todo!(),
-                    }
-                }
-                OutputType::GPpl => unimplemented!(),
-            }
+                    .await
+                })
+                .await?;
+            gppl.set_continue_on_error(args.continue_on_error);
+            gppl.restore(contacts_backup).await?;
         }
+        _ => {}
     }
 
     Ok(())
 }
 
-async fn process_users(args: Cli) -> Result<(), Box<dyn std::error::Error>> {
-    let users = match args.input {
-        InputType::Web => {
-            Web::new(&args.username, &args.password, DateSelect::NotPast)
-                .await?
-                .fetch_users()
-                .await?
-        }
-        InputType::Yaml => {
-            info!(input=?args.input_file, "Reading users");
-            let users_yaml = match args.input_file {
-                PipeFile::Pipe => todo!(),
-                PipeFile::File(ref path) => std::fs::read_to_string(path)?,
-            };
-            serde_yaml::from_str(&users_yaml)?
-        }
+/// Removes the member everywhere scma-gsync writes to.
+///
+/// The member is removed from the calendar ACLs, the contact group, the state file, the sent
+/// invitations and reminders files, and the archive.  The ACL email alias of the member is removed
+/// too.
+async fn process_purge_member(
+    args: Cli,
+    purge: &PurgeMemberArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let email = if purge.member.contains('@') {
+        purge.member.clone()
+    } else {
+        read_users(&args, &purge.input, false, summary)
+            .await?
+            .into_iter()
+            .find(|user| user.id == purge.member)
+            .map(|user| user.email)
+            .filter(|email| !email.is_empty())
+            .ok_or_else(|| format!("no email found for SCMA user ID `{}`", purge.member))?
     };
+    let mut emails = vec![email.as_str()];
+    let email_aliases = acl_email_aliases_from_args(&args)?;
+    if let Some(alias) = email_aliases.get(&email) {
+        emails.push(alias);
+    }
+    info!(?emails, "Purging member");
 
-    match args.output {
-        OutputType::GCal => {
-            let email_aliases: HashMap<String, String> = match args.email_aliases_file {
-                None => HashMap::new(),
-                Some(ref path) => {
-                    let email_aliases = std::fs::read_to_string(path)
-                        .context(format!("unable to read email aliases file `{path}`"))?;
-                    serde_yaml::from_str(&email_aliases)
-                        .context(format!("unable to parse email aliases file `{path}`"))?
-                }
-            };
+    if !purge.skip_acl {
+        let mut calendars = calendars_from_args_with_auth(&args, &[], summary).await?;
+        load_gcal_state(&args, &mut calendars.default)?;
+        for gcal in calendars.all() {
+            gcal.acl_remove(&emails).await?;
+        }
+        save_gcal_state(&args, &calendars.default)?;
+    }
 
-            info!(?email_aliases, "Applying email aliases");
-            let emails: Vec<&str> = users
-                .iter()
-                .map(|user| user.email.as_str())
-                .map(|email| email_aliases.get(email).map(AsRef::as_ref).unwrap_or(email))
-                .collect();
-
-            let auth = auth_from_args(&args, AuthType::ServiceAccount).await?;
-            GCal::new(
-                &args.calendar,
-                &args.calendar_owners,
-                auth,
-                args.dry_run,
-                args.notify_acl_insert.into(),
-            )
-            .await?
-            .acl_sync(&emails, &args.calendar_owners)
+    if !purge.skip_contacts {
+        let gppl = summary
+            .time(Phase::GoogleAuth, async {
+                let auth = auth_from_args(&args, AuthType::OAuth).await?;
+                GPpl::new(&args.group, auth, args.dry_run, summary.plan()).await
+            })
             .await?;
+        gppl.people_remove(&emails).await?;
+    }
+
+    purge_local_state(&args, &emails)?;
+
+    Ok(())
+}
+
+/// Removes the emails from the state file, the sent invitations and reminders files, and the
+/// archive.  Nothing is written on a dry run.
+fn purge_local_state(args: &Cli, emails: &[&str]) -> anyhow::Result<()> {
+    let matches = |entry: &str| emails.iter().any(|email| email.eq_ignore_ascii_case(entry));
+
+    let state_file = Path::new(&args.state_file);
+    if state_file.exists() {
+        let mut state = State::load(state_file)?;
+        let aliases = state.email_aliases.len();
+        state
+            .email_aliases
+            .retain(|email, alias| !matches(email) && !matches(alias));
+        let acl = state.pushed.as_mut().and_then(|pushed| pushed.acl.as_mut());
+        let acl = acl.map_or(0, |acl| {
+            let before = acl.len();
+            acl.retain(|entry| !matches(entry));
+            before - acl.len()
+        });
+        let aliases = aliases - state.email_aliases.len();
+        info!(state_file=%state_file.display(), %aliases, %acl, "Purging member from state");
+        if !args.dry_run && aliases + acl > 0 {
+            state.save(state_file)?;
         }
-        OutputType::Yaml => {
-            info!(output=?args.output_file, "Writing users");
-            match args.output_file {
-                PipeFile::Pipe => println!("{}", serde_yaml::to_string(&users)?),
-                PipeFile::File(_) => todo!(),
+    }
+
+    for email in emails {
+        let invitations = forget_invitee(Path::new(&args.invites_sent_file), email, args.dry_run)?;
+        let reminders = forget_recipient(Path::new(&args.sms_sent_file), email, args.dry_run)?;
+        info!(%email, %invitations, %reminders, "Purging member from sent records");
+    }
+
+    if let Some(ref path) = args.archive_db {
+        if Path::new(path).exists() {
+            let mut archive = Archive::open(Path::new(path))?;
+            for email in emails {
+                archive.purge_member(email, args.dry_run)?;
             }
         }
-        OutputType::GPpl => {
-            let auth = auth_from_args(&args, AuthType::OAuth).await?;
-            GPpl::new(&args.group, auth, args.dry_run)
-                .await?
-                .people_sync(users)
-                .await?;
-        }
     }
 
     Ok(())
 }
 
-async fn scma_to_gcal(
+/// Finds an event in the SCMA event list by ID.
+async fn web_event(
+    web: &Web,
+    event_id: &str,
+    summary: &Summary,
+) -> Result<Event, Box<dyn std::error::Error>> {
+    summary
+        .time(Phase::EventListFetch, web.fetch_events())
+        .await?
+        .into_iter()
+        .find(|event| event.id == event_id)
+        .ok_or_else(|| {
+            format!("unable to find event `{event_id}`, past events are not supported").into()
+        })
+}
+
+async fn process_rsvp(
+    args: Cli,
+    rsvp: &RsvpArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let web = web_from_args(&args, DateSelect::NotPast, summary).await?;
+    let event = web_event(&web, &rsvp.event_id, summary).await?;
+
+    info!(%event.id, %event, count=%rsvp.count, "Registering");
+    if !args.dry_run {
+        web.rsvp(&event, rsvp.count).await?;
+    }
+
+    Ok(())
+}
+
+async fn process_comment(
+    args: Cli,
+    comment: &CommentArgs,
+    summary: &Summary,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let message = &comment.message;
+    let web = web_from_args(&args, DateSelect::NotPast, summary).await?;
+    let event = web_event(&web, &comment.event_id, summary).await?;
+
+    info!(%event.id, %event, %message, "Commenting");
+    if !args.dry_run {
+        web.comment(&event, message).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the SCMA user for `--only-mine`.
+async fn attendee_from_args(
+    args: &Cli,
+    web: &Web,
+    events: &[Event],
+    summary: &Summary,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if !args.only_mine {
+        return Ok(None);
+    }
+
+    // The name is only available from an event page
+    let Some(event) = events.first() else {
+        return Ok(None);
+    };
+    let name = summary
+        .time(Phase::EventDetailsFetch, web.fetch_account_name(event))
+        .await?;
+    info!(%name, "Limiting sync to events the user is going to");
+
+    Ok(Some(name))
+}
+
+async fn scma_to_gcal_description(
     event: Event,
     web: &Web,
     gcal: &GCal,
+    attendee: Option<&str>,
+    summary: &Summary,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let event = web.fetch_event_details(event).await?;
-    gcal.events_patch_or_insert(&event).await
+    let event = summary
+        .time(Phase::EventDetailsFetch, web.fetch_event_details(event))
+        .await?;
+    if attendee.is_some_and(|attendee| !event.is_attending(attendee)) {
+        return Ok(());
+    }
+    summary
+        .time(Phase::EventWrites, gcal.events_refresh_description(&event))
+        .await?;
+
+    Ok(())
 }
 
-async fn web_events<'a>(
-    username: &str,
-    password: &str,
+async fn web_events(
+    args: &Cli,
     dates: DateSelect,
+    filter: &EventFilter,
+    summary: &Summary,
 ) -> Result<(Web, Vec<Event>), Box<dyn std::error::Error>> {
-    let web = Web::new(username, password, dates).await?;
-    let events = web.fetch_events().await?;
+    let web = web_from_args(args, dates, summary).await?;
+    let events = summary
+        .time(Phase::EventListFetch, web.fetch_events())
+        .await?;
+    let events = filter.apply(events);
     Ok((web, events))
 }
 
@@ -400,7 +3539,199 @@ mod test {
 
     #[test]
     fn verify_cli() {
-        use clap::CommandFactory;
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn env_file_arg() {
+        // The required --mailchimp-api-key may be provided by the env file
+        let args = [
+            "scma-gsync",
+            "users",
+            "sync-mailchimp",
+            "--mailchimp-list-id",
+            "abc123",
+            "--env-file",
+            "scma.env",
+        ];
+        assert_eq!(
+            super::env_file_arg(args.map(OsString::from)).as_deref(),
+            Some("scma.env")
+        );
+
+        let args = ["scma-gsync", "users", "sync-mailchimp"];
+        assert_eq!(super::env_file_arg(args.map(OsString::from)), None);
+    }
+
+    #[test]
+    fn config_args() {
+        let config: serde_yaml::Mapping = serde_yaml::from_str(
+            "
+            calendar: SCMA Test
+            calendar-owner: [john@example.com, jane@example.com]
+            smtp-port: 2525
+            dry-run: true
+            dedup: false
+            notify-acl-insert: true
+            group: SCMA Test
+            ",
+        )
+        .unwrap();
+        let matches =
+            Cli::command().get_matches_from(["scma-gsync", "events", "sync", "--group", "SCMA"]);
+
+        assert_eq!(
+            super::config_args(&config, &matches).unwrap(),
+            (
+                vec![
+                    "--calendar=SCMA Test".to_string(),
+                    "--calendar-owner=john@example.com".to_string(),
+                    "--calendar-owner=jane@example.com".to_string(),
+                    "--smtp-port=2525".to_string(),
+                    "--dry-run".to_string(),
+                    "--notify-acl-insert=true".to_string(),
+                ],
+                vec![],
+            )
+        );
+
+        let config: serde_yaml::Mapping = serde_yaml::from_str("calender: SCMA").unwrap();
+        assert!(super::config_args(&config, &matches).is_err());
+    }
+
+    #[test]
+    fn config_args_subcommand() {
+        let config: serde_yaml::Mapping = serde_yaml::from_str(
+            "
+            spreadsheet-id: abc123
+            ifile: users.yml
+            mailchimp-list-id: def456
+            dry-run: true
+            ",
+        )
+        .unwrap();
+        // The required --spreadsheet-id is provided by the config file
+        let args = ["scma-gsync", "users", "sync-sheet", "--ifile", "other.yml"];
+        let matches = Cli::command()
+            .ignore_errors(true)
+            .try_get_matches_from(args)
+            .unwrap();
+
+        // The options of other subcommands (e.g. --mailchimp-list-id) are skipped
+        let (top_args, subcommand_args) = super::config_args(&config, &matches).unwrap();
+        assert_eq!(top_args, vec!["--dry-run"]);
+        assert_eq!(subcommand_args, vec!["--spreadsheet-id=abc123"]);
+
+        let args = args
+            .into_iter()
+            .map(String::from)
+            .chain(top_args)
+            .chain(subcommand_args);
+        let Command::Users(UsersCommand::SyncSheet(sheet)) =
+            Cli::try_parse_from(args).unwrap().command
+        else {
+            panic!("expected users sync-sheet");
+        };
+        assert_eq!(sheet.spreadsheet_id, "abc123");
+        assert_eq!(
+            sheet.input.input_file,
+            Some(PipeFile::File("other.yml".to_string()))
+        );
+    }
+
+    #[test]
+    fn profile_config() {
+        let config: serde_yaml::Mapping = serde_yaml::from_str(
+            "
+            calendar: SCMA
+            secret-file: service-account.json
+            profiles:
+              staging:
+                calendar: SCMA Test
+                group: SCMA Test
+            ",
+        )
+        .unwrap();
+        let expected: serde_yaml::Mapping = serde_yaml::from_str(
+            "
+            calendar: SCMA Test
+            secret-file: service-account.json
+            group: SCMA Test
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            super::profile_config(config.clone(), Some("staging")).unwrap(),
+            expected
+        );
+
+        let expected: serde_yaml::Mapping = serde_yaml::from_str(
+            "
+            calendar: SCMA
+            secret-file: service-account.json
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            super::profile_config(config.clone(), None).unwrap(),
+            expected
+        );
+
+        assert!(super::profile_config(config, Some("prod")).is_err());
+    }
+
+    #[test]
+    fn input_file() {
+        let args = Cli::parse_from(["scma-gsync", "events", "sync", "--ifile", "-"]);
+        let Command::Events(EventsCommand::Sync(input)) = args.command else {
+            panic!("expected events sync");
+        };
+        assert_eq!(input.input_file, Some(PipeFile::Pipe));
+
+        let args = Cli::parse_from(["scma-gsync", "users", "sync-acl", "--ifile", "users.yml"]);
+        let Command::Users(UsersCommand::SyncAcl(input)) = args.command else {
+            panic!("expected users sync-acl");
+        };
+        assert_eq!(input.input_file, Some(PipeFile::File("users.yml".into())));
+    }
+
+    #[test]
+    fn parse_ttl() {
+        assert_eq!(super::parse_ttl("30m"), Ok(chrono::Duration::minutes(30)));
+        assert_eq!(super::parse_ttl("12h"), Ok(chrono::Duration::hours(12)));
+        assert_eq!(super::parse_ttl("1d"), Ok(chrono::Duration::days(1)));
+        assert!(super::parse_ttl("30").is_err());
+        assert!(super::parse_ttl("").is_err());
+    }
+
+    #[test]
+    fn parse_date() {
+        assert_eq!(
+            super::parse_date("2023-01-01"),
+            Ok(DateArg::Date(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()))
+        );
+        assert_eq!(super::parse_date("90d"), Ok(DateArg::Days(Days::new(90))));
+        assert!(super::parse_date("2023-13-01").is_err());
+    }
+
+    #[test]
+    fn parse_days() {
+        assert_eq!(super::parse_days("90d"), Ok(Days::new(90)));
+        assert_eq!(super::parse_days("90"), Ok(Days::new(90)));
+        assert_eq!(super::parse_days("12w"), Ok(Days::new(84)));
+        assert!(super::parse_days("d").is_err());
+        assert!(super::parse_days("90y").is_err());
+        assert!(super::parse_days("3000000000000000000w").is_err());
+    }
+
+    #[test]
+    fn days_from_today() {
+        let tz = scma_gsync::DEFAULT_TIMEZONE;
+        assert!(super::days_after_today(tz, Days::new(90)).is_ok());
+        assert!(super::days_before_today(tz, Days::new(90)).is_ok());
+        // Valid numbers of days may still be out of range of a date
+        let days = super::parse_days("999999999d").unwrap();
+        assert!(super::days_after_today(tz, days).is_err());
+        assert!(super::days_before_today(tz, days).is_err());
+    }
 }
@@ -1,10 +1,26 @@
-use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
-use serde::{Deserialize, Serialize, Serializer};
+use crate::Error;
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
 
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The default timezone of SCMA events
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::America::Los_Angeles;
+
+/// Formats a timestamp in the SCMA timezone (e.g. 2022-01-07T12:28:38-08:00).
+fn format_timestamp(timestamp: Option<DateTime<Utc>>, timezone: Tz) -> String {
+    match timestamp {
+        Some(timestamp) => timestamp
+            .with_timezone(&timezone)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        None => "".to_string(),
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Event {
     pub id: String,
     pub title: String,
@@ -13,6 +29,26 @@ pub struct Event {
     #[serde(alias = "date")]
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
+    /// The start and end time of a timed event.  None for an all-day event.
+    // Not present in SCMA JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<EventTime>,
+    /// How the event repeats.  Only set on the first event of a series.
+    // Not present in SCMA JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// The SCMA event ID of the first event of the series this event is an occurrence of.
+    // Not present in SCMA JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurring_parent: Option<String>,
+    /// The SCMA category ID (e.g. "2").
+    // SCMA JSON uses "ohanah_category_id"
+    #[serde(
+        alias = "ohanah_category_id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub category: Option<String>,
     // SCMA JSON uses "venue"
     #[serde(alias = "venue")]
     pub location: String,
@@ -29,47 +65,218 @@ pub struct Event {
 }
 
 impl Event {
-    pub fn timestamp(&self) -> String {
-        if let Some(timestamp) = self.timestamp {
-            let pacific = chrono::FixedOffset::west_opt(8 * 60 * 60).unwrap();
-            timestamp
-                .with_timezone(&pacific)
-                .to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
-                .to_string()
+    /// Returns the total number of attendees including guests.
+    pub fn attendee_count(&self) -> u32 {
+        self.attendees
+            .iter()
+            .flatten()
+            .map(|attendee| u32::from(attendee.count))
+            .sum()
+    }
+
+    /// Returns true if the named user (ignoring case and whitespace) is attending the event.
+    pub fn is_attending(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.attendees
+            .iter()
+            .flatten()
+            .any(|attendee| normalize_name(&attendee.name) == name)
+    }
+
+    /// Returns the users that are attending the event.
+    ///
+    /// Attendees are matched to users by name (ignoring case and whitespace).  Attendees without
+    /// a matching user (e.g. renamed or deleted users) are skipped.
+    pub fn attendee_users<'a>(&self, users: &'a [User]) -> Vec<&'a User> {
+        let mut attendee_users: Vec<&User> = Vec::new();
+        for attendee in self.attendees.iter().flatten() {
+            let name = normalize_name(&attendee.name);
+            let user = users
+                .iter()
+                .find(|user| !user.email.is_empty() && normalize_name(&user.name) == name);
+            if let Some(user) = user {
+                if !attendee_users.iter().any(|u| u.email == user.email) {
+                    attendee_users.push(user);
+                }
+            }
+        }
+        attendee_users
+    }
+
+    /// Sets the emails of the attendees from the users.
+    ///
+    /// Attendees are matched to users by name (ignoring case and whitespace).  Attendees without
+    /// a matching user are left without an email.
+    pub fn set_attendee_emails(&mut self, users: &[User]) {
+        for attendee in self.attendees.iter_mut().flatten() {
+            let name = normalize_name(&attendee.name);
+            attendee.email = users
+                .iter()
+                .find(|user| !user.email.is_empty() && normalize_name(&user.name) == name)
+                .map(|user| user.email.clone());
+        }
+    }
+
+    /// Returns the timestamp in the SCMA timezone.
+    pub fn timestamp(&self, timezone: Tz) -> String {
+        format_timestamp(self.timestamp, timezone)
+    }
+}
+
+/// The start and end time of a timed event in the SCMA timezone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventTime {
+    pub start: NaiveTime,
+    /// The end time of a single-day event that is not after the start time is on the next day.
+    pub end: NaiveTime,
+}
+
+impl Event {
+    /// Returns the start and end of a timed event.
+    ///
+    /// None for an all-day event or if a time does not exist in the SCMA timezone (e.g. skipped
+    /// by a DST transition).
+    pub fn start_end(&self, timezone: Tz) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let time = self.time?;
+        let end_date = if time.end <= time.start && self.start_date == self.end_date {
+            self.end_date + Duration::days(1)
         } else {
-            "".to_string()
+            self.end_date
+        };
+        Some((
+            local_datetime(timezone, self.start_date, time.start)?,
+            local_datetime(timezone, end_date, time.end)?,
+        ))
+    }
+}
+
+/// How a series of SCMA events repeats.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Repeats every interval days, weeks, months, or years
+    pub interval: u32,
+    /// The date of the last occurrence (inclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    /// The number of occurrences
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Recurrence {
+    /// Returns the iCalendar (RFC 5545) RRULE of the recurrence.
+    ///
+    /// The UNTIL of a timed event is the end of the until date in the SCMA timezone.
+    pub fn rrule(&self, timed: bool, timezone: Tz) -> String {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let mut rrule = format!("RRULE:FREQ={frequency};INTERVAL={}", self.interval);
+        if let Some(count) = self.count {
+            rrule.push_str(&format!(";COUNT={count}"));
+        } else if let Some(until) = self.until {
+            let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
+            match local_datetime(timezone, until, end_of_day).filter(|_| timed) {
+                Some(until) => {
+                    rrule.push_str(&format!(";UNTIL={}", until.format("%Y%m%dT%H%M%SZ")))
+                }
+                None => rrule.push_str(&format!(";UNTIL={}", until.format("%Y%m%d"))),
+            }
         }
+
+        rrule
+    }
+}
+
+impl Event {
+    /// Returns the start of the occurrence of a timed event on the date.
+    pub fn occurrence_start(&self, timezone: Tz, date: NaiveDate) -> Option<DateTime<Utc>> {
+        local_datetime(timezone, date, self.time?.start)
     }
 }
 
+/// Converts a date and time in the SCMA timezone to UTC.
+///
+/// The earlier of an ambiguous time (e.g. repeated by a DST transition) is used.
+fn local_datetime(timezone: Tz, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+    timezone
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// Sorts events by start date then by SCMA event ID.
+///
+/// Provides a stable order independent of the order in which event details were fetched.
+pub fn sort_events(events: &mut [Event]) {
+    events.sort_by(|a, b| {
+        a.start_date
+            .cmp(&b.start_date)
+            .then_with(|| id_sort_key(&a.id).cmp(&id_sort_key(&b.id)))
+    });
+}
+
+/// Sorts users by name then by SCMA user ID then by email.
+pub fn sort_users(users: &mut [User]) {
+    users.sort_by(|a, b| {
+        (&a.name, id_sort_key(&a.id), &a.email).cmp(&(&b.name, id_sort_key(&b.id), &b.email))
+    });
+}
+
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Orders SCMA IDs numerically with non-numeric IDs last.
+pub(crate) fn id_sort_key(id: &str) -> (u64, &str) {
+    (id.parse().unwrap_or(u64::MAX), id)
+}
+
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} ({}/{})", self.title, self.start_date, self.end_date)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Comment {
     pub author: String,
-    #[serde(serialize_with = "serialize_datetime_pacific")]
-    pub date: DateTime<Local>,
+    /// The date with the offset given by SCMA
+    pub date: DateTime<FixedOffset>,
     pub text: String,
 }
 
-fn serialize_datetime_pacific<S>(dt: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    let pacific = FixedOffset::west_opt(8 * 60 * 60).unwrap();
-    let s = dt.with_timezone(&pacific).to_rfc3339();
-    serializer.serialize_str(&s)
+impl Comment {
+    /// Returns the date in the SCMA timezone.
+    pub fn local_date(&self, timezone: Tz) -> DateTime<Tz> {
+        self.date.with_timezone(&timezone)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attendee {
     pub name: String,
     pub count: u8,
     pub comment: String,
+    /// The email of the matching user.  Not present on SCMA event pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
 }
 
 /// Provides event selection by date
@@ -79,10 +286,13 @@ pub enum DateSelect {
     All,
     /// Only present (in-progress) and future events
     NotPast,
+    /// Only events ending on or after the date
+    Since(NaiveDate),
 }
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MemberStatus {
+    #[default]
     Applicant,
     Student,
     AM,
@@ -90,14 +300,8 @@ pub enum MemberStatus {
     RM,
 }
 
-impl Default for MemberStatus {
-    fn default() -> Self {
-        Self::Applicant
-    }
-}
-
 impl FromStr for MemberStatus {
-    type Err = Box<dyn std::error::Error>;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
@@ -106,7 +310,7 @@ impl FromStr for MemberStatus {
             "AM" => Ok(Self::AM),
             "HM" => Ok(Self::HM),
             "RM" => Ok(Self::RM),
-            _ => Err(format!("unrecognized member status '{s}'").into()),
+            _ => Err(Error::parse(format!("unrecognized member status '{s}'"))),
         }
     }
 }
@@ -123,28 +327,25 @@ impl fmt::Display for MemberStatus {
     }
 }
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TripLeaderStatus {
+    #[default]
     G,
     S1,
     S2,
 }
 
-impl Default for TripLeaderStatus {
-    fn default() -> Self {
-        Self::G
-    }
-}
-
 impl FromStr for TripLeaderStatus {
-    type Err = Box<dyn std::error::Error>;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "G" => Ok(Self::G),
             "S1" => Ok(Self::S1),
             "S2" => Ok(Self::S2),
-            _ => Err(format!("unrecognized trip leader status '{s}'").into()),
+            _ => Err(Error::parse(format!(
+                "unrecognized trip leader status '{s}'"
+            ))),
         }
     }
 }
@@ -159,7 +360,7 @@ impl fmt::Display for TripLeaderStatus {
     }
 }
 
-#[derive(Debug, Default, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
 pub struct User {
     pub id: String,
     pub name: String,
@@ -196,6 +397,9 @@ pub struct User {
     pub lastvisit_date: String,
     /// The date and time the event page was downloaded.
     pub timestamp: Option<DateTime<Utc>>,
+    /// The profile photo.  Only fetched to sync Google Contacts.
+    #[serde(skip)]
+    pub photo: Option<Vec<u8>>,
 }
 
 impl User {
@@ -203,6 +407,18 @@ impl User {
         format!("{} <{}>", self.name, self.email)
     }
 
+    /// Returns true if the user has opted out of communication or has a blocked account.
+    pub fn is_optedout_or_blocked(&self) -> bool {
+        self.optedout || self.block
+    }
+
+    /// Returns true if the user has resigned their membership.
+    pub fn is_resigned(&self) -> bool {
+        self.resignedmembership
+            .as_deref()
+            .is_some_and(|date| !date.trim().is_empty())
+    }
+
     pub fn address(&self) -> String {
         format!(
             "{}, {}, {} {}",
@@ -210,15 +426,224 @@ impl User {
         )
     }
 
-    pub fn timestamp(&self) -> String {
-        if let Some(timestamp) = self.timestamp {
-            let pacific = chrono::FixedOffset::west_opt(8 * 60 * 60).unwrap();
-            timestamp
-                .with_timezone(&pacific)
-                .to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
-                .to_string()
-        } else {
-            "".to_string()
-        }
+    /// Returns the timestamp in the SCMA timezone.
+    pub fn timestamp(&self, timezone: Tz) -> String {
+        format_timestamp(self.timestamp, timezone)
+    }
+
+    /// Returns the date of birth if set and valid.
+    ///
+    /// Accepts `YYYY-MM-DD` (optionally followed by a time) and `MM/DD/YYYY`.  Unset dates (e.g.
+    /// `0000-00-00`) are treated as missing.
+    pub fn birthday(&self) -> Option<NaiveDate> {
+        let dob = self.dob.as_deref()?.trim();
+        let date = dob.split_whitespace().next().unwrap_or(dob);
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(date, "%m/%d/%Y"))
+            .ok()
+    }
+}
+
+/// Returns a single-day, all-day event for the tests to customize with struct update syntax.
+#[cfg(test)]
+pub(crate) fn event(id: &str, start_date: &str) -> Event {
+    Event {
+        id: id.into(),
+        title: "a title".into(),
+        url: "a url".into(),
+        start_date: start_date.parse().unwrap(),
+        end_date: start_date.parse().unwrap(),
+        time: None,
+        recurrence: None,
+        recurring_parent: None,
+        category: None,
+        location: "a location".into(),
+        description: "a description".into(),
+        comments: None,
+        attendees: None,
+        timestamp: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sort_events() {
+        let mut events = vec![
+            event("100", "2022-01-15"),
+            event("99", "2022-01-15"),
+            event("101", "2022-01-14"),
+        ];
+        super::sort_events(&mut events);
+        let actual: Vec<&str> = events.iter().map(|event| event.id.as_str()).collect();
+        assert_eq!(actual, vec!["101", "99", "100"]);
+    }
+
+    #[test]
+    fn sort_users() {
+        let user = |id: &str, name: &str| User {
+            id: id.into(),
+            name: name.into(),
+            ..Default::default()
+        };
+        let mut users = vec![user("10", "B"), user("9", "B"), user("11", "A")];
+        super::sort_users(&mut users);
+        let actual: Vec<&str> = users.iter().map(|user| user.id.as_str()).collect();
+        assert_eq!(actual, vec!["11", "9", "10"]);
+    }
+
+    #[test]
+    fn timestamp() {
+        let mut event = event("527", "2022-07-14");
+        assert_eq!(event.timestamp(DEFAULT_TIMEZONE), "");
+
+        event.timestamp = Some("2022-07-14T19:00:00Z".parse().unwrap());
+        assert_eq!(
+            event.timestamp(DEFAULT_TIMEZONE),
+            "2022-07-14T12:00:00-07:00"
+        );
+
+        event.timestamp = Some("2022-01-14T20:00:00Z".parse().unwrap());
+        assert_eq!(
+            event.timestamp(DEFAULT_TIMEZONE),
+            "2022-01-14T12:00:00-08:00"
+        );
+        assert_eq!(
+            event.timestamp(chrono_tz::America::New_York),
+            "2022-01-14T15:00:00-05:00"
+        );
+    }
+
+    #[test]
+    fn rrule() {
+        let recurrence = |until: Option<&str>, count| Recurrence {
+            frequency: Frequency::Monthly,
+            interval: 2,
+            until: until.map(|until| until.parse().unwrap()),
+            count,
+        };
+
+        assert_eq!(
+            recurrence(None, None).rrule(false, DEFAULT_TIMEZONE),
+            "RRULE:FREQ=MONTHLY;INTERVAL=2"
+        );
+        assert_eq!(
+            recurrence(None, Some(6)).rrule(false, DEFAULT_TIMEZONE),
+            "RRULE:FREQ=MONTHLY;INTERVAL=2;COUNT=6"
+        );
+        assert_eq!(
+            recurrence(Some("2022-12-31"), None).rrule(false, DEFAULT_TIMEZONE),
+            "RRULE:FREQ=MONTHLY;INTERVAL=2;UNTIL=20221231"
+        );
+        assert_eq!(
+            recurrence(Some("2022-12-31"), None).rrule(true, DEFAULT_TIMEZONE),
+            "RRULE:FREQ=MONTHLY;INTERVAL=2;UNTIL=20230101T075959Z"
+        );
+    }
+
+    #[test]
+    fn start_end() {
+        let time = |start: &str, end: &str| EventTime {
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        };
+        let utc = |datetime: &str| Some(datetime.parse::<DateTime<Utc>>().unwrap());
+
+        let mut event = event("527", "2022-07-14");
+        assert_eq!(event.start_end(DEFAULT_TIMEZONE), None);
+
+        // PDT
+        event.time = Some(time("19:30:00", "21:30:00"));
+        let (start, end) = event.start_end(DEFAULT_TIMEZONE).unwrap();
+        assert_eq!(Some(start), utc("2022-07-15T02:30:00Z"));
+        assert_eq!(Some(end), utc("2022-07-15T04:30:00Z"));
+
+        // Ends after midnight
+        event.time = Some(time("22:00:00", "00:30:00"));
+        let (_, end) = event.start_end(DEFAULT_TIMEZONE).unwrap();
+        assert_eq!(Some(end), utc("2022-07-15T07:30:00Z"));
+
+        // PST
+        event.start_date = "2022-01-14".parse().unwrap();
+        event.end_date = event.start_date;
+        event.time = Some(time("19:30:00", "21:30:00"));
+        let (start, _) = event.start_end(DEFAULT_TIMEZONE).unwrap();
+        assert_eq!(Some(start), utc("2022-01-15T03:30:00Z"));
+
+        // EST
+        let (start, _) = event.start_end(chrono_tz::America::New_York).unwrap();
+        assert_eq!(Some(start), utc("2022-01-15T00:30:00Z"));
+    }
+
+    #[test]
+    fn birthday() {
+        let user = |dob: Option<&str>| User {
+            dob: dob.map(str::to_string),
+            ..Default::default()
+        };
+        let date = NaiveDate::from_ymd_opt(1980, 7, 4);
+
+        assert_eq!(user(Some("1980-07-04")).birthday(), date);
+        assert_eq!(user(Some("1980-07-04 00:00:00")).birthday(), date);
+        assert_eq!(user(Some("07/04/1980")).birthday(), date);
+        assert_eq!(user(Some("0000-00-00")).birthday(), None);
+        assert_eq!(user(Some("")).birthday(), None);
+        assert_eq!(user(None).birthday(), None);
+    }
+
+    #[test]
+    fn attendee_users() {
+        let user = |name: &str, email: &str| User {
+            name: name.into(),
+            email: email.into(),
+            ..Default::default()
+        };
+        let users = vec![
+            user("John Doe", "john@example.com"),
+            user("Jane  Roe", "jane@example.com"),
+            user("No Email", ""),
+        ];
+        let attendee = |name: &str| Attendee {
+            name: name.into(),
+            count: 1,
+            comment: "".into(),
+            email: None,
+        };
+        let mut event = event("1", "2022-01-15");
+        event.attendees = Some(vec![
+            attendee("jane roe"),
+            attendee("Unknown"),
+            attendee("John Doe"),
+            attendee("No Email"),
+            attendee("JOHN DOE"),
+        ]);
+        let actual: Vec<&str> = event
+            .attendee_users(&users)
+            .iter()
+            .map(|user| user.email.as_str())
+            .collect();
+        assert_eq!(actual, vec!["jane@example.com", "john@example.com"]);
+        assert!(event.is_attending("Jane Roe"));
+        assert!(!event.is_attending("Jane"));
+
+        event.set_attendee_emails(&users);
+        let actual: Vec<Option<&str>> = event
+            .attendees
+            .iter()
+            .flatten()
+            .map(|attendee| attendee.email.as_deref())
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                Some("jane@example.com"),
+                None,
+                Some("john@example.com"),
+                None,
+                Some("john@example.com"),
+            ]
+        );
     }
 }
@@ -0,0 +1,351 @@
+use crate::model::{Event, User};
+use crate::Error;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use tracing::info;
+
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    url TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    start_time TEXT,
+    end_time TEXT,
+    category TEXT,
+    location TEXT NOT NULL,
+    description TEXT NOT NULL,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS attendees (
+    event_id TEXT NOT NULL REFERENCES events (id),
+    name TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    comment TEXT NOT NULL,
+    email TEXT,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    PRIMARY KEY (event_id, name)
+);
+CREATE TABLE IF NOT EXISTS comments (
+    event_id TEXT NOT NULL REFERENCES events (id),
+    author TEXT NOT NULL,
+    date TEXT NOT NULL,
+    text TEXT NOT NULL,
+    first_seen TEXT NOT NULL,
+    PRIMARY KEY (event_id, author, date)
+);
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    phone TEXT,
+    member_status TEXT NOT NULL,
+    trip_leader_status TEXT,
+    position TEXT,
+    address TEXT NOT NULL,
+    city TEXT NOT NULL,
+    state TEXT NOT NULL,
+    zipcode TEXT NOT NULL,
+    member_since TEXT,
+    optedout INTEGER NOT NULL,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+);
+";
+
+/// A queryable archive of the SCMA events, attendees, comments, and users in a SQLite database.
+///
+/// Each run upserts what it read so that the archive accumulates the history of the club.  Rows
+/// are only deleted by purging a member (see Archive::purge_member).  The `first_seen` and `last_seen` columns record the first and last runs a
+/// row was read (e.g. an attendee with a `last_seen` before the event's `last_seen` withdrew).
+pub struct Archive {
+    conn: Connection,
+}
+
+impl Archive {
+    /// Opens the database.  Creates the database and its tables if they do not exist.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let conn = Connection::open(path)
+            .context(format!("unable to open archive `{}`", path.display()))
+            .map_err(Error::file)?;
+        conn.execute_batch(SCHEMA)
+            .context(format!("unable to create archive `{}`", path.display()))
+            .map_err(Error::file)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Upserts the events.  The attendees and comments are upserted if present.
+    pub fn write_events(&mut self, events: &[Event], seen: DateTime<Utc>) -> Result<(), Error> {
+        info!(count=%events.len(), "Archiving events");
+        let seen = seen.to_rfc3339();
+        let tx = self.conn.transaction().map_err(Error::file)?;
+        for event in events {
+            upsert_event(&tx, event, &seen)
+                .context(format!("unable to archive event `{}`", event.id))
+                .map_err(Error::file)?;
+        }
+        tx.commit().map_err(Error::file)
+    }
+
+    pub fn write_users(&mut self, users: &[User], seen: DateTime<Utc>) -> Result<(), Error> {
+        info!(count=%users.len(), "Archiving users");
+        let seen = seen.to_rfc3339();
+        let tx = self.conn.transaction().map_err(Error::file)?;
+        for user in users {
+            upsert_user(&tx, user, &seen)
+                .context(format!("unable to archive user `{}`", user.id))
+                .map_err(Error::file)?;
+        }
+        tx.commit().map_err(Error::file)
+    }
+
+    /// Deletes the user with the email and their attendances and comments.
+    ///
+    /// The attendances and comments are matched by the name of the user and by email.  Returns the
+    /// number of deleted rows.  Nothing is deleted on a dry run but the rows are still counted.
+    pub fn purge_member(&mut self, email: &str, dry_run: bool) -> Result<usize, Error> {
+        let tx = self.conn.transaction().map_err(Error::file)?;
+        let count = purge_member(&tx, email)
+            .context(format!("unable to purge member `{email}` from archive"))
+            .map_err(Error::file)?;
+        info!(%email, rows=%count, "Purging member from archive");
+        if !dry_run {
+            tx.commit().map_err(Error::file)?;
+        }
+
+        Ok(count)
+    }
+}
+
+fn purge_member(conn: &Connection, email: &str) -> rusqlite::Result<usize> {
+    let names = conn
+        .prepare("SELECT name FROM users WHERE email = ?1 COLLATE NOCASE")?
+        .query_map([email], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    let mut count = conn.execute(
+        "DELETE FROM attendees WHERE email = ?1 COLLATE NOCASE",
+        [email],
+    )?;
+    for name in &names {
+        count += conn.execute("DELETE FROM attendees WHERE name = ?1", [name])?;
+        count += conn.execute("DELETE FROM comments WHERE author = ?1", [name])?;
+    }
+    count += conn.execute("DELETE FROM users WHERE email = ?1 COLLATE NOCASE", [email])?;
+
+    Ok(count)
+}
+
+fn upsert_event(conn: &Connection, event: &Event, seen: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO events (id, title, url, start_date, end_date, start_time, end_time, \
+            category, location, description, first_seen, last_seen) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11) \
+         ON CONFLICT (id) DO UPDATE SET title = ?2, url = ?3, start_date = ?4, end_date = ?5, \
+            start_time = ?6, end_time = ?7, category = ?8, location = ?9, description = ?10, \
+            last_seen = ?11",
+        params![
+            event.id,
+            event.title,
+            event.url,
+            event.start_date.to_string(),
+            event.end_date.to_string(),
+            event
+                .time
+                .as_ref()
+                .map(|time| time.start.format("%H:%M").to_string()),
+            event
+                .time
+                .as_ref()
+                .map(|time| time.end.format("%H:%M").to_string()),
+            event.category,
+            event.location,
+            event.description,
+            seen,
+        ],
+    )?;
+
+    for attendee in event.attendees.iter().flatten() {
+        conn.execute(
+            "INSERT INTO attendees (event_id, name, count, comment, email, first_seen, last_seen) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6) \
+             ON CONFLICT (event_id, name) DO UPDATE SET count = ?3, comment = ?4, \
+                email = COALESCE(?5, email), last_seen = ?6",
+            params![
+                event.id,
+                attendee.name,
+                attendee.count,
+                attendee.comment,
+                attendee.email,
+                seen,
+            ],
+        )?;
+    }
+
+    for comment in event.comments.iter().flatten() {
+        conn.execute(
+            "INSERT INTO comments (event_id, author, date, text, first_seen) \
+             VALUES (?1, ?2, ?3, ?4, ?5) \
+             ON CONFLICT (event_id, author, date) DO UPDATE SET text = ?4",
+            params![
+                event.id,
+                comment.author,
+                comment.date.to_rfc3339(),
+                comment.text,
+                seen,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn upsert_user(conn: &Connection, user: &User, seen: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO users (id, name, email, phone, member_status, trip_leader_status, position, \
+            address, city, state, zipcode, member_since, optedout, first_seen, last_seen) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?14) \
+         ON CONFLICT (id) DO UPDATE SET name = ?2, email = ?3, phone = ?4, member_status = ?5, \
+            trip_leader_status = ?6, position = ?7, address = ?8, city = ?9, state = ?10, \
+            zipcode = ?11, member_since = ?12, optedout = ?13, last_seen = ?14",
+        params![
+            user.id,
+            user.name,
+            user.email,
+            user.phone,
+            user.member_status.to_string(),
+            user.trip_leader_status.as_ref().map(ToString::to_string),
+            user.position,
+            user.address,
+            user.city,
+            user.state,
+            user.zipcode,
+            user.membersince,
+            user.optedout,
+            seen,
+        ],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{event, Attendee, MemberStatus};
+
+    use chrono::TimeZone;
+
+    fn attendees(names: &[&str]) -> Option<Vec<Attendee>> {
+        let attendees = names
+            .iter()
+            .map(|name| Attendee {
+                name: name.to_string(),
+                count: 1,
+                comment: String::new(),
+                email: None,
+            })
+            .collect();
+        Some(attendees)
+    }
+
+    #[test]
+    fn write_events() {
+        let mut archive = Archive::open(Path::new(":memory:")).unwrap();
+        let first = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap();
+        let mut event = event("527", "2024-03-09");
+        event.attendees = attendees(&["Jane Doe", "John Doe"]);
+        archive.write_events(&[event.clone()], first).unwrap();
+        event.attendees = attendees(&["Jane Doe"]);
+        archive.write_events(&[event], second).unwrap();
+
+        let events: i64 = archive
+            .conn
+            .query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(events, 1);
+
+        let mut stmt = archive
+            .conn
+            .prepare("SELECT name, last_seen FROM attendees ORDER BY name")
+            .unwrap();
+        let attendees = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<Vec<(String, String)>, _>>()
+            .unwrap();
+        assert_eq!(
+            attendees,
+            [
+                ("Jane Doe".to_string(), second.to_rfc3339()),
+                ("John Doe".to_string(), first.to_rfc3339()),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_users() {
+        let mut archive = Archive::open(Path::new(":memory:")).unwrap();
+        let seen = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let mut user = User {
+            id: "42".into(),
+            name: "Jane Doe".into(),
+            member_status: MemberStatus::Student,
+            ..Default::default()
+        };
+        archive.write_users(&[user.clone()], seen).unwrap();
+        user.member_status = MemberStatus::RM;
+        archive.write_users(&[user], seen).unwrap();
+
+        let member_status: String = archive
+            .conn
+            .query_row(
+                "SELECT member_status FROM users WHERE id = '42'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(member_status, "RM");
+    }
+
+    #[test]
+    fn purge_member() {
+        let mut archive = Archive::open(Path::new(":memory:")).unwrap();
+        let seen = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let user = User {
+            id: "42".into(),
+            name: "Jane Doe".into(),
+            email: "jane@example.com".into(),
+            ..Default::default()
+        };
+        archive.write_users(&[user], seen).unwrap();
+        let event = Event {
+            attendees: attendees(&["Jane Doe", "John Doe"]),
+            ..event("527", "2024-03-09")
+        };
+        archive.write_events(&[event], seen).unwrap();
+
+        assert_eq!(archive.purge_member("Jane@Example.com", true).unwrap(), 2);
+        assert_eq!(archive.purge_member("Jane@Example.com", false).unwrap(), 2);
+        assert_eq!(archive.purge_member("Jane@Example.com", false).unwrap(), 0);
+
+        let names = archive
+            .conn
+            .prepare("SELECT name FROM attendees")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        assert_eq!(names, ["John Doe"]);
+    }
+}
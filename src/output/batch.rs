@@ -0,0 +1,225 @@
+//! Google API batch requests
+//!
+//! Combines multiple API calls into a single HTTP request (multipart/mixed).  See
+//! https://developers.google.com/calendar/api/guides/batch.
+
+use anyhow::{anyhow, bail, Context};
+
+use std::fmt::Write;
+
+const BOUNDARY: &str = "batch_scma_gsync";
+
+/// A single API call within a batch request.
+pub(crate) struct BatchRequest {
+    pub method: &'static str,
+    /// The request path (e.g. "/calendar/v3/calendars/primary/events")
+    pub path: String,
+    /// The JSON request body
+    pub body: Option<String>,
+}
+
+/// The response to a single API call within a batch request.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct BatchResponse {
+    pub status: u16,
+    /// The JSON response body
+    pub body: String,
+}
+
+/// Sends the batch request and returns the responses in the order of the requests.
+pub(crate) async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    token: &str,
+    requests: &[BatchRequest],
+) -> anyhow::Result<Vec<BatchResponse>> {
+    super::record_api_calls(requests.len());
+    let rsp = client
+        .post(url)
+        .bearer_auth(token)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            format!("multipart/mixed; boundary={BOUNDARY}"),
+        )
+        .body(encode(requests))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let content_type = rsp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let body = rsp.text().await?;
+    let responses = decode(&content_type, &body)?;
+
+    if responses.len() != requests.len() {
+        bail!(
+            "expected {} batch responses, got {}",
+            requests.len(),
+            responses.len()
+        );
+    }
+
+    Ok(responses)
+}
+
+fn encode(requests: &[BatchRequest]) -> String {
+    let mut buffer = String::new();
+
+    for (i, request) in requests.iter().enumerate() {
+        write!(buffer, "--{BOUNDARY}\r\n").unwrap();
+        write!(buffer, "Content-Type: application/http\r\n").unwrap();
+        write!(buffer, "Content-ID: <item{i}>\r\n\r\n").unwrap();
+        write!(buffer, "{} {}\r\n", request.method, request.path).unwrap();
+        match &request.body {
+            Some(body) => {
+                write!(buffer, "Content-Type: application/json\r\n\r\n").unwrap();
+                write!(buffer, "{body}\r\n").unwrap();
+            }
+            None => write!(buffer, "\r\n").unwrap(),
+        }
+    }
+    write!(buffer, "--{BOUNDARY}--\r\n").unwrap();
+
+    buffer
+}
+
+/// Parses a multipart/mixed batch response and returns the responses ordered by Content-ID.
+fn decode(content_type: &str, body: &str) -> anyhow::Result<Vec<BatchResponse>> {
+    let boundary = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .ok_or_else(|| anyhow!("no boundary in batch response content type `{content_type}`"))?;
+    let body = body.replace("\r\n", "\n");
+
+    let mut responses = Vec::new();
+    for part in body.split(&format!("--{boundary}")) {
+        let part = part.trim_start_matches('\n');
+        if part.is_empty() || part.starts_with("--") {
+            continue;
+        }
+
+        let (headers, http) = part
+            .split_once("\n\n")
+            .context("malformed batch response part")?;
+        let index: usize = headers
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("content-id").then_some(value)
+            })
+            .and_then(|content_id| {
+                content_id
+                    .trim()
+                    .strip_prefix("<response-item")?
+                    .strip_suffix('>')?
+                    .parse()
+                    .ok()
+            })
+            .context("missing Content-ID in batch response part")?;
+
+        let (status_line, rest) = http.split_once('\n').unwrap_or((http, ""));
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .context(format!("malformed batch response status `{status_line}`"))?;
+        let body = rest
+            .split_once("\n\n")
+            .map(|(_headers, body)| body.trim())
+            .unwrap_or_default()
+            .to_string();
+
+        responses.push((index, BatchResponse { status, body }));
+    }
+    responses.sort_by_key(|(index, _)| *index);
+
+    Ok(responses
+        .into_iter()
+        .map(|(_, response)| response)
+        .collect())
+}
+
+/// Percent-encodes a URL path segment (e.g. a calendar ID).
+pub(crate) fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => write!(encoded, "%{byte:02X}").unwrap(),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let requests = [
+            BatchRequest {
+                method: "PATCH",
+                path: "/calendar/v3/calendars/primary/events/00527".into(),
+                body: Some(r#"{"summary":"SCMA: Joshua Tree"}"#.into()),
+            },
+            BatchRequest {
+                method: "DELETE",
+                path: "/calendar/v3/calendars/primary/events/00528".into(),
+                body: None,
+            },
+        ];
+        insta::assert_snapshot!(super::encode(&requests).replace("\r\n", "\n"));
+    }
+
+    #[test]
+    fn decode() {
+        let body = "--batch_abc\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item1>\r\n\
+            \r\n\
+            HTTP/1.1 409 Conflict\r\n\
+            Content-Type: application/json; charset=UTF-8\r\n\
+            \r\n\
+            {\"error\": {\"code\": 409}}\r\n\
+            --batch_abc\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item0>\r\n\
+            \r\n\
+            HTTP/1.1 204 No Content\r\n\
+            \r\n\
+            \r\n\
+            --batch_abc--\r\n";
+
+        let responses = super::decode("multipart/mixed; boundary=batch_abc", body).unwrap();
+        assert_eq!(
+            responses,
+            vec![
+                BatchResponse {
+                    status: 204,
+                    body: "".into(),
+                },
+                BatchResponse {
+                    status: 409,
+                    body: r#"{"error": {"code": 409}}"#.into(),
+                },
+            ]
+        );
+        assert!(super::decode("multipart/mixed", body).is_err());
+    }
+
+    #[test]
+    fn encode_path_segment() {
+        assert_eq!(
+            super::encode_path_segment("abc123@group.calendar.google.com"),
+            "abc123%40group.calendar.google.com"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::model::{Event, User};
+use crate::Error;
+
+use serde::Serialize;
+
+/// The CSV columns of an event.  The column order is stable.
+#[derive(Serialize)]
+struct EventRow<'a> {
+    id: &'a str,
+    title: &'a str,
+    start_date: String,
+    end_date: String,
+    /// Empty for an all-day event
+    start_time: String,
+    /// Empty for an all-day event
+    end_time: String,
+    category: &'a str,
+    location: &'a str,
+    url: &'a str,
+    /// The total number of attendees including guests
+    attendees: u32,
+}
+
+/// The CSV columns of a user.  The column order is stable.
+#[derive(Serialize)]
+struct UserRow<'a> {
+    id: &'a str,
+    name: &'a str,
+    email: &'a str,
+    phone: &'a str,
+    member_status: String,
+    trip_leader_status: String,
+    position: &'a str,
+    address: &'a str,
+    city: &'a str,
+    state: &'a str,
+    zipcode: &'a str,
+    member_since: &'a str,
+}
+
+/// Renders the events as CSV with a header row.
+pub fn events_to_csv(events: &[Event]) -> Result<String, Error> {
+    to_csv(events.iter().map(|event| {
+        EventRow {
+            id: &event.id,
+            title: &event.title,
+            start_date: event.start_date.to_string(),
+            end_date: event.end_date.to_string(),
+            start_time: event
+                .time
+                .as_ref()
+                .map(|time| time.start.format("%H:%M").to_string())
+                .unwrap_or_default(),
+            end_time: event
+                .time
+                .as_ref()
+                .map(|time| time.end.format("%H:%M").to_string())
+                .unwrap_or_default(),
+            category: event.category.as_deref().unwrap_or_default(),
+            location: &event.location,
+            url: &event.url,
+            attendees: event.attendee_count(),
+        }
+    }))
+}
+
+/// Renders the users as CSV with a header row.
+pub fn users_to_csv(users: &[User]) -> Result<String, Error> {
+    to_csv(users.iter().map(|user| {
+        UserRow {
+            id: &user.id,
+            name: &user.name,
+            email: &user.email,
+            phone: user.phone.as_deref().unwrap_or_default(),
+            member_status: user.member_status.to_string(),
+            trip_leader_status: user
+                .trip_leader_status
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            position: user.position.as_deref().unwrap_or_default(),
+            address: &user.address,
+            city: &user.city,
+            state: &user.state,
+            zipcode: &user.zipcode,
+            member_since: user.membersince.as_deref().unwrap_or_default(),
+        }
+    }))
+}
+
+fn to_csv<T: Serialize>(rows: impl Iterator<Item = T>) -> Result<String, Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row).map_err(Error::file)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| Error::file(err.to_string()))?;
+
+    String::from_utf8(bytes).map_err(Error::file)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{event, EventTime, MemberStatus, TripLeaderStatus};
+
+    use chrono::NaiveTime;
+
+    #[test]
+    fn events_to_csv() {
+        let events = vec![
+            Event {
+                title: "Joshua Tree, Hidden Valley".into(),
+                url: "https://www.rockclimbing.org/index.php/event-list/events-list/event/527"
+                    .into(),
+                category: Some("2".into()),
+                location: "Joshua Tree, CA".into(),
+                ..event("527", "2024-03-09")
+            },
+            Event {
+                title: "Board Meeting".into(),
+                time: Some(EventTime {
+                    start: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                    end: NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+                }),
+                ..event("531", "2024-03-09")
+            },
+        ];
+
+        insta::assert_snapshot!(super::events_to_csv(&events).unwrap());
+    }
+
+    #[test]
+    fn users_to_csv() {
+        let users = vec![User {
+            id: "42".into(),
+            name: "Jane Doe".into(),
+            email: "jane@example.com".into(),
+            phone: Some("555-1234".into()),
+            member_status: MemberStatus::RM,
+            trip_leader_status: Some(TripLeaderStatus::S1),
+            city: "Pasadena, \"Crown City\"".into(),
+            ..Default::default()
+        }];
+
+        insta::assert_snapshot!(super::users_to_csv(&users).unwrap());
+    }
+}
@@ -1,55 +1,206 @@
-use crate::Connector;
+use crate::{keyring_get, keyring_set, Connector, Error};
 
 use anyhow::Context;
+use async_trait::async_trait;
 use tracing::info;
 use yup_oauth2::{
-    authenticator::Authenticator, InstalledFlowAuthenticator, InstalledFlowReturnMethod,
-    ServiceAccountAuthenticator,
+    authenticator::Authenticator,
+    storage::{TokenInfo, TokenStorage},
+    ApplicationSecret, DeviceFlowAuthenticator, InstalledFlowAuthenticator,
+    InstalledFlowReturnMethod, ServiceAccountAuthenticator, ServiceAccountKey,
 };
 
+use std::collections::BTreeMap;
+
 pub struct GAuth {
     auth: Authenticator<Connector>,
+    /// Overrides the root URL of the Google Calendar API if set
+    calendar_root_url: Option<String>,
+    /// Overrides the root URL of the Google People API if set
+    people_root_url: Option<String>,
+}
+
+/// The source of a client secret or service account key.
+#[derive(Clone, Debug)]
+pub enum Secret {
+    /// The path to the JSON file
+    File(String),
+    /// The JSON itself (e.g. from an environment variable)
+    Json(String),
+}
+
+impl Secret {
+    async fn application_secret(&self) -> Result<ApplicationSecret, Error> {
+        match self {
+            Self::File(path) => yup_oauth2::read_application_secret(path)
+                .await
+                .with_context(|| {
+                    format!("could not read OAuth application secret from file `{path}`")
+                }),
+            Self::Json(json) => yup_oauth2::parse_application_secret(json)
+                .context("could not parse OAuth application secret from JSON"),
+        }
+        .map_err(Error::auth)
+    }
+
+    async fn service_account_key(&self) -> Result<ServiceAccountKey, Error> {
+        match self {
+            Self::File(path) => yup_oauth2::read_service_account_key(path)
+                .await
+                .with_context(|| {
+                    format!("could not read Google service account key from file `{path}`")
+                }),
+            Self::Json(json) => yup_oauth2::parse_service_account_key(json)
+                .context("could not parse Google service account key from JSON"),
+        }
+        .map_err(Error::auth)
+    }
+}
+
+/// Where the OAuth tokens are persisted.
+#[derive(Clone, Debug)]
+pub enum TokenStore {
+    /// A plaintext JSON file at the path
+    File(String),
+    /// The OS keyring entry of the name
+    Keyring(String),
+}
+
+/// Persists the OAuth tokens in an OS keyring entry instead of a plaintext file.
+///
+/// The entry holds the JSON of the tokens keyed by the space separated scopes.
+struct KeyringTokenStorage {
+    entry: String,
+}
+
+impl KeyringTokenStorage {
+    fn tokens(&self) -> BTreeMap<String, TokenInfo> {
+        keyring_get(&self.entry)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl TokenStorage for KeyringTokenStorage {
+    async fn set(&self, scopes: &[&str], token: TokenInfo) -> anyhow::Result<()> {
+        let mut tokens = self.tokens();
+        tokens.insert(scopes.join(" "), token);
+        keyring_set(&self.entry, &serde_json::to_string(&tokens)?)?;
+
+        Ok(())
+    }
+
+    async fn get(&self, scopes: &[&str]) -> Option<TokenInfo> {
+        self.tokens().remove(&scopes.join(" "))
+    }
 }
 
 impl GAuth {
+    fn new(auth: Authenticator<Connector>) -> Self {
+        Self {
+            auth,
+            calendar_root_url: None,
+            people_root_url: None,
+        }
+    }
+
     pub async fn with_oauth(
-        client_secret_json_path: &str,
-        oauth_token_json_path: &str,
-    ) -> anyhow::Result<Self> {
-        let secret = yup_oauth2::read_application_secret(client_secret_json_path)
-            .await
-            .with_context(|| {
-                format!(
-                    "could not read OAuth application secret from file `{client_secret_json_path}`"
-                )
-            })?;
-
-        info!(client_id=?secret.client_id, "Authenticating using OAuth");
-        let auth =
-            InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
-                .persist_tokens_to_disk(oauth_token_json_path)
-                .build()
-                .await?;
-
-        Ok(Self { auth })
-    }
-
-    pub async fn with_service_account(client_secret_json_path: &str) -> anyhow::Result<Self> {
-        let secret = yup_oauth2::read_service_account_key(client_secret_json_path)
-            .await
-            .with_context(|| {
-                format!(
-                    "could not read Google service account key from file `{client_secret_json_path}`"
-                )
-            })?;
-
-        info!(client_id=?secret.client_id, client_email=?secret.client_email, "Authenticating using service account");
-        let auth = ServiceAccountAuthenticator::builder(secret).build().await?;
-
-        Ok(Self { auth })
+        client_secret: &Secret,
+        token_store: &TokenStore,
+    ) -> Result<Self, Error> {
+        let secret = client_secret.application_secret().await?;
+
+        info!(client_id=?secret.client_id, ?token_store, "Authenticating using OAuth");
+        let builder =
+            InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect);
+        let builder = match token_store {
+            TokenStore::File(path) => builder.persist_tokens_to_disk(path),
+            TokenStore::Keyring(entry) => builder.with_storage(Box::new(KeyringTokenStorage {
+                entry: entry.clone(),
+            })),
+        };
+        let auth = builder.build().await.map_err(Error::auth)?;
+
+        Ok(Self::new(auth))
+    }
+
+    /// Authenticates using the OAuth device authorization flow.
+    ///
+    /// Prints a URL and a code to enter on another device.  Intended for headless servers where
+    /// the browser redirect of the installed flow is not possible.
+    pub async fn with_device_flow(
+        client_secret: &Secret,
+        token_store: &TokenStore,
+    ) -> Result<Self, Error> {
+        let secret = client_secret.application_secret().await?;
+
+        info!(client_id=?secret.client_id, ?token_store, "Authenticating using OAuth device flow");
+        let builder = DeviceFlowAuthenticator::builder(secret);
+        let builder = match token_store {
+            TokenStore::File(path) => builder.persist_tokens_to_disk(path),
+            TokenStore::Keyring(entry) => builder.with_storage(Box::new(KeyringTokenStorage {
+                entry: entry.clone(),
+            })),
+        };
+        let auth = builder.build().await.map_err(Error::auth)?;
+
+        Ok(Self::new(auth))
+    }
+
+    /// Authenticates using a service account.
+    ///
+    /// Acts as the given user if impersonate is set.  This requires domain-wide delegation of the
+    /// service account in Google Workspace.
+    pub async fn with_service_account(
+        service_account_key: &Secret,
+        impersonate: Option<&str>,
+    ) -> Result<Self, Error> {
+        let secret = service_account_key.service_account_key().await?;
+
+        info!(client_id=?secret.client_id, client_email=?secret.client_email, ?impersonate, "Authenticating using service account");
+        let mut builder = ServiceAccountAuthenticator::builder(secret);
+        if let Some(subject) = impersonate {
+            builder = builder.subject(subject);
+        }
+        let auth = builder.build().await.map_err(Error::auth)?;
+
+        Ok(Self::new(auth))
     }
 
     pub fn auth(&self) -> Authenticator<Connector> {
         self.auth.clone()
     }
+
+    /// Sends the Google Calendar API requests to the given root URL instead of
+    /// `https://www.googleapis.com/` (e.g. a local mock server for testing).
+    ///
+    /// Plain HTTP is allowed for an overridden root URL.
+    pub fn set_calendar_root_url(&mut self, url: &str) {
+        self.calendar_root_url = Some(with_trailing_slash(url));
+    }
+
+    /// Sends the Google People API requests to the given root URL instead of
+    /// `https://people.googleapis.com/` (e.g. a local mock server for testing).
+    ///
+    /// Plain HTTP is allowed for an overridden root URL.
+    pub fn set_people_root_url(&mut self, url: &str) {
+        self.people_root_url = Some(with_trailing_slash(url));
+    }
+
+    pub(crate) fn calendar_root_url(&self) -> Option<&str> {
+        self.calendar_root_url.as_deref()
+    }
+
+    pub(crate) fn people_root_url(&self) -> Option<&str> {
+        self.people_root_url.as_deref()
+    }
+}
+
+fn with_trailing_slash(url: &str) -> String {
+    if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{url}/")
+    }
 }
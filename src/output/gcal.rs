@@ -1,35 +1,765 @@
-use crate::model::Event;
+use crate::model::{Event, User, DEFAULT_TIMEZONE};
+use crate::output::batch::{self, encode_path_segment, BatchRequest};
+use crate::output::i18n::Lang;
+use crate::output::retry::{backoff, is_rate_limited, jitter, RetryDelegate, MAX_RETRIES};
+use crate::output::{fail_or_continue, EventSink, UserSink};
+use crate::plan::{Plan, PlannedWrite};
 use crate::Connector;
-use crate::GAuth;
+use crate::{Error, GAuth};
 
-use chrono::Duration;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
 use futures::{stream, StreamExt, TryStreamExt};
 use google_calendar3::{api, CalendarHub};
 use hyper_util::{client::legacy::Client, rt::TokioExecutor};
-use tracing::{debug, info, trace};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tera::Tera;
+use tracing::{debug, info, trace, warn};
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write;
+use std::sync::{Arc, Mutex};
 
 pub struct GCal {
     calendar_id: String,
+    /// Excluded from the ACL sync of the calendar readers
+    calendar_owners: Vec<String>,
     hub: CalendarHub<Connector>,
+    /// For batch requests which are not supported by the hub
+    http: reqwest::Client,
+    batch_url: String,
     dry_run: bool,
     notify_acl_insert: bool,
+    /// Syncs the calendar readers with the users if set
+    acl_users: bool,
+    /// The groups granted read access.  Group rules are only synced if not empty.
+    acl_groups: Vec<String>,
+    /// The domain granted read access.  Domain rules are only synced if set.
+    acl_domain: Option<String>,
+    /// Grants everyone read access if set.  The public rule is only synced if set.
+    acl_public: bool,
+    /// Reports the extra ACL rules instead of deleting them if set
+    acl_no_delete: bool,
+    /// Inserts ACL rules for opted-out and blocked users if set
+    acl_include_optedout: bool,
+    /// Maps the inserted user emails to the emails reported by acl.insert if they differ
+    learned_aliases: Mutex<BTreeMap<String, String>>,
+    event_options: EventOptions,
+    /// Enables incremental sync of the existing calendar events if set
+    event_cache: Mutex<Option<EventCache>>,
+    /// Diffs against what was last pushed instead of reading the calendar if set
+    pushed_state: Mutex<Option<PushedState>>,
+    /// Records the writes (or the writes that would be made if dry_run)
+    plan: Arc<Plan>,
+    /// Records failed writes in the plan and continues instead of stopping if set
+    continue_on_error: bool,
+}
+
+/// The default format of the event summary
+pub const DEFAULT_SUMMARY_FORMAT: &str = "SCMA: {title}";
+
+/// Controls how SCMA events are rendered as Google Calendar events.
+#[derive(Clone, Debug, Default)]
+pub struct EventOptions {
+    /// The format of the summary.  Uses DEFAULT_SUMMARY_FORMAT if None.
+    ///
+    /// The placeholders `{title}`, `{location}`, `{id}`, `{start_date}`, `{end_date}`, and `{url}`
+    /// are replaced with the event fields.
+    pub summary_format: Option<String>,
+    /// Appends the number of attendees to the summary (e.g. "SCMA: Joshua Tree (12 going)").
+    pub attendee_count: bool,
+    /// Maps title keywords to summary prefixes (e.g. "campout" to "🏕").
+    ///
+    /// Keywords are matched case-insensitively against the event title.  The first matching
+    /// keyword wins.
+    pub title_markers: IndexMap<String, String>,
+    /// Maps SCMA category IDs to event colors (e.g. "2" to basil).
+    ///
+    /// Events of unmapped categories use the calendar color.
+    pub category_colors: IndexMap<String, EventColor>,
+    /// Overrides the reminders of the calendar user (e.g. a popup 1 day before).  Uses the default
+    /// reminders of the calendar if None.
+    pub reminders: Option<Vec<EventReminder>>,
+    /// Adds the attendees with an email (see Event::set_attendee_emails) as guests.
+    ///
+    /// Guests see the event on their own calendar.
+    pub attendee_guests: bool,
+    /// Sets Event.guestsCanInviteOthers.  Left unchanged if None.
+    pub guests_can_invite_others: Option<bool>,
+    /// Sets Event.guestsCanModify.  Left unchanged if None.
+    pub guests_can_modify: Option<bool>,
+    /// Sets Event.guestsCanSeeOtherGuests.  Left unchanged if None.
+    pub guests_can_see_other_guests: Option<bool>,
+    /// Selects the content of the description.  Ignored if description_template is set.
+    pub description_include: DescriptionInclude,
+    /// Replaces the built-in description layout.
+    pub description_template: Option<DescriptionTemplate>,
+    /// The language of the description headings, the sync footer, and the attendee count.
+    pub lang: Lang,
+    /// The timezone of SCMA events and of the sync footer timestamp.  Uses DEFAULT_TIMEZONE if
+    /// None.
+    pub timezone: Option<Tz>,
+}
+
+impl EventOptions {
+    fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or(DEFAULT_TIMEZONE)
+    }
+}
+
+/// Selects the optional sections of the event description.
+///
+/// The link to the SCMA event and the SCMA event description are always included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DescriptionInclude {
+    /// Includes the attendee names
+    pub attendees: bool,
+    /// Includes the attendee comments.  Ignored if attendees is false.
+    pub attendee_comments: bool,
+    /// Includes the comment thread
+    pub comments: bool,
+    /// Includes the number of attendees (including guests) in place of the attendee names and the
+    /// number of comments in place of the comment thread if they are omitted
+    pub counts: bool,
+}
+
+impl Default for DescriptionInclude {
+    fn default() -> Self {
+        Self {
+            attendees: true,
+            attendee_comments: true,
+            comments: true,
+            counts: false,
+        }
+    }
+}
+
+/// A Google Calendar event color.
+///
+/// The names are those shown by the Google Calendar UI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventColor {
+    Lavender,
+    Sage,
+    Grape,
+    Flamingo,
+    Banana,
+    Tangerine,
+    Peacock,
+    Graphite,
+    Blueberry,
+    Basil,
+    Tomato,
+}
+
+impl EventColor {
+    /// Returns the Event.colorId of the color.
+    fn color_id(self) -> &'static str {
+        match self {
+            Self::Lavender => "1",
+            Self::Sage => "2",
+            Self::Grape => "3",
+            Self::Flamingo => "4",
+            Self::Banana => "5",
+            Self::Tangerine => "6",
+            Self::Peacock => "7",
+            Self::Graphite => "8",
+            Self::Blueberry => "9",
+            Self::Basil => "10",
+            Self::Tomato => "11",
+        }
+    }
+}
+
+/// How a reminder is delivered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReminderMethod {
+    Popup,
+    Email,
+}
+
+impl ReminderMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Popup => "popup",
+            Self::Email => "email",
+        }
+    }
+}
+
+/// A reminder before the start of an event.
+///
+/// Parsed from `<method>:<time>` where the time is in minutes or has a unit (e.g. `popup:1d`,
+/// `email:12h`, or `popup:30`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventReminder {
+    pub method: ReminderMethod,
+    pub minutes: i32,
+}
+
+impl EventReminder {
+    /// The maximum time before the start of an event allowed by Google Calendar (4 weeks)
+    const MAX_MINUTES: i32 = 40320;
+
+    fn to_api(self) -> api::EventReminder {
+        api::EventReminder {
+            method: Some(self.method.as_str().to_string()),
+            minutes: Some(self.minutes),
+        }
+    }
+
+    fn from_api(reminder: &api::EventReminder) -> Option<Self> {
+        let method = match reminder.method.as_deref()? {
+            "popup" => ReminderMethod::Popup,
+            "email" => ReminderMethod::Email,
+            _ => return None,
+        };
+
+        Some(Self {
+            method,
+            minutes: reminder.minutes?,
+        })
+    }
+}
+
+impl std::fmt::Display for EventReminder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.method.as_str(), self.minutes)
+    }
+}
+
+impl std::str::FromStr for EventReminder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid reminder `{s}`, expected e.g. `popup:1d` or `email:30m`");
+        let (method, time) = s.trim().split_once(':').ok_or_else(err)?;
+        let method = match method {
+            "popup" => ReminderMethod::Popup,
+            "email" => ReminderMethod::Email,
+            _ => return Err(err()),
+        };
+        let (number, multiplier) = match time.char_indices().last() {
+            Some((i, 'm')) => (&time[..i], 1),
+            Some((i, 'h')) => (&time[..i], 60),
+            Some((i, 'd')) => (&time[..i], 60 * 24),
+            Some((i, 'w')) => (&time[..i], 60 * 24 * 7),
+            _ => (time, 1),
+        };
+        let minutes = number
+            .parse::<i32>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .filter(|minutes| (0..=Self::MAX_MINUTES).contains(minutes))
+            .ok_or_else(err)?;
+
+        Ok(Self { method, minutes })
+    }
+}
+
+/// A custom layout of the event description.
+///
+/// The template is a [Tera](https://keats.github.io/tera/docs/) template rendered with the SCMA
+/// event as `event` (e.g. `{{ event.title }}`, `{% for attendee in event.attendees %}`).  Values
+/// are not escaped since the SCMA event description is already HTML.  The sync footer is appended
+/// to the rendered template.
+#[derive(Clone, Debug)]
+pub struct DescriptionTemplate(Tera);
+
+impl DescriptionTemplate {
+    const NAME: &'static str = "description";
+
+    pub fn new(template: &str) -> Result<Self, Error> {
+        let mut tera = Tera::default();
+        tera.autoescape_on(Vec::new());
+        tera.add_raw_template(Self::NAME, template)
+            .map_err(Error::parse)?;
+
+        Ok(Self(tera))
+    }
+
+    fn render(&self, event: &Event) -> Result<String, Error> {
+        let mut context = tera::Context::new();
+        context.insert("event", event);
+
+        self.0.render(Self::NAME, &context).map_err(Error::parse)
+    }
+}
+
+/// Selects the calendar to sync to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalendarSelect {
+    /// The calendar with the name (i.e. the summary).  Inserted if it does not exist.
+    Name(String),
+    /// The calendar with the ID.  Must exist.
+    Id(String),
+}
+
+/// Selects the events synced to a calendar other than the default calendar.
+///
+/// An event matches if it matches any of the categories, locations, or keywords.  Locations and
+/// keywords are matched case-insensitively against the event location and title.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarRoute {
+    /// SCMA category IDs (e.g. "4")
+    pub categories: Vec<String>,
+    pub locations: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+impl CalendarRoute {
+    pub fn matches(&self, event: &Event) -> bool {
+        let contains = |text: &str, patterns: &[String]| {
+            let text = text.to_lowercase();
+            patterns
+                .iter()
+                .any(|pattern| text.contains(&pattern.to_lowercase()))
+        };
+
+        event
+            .category
+            .as_ref()
+            .is_some_and(|category| self.categories.contains(category))
+            || contains(&event.location, &self.locations)
+            || contains(&event.title, &self.keywords)
+    }
+}
+
+/// What to do with synced calendar events that no longer exist on SCMA.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrphanAction {
+    /// Prefixes the summary with a cancelled marker (e.g. "CANCELLED: SCMA: Joshua Tree").  The
+    /// event remains on the calendar.
+    ///
+    /// The status is left confirmed because Google Calendar hides events with the cancelled status
+    /// the same as deleted events.
+    Cancel,
+    /// Prefixes the summary with a cancelled marker and sets the status to cancelled.
+    ///
+    /// Google Calendar hides the event but clients that sync the calendar (e.g. via CalDAV) see
+    /// that it was cancelled rather than deleted.
+    CancelStatus,
+    /// Deletes the event from the calendar
+    Delete,
+}
+
+/// A cache of the existing calendar events kept up to date by incremental sync.
+///
+/// Instead of listing the calendar events on every run, only the events changed since the
+/// previous run are fetched using the sync token.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCache {
+    /// The calendar of the cached events.  The cache is discarded if the calendar changes.
+    pub calendar_id: String,
+    /// Fetches the events changed since the cache was last updated.  None for a full sync.
+    pub sync_token: Option<String>,
+    /// Maps the calendar event IDs to the cached events
+    pub events: BTreeMap<String, CachedEvent>,
+}
+
+/// The parts of a calendar event needed to sync
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedEvent {
+    pub summary: Option<String>,
+    pub status: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    /// Exclusive
+    pub end_date: Option<NaiveDate>,
+    /// The content hash (see HASH_PROPERTY)
+    pub hash: Option<String>,
+}
+
+impl EventCache {
+    fn new(calendar_id: &str) -> Self {
+        Self {
+            calendar_id: calendar_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Applies changed events (including deleted events) to the cache.
+    fn apply(&mut self, g_events: Vec<api::Event>) {
+        for g_event in g_events {
+            let Some(id) = g_event.id.clone() else {
+                continue;
+            };
+            self.events.insert(id, CachedEvent::from(g_event));
+        }
+    }
+
+    /// Returns the cached events that overlap the given dates.
+    fn events(&self, since: Option<NaiveDate>, until: Option<NaiveDate>) -> Vec<api::Event> {
+        overlapping_events(&self.events, since, until)
+    }
+}
+
+/// What was last pushed to the calendar.
+///
+/// Used to diff against instead of re-reading the calendar on every run.  Changes made to the
+/// calendar by others (e.g. an event deleted in Google Calendar) are not seen.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushedState {
+    /// The calendar of the pushed state.  The state is discarded if the calendar changes.
+    pub calendar_id: String,
+    /// Maps the calendar event IDs to the pushed events.  None until read from the calendar once.
+    pub events: Option<BTreeMap<String, CachedEvent>>,
+    /// The emails of the users (and the `group:` and `domain:` prefixed groups and domains)
+    /// granted read access.  None until read from the calendar once.
+    pub acl: Option<BTreeSet<String>>,
+}
+
+impl PushedState {
+    fn new(calendar_id: &str) -> Self {
+        Self {
+            calendar_id: calendar_id.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns a rule that grants the user read access.
+fn reader_rule(scope: &AclScope) -> api::AclRule {
+    api::AclRule {
+        role: Some("reader".to_string()),
+        scope: Some(scope.to_api()),
+        ..Default::default()
+    }
+}
+
+/// Returns the URL of the public web page of the calendar.
+fn public_html_url(calendar_id: &str) -> String {
+    reqwest::Url::parse_with_params(
+        "https://calendar.google.com/calendar/embed",
+        [("src", calendar_id)],
+    )
+    .unwrap()
+    .to_string()
+}
+
+/// Returns the URL of the public iCalendar feed of the calendar.
+fn public_ics_url(calendar_id: &str) -> String {
+    let mut url = reqwest::Url::parse("https://calendar.google.com/calendar/ical").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .extend([calendar_id, "public", "basic.ics"]);
+    url.to_string()
+}
+
+/// Returns the URL of the batch endpoint of the Google Calendar API at the root URL.
+fn batch_url(root_url: &str) -> String {
+    format!("{root_url}batch/calendar/v3")
+}
+
+/// Returns the events that overlap the given dates.
+///
+/// Matches the semantics of the timeMin and timeMax parameters of events.list.
+fn overlapping_events(
+    events: &BTreeMap<String, CachedEvent>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<api::Event> {
+    events
+        .iter()
+        .filter(|(_, event)| {
+            since.is_none_or(|since| event.end_date.is_some_and(|end_date| end_date > since))
+        })
+        .filter(|(_, event)| {
+            until.is_none_or(|until| {
+                event
+                    .start_date
+                    .is_some_and(|start_date| start_date <= until)
+            })
+        })
+        .map(|(id, event)| event.to_api_event(id))
+        .collect()
+}
+
+impl From<api::Event> for CachedEvent {
+    fn from(g_event: api::Event) -> Self {
+        let hash = g_event
+            .extended_properties
+            .and_then(|properties| properties.private)
+            .and_then(|mut private| private.remove(HASH_PROPERTY));
+
+        Self {
+            summary: g_event.summary,
+            status: g_event.status,
+            // The UTC date of a timed event may differ from its local date so widen the dates of
+            // timed events by a day
+            start_date: g_event.start.and_then(|start| {
+                start.date.or(start
+                    .date_time
+                    .map(|start| start.date_naive() - Duration::days(1)))
+            }),
+            end_date: g_event.end.and_then(|end| {
+                end.date.or(end
+                    .date_time
+                    .map(|end| end.date_naive() + Duration::days(1)))
+            }),
+            hash,
+        }
+    }
+}
+
+impl CachedEvent {
+    fn to_api_event(&self, id: &str) -> api::Event {
+        api::Event {
+            id: Some(id.to_string()),
+            summary: self.summary.clone(),
+            status: self.status.clone(),
+            start: Some(api::EventDateTime {
+                date: self.start_date,
+                ..Default::default()
+            }),
+            end: Some(api::EventDateTime {
+                date: self.end_date,
+                ..Default::default()
+            }),
+            extended_properties: self.hash.as_deref().map(hash_properties),
+            ..Default::default()
+        }
+    }
+}
+
+/// A write of a synced event
+#[derive(Clone)]
+enum EventWrite {
+    Insert(api::Event),
+    Patch(api::Event),
+}
+
+impl EventWrite {
+    fn request(&self, calendar_id: &str) -> Result<BatchRequest, serde_json::Error> {
+        let events_path = format!(
+            "/calendar/v3/calendars/{}/events",
+            encode_path_segment(calendar_id)
+        );
+        let request = match self {
+            Self::Insert(g_event) => BatchRequest {
+                method: "POST",
+                path: events_path,
+                body: Some(event_json(g_event)?),
+            },
+            Self::Patch(g_event) => BatchRequest {
+                method: "PATCH",
+                path: format!(
+                    "{events_path}/{}",
+                    encode_path_segment(g_event.id.as_deref().unwrap_or_default())
+                ),
+                body: Some(event_json(g_event)?),
+            },
+        };
+
+        Ok(request)
+    }
+
+    fn event(&self) -> &api::Event {
+        match self {
+            Self::Insert(g_event) | Self::Patch(g_event) => g_event,
+        }
+    }
+
+    fn planned(&self) -> PlannedWrite {
+        let g_event = self.event();
+        let event_id = g_event.id.clone().unwrap_or_default();
+        let summary = g_event.summary.clone().unwrap_or_default();
+        match self {
+            Self::Insert(_) => PlannedWrite::EventInsert { event_id, summary },
+            Self::Patch(_) => PlannedWrite::EventPatch { event_id, summary },
+        }
+    }
+
+    fn past_tense(&self) -> &'static str {
+        match self {
+            Self::Insert(_) => "Inserted",
+            Self::Patch(_) => "Updated",
+        }
+    }
 }
 
 type Email = String;
 
+/// The grantee of an ACL rule synced by the ACL sync.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum AclScope {
+    User(Email),
+    Group(Email),
+    Domain(String),
+    /// Everyone (i.e. the calendar is public)
+    Default,
+}
+
+impl AclScope {
+    /// Returns the scope of the rule if it is a user, group, domain, or default scope.
+    fn from_api(scope: &api::AclRuleScope) -> Option<Self> {
+        let value = scope.value.clone();
+        match scope.type_.as_deref()? {
+            "user" => Some(Self::User(value?)),
+            "group" => Some(Self::Group(value?)),
+            "domain" => Some(Self::Domain(value?)),
+            "default" => Some(Self::Default),
+            _ => None,
+        }
+    }
+
+    fn to_api(&self) -> api::AclRuleScope {
+        api::AclRuleScope {
+            type_: Some(self.type_().to_string()),
+            value: match self {
+                Self::Default => None,
+                _ => Some(self.value().to_string()),
+            },
+        }
+    }
+
+    fn type_(&self) -> &'static str {
+        match self {
+            Self::User(_) => "user",
+            Self::Group(_) => "group",
+            Self::Domain(_) => "domain",
+            Self::Default => "default",
+        }
+    }
+
+    /// Returns the email of the user or group or the domain name.  Empty for the default scope.
+    fn value(&self) -> &str {
+        match self {
+            Self::User(value) | Self::Group(value) | Self::Domain(value) => value,
+            Self::Default => "",
+        }
+    }
+
+    /// Returns the ID of the ACL rule (e.g. "user:john@example.com" or "default").
+    fn rule_id(&self) -> String {
+        match self {
+            Self::Default => self.type_().to_string(),
+            _ => format!("{}:{}", self.type_(), self.value()),
+        }
+    }
+
+    /// Parses an entry of the pushed ACL.  Users are not prefixed with the scope type.
+    fn from_pushed(entry: &str) -> Self {
+        match entry.split_once(':') {
+            Some(("group", email)) => Self::Group(email.to_string()),
+            Some(("domain", domain)) => Self::Domain(domain.to_string()),
+            _ if entry == "default" => Self::Default,
+            _ => Self::User(entry.to_string()),
+        }
+    }
+
+    fn to_pushed(&self) -> String {
+        match self {
+            Self::User(email) => email.clone(),
+            Self::Group(_) | Self::Domain(_) | Self::Default => self.rule_id(),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum AclSyncOp {
-    Insert(Email),
-    Delete(Email),
+    Insert(AclScope),
+    Delete(AclScope),
 }
 
 #[derive(Debug, PartialEq, Eq)]
 struct AclSyncOpsResult {
-    inserts: HashSet<Email>,
-    deletes: HashSet<Email>,
+    inserts: HashSet<AclScope>,
+    deletes: HashSet<AclScope>,
+}
+
+/// A calendar ACL rule cross-referenced against the SCMA users.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct AclReportEntry {
+    pub role: String,
+    pub scope_type: String,
+    pub scope_value: Option<String>,
+    pub status: AclReportStatus,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum AclReportStatus {
+    /// The rule grants access to a current SCMA user
+    Member,
+    /// The rule grants access to an account that is not a current SCMA user
+    Unknown,
+    /// The rule does not grant access to an individual user (e.g. group, domain, or default scope)
+    NotUser,
+}
+
+/// A synced field of a calendar event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum EventField {
+    Summary,
+    Description,
+    Location,
+    Start,
+    End,
+}
+
+impl EventField {
+    const ALL: [Self; 5] = [
+        Self::Summary,
+        Self::Description,
+        Self::Location,
+        Self::Start,
+        Self::End,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::Summary => "summary",
+            Self::Description => "description",
+            Self::Location => "location",
+            Self::Start => "start",
+            Self::End => "end",
+        }
+    }
+
+    /// Returns the value of the field if the event has it.  The sync footer of the description is
+    /// excluded.
+    fn get(self, g_event: &api::Event) -> Option<String> {
+        let date_time = |date_time: &api::EventDateTime| {
+            date_time
+                .date
+                .map(|date| date.to_string())
+                .or(date_time.date_time.map(|date_time| date_time.to_rfc3339()))
+                .unwrap_or_default()
+        };
+        match self {
+            Self::Summary => g_event.summary.clone(),
+            Self::Description => g_event
+                .description
+                .as_deref()
+                .map(|description| description_content(description).to_string()),
+            Self::Location => g_event.location.clone(),
+            Self::Start => g_event.start.as_ref().map(date_time),
+            Self::End => g_event.end.as_ref().map(date_time),
+        }
+    }
+}
+
+/// A synced calendar event that was edited in Google Calendar since it was last synced.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct EventDrift {
+    pub calendar_id: String,
+    pub event_id: String,
+    pub summary: String,
+    /// The fields that were edited
+    pub fields: Vec<EventField>,
+}
+
+/// The events and the ACL of a calendar as backed up (see GCal::backup).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarBackup {
+    pub calendar_id: String,
+    /// Every event including the deleted events
+    pub events: Vec<api::Event>,
+    pub acl: Vec<api::AclRule>,
 }
 
 // To enable named argument
@@ -55,63 +785,134 @@ const CONCURRENT_REQUESTS: usize = 3;
 /// value of 3.
 const CONCURRENT_REQUESTS_ACL: usize = 1;
 const SCOPE: api::Scope = api::Scope::Full;
+const ROOT_URL: &str = "https://www.googleapis.com/";
+/// The maximum number of event writes per batch request.  Google recommends at most 50.
+const BATCH_SIZE: usize = 50;
+/// The private extended property that stores the hash of the synced event content
+const HASH_PROPERTY: &str = "scma-gsync-hash";
+/// The private extended property that stores the hashes of the synced fields as last written.
+/// Detects manual edits in Google Calendar (see GCal::drift_report).
+const FIELDS_PROPERTY: &str = "scma-gsync-fields";
 
 impl GCal {
     pub async fn new(
-        calendar_name: &str,
+        calendar: &CalendarSelect,
         calendar_owners: &[String],
         auth: GAuth,
         dry_run: bool,
         notify_acl_insert: bool,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+        event_options: EventOptions,
+        plan: Arc<Plan>,
+    ) -> Result<Self, Error> {
+        let batch_url = batch_url(auth.calendar_root_url().unwrap_or(ROOT_URL));
         let hub = Self::create_hub(auth).await?;
-        let calendar_id =
-            Self::calendars_get_or_insert_by_name(&hub, calendar_name, dry_run).await?;
+        let calendar_id = match calendar {
+            CalendarSelect::Name(calendar_name) => {
+                Self::calendars_get_or_insert_by_name(&hub, calendar_name, dry_run).await?
+            }
+            CalendarSelect::Id(calendar_id) => Self::calendars_get(&hub, calendar_id).await?,
+        };
 
         let gcal = Self {
             calendar_id,
+            calendar_owners: calendar_owners.to_vec(),
             hub,
+            http: reqwest::Client::new(),
+            batch_url,
             dry_run,
             notify_acl_insert,
+            acl_users: true,
+            acl_groups: Vec::new(),
+            acl_domain: None,
+            acl_public: false,
+            acl_no_delete: false,
+            acl_include_optedout: false,
+            learned_aliases: Mutex::new(BTreeMap::new()),
+            event_options,
+            event_cache: Mutex::new(None),
+            pushed_state: Mutex::new(None),
+            plan,
+            continue_on_error: false,
         };
 
         for calendar_owner in calendar_owners {
-            gcal.acl_insert(calendar_owner, "owner", SendNotifications(false))
-                .await?;
+            gcal.acl_insert(
+                &AclScope::User(calendar_owner.clone()),
+                "owner",
+                SendNotifications(false),
+            )
+            .await?;
         }
 
         Ok(gcal)
     }
 
-    async fn create_hub(
-        gauth: GAuth,
-    ) -> Result<CalendarHub<Connector>, Box<dyn std::error::Error>> {
+    async fn create_hub(gauth: GAuth) -> Result<CalendarHub<Connector>, Error> {
         let scopes = [SCOPE];
-        let token = gauth.auth().token(&scopes).await?;
+        let token = gauth.auth().token(&scopes).await.map_err(Error::auth)?;
         info!(expiration_time=?token.expiration_time(), "Got token");
 
-        let https = hyper_rustls::HttpsConnectorBuilder::new()
-            .with_native_roots()?
-            .https_only()
-            .enable_http1()
-            .build();
+        let builder = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .map_err(Error::google)?;
+        let builder = match gauth.calendar_root_url() {
+            Some(_) => builder.https_or_http(),
+            None => builder.https_only(),
+        };
+        let https = builder.enable_http1().build();
         let client = Client::builder(TokioExecutor::new()).build(https);
 
-        let hub = CalendarHub::new(client, gauth.auth());
+        let mut hub = CalendarHub::new(client, gauth.auth());
+        if let Some(root_url) = gauth.calendar_root_url() {
+            info!(%root_url, "Using Google Calendar API root URL");
+            hub.root_url(root_url.to_string());
+            hub.base_url(format!("{root_url}calendar/v3/"));
+        }
 
         Ok(hub)
     }
 
+    /// Returns the Calendar.id of the calendar with the ID.
+    ///
+    /// Fails if the calendar does not exist or is not shared with the authenticated user.
+    async fn calendars_get(
+        hub: &CalendarHub<Connector>,
+        calendar_id: &str,
+    ) -> Result<String, Error> {
+        info!(%calendar_id, "Finding calendar");
+        let (rsp, calendar) = hub
+            .calendars()
+            .get(calendar_id)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "calendars.get");
+        debug!(?calendar, "calendars.get");
+
+        let calendar_name = calendar.summary.unwrap_or_default();
+        info!(%calendar_name, %calendar_id, "Found existing calendar");
+
+        Ok(calendar.id.unwrap_or_else(|| calendar_id.to_string()))
+    }
+
     /// Returns the Calendar.id of the named calendar.
     ///
-    /// If named calendar does not exist, a new calendar will be created.
+    /// If named calendar does not exist, a new calendar will be created unless dry_run.  Read-only
+    /// uses (e.g. reports) set dry_run so that they fail instead.
     async fn calendars_get_or_insert_by_name(
         hub: &CalendarHub<Connector>,
         calendar_name: &str,
         dry_run: bool,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    ) -> Result<String, Error> {
         info!(%calendar_name, "Finding calendar");
-        let (rsp, list) = hub.calendar_list().list().add_scope(SCOPE).doit().await?;
+        let (rsp, list) = hub
+            .calendar_list()
+            .list()
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
         trace!(?rsp, "calendar_list.list");
         debug!(?list, "calendar_list.list");
         let calendars = list.items.unwrap();
@@ -130,15 +931,23 @@ impl GCal {
                 info!(%calendar_name, "Calendar not found, inserting new calendar");
 
                 let calendar_id = if dry_run {
-                    return Err("Cannot create calendar during dry run".into());
+                    return Err(Error::google(format!(
+                        "calendar `{calendar_name}` not found, not creating it during a dry run \
+                         or a read-only command"
+                    )));
                 } else {
                     let req = api::Calendar {
                         summary: Some(calendar_name.to_string()),
                         description: Some(CALENDAR_DESCRIPTION.to_string()),
                         ..Default::default()
                     };
-                    let (rsp, calendar) =
-                        hub.calendars().insert(req).add_scope(SCOPE).doit().await?;
+                    let (rsp, calendar) = hub
+                        .calendars()
+                        .insert(req)
+                        .add_scope(SCOPE)
+                        .delegate(&mut RetryDelegate::default())
+                        .doit()
+                        .await?;
                     trace!(?rsp, "calendars.insert");
                     debug!(?calendar, "calendars.insert");
 
@@ -154,23 +963,94 @@ impl GCal {
         Ok(calendar_id)
     }
 
+    /// Grants read access to the groups instead of or in addition to the users.
+    ///
+    /// The reader rules of other groups are deleted.  The reader rules of users are deleted if
+    /// users is false.
+    pub fn set_acl_groups(&mut self, groups: Vec<String>, users: bool) {
+        self.acl_groups = groups;
+        self.acl_users = users;
+    }
+
+    /// Grants read access to everyone in the domain (e.g. example.org).
+    ///
+    /// The reader rules of other domains are deleted.
+    pub fn set_acl_domain(&mut self, domain: Option<String>) {
+        self.acl_domain = domain;
+    }
+
+    /// Makes the calendar public by granting everyone read access.
+    pub fn set_acl_public(&mut self, public: bool) {
+        self.acl_public = public;
+    }
+
+    /// Continues with the remaining writes if a write fails.
+    ///
+    /// The errors of the failed writes are recorded in the plan (see Plan::failures).
+    pub fn set_continue_on_error(&mut self, continue_on_error: bool) {
+        self.continue_on_error = continue_on_error;
+    }
+
+    /// Only inserts the missing ACL rules during the ACL sync.
+    ///
+    /// The extra ACL rules are reported instead of deleted.
+    pub fn set_acl_no_delete(&mut self, no_delete: bool) {
+        self.acl_no_delete = no_delete;
+    }
+
+    /// Inserts ACL rules for opted-out and blocked users (see User::is_optedout_or_blocked).
+    ///
+    /// By default, the missing ACL rules of these users are not inserted.  Their existing ACL rules
+    /// are kept.
+    pub fn set_acl_include_optedout(&mut self, include_optedout: bool) {
+        self.acl_include_optedout = include_optedout;
+    }
+
     // Syncs emails with readers in calendar ACL
+    //
+    // The missing rules of the `no_inserts` emails are not inserted but their existing rules are
+    // kept.
     pub async fn acl_sync(
         &self,
         emails: &[&str],
+        no_inserts: &[&str],
         owners: &[String],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let acls = self.acl_list().await?;
-        let ops = Self::acl_sync_ops(emails, &acls);
+    ) -> Result<(), Error> {
+        let acls = self.acl_existing().await?;
+        let emails = if self.acl_users { emails } else { &[] };
+        let groups: Vec<&str> = self.acl_groups.iter().map(String::as_str).collect();
+        let ops = Self::acl_sync_ops(
+            emails,
+            &groups,
+            self.acl_domain.as_deref(),
+            self.acl_public,
+            &acls,
+        );
 
         // Remove owners so that we don't operator on them
         let ops = {
             let mut ops = ops;
             for owner in owners {
-                ops.inserts.remove(owner);
-                ops.deletes.remove(owner);
+                let owner = AclScope::User(owner.clone());
+                ops.inserts.remove(&owner);
+                ops.deletes.remove(&owner);
+            }
+            for email in no_inserts {
+                if ops.inserts.remove(&AclScope::User(email.to_string())) {
+                    info!(%email, "Skipped insert of ACL rule for opted-out or blocked user");
+                }
+            }
+            ops
+        };
+
+        let ops = if self.acl_no_delete {
+            let mut ops = ops;
+            for scope in std::mem::take(&mut ops.deletes) {
+                warn!(scope.type_=%scope.type_(), scope.value=%scope.value(), "Skipped delete of extra ACL rule");
             }
             ops
+        } else {
+            ops
         };
 
         info!(ops.inserts.len=%ops.inserts.len(), ops.deletes.len=%ops.deletes.len(), ?ops, "Determined sync operations");
@@ -181,34 +1061,333 @@ impl GCal {
             .map(AclSyncOp::Insert)
             .chain(ops.deletes.into_iter().map(AclSyncOp::Delete));
         stream::iter(ops)
-            .map(|op| self.acl_insert_or_delete(op))
+            .map(|op| async {
+                let result = self.acl_insert_or_delete(op).await;
+                fail_or_continue(&self.plan, self.continue_on_error, result)
+            })
             .buffer_unordered(CONCURRENT_REQUESTS_ACL)
             .try_collect::<Vec<_>>()
             .await?;
 
+        if self.acl_public {
+            info!(calendar_id=%self.calendar_id, html_url=%public_html_url(&self.calendar_id), ics_url=%public_ics_url(&self.calendar_id), "Calendar is public");
+        }
+
         Ok(())
     }
 
-    async fn acl_insert_or_delete(&self, op: AclSyncOp) -> Result<(), Box<dyn std::error::Error>> {
+    /// Deletes the reader rules of the emails from the ACL.
+    ///
+    /// Returns the number of deleted rules.  Emails are matched case-insensitively.
+    pub async fn acl_remove(&self, emails: &[&str]) -> Result<usize, Error> {
+        let acls = self.acl_existing().await?;
+        let scopes: Vec<AclScope> = acls
+            .iter()
+            .filter(|rule| rule.role.as_deref() == Some("reader"))
+            .filter_map(|rule| rule.scope.as_ref())
+            .filter_map(AclScope::from_api)
+            .filter(|scope| match scope {
+                AclScope::User(email) => emails.iter().any(|e| e.eq_ignore_ascii_case(email)),
+                _ => false,
+            })
+            .collect();
+
+        info!(calendar_id=%self.calendar_id, count=%scopes.len(), "Removing ACL rules");
+        for scope in &scopes {
+            self.acl_delete(scope).await?;
+        }
+
+        Ok(scopes.len())
+    }
+
+    /// Returns the ACL rules.
+    ///
+    /// Uses the pushed state if enabled.  Otherwise, lists the ACL.
+    async fn acl_existing(&self) -> Result<Vec<api::AclRule>, Error> {
+        let pushed_acl = self
+            .pushed_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| state.acl.clone());
+        match pushed_acl {
+            None => self.acl_list().await,
+            Some(Some(acl)) => {
+                info!(rules=%acl.len(), "Using pushed ACL");
+                Ok(acl
+                    .iter()
+                    .map(|entry| reader_rule(&AclScope::from_pushed(entry)))
+                    .collect())
+            }
+            Some(None) => {
+                let rules = self.acl_list().await?;
+                let acl = rules
+                    .iter()
+                    .filter(|rule| rule.role.as_deref() == Some("reader"))
+                    .filter_map(|rule| rule.scope.as_ref())
+                    .filter_map(AclScope::from_api)
+                    .map(|scope| scope.to_pushed())
+                    .collect();
+                if let Some(state) = self.pushed_state.lock().unwrap().as_mut() {
+                    state.acl = Some(acl);
+                }
+                Ok(rules)
+            }
+        }
+    }
+
+    async fn acl_insert_or_delete(&self, op: AclSyncOp) -> Result<(), Error> {
         match op {
-            AclSyncOp::Insert(email) => {
-                self.acl_insert(&email, "reader", self.notify_acl_insert.into())
+            AclSyncOp::Insert(scope) => {
+                self.acl_insert(&scope, "reader", self.notify_acl_insert.into())
                     .await?
             }
-            AclSyncOp::Delete(email) => self.acl_delete(&email).await?,
+            AclSyncOp::Delete(scope) => self.acl_delete(&scope).await?,
         }
 
         Ok(())
     }
 
-    /// Returns a list of operations that need to be performed on the ACL to bring the ACL in sync
-    /// with a set of user emails.
+    /// Returns every ACL rule cross-referenced against the given user emails.
     ///
-    /// Operates on the "reader" role only.
+    /// Rules for accounts that are not in the given user emails are flagged as unknown.  The
+    /// report is sorted by role, scope type, and scope value.
+    pub async fn acl_report(&self, emails: &[&str]) -> Result<Vec<AclReportEntry>, Error> {
+        let rules = self.acl_list().await?;
+        let report = Self::acl_report_entries(emails, &rules);
+
+        let unknown: Vec<_> = report
+            .iter()
+            .filter(|entry| entry.status == AclReportStatus::Unknown)
+            .map(|entry| entry.scope_value.as_deref().unwrap_or_default())
+            .collect();
+        info!(rules=%report.len(), unknown.len=%unknown.len(), ?unknown, "Determined ACL report");
+
+        Ok(report)
+    }
+
+    /// Reports the synced events that were edited in Google Calendar since they were last synced.
     ///
-    /// This effectively performs a diff from readers to emails.
+    /// Read-only.  Always reads the calendar (i.e. ignores the incremental and local state).
+    /// Cancelled events and events last synced before field hashes were recorded are skipped.
+    pub async fn drift_report(&self) -> Result<Vec<EventDrift>, Error> {
+        let g_events = self.events_list(None, None).await?;
+        let cancelled = format!("{}: ", self.event_options.lang.strings().cancelled);
+
+        let mut report: Vec<EventDrift> = g_events
+            .iter()
+            .filter(|g_event| g_event.status.as_deref() != Some("cancelled"))
+            .filter(|g_event| g_event.id.as_deref().is_some_and(is_synced_event_id))
+            .filter(|g_event| {
+                !g_event
+                    .summary
+                    .as_deref()
+                    .is_some_and(|summary| summary.starts_with(&cancelled))
+            })
+            .filter_map(|g_event| {
+                let fields = drifted_fields(g_event);
+                (!fields.is_empty()).then(|| EventDrift {
+                    calendar_id: self.calendar_id.clone(),
+                    event_id: g_event.id.clone().unwrap_or_default(),
+                    summary: g_event.summary.clone().unwrap_or_default(),
+                    fields,
+                })
+            })
+            .collect();
+        report.sort();
+        info!(calendar_id=%self.calendar_id, drifted=%report.len(), "Determined event drift");
+
+        Ok(report)
+    }
+
+    /// Returns every event (including the deleted events) and every ACL rule of the calendar.
     ///
-    /// For example, if given the set of emails:
+    /// Read-only.  Always reads the calendar (i.e. ignores the incremental and local state).
+    pub async fn backup(&self) -> Result<CalendarBackup, Error> {
+        let events = self.events_list(None, None).await?;
+        let acl = self.acl_list().await?;
+        info!(calendar_id=%self.calendar_id, events=%events.len(), acl=%acl.len(), "Backed up calendar");
+
+        Ok(CalendarBackup {
+            calendar_id: self.calendar_id.clone(),
+            events,
+            acl,
+        })
+    }
+
+    /// Restores the events and the ACL rules of the backup.
+    ///
+    /// Events that are missing from the calendar, deleted, or differ from the backup are written
+    /// as they were backed up.  ACL rules that are missing are inserted without notifications.
+    /// Events deleted before the backup and events and rules that are not in the backup are left
+    /// alone.
+    pub async fn restore(&self, backup: &CalendarBackup) -> Result<(), Error> {
+        let existing: HashMap<String, api::Event> = self
+            .events_list(None, None)
+            .await?
+            .into_iter()
+            .filter_map(|g_event| Some((g_event.id.clone()?, g_event)))
+            .collect();
+        let (instances, events): (Vec<_>, Vec<_>) = backup
+            .events
+            .iter()
+            .filter(|g_event| g_event.status.as_deref() != Some("cancelled"))
+            .filter(
+                |g_event| match g_event.id.as_ref().and_then(|id| existing.get(id)) {
+                    Some(existing) => !is_restored(existing, g_event),
+                    None => true,
+                },
+            )
+            .partition(|g_event| g_event.recurring_event_id.is_some());
+        info!(calendar_id=%self.calendar_id, events=%events.len(), instances=%instances.len(), "Determined events to restore");
+
+        // Instances are written after their recurring events so that they exist
+        for g_events in [events, instances] {
+            let writes: Vec<_> = g_events
+                .into_iter()
+                .map(|g_event| {
+                    let existing = g_event.id.as_ref().and_then(|id| existing.get(id));
+                    async move {
+                        let result = self.event_restore(g_event, existing).await;
+                        fail_or_continue(&self.plan, self.continue_on_error, result)
+                    }
+                })
+                .collect();
+            stream::iter(writes)
+                .buffer_unordered(CONCURRENT_REQUESTS)
+                .try_collect::<Vec<_>>()
+                .await?;
+        }
+
+        let scopes: HashSet<AclScope> = self
+            .acl_list()
+            .await?
+            .iter()
+            .filter_map(|rule| rule.scope.as_ref())
+            .filter_map(AclScope::from_api)
+            .collect();
+        let rules: Vec<(AclScope, &str)> = backup
+            .acl
+            .iter()
+            .filter_map(|rule| {
+                let scope = AclScope::from_api(rule.scope.as_ref()?)?;
+                Some((scope, rule.role.as_deref()?))
+            })
+            .filter(|(scope, _)| !scopes.contains(scope))
+            .collect();
+        info!(calendar_id=%self.calendar_id, rules=%rules.len(), "Determined ACL rules to restore");
+        for (scope, role) in &rules {
+            let result = self.acl_insert(scope, role, false.into()).await;
+            fail_or_continue(&self.plan, self.continue_on_error, result)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the backed up event.  Inserts the event if it is not in the calendar.  Otherwise,
+    /// patches it.
+    async fn event_restore(
+        &self,
+        g_event: &api::Event,
+        existing: Option<&api::Event>,
+    ) -> Result<(), Error> {
+        let event_id = g_event.id.as_deref().unwrap_or_default();
+        let summary = g_event.summary.as_deref().unwrap_or_default();
+        let req = restored_event(g_event);
+
+        // An unmodified instance is not listed but exists once the recurring event is written
+        if existing.is_some() || g_event.recurring_event_id.is_some() {
+            info!(%event_id, %summary, "Restoring event");
+            let write = PlannedWrite::EventPatch {
+                event_id: event_id.to_string(),
+                summary: summary.to_string(),
+            };
+            self.plan.record(write.clone());
+            if !self.dry_run {
+                let (rsp, g_event) = self
+                    .hub
+                    .events()
+                    .patch(req, &self.calendar_id, event_id)
+                    .add_scope(SCOPE)
+                    .delegate(&mut RetryDelegate::default())
+                    .doit()
+                    .await?;
+                trace!(?rsp, "events.patch");
+                debug!(?g_event, "events.patch");
+                let before = existing.and_then(|existing| existing.summary.as_deref());
+                self.plan
+                    .audit(&self.calendar_id, &write, before, Some(summary))?;
+
+                self.update_pushed_event(g_event);
+            }
+        } else {
+            info!(%event_id, %summary, "Restoring deleted event");
+            let write = PlannedWrite::EventInsert {
+                event_id: event_id.to_string(),
+                summary: summary.to_string(),
+            };
+            self.plan.record(write.clone());
+            if !self.dry_run {
+                let (rsp, g_event) = self
+                    .hub
+                    .events()
+                    .insert(req, &self.calendar_id)
+                    .add_scope(SCOPE)
+                    .delegate(&mut RetryDelegate::default())
+                    .doit()
+                    .await?;
+                trace!(?rsp, "events.insert");
+                debug!(?g_event, "events.insert");
+                self.plan
+                    .audit(&self.calendar_id, &write, None, Some(summary))?;
+
+                self.update_pushed_event(g_event);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn acl_report_entries(emails: &[&str], rules: &[api::AclRule]) -> Vec<AclReportEntry> {
+        let emails: HashSet<&str> = emails.iter().copied().collect();
+
+        let mut report: Vec<AclReportEntry> = rules
+            .iter()
+            .map(|rule| {
+                let scope = rule.scope.as_ref();
+                let scope_type = scope
+                    .and_then(|scope| scope.type_.clone())
+                    .unwrap_or_default();
+                let scope_value = scope.and_then(|scope| scope.value.clone());
+                let status = match (scope_type.as_str(), scope_value.as_deref()) {
+                    ("user", Some(email)) if emails.contains(email) => AclReportStatus::Member,
+                    ("user", _) => AclReportStatus::Unknown,
+                    _ => AclReportStatus::NotUser,
+                };
+
+                AclReportEntry {
+                    role: rule.role.clone().unwrap_or_default(),
+                    scope_type,
+                    scope_value,
+                    status,
+                }
+            })
+            .collect();
+        report.sort();
+
+        report
+    }
+
+    /// Returns a list of operations that need to be performed on the ACL to bring the ACL in sync
+    /// with a set of user emails, group emails, and a domain.
+    ///
+    /// Operates on the "reader" role and the user, group, domain, and default scopes only.  Group
+    /// rules are left unchanged if no groups are given.  Domain rules are left unchanged if no
+    /// domain is given.  The default (public) rule is left unchanged if not public.
+    ///
+    /// This effectively performs a diff from readers to emails.
+    ///
+    /// For example, if given the set of emails:
     ///
     /// * user0@example.com
     /// * user1@example.com
@@ -223,42 +1402,156 @@ impl GCal {
     ///
     /// * Insert user0@example.com
     /// * Delete user2@example.com
-    fn acl_sync_ops(emails: &[&str], rules: &[api::AclRule]) -> AclSyncOpsResult {
-        let acl_readers: HashSet<Email> = rules
+    fn acl_sync_ops(
+        emails: &[&str],
+        groups: &[&str],
+        domain: Option<&str>,
+        public: bool,
+        rules: &[api::AclRule],
+    ) -> AclSyncOpsResult {
+        let acl_readers: HashSet<AclScope> = rules
             .iter()
             .filter(|rule| rule.role == Some("reader".to_string()))
-            .map(|rule| {
-                rule.scope
-                    .as_ref()
-                    .unwrap()
-                    .value
-                    .as_ref()
-                    .unwrap()
-                    .to_string()
+            .filter_map(|rule| rule.scope.as_ref())
+            .filter_map(AclScope::from_api)
+            .filter(|scope| match scope {
+                AclScope::User(_) => true,
+                AclScope::Group(_) => !groups.is_empty(),
+                AclScope::Domain(_) => domain.is_some(),
+                AclScope::Default => public,
             })
             .collect();
-        let emails: HashSet<Email> = emails.iter().map(|email| email.to_string()).collect();
+        let scopes: HashSet<AclScope> = emails
+            .iter()
+            .map(|email| AclScope::User(email.to_string()))
+            .chain(
+                groups
+                    .iter()
+                    .map(|group| AclScope::Group(group.to_string())),
+            )
+            .chain(domain.map(|domain| AclScope::Domain(domain.to_string())))
+            .chain(public.then_some(AclScope::Default))
+            .collect();
 
-        let inserts = emails.difference(&acl_readers).cloned().collect();
-        let deletes = acl_readers.difference(&emails).cloned().collect();
+        let inserts = scopes.difference(&acl_readers).cloned().collect();
+        let deletes = acl_readers.difference(&scopes).cloned().collect();
 
         AclSyncOpsResult { inserts, deletes }
     }
 
+    /// Sets the default reminders of the calendar.
+    ///
+    /// Reminders are per user.  The default reminders are those of the authenticated user (e.g.
+    /// the calendar owner) and are only changed if they differ.
+    pub async fn set_default_reminders(&self, reminders: &[EventReminder]) -> Result<(), Error> {
+        let (rsp, entry) = self
+            .hub
+            .calendar_list()
+            .get(&self.calendar_id)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "calendar_list.get");
+        debug!(?entry, "calendar_list.get");
+
+        let existing: Vec<Option<EventReminder>> = entry
+            .default_reminders
+            .iter()
+            .flatten()
+            .map(EventReminder::from_api)
+            .collect();
+        let reminders_str = reminders
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        if existing
+            .iter()
+            .copied()
+            .eq(reminders.iter().copied().map(Some))
+        {
+            info!(reminders=%reminders_str, "Skipped default reminders, unchanged");
+            return Ok(());
+        }
+
+        info!(reminders=%reminders_str, "Setting default reminders");
+        let write = PlannedWrite::CalendarRemindersPatch {
+            calendar_id: self.calendar_id.clone(),
+            reminders: reminders_str,
+        };
+        self.plan.record(write.clone());
+
+        if !self.dry_run {
+            let req = api::CalendarListEntry {
+                default_reminders: Some(
+                    reminders.iter().map(|reminder| reminder.to_api()).collect(),
+                ),
+                ..Default::default()
+            };
+            let (rsp, entry) = self
+                .hub
+                .calendar_list()
+                .patch(req, &self.calendar_id)
+                .add_scope(SCOPE)
+                .delegate(&mut RetryDelegate::default())
+                .doit()
+                .await?;
+            trace!(?rsp, "calendar_list.patch");
+            debug!(?entry, "calendar_list.patch");
+            let before = existing
+                .iter()
+                .flatten()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            self.plan
+                .audit(&self.calendar_id, &write, Some(&before), None)?;
+        }
+
+        Ok(())
+    }
+
     async fn acl_insert(
         &self,
-        email: &str,
+        scope: &AclScope,
         role: &str,
         send_notifications: SendNotifications,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        info!(%email, %role, send_notifications=%bool::from(send_notifications), "Adding user");
+    ) -> Result<(), Error> {
+        let write = match scope {
+            AclScope::User(email) => {
+                info!(%email, %role, send_notifications=%bool::from(send_notifications), "Adding user");
+                PlannedWrite::AclInsert {
+                    email: email.to_string(),
+                    role: role.to_string(),
+                }
+            }
+            AclScope::Group(group) => {
+                info!(%group, %role, send_notifications=%bool::from(send_notifications), "Adding group");
+                PlannedWrite::AclGroupInsert {
+                    group: group.to_string(),
+                    role: role.to_string(),
+                }
+            }
+            AclScope::Domain(domain) => {
+                info!(%domain, %role, "Adding domain");
+                PlannedWrite::AclDomainInsert {
+                    domain: domain.to_string(),
+                    role: role.to_string(),
+                }
+            }
+            AclScope::Default => {
+                info!(%role, "Adding public access");
+                PlannedWrite::AclPublicInsert {
+                    role: role.to_string(),
+                }
+            }
+        };
+        self.plan.record(write.clone());
 
         let req = api::AclRule {
             role: Some(role.to_string()),
-            scope: Some(api::AclRuleScope {
-                type_: Some("user".to_string()),
-                value: Some(email.to_string()),
-            }),
+            scope: Some(scope.to_api()),
             ..Default::default()
         };
         if !self.dry_run {
@@ -267,34 +1560,86 @@ impl GCal {
                 .acl()
                 .insert(req, &self.calendar_id)
                 .send_notifications(send_notifications.into())
+                .delegate(&mut RetryDelegate::default())
                 .doit()
                 .await?;
             trace!(?rsp, "acl.insert");
             debug!(?rule, "acl.insert");
+            self.plan
+                .audit(&self.calendar_id, &write, None, Some(role))?;
+
+            // Google resolves some emails to the primary email of the account
+            let scope = match (scope, rule.scope.as_ref().and_then(AclScope::from_api)) {
+                (AclScope::User(email), Some(AclScope::User(resolved))) if *email != resolved => {
+                    info!(%email, %resolved, "Learned email alias");
+                    self.learned_aliases
+                        .lock()
+                        .unwrap()
+                        .insert(email.clone(), resolved.clone());
+                    AclScope::User(resolved)
+                }
+                _ => scope.clone(),
+            };
+
+            if role == "reader" {
+                self.update_pushed_acl(|acl| {
+                    acl.insert(scope.to_pushed());
+                });
+            }
         }
 
         Ok(())
     }
 
-    async fn acl_delete(&self, email: &str) -> Result<(), Box<dyn std::error::Error>> {
-        info!(%email, "Deleting user");
+    async fn acl_delete(&self, scope: &AclScope) -> Result<(), Error> {
+        let write = match scope {
+            AclScope::User(email) => {
+                info!(%email, "Deleting user");
+                PlannedWrite::AclDelete {
+                    email: email.to_string(),
+                }
+            }
+            AclScope::Group(group) => {
+                info!(%group, "Deleting group");
+                PlannedWrite::AclGroupDelete {
+                    group: group.to_string(),
+                }
+            }
+            AclScope::Domain(domain) => {
+                info!(%domain, "Deleting domain");
+                PlannedWrite::AclDomainDelete {
+                    domain: domain.to_string(),
+                }
+            }
+            AclScope::Default => {
+                info!("Deleting public access");
+                PlannedWrite::AclPublicDelete
+            }
+        };
+        self.plan.record(write.clone());
 
-        let rule_id = format!("user:{email}");
+        let rule_id = scope.rule_id();
         if !self.dry_run {
             let rsp = self
                 .hub
                 .acl()
                 .delete(&self.calendar_id, &rule_id)
+                .delegate(&mut RetryDelegate::default())
                 .doit()
                 .await?;
             trace!(?rsp, "acl.delete");
+            self.plan.audit(&self.calendar_id, &write, None, None)?;
+
+            self.update_pushed_acl(|acl| {
+                acl.remove(&scope.to_pushed());
+            });
         }
 
         Ok(())
     }
 
     /// Fetches entire ACL by fetching all pages of the ACL
-    async fn acl_list(&self) -> Result<Vec<api::AclRule>, Box<dyn std::error::Error>> {
+    async fn acl_list(&self) -> Result<Vec<api::AclRule>, Error> {
         let mut rules = Vec::new();
         let mut page_token = None;
         let mut page = 1;
@@ -320,22 +1665,236 @@ impl GCal {
     async fn acl_list_page(
         &self,
         page_token: Option<String>,
-    ) -> Result<(Vec<api::AclRule>, Option<String>), Box<dyn std::error::Error>> {
+    ) -> Result<(Vec<api::AclRule>, Option<String>), Error> {
         let call = self.hub.acl().list(&self.calendar_id).add_scope(SCOPE);
         let call = match page_token {
             Some(page_token) => call.page_token(&page_token),
             None => call,
         };
-        let (rsp, acl) = call.doit().await?;
+        let (rsp, acl) = call.delegate(&mut RetryDelegate::default()).doit().await?;
         trace!(?rsp, "acl.list");
         debug!(?acl, "acl.list");
 
         Ok((acl.items.unwrap(), acl.next_page_token))
     }
 
-    pub async fn write(&self, events: &[Event]) -> Result<(), Box<dyn std::error::Error>> {
-        stream::iter(events)
-            .map(|event| self.events_patch_or_insert(event))
+    /// Inserts the events or patches the existing events if their content has changed.
+    ///
+    /// A hash of the event content is stored in the private extended properties of each event.
+    /// Existing events with a matching hash are skipped to avoid needless writes.  The remaining
+    /// writes are sent in batch requests of up to BATCH_SIZE events.
+    ///
+    /// The first event of a recurring series is written as a recurring event.  The other events
+    /// of the series are written as patches of the instances of the recurring event after the
+    /// recurring event has been written.
+    pub async fn write(&self, events: &[Event]) -> Result<(), Error> {
+        let Some(since) = events.iter().map(|event| event.start_date).min() else {
+            return Ok(());
+        };
+        let existing: HashMap<String, api::Event> = self
+            .events_existing(Some(since), None)
+            .await?
+            .into_iter()
+            .filter_map(|g_event| Some((g_event.id.clone()?, g_event)))
+            .collect();
+
+        let parents = series_parents(events);
+        let mut writes = Vec::new();
+        let mut instance_writes = Vec::new();
+        for event in events {
+            let mut g_event = create_api_event(event, &self.event_options)?;
+            let parent = series_parent(event, &parents);
+            if let Some(parent) = parent {
+                g_event = instance_event(g_event, event, parent, self.event_options.timezone())
+                    .map_err(Error::parse)?;
+            }
+            let hash = event_hash(&g_event).map_err(Error::google)?;
+            let mut properties = hash_properties(&hash);
+            properties
+                .private
+                .get_or_insert_with(Default::default)
+                .insert(FIELDS_PROPERTY.to_string(), field_hashes(&g_event));
+            g_event.extended_properties = Some(properties);
+
+            let event_id = g_event.id.clone().unwrap();
+            let summary = g_event.summary.clone().unwrap_or_default();
+            match existing.get(&event_id) {
+                Some(existing) if is_unchanged(existing, &hash) => {
+                    info!(%event.id, %event, "Skipped, unchanged");
+                }
+                Some(existing) => {
+                    let drifted = drifted_fields(existing);
+                    if !drifted.is_empty() {
+                        warn!(%event.id, %event, ?drifted, "Overwriting fields edited in Google Calendar");
+                    }
+                    // Restore events that were deleted from the calendar
+                    if existing.status.as_deref() == Some("cancelled") {
+                        g_event.status = Some("confirmed".to_string());
+                    }
+                    info!(%event.id, %event, "Updating");
+                    self.plan
+                        .record(PlannedWrite::EventPatch { event_id, summary });
+                    if parent.is_some() {
+                        instance_writes.push((event, EventWrite::Patch(g_event)));
+                    } else {
+                        writes.push((event, EventWrite::Patch(g_event)));
+                    }
+                }
+                // An unmodified instance is not listed but exists once the series is written
+                None if parent.is_some() => {
+                    info!(%event.id, %event, "Updating instance");
+                    self.plan
+                        .record(PlannedWrite::EventPatch { event_id, summary });
+                    instance_writes.push((event, EventWrite::Patch(g_event)));
+                }
+                None => {
+                    info!(%event.id, %event, "Inserting");
+                    self.plan
+                        .record(PlannedWrite::EventInsert { event_id, summary });
+                    writes.push((event, EventWrite::Insert(g_event)));
+                }
+            }
+        }
+
+        if !self.dry_run {
+            self.events_batch(writes, &existing).await?;
+            self.events_batch(instance_writes, &existing).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the event writes in batch requests.
+    ///
+    /// Rate limited writes are retried with exponential backoff.  Inserts that conflict with an
+    /// existing event (e.g. an event that was rescheduled from before the listed dates) are
+    /// retried as patches.  The existing events are the before of the audit log entries.
+    async fn events_batch(
+        &self,
+        writes: Vec<(&Event, EventWrite)>,
+        existing: &HashMap<String, api::Event>,
+    ) -> Result<(), Error> {
+        let mut pending = writes;
+        let mut retries = 0;
+
+        while !pending.is_empty() {
+            let mut retry = Vec::new();
+            let mut rate_limited = false;
+
+            for chunk in pending.chunks(BATCH_SIZE) {
+                let requests = chunk
+                    .iter()
+                    .map(|(_, write)| write.request(&self.calendar_id))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(Error::google)?;
+                let token = self
+                    .hub
+                    .auth
+                    .get_token(&[SCOPE.as_ref()])
+                    .await
+                    .map_err(Error::auth)?
+                    .unwrap_or_default();
+                let responses = batch::send(&self.http, &self.batch_url, &token, &requests)
+                    .await
+                    .map_err(Error::google)?;
+                trace!(?responses, "batch");
+
+                for ((event, write), response) in chunk.iter().zip(responses) {
+                    let body: Option<serde_json::Value> = serde_json::from_str(&response.body).ok();
+                    match (write, response.status) {
+                        (_, 200..=299) => {
+                            let link = body
+                                .as_ref()
+                                .and_then(|body| body["htmlLink"].as_str())
+                                .unwrap_or_default();
+                            info!(%event.id, %event, %link, "{}", write.past_tense());
+                            let planned = write.planned();
+                            let before = write
+                                .event()
+                                .id
+                                .as_ref()
+                                .and_then(|event_id| existing.get(event_id))
+                                .and_then(|g_event| g_event.summary.as_deref());
+                            self.plan.audit(
+                                &self.calendar_id,
+                                &planned,
+                                before,
+                                write.event().summary.as_deref(),
+                            )?;
+
+                            let g_event = serde_json::from_str(&response.body)
+                                .unwrap_or_else(|_| write.event().clone());
+                            self.update_pushed_event(g_event);
+                        }
+                        (EventWrite::Insert(g_event), 409) => {
+                            retry.push((*event, EventWrite::Patch(g_event.clone())));
+                        }
+                        // E.g. an occurrence rescheduled on SCMA to a date not in the series
+                        (EventWrite::Patch(g_event), 404)
+                            if g_event.recurring_event_id.is_some() =>
+                        {
+                            warn!(%event.id, %event, "Skipped, not an instance of the recurring event");
+                        }
+                        (_, status)
+                            if is_rate_limited(status, body.as_ref()) && retries < MAX_RETRIES =>
+                        {
+                            rate_limited = true;
+                            retry.push((*event, write.clone()));
+                        }
+                        (_, status) => {
+                            let message = format!(
+                                "unable to write event `{event}`: {status} {}",
+                                response.body
+                            );
+                            let err = if is_rate_limited(status, body.as_ref()) {
+                                Error::Quota(message.into())
+                            } else {
+                                Error::google(message)
+                            };
+                            fail_or_continue(&self.plan, self.continue_on_error, Err(err))?;
+                        }
+                    }
+                }
+            }
+
+            if rate_limited {
+                let delay = backoff(retries) + jitter();
+                retries += 1;
+                warn!(retry=%retries, ?delay, "Rate limited, retrying");
+                tokio::time::sleep(delay).await;
+            }
+            pending = retry;
+        }
+
+        Ok(())
+    }
+
+    /// Cancels or deletes synced calendar events that are not in the given events.
+    ///
+    /// Only events between `since` and `until` (inclusive) are considered so that events outside
+    /// of the sync window are left alone.  Events that were not created by scma-gsync (i.e. do not
+    /// have an SCMA event ID) are never touched.
+    pub async fn events_reconcile(
+        &self,
+        events: &[Event],
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+        action: OrphanAction,
+    ) -> Result<(), Error> {
+        let g_events = self.events_existing(since, until).await?;
+        let cancelled = self.event_options.lang.strings().cancelled;
+        let orphans = orphan_events(events, &g_events, cancelled);
+        info!(orphans.len=%orphans.len(), ?action, "Determined orphaned events");
+
+        // Creates the futures up front so that the returned future is provably Send
+        let writes: Vec<_> = orphans
+            .into_iter()
+            .map(|g_event| async move {
+                let result = self.events_cancel_or_delete(g_event, action).await;
+                fail_or_continue(&self.plan, self.continue_on_error, result)
+            })
+            .collect();
+        stream::iter(writes)
             .buffer_unordered(CONCURRENT_REQUESTS)
             .try_collect::<Vec<_>>()
             .await?;
@@ -343,159 +1902,966 @@ impl GCal {
         Ok(())
     }
 
-    pub async fn events_patch_or_insert(
+    async fn events_cancel_or_delete(
         &self,
-        event: &Event,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let g_event = api::Event::try_from(event)?;
-
-        let event_id = g_event.id.as_ref().unwrap().clone();
-        if !self.dry_run {
-            let result = self
-                .hub
-                .events()
-                .patch(g_event.clone(), &self.calendar_id, &event_id)
-                .add_scope(SCOPE)
-                .doit()
-                .await;
-            match result {
-                Ok(rsp) => {
-                    let (rsp, g_event) = rsp;
+        g_event: &api::Event,
+        action: OrphanAction,
+    ) -> Result<(), Error> {
+        let event_id = g_event.id.as_deref().unwrap_or_default();
+        let summary = g_event.summary.as_deref().unwrap_or_default();
+
+        match action {
+            OrphanAction::Cancel | OrphanAction::CancelStatus => {
+                info!(%event_id, %summary, ?action, "Cancelling orphaned event");
+                let write = PlannedWrite::EventCancel {
+                    event_id: event_id.to_string(),
+                    summary: summary.to_string(),
+                };
+                self.plan.record(write.clone());
+                if !self.dry_run {
+                    let cancelled = self.event_options.lang.strings().cancelled;
+                    let mut req = cancel_request(summary, cancelled, action);
+                    let after = req.summary.clone().unwrap_or_default();
+                    insert_patched_field_hashes(g_event, &mut req);
+                    let (rsp, g_event) = self
+                        .hub
+                        .events()
+                        .patch(req, &self.calendar_id, event_id)
+                        .add_scope(SCOPE)
+                        .delegate(&mut RetryDelegate::default())
+                        .doit()
+                        .await?;
                     trace!(?rsp, "events.patch");
                     debug!(?g_event, "events.patch");
+                    self.plan
+                        .audit(&self.calendar_id, &write, Some(summary), Some(&after))?;
 
-                    let link = g_event.html_link.as_ref().unwrap();
-                    info!(%event.id, %event, %link, "Updated");
+                    self.update_pushed_event(g_event);
                 }
-                Err(_) => {
-                    let (rsp, g_event) = self
+            }
+            OrphanAction::Delete => {
+                info!(%event_id, %summary, "Deleting orphaned event");
+                let write = PlannedWrite::EventDelete {
+                    event_id: event_id.to_string(),
+                    summary: summary.to_string(),
+                };
+                self.plan.record(write.clone());
+                if !self.dry_run {
+                    let rsp = self
                         .hub
                         .events()
-                        .insert(g_event, &self.calendar_id)
+                        .delete(&self.calendar_id, event_id)
                         .add_scope(SCOPE)
+                        .delegate(&mut RetryDelegate::default())
                         .doit()
                         .await?;
-                    trace!(?rsp, "events.insert");
-                    debug!(?g_event, "events.insert");
+                    trace!(?rsp, "events.delete");
+                    self.plan
+                        .audit(&self.calendar_id, &write, Some(summary), None)?;
 
-                    let link = g_event.html_link.as_ref().unwrap();
-                    info!(%event.id, %event, %link, "Inserted");
+                    self.update_pushed_events(|events| {
+                        events.remove(event_id);
+                    });
                 }
             }
         }
 
         Ok(())
     }
-}
 
-impl TryFrom<&Event> for api::Event {
-    type Error = Box<dyn ::std::error::Error>;
-
-    fn try_from(event: &Event) -> Result<Self, Self::Error> {
-        let id = event_id(event)?;
-        let summary = event_summary(event);
-        let start = event_start(event);
-        let end = event_end(event);
-        let description = event_description(event)?;
-        let location = event.location.clone();
-
-        let g_event = api::Event {
-            id: Some(id),
-            summary: Some(summary),
-            start: Some(start),
-            end: Some(end),
-            description: Some(description),
-            location: Some(location),
-            ..Default::default()
+    /// Enables incremental sync of the existing calendar events using the given cache.
+    ///
+    /// The cache is discarded if it is for a different calendar.  Get the updated cache with
+    /// event_cache() to persist it for the next run.
+    pub fn set_event_cache(&mut self, cache: EventCache) {
+        let cache = if cache.calendar_id == self.calendar_id {
+            cache
+        } else {
+            EventCache::new(&self.calendar_id)
         };
+        *self.event_cache.get_mut().unwrap() = Some(cache);
+    }
 
-        Ok(g_event)
+    /// Enables diffing against what was last pushed instead of reading the calendar.
+    ///
+    /// The state is discarded if it is for a different calendar.  Events and ACL rules that have
+    /// not been pushed yet are read from the calendar once.  Get the updated state with
+    /// pushed_state() to persist it for the next run.
+    pub fn set_pushed_state(&mut self, state: PushedState) {
+        let state = if state.calendar_id == self.calendar_id {
+            state
+        } else {
+            PushedState::new(&self.calendar_id)
+        };
+        *self.pushed_state.get_mut().unwrap() = Some(state);
     }
-}
 
-fn event_id(event: &Event) -> Result<String, std::num::ParseIntError> {
-    let id: u32 = event.id.parse()?;
-    let id = format!("{id:05}");
-    Ok(id)
-}
+    /// Returns the pushed state if enabled.
+    pub fn pushed_state(&self) -> Option<PushedState> {
+        self.pushed_state.lock().unwrap().clone()
+    }
 
-fn event_summary(event: &Event) -> String {
-    format!("SCMA: {}", event.title)
-}
+    /// Returns the email aliases learned from the ACL inserts of this run.
+    ///
+    /// Maps the inserted user emails to the emails Google resolved them to (e.g. the primary email
+    /// of the account).  Apply them to the user emails of the next ACL sync to avoid inserting and
+    /// deleting the same users on every run.
+    pub fn learned_aliases(&self) -> BTreeMap<String, String> {
+        self.learned_aliases.lock().unwrap().clone()
+    }
 
-fn event_start(event: &Event) -> api::EventDateTime {
-    api::EventDateTime {
-        date: Some(event.start_date),
-        ..Default::default()
+    /// Records a pushed event in the pushed state if enabled.
+    fn update_pushed_event(&self, g_event: api::Event) {
+        let Some(id) = g_event.id.clone() else {
+            return;
+        };
+        self.update_pushed_events(|events| {
+            events.insert(id, CachedEvent::from(g_event));
+        });
     }
-}
 
-fn event_end(event: &Event) -> api::EventDateTime {
-    // WORKAROUND: Google Calendar seems to require all-day events to end on the day after.
-    // Otherwise they show as 1 day short.
-    let end_date = event.end_date + Duration::days(1);
+    /// Applies the update to the pushed events if enabled and read.
+    fn update_pushed_events(&self, update: impl FnOnce(&mut BTreeMap<String, CachedEvent>)) {
+        let mut state = self.pushed_state.lock().unwrap();
+        if let Some(events) = state.as_mut().and_then(|state| state.events.as_mut()) {
+            update(events);
+        }
+    }
 
-    api::EventDateTime {
-        date: Some(end_date),
-        ..Default::default()
+    /// Applies the update to the pushed ACL if enabled and read.
+    fn update_pushed_acl(&self, update: impl FnOnce(&mut BTreeSet<String>)) {
+        let mut state = self.pushed_state.lock().unwrap();
+        if let Some(acl) = state.as_mut().and_then(|state| state.acl.as_mut()) {
+            update(acl);
+        }
     }
-}
 
-fn event_description(event: &Event) -> Result<String, Box<dyn ::std::error::Error>> {
-    let mut buffer = String::with_capacity(DESCRIPTION_BUFFER_SIZE);
-    write!(buffer, "{}", event.url)?;
-    write!(buffer, "<h3>Description</h3>")?;
-    write!(buffer, "{}", event.description)?;
+    /// Returns the event cache if incremental sync is enabled.
+    pub fn event_cache(&self) -> Option<EventCache> {
+        self.event_cache.lock().unwrap().clone()
+    }
 
-    write!(buffer, "<h3>Attendees</h3>")?;
-    match event.attendees.as_ref() {
-        Some(attendees) => {
-            write!(buffer, "<ol>")?;
-            for attendee in attendees {
-                write!(
-                    buffer,
-                    "<li>{} ({}) {}</li>",
-                    attendee.name, attendee.count, attendee.comment
-                )?;
+    /// Returns the existing calendar events that overlap the given dates.
+    ///
+    /// Uses incremental sync if enabled.  Otherwise, lists the events.
+    async fn events_existing(
+        &self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Result<Vec<api::Event>, Error> {
+        let pushed_events = self
+            .pushed_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|state| state.events.clone());
+        match pushed_events {
+            Some(Some(events)) => {
+                info!(events=%events.len(), "Using pushed events");
+                return Ok(overlapping_events(&events, since, until));
             }
-            write!(buffer, "</ol>")?;
-        }
-        None => {
-            write!(buffer, "None")?;
+            Some(None) => {
+                // Read all events once so that the pushed state covers the entire calendar
+                let g_events = self.events_list(None, None).await?;
+                let events: BTreeMap<String, CachedEvent> = g_events
+                    .into_iter()
+                    .filter_map(|g_event| Some((g_event.id.clone()?, CachedEvent::from(g_event))))
+                    .collect();
+                let g_events = overlapping_events(&events, since, until);
+                if let Some(state) = self.pushed_state.lock().unwrap().as_mut() {
+                    state.events = Some(events);
+                }
+                return Ok(g_events);
+            }
+            None => {}
         }
+
+        let Some(cache) = self.event_cache() else {
+            return self.events_list(since, until).await;
+        };
+
+        let cache = self.events_sync(cache).await?;
+        let g_events = cache.events(since, until);
+        *self.event_cache.lock().unwrap() = Some(cache);
+
+        Ok(g_events)
     }
 
-    write!(buffer, "<h3>Comments</h3>")?;
-    match event.comments.as_ref() {
-        Some(comments) => {
-            write!(buffer, "<ul>")?;
-            for comment in comments {
-                write!(
-                    buffer,
-                    "<li>{} ({}) {}</li>",
-                    comment.author, comment.date, comment.text
-                )?;
+    /// Fetches the events changed since the last sync and applies them to the cache.
+    ///
+    /// Performs a full sync if there is no sync token or the sync token has expired.
+    async fn events_sync(&self, mut cache: EventCache) -> Result<EventCache, Error> {
+        let mut page_token = None;
+        let mut page = 1;
+
+        loop {
+            info!(%page, full=%cache.sync_token.is_none(), "Syncing events");
+            let result = self
+                .events_sync_page(cache.sync_token.as_deref(), page_token.as_deref())
+                .await;
+            let g_events = match result {
+                Ok(g_events) => g_events,
+                Err(google_calendar3::Error::BadRequest(error))
+                    if error["error"]["code"] == 410 =>
+                {
+                    info!("Sync token expired, performing full sync");
+                    cache = EventCache::new(&self.calendar_id);
+                    page_token = None;
+                    page = 1;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            info!(%page, changes=%g_events.items.as_ref().map_or(0, Vec::len), "Synced events");
+            cache.apply(g_events.items.unwrap_or_default());
+            page_token = g_events.next_page_token;
+
+            if page_token.is_none() {
+                cache.sync_token = g_events.next_sync_token;
+                break;
             }
-            write!(buffer, "</ul>")?;
+
+            page += 1;
         }
-        None => {
-            write!(buffer, "None")?;
+
+        Ok(cache)
+    }
+
+    /// Fetches a single page of changed events
+    async fn events_sync_page(
+        &self,
+        sync_token: Option<&str>,
+        page_token: Option<&str>,
+    ) -> Result<api::Events, google_calendar3::Error> {
+        // Deleted events are always included in incremental results.  Include them in full
+        // results too so that re-synced events are restored rather than re-inserted.
+        let call = self
+            .hub
+            .events()
+            .list(&self.calendar_id)
+            .show_deleted(true)
+            .add_scope(SCOPE);
+        let call = match sync_token {
+            Some(sync_token) => call.sync_token(sync_token),
+            None => call,
+        };
+        let call = match page_token {
+            Some(page_token) => call.page_token(page_token),
+            None => call,
+        };
+        let (rsp, g_events) = call.delegate(&mut RetryDelegate::default()).doit().await?;
+        trace!(?rsp, "events.list");
+        debug!(?g_events, "events.list");
+
+        Ok(g_events)
+    }
+
+    /// Fetches all calendar events that overlap the given dates by fetching all pages
+    async fn events_list(
+        &self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Result<Vec<api::Event>, Error> {
+        let mut g_events = Vec::new();
+        let mut page_token = None;
+        let mut page = 1;
+
+        loop {
+            info!(%page, "Getting events");
+            let (mut next_g_events, next_page_token) =
+                self.events_list_page(since, until, page_token).await?;
+            g_events.append(&mut next_g_events);
+            page_token = next_page_token;
+
+            if page_token.is_none() {
+                break;
+            }
+
+            page += 1;
         }
+
+        Ok(g_events)
     }
 
-    if event.timestamp.is_some() {
-        write!(buffer, "\n\nLast synced at {} by <a href='https://github.com/rfdonnelly/scma-gsync'>scma-gsync</a>.", event.timestamp())?;
+    /// Fetches a single page of calendar events
+    async fn events_list_page(
+        &self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+        page_token: Option<String>,
+    ) -> Result<(Vec<api::Event>, Option<String>), Error> {
+        // Include deleted events so that re-synced events are restored rather than re-inserted
+        let call = self
+            .hub
+            .events()
+            .list(&self.calendar_id)
+            .show_deleted(true)
+            .add_scope(SCOPE);
+        let call = match since {
+            Some(since) => call.time_min(since.and_time(Default::default()).and_utc()),
+            None => call,
+        };
+        let call = match until {
+            Some(until) => call.time_max(
+                (until + Duration::days(1))
+                    .and_time(Default::default())
+                    .and_utc(),
+            ),
+            None => call,
+        };
+        let call = match page_token {
+            Some(page_token) => call.page_token(&page_token),
+            None => call,
+        };
+        let (rsp, g_events) = call.delegate(&mut RetryDelegate::default()).doit().await?;
+        trace!(?rsp, "events.list");
+        debug!(?g_events, "events.list");
+
+        Ok((g_events.items.unwrap_or_default(), g_events.next_page_token))
     }
 
-    Ok(buffer)
-}
+    /// Patches the description of an existing event if its content has changed.
+    ///
+    /// Only the description (and the summary if it includes the attendee count) is patched.
+    /// Events that do not exist in the calendar are skipped.  The sync footer is ignored when
+    /// comparing so that only changes to the event content (e.g. new attendees or comments)
+    /// trigger a patch.
+    pub async fn events_refresh_description(&self, event: &Event) -> Result<(), Error> {
+        let event_id = event_id(event).map_err(Error::parse)?;
+        let summary = event_summary(event, &self.event_options);
+        let description = event_description(event, &self.event_options)?;
+
+        let result = self
+            .hub
+            .events()
+            .get(&self.calendar_id, &event_id)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await;
+        let g_event = match result {
+            Ok((rsp, g_event)) => {
+                trace!(?rsp, "events.get");
+                debug!(?g_event, "events.get");
+                g_event
+            }
+            Err(err) if is_not_found(&err) => {
+                info!(%event.id, %event, "Skipped refresh, event not found");
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
 
-#[cfg(test)]
-mod test {
-    use super::*;
+        let existing_description = g_event.description.as_deref().unwrap_or_default();
+        let description_changed =
+            description_content(existing_description) != description_content(&description);
+        let summary_changed =
+            self.event_options.attendee_count && g_event.summary.as_ref() != Some(&summary);
+        if !description_changed && !summary_changed {
+            info!(%event.id, %event, "Skipped refresh, description unchanged");
+            return Ok(());
+        }
 
-    #[test]
-    fn acl_sync_ops() {
+        info!(%event.id, %event, %description_changed, %summary_changed, "Refreshing description");
+        let drifted = drifted_fields(&g_event);
+        if !drifted.is_empty() {
+            warn!(%event.id, %event, ?drifted, "Overwriting fields edited in Google Calendar");
+        }
+        let before = g_event.summary.clone().unwrap_or_default();
+        let write = PlannedWrite::EventPatch {
+            event_id: event_id.clone(),
+            summary: before.clone(),
+        };
+        self.plan.record(write.clone());
+        if !self.dry_run {
+            let mut req = api::Event {
+                summary: summary_changed.then_some(summary),
+                description: description_changed.then_some(description),
+                ..Default::default()
+            };
+            insert_patched_field_hashes(&g_event, &mut req);
+            let (rsp, g_event) = self
+                .hub
+                .events()
+                .patch(req, &self.calendar_id, &event_id)
+                .add_scope(SCOPE)
+                .delegate(&mut RetryDelegate::default())
+                .doit()
+                .await?;
+            trace!(?rsp, "events.patch");
+            debug!(?g_event, "events.patch");
+            self.plan.audit(
+                &self.calendar_id,
+                &write,
+                Some(&before),
+                g_event.summary.as_deref(),
+            )?;
+
+            let link = g_event.html_link.as_ref().unwrap();
+            info!(%event.id, %event, %link, "Refreshed");
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSink for GCal {
+    async fn write_events(&self, events: &[Event]) -> Result<(), Error> {
+        self.write(events).await
+    }
+}
+
+/// Syncs the users with the readers in the calendar ACL.
+#[async_trait]
+impl UserSink for GCal {
+    async fn write_users(&self, users: Vec<User>) -> Result<(), Error> {
+        let emails: Vec<&str> = users.iter().map(|user| user.email.as_str()).collect();
+        let no_inserts: Vec<&str> = users
+            .iter()
+            .filter(|user| !self.acl_include_optedout && user.is_optedout_or_blocked())
+            .map(|user| user.email.as_str())
+            .collect();
+        self.acl_sync(&emails, &no_inserts, &self.calendar_owners)
+            .await
+    }
+}
+
+fn create_api_event(event: &Event, options: &EventOptions) -> Result<api::Event, Error> {
+    let id = event_id(event).map_err(Error::parse)?;
+    let summary = event_summary(event, options);
+    let start = event_start(event, options.timezone());
+    let end = event_end(event, options.timezone());
+    let description = event_description(event, options)?;
+    let location = event.location.clone();
+
+    let g_event = api::Event {
+        id: Some(id),
+        summary: Some(summary),
+        start: Some(start),
+        end: Some(end),
+        description: Some(description),
+        location: Some(location),
+        guests_can_invite_others: options.guests_can_invite_others,
+        guests_can_modify: options.guests_can_modify,
+        guests_can_see_other_guests: options.guests_can_see_other_guests,
+        attendees: options.attendee_guests.then(|| event_guests(event)),
+        color_id: event_color(event, options).map(|color| color.color_id().to_string()),
+        reminders: options
+            .reminders
+            .as_ref()
+            .map(|reminders| api::EventReminders {
+                use_default: Some(false),
+                overrides: Some(reminders.iter().map(|reminder| reminder.to_api()).collect()),
+            }),
+        recurrence: event.recurrence.as_ref().map(|recurrence| {
+            let timezone = options.timezone();
+            vec![recurrence.rrule(event.start_end(timezone).is_some(), timezone)]
+        }),
+        ..Default::default()
+    };
+
+    Ok(g_event)
+}
+
+/// Returns the attendees with an email as accepted guests.
+///
+/// The guests of an attendee are additional guests.  Duplicate attendees are merged.
+fn event_guests(event: &Event) -> Vec<api::EventAttendee> {
+    let mut guests: Vec<api::EventAttendee> = Vec::new();
+    for attendee in event.attendees.iter().flatten() {
+        let Some(ref email) = attendee.email else {
+            continue;
+        };
+        if guests
+            .iter()
+            .any(|guest| guest.email.as_ref() == Some(email))
+        {
+            continue;
+        }
+        guests.push(api::EventAttendee {
+            email: Some(email.clone()),
+            display_name: Some(attendee.name.clone()),
+            response_status: Some("accepted".to_string()),
+            additional_guests: Some(i32::from(attendee.count.saturating_sub(1))),
+            ..Default::default()
+        });
+    }
+
+    guests
+}
+
+/// Returns the first events of the recurring series by SCMA event ID.
+fn series_parents(events: &[Event]) -> HashMap<&str, &Event> {
+    events
+        .iter()
+        .filter(|event| event.recurrence.is_some())
+        .map(|event| (event.id.as_str(), event))
+        .collect()
+}
+
+/// Returns the first event of the series if the event is a later occurrence of a synced series.
+fn series_parent<'a>(event: &Event, parents: &HashMap<&str, &'a Event>) -> Option<&'a Event> {
+    event
+        .recurring_parent
+        .as_deref()
+        .filter(|parent_id| *parent_id != event.id)
+        .and_then(|parent_id| parents.get(parent_id))
+        .copied()
+}
+
+/// Converts the calendar event of an occurrence into a patch of the instance of the recurring
+/// event.
+///
+/// The instance is identified by the recurring event ID and the original start of the
+/// occurrence (e.g. 00527_20220114 or 00527_20220115T033000Z).
+fn instance_event(
+    g_event: api::Event,
+    event: &Event,
+    parent: &Event,
+    timezone: Tz,
+) -> Result<api::Event, std::num::ParseIntError> {
+    let parent_id = event_id(parent)?;
+    let (original_start, suffix) = match parent.occurrence_start(timezone, event.start_date) {
+        Some(start) => (
+            api::EventDateTime {
+                date_time: Some(start),
+                time_zone: Some(timezone.name().to_string()),
+                ..Default::default()
+            },
+            start.format("%Y%m%dT%H%M%SZ").to_string(),
+        ),
+        None => (
+            api::EventDateTime {
+                date: Some(event.start_date),
+                ..Default::default()
+            },
+            event.start_date.format("%Y%m%d").to_string(),
+        ),
+    };
+
+    Ok(api::Event {
+        id: Some(format!("{parent_id}_{suffix}")),
+        recurring_event_id: Some(parent_id),
+        original_start_time: Some(original_start),
+        ..g_event
+    })
+}
+
+/// Returns the JSON request body for the event.
+///
+/// Unset fields are omitted rather than null.  A null clears the field on patch.
+fn event_json(g_event: &api::Event) -> Result<String, serde_json::Error> {
+    let mut json = serde_json::to_value(g_event)?;
+    google_calendar3::common::remove_json_null_values(&mut json);
+    serde_json::to_string(&json)
+}
+
+/// Returns a hash of the event content.
+///
+/// The sync footer of the description is excluded because it changes on every sync.
+fn event_hash(g_event: &api::Event) -> Result<String, serde_json::Error> {
+    let content = api::Event {
+        description: g_event
+            .description
+            .as_deref()
+            .map(|description| description_content(description).to_string()),
+        ..g_event.clone()
+    };
+    let json = serde_json::to_string(&content)?;
+
+    Ok(format!("{:x}", Sha256::digest(json)))
+}
+
+fn hash_properties(hash: &str) -> api::EventExtendedProperties {
+    api::EventExtendedProperties {
+        private: Some([(HASH_PROPERTY.to_string(), hash.to_string())].into()),
+        ..Default::default()
+    }
+}
+
+/// Returns the short hashes of the synced fields (e.g. "summary=1a2b3c4d;description=...").
+fn field_hashes(g_event: &api::Event) -> String {
+    EventField::ALL
+        .iter()
+        .map(|field| {
+            let value = field.get(g_event).unwrap_or_default();
+            format!("{}={}", field.key(), short_hash(&value))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Adds the field hashes of the event with the hashes of the patched fields replaced to the patch.
+///
+/// Keeps the patch from being reported as drift.  Does nothing if the event has no field hashes.
+fn insert_patched_field_hashes(g_event: &api::Event, patch: &mut api::Event) {
+    let Some(hashes) = parse_field_hashes(g_event) else {
+        return;
+    };
+    let hashes = EventField::ALL
+        .iter()
+        .filter_map(|field| {
+            let hash = match field.get(patch) {
+                Some(value) => short_hash(&value),
+                None => hashes.get(field.key())?.to_string(),
+            };
+            Some(format!("{}={hash}", field.key()))
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+
+    patch
+        .extended_properties
+        .get_or_insert_with(Default::default)
+        .private
+        .get_or_insert_with(Default::default)
+        .insert(FIELDS_PROPERTY.to_string(), hashes);
+}
+
+fn parse_field_hashes(g_event: &api::Event) -> Option<HashMap<&str, &str>> {
+    let hashes = g_event
+        .extended_properties
+        .as_ref()
+        .and_then(|properties| properties.private.as_ref())
+        .and_then(|private| private.get(FIELDS_PROPERTY))?;
+
+    Some(
+        hashes
+            .split(';')
+            .filter_map(|entry| entry.split_once('='))
+            .collect(),
+    )
+}
+
+/// Returns the synced fields of the event that were edited since the event was last written.
+///
+/// Empty if the event has no field hashes (i.e. it was last written by an older version).
+fn drifted_fields(g_event: &api::Event) -> Vec<EventField> {
+    let Some(hashes) = parse_field_hashes(g_event) else {
+        return Vec::new();
+    };
+
+    EventField::ALL
+        .into_iter()
+        .filter(|field| {
+            hashes
+                .get(field.key())
+                .is_some_and(|hash| *hash != short_hash(&field.get(g_event).unwrap_or_default()))
+        })
+        .collect()
+}
+
+fn short_hash(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value))[..8].to_string()
+}
+
+/// Returns true if the existing calendar event matches the backed up event.
+///
+/// Compares the synced fields, the status, and the color.  The sync footer of the description is
+/// ignored.
+fn is_restored(existing: &api::Event, backed_up: &api::Event) -> bool {
+    existing.status == backed_up.status
+        && existing.color_id == backed_up.color_id
+        && EventField::ALL
+            .into_iter()
+            .all(|field| field.get(existing) == field.get(backed_up))
+}
+
+/// Returns the backed up event without the fields set by Google Calendar.
+fn restored_event(g_event: &api::Event) -> api::Event {
+    api::Event {
+        etag: None,
+        html_link: None,
+        i_cal_uid: None,
+        created: None,
+        updated: None,
+        creator: None,
+        organizer: None,
+        sequence: None,
+        ..g_event.clone()
+    }
+}
+
+/// Returns true if the Google API error is a 404 Not Found or a 410 Gone.
+fn is_not_found(err: &google_calendar3::Error) -> bool {
+    let status = match err {
+        google_calendar3::Error::BadRequest(error) => error["error"]["code"].as_u64(),
+        google_calendar3::Error::Failure(response) => Some(response.status().as_u16().into()),
+        _ => None,
+    };
+
+    matches!(status, Some(404 | 410))
+}
+
+/// Returns true if the existing calendar event was synced with the same content hash.
+fn is_unchanged(existing: &api::Event, hash: &str) -> bool {
+    let existing_hash = existing
+        .extended_properties
+        .as_ref()
+        .and_then(|properties| properties.private.as_ref())
+        .and_then(|private| private.get(HASH_PROPERTY));
+
+    existing.status.as_deref() != Some("cancelled") && existing_hash.is_some_and(|h| h == hash)
+}
+
+fn event_id(event: &Event) -> Result<String, std::num::ParseIntError> {
+    let id: u32 = event.id.parse()?;
+    let id = format!("{id:05}");
+    Ok(id)
+}
+
+/// Returns the patch that cancels an orphaned event.
+fn cancel_request(summary: &str, cancelled: &str, action: OrphanAction) -> api::Event {
+    api::Event {
+        summary: Some(format!("{cancelled}: {summary}")),
+        status: (action == OrphanAction::CancelStatus).then(|| "cancelled".to_string()),
+        // Patch the event (and restore its status) if it reappears on SCMA
+        extended_properties: Some(hash_properties("")),
+        ..Default::default()
+    }
+}
+
+/// Returns the calendar events that were synced from SCMA but are not in the given events.
+///
+/// Events that have already been cancelled (i.e. the summary has the cancelled marker) are
+/// excluded.
+fn orphan_events<'a>(
+    events: &[Event],
+    g_events: &'a [api::Event],
+    cancelled: &str,
+) -> Vec<&'a api::Event> {
+    // The standalone events of occurrences written as instances of a recurring event are orphans
+    let parents = series_parents(events);
+    let event_ids: HashSet<String> = events
+        .iter()
+        .filter(|event| series_parent(event, &parents).is_none())
+        .filter_map(|event| event_id(event).ok())
+        .collect();
+    let cancelled = format!("{cancelled}: ");
+
+    g_events
+        .iter()
+        .filter(|g_event| g_event.status.as_deref() != Some("cancelled"))
+        .filter(|g_event| {
+            g_event
+                .id
+                .as_deref()
+                .is_some_and(|id| is_synced_event_id(id) && !event_ids.contains(id))
+        })
+        .filter(|g_event| {
+            !g_event
+                .summary
+                .as_deref()
+                .is_some_and(|summary| summary.starts_with(&cancelled))
+        })
+        .collect()
+}
+
+/// Returns true if the calendar event ID was derived from an SCMA event ID (see event_id).
+fn is_synced_event_id(id: &str) -> bool {
+    id.len() >= 5 && id.chars().all(|c| c.is_ascii_digit())
+}
+
+fn event_summary(event: &Event, options: &EventOptions) -> String {
+    let mut summary = String::new();
+
+    if let Some(marker) = event_marker(event, options) {
+        summary.push_str(marker);
+        summary.push(' ');
+    }
+
+    let format = options
+        .summary_format
+        .as_deref()
+        .unwrap_or(DEFAULT_SUMMARY_FORMAT);
+    summary.push_str(&format_summary(format, event));
+
+    let attendee_count = event.attendee_count();
+    if options.attendee_count && attendee_count > 0 {
+        let going = options.lang.strings().going;
+        summary.push_str(&format!(" ({attendee_count} {going})"));
+    }
+
+    summary
+}
+
+/// Replaces the placeholders of the summary format with the event fields.
+///
+/// Unknown placeholders are left as is.
+fn format_summary(format: &str, event: &Event) -> String {
+    [
+        ("{title}", event.title.clone()),
+        ("{location}", event.location.clone()),
+        ("{id}", event.id.clone()),
+        ("{start_date}", event.start_date.to_string()),
+        ("{end_date}", event.end_date.to_string()),
+        ("{url}", event.url.clone()),
+    ]
+    .iter()
+    .fold(format.to_string(), |summary, (placeholder, value)| {
+        summary.replace(placeholder, value)
+    })
+}
+
+fn event_color(event: &Event, options: &EventOptions) -> Option<EventColor> {
+    let category = event.category.as_ref()?;
+    options.category_colors.get(category).copied()
+}
+
+fn event_marker<'a>(event: &Event, options: &'a EventOptions) -> Option<&'a str> {
+    let title = event.title.to_lowercase();
+    options
+        .title_markers
+        .iter()
+        .find(|(keyword, _)| title.contains(&keyword.to_lowercase()))
+        .map(|(_, marker)| marker.as_str())
+}
+
+fn event_start(event: &Event, timezone: Tz) -> api::EventDateTime {
+    if let Some((start, _)) = event.start_end(timezone) {
+        return api::EventDateTime {
+            date_time: Some(start),
+            time_zone: Some(timezone.name().to_string()),
+            ..Default::default()
+        };
+    }
+
+    api::EventDateTime {
+        date: Some(event.start_date),
+        ..Default::default()
+    }
+}
+
+fn event_end(event: &Event, timezone: Tz) -> api::EventDateTime {
+    if let Some((_, end)) = event.start_end(timezone) {
+        return api::EventDateTime {
+            date_time: Some(end),
+            time_zone: Some(timezone.name().to_string()),
+            ..Default::default()
+        };
+    }
+
+    // WORKAROUND: Google Calendar seems to require all-day events to end on the day after.
+    // Otherwise they show as 1 day short.
+    let end_date = event.end_date + Duration::days(1);
+
+    api::EventDateTime {
+        date: Some(end_date),
+        ..Default::default()
+    }
+}
+
+fn event_description(event: &Event, options: &EventOptions) -> Result<String, Error> {
+    let strings = options.lang.strings();
+
+    let mut buffer = match options.description_template {
+        Some(ref template) => template.render(event)?,
+        None => default_description(event, options).map_err(Error::parse)?,
+    };
+
+    if event.timestamp.is_some() {
+        write!(
+            buffer,
+            "{}{} {} <a href='https://github.com/rfdonnelly/scma-gsync'>scma-gsync</a>.",
+            strings.footer_separator,
+            event.timestamp(options.timezone()),
+            strings.footer_by
+        )
+        .map_err(Error::parse)?;
+    }
+
+    Ok(buffer)
+}
+
+/// Returns the built-in description layout without the sync footer.
+fn default_description(event: &Event, options: &EventOptions) -> Result<String, std::fmt::Error> {
+    let include = &options.description_include;
+    let strings = options.lang.strings();
+
+    let mut buffer = String::with_capacity(DESCRIPTION_BUFFER_SIZE);
+    write!(buffer, "{}", event.url)?;
+    write!(buffer, "<h3>{}</h3>", strings.description)?;
+    write!(buffer, "{}", event.description)?;
+
+    if include.attendees {
+        write!(buffer, "<h3>{}</h3>", strings.attendees)?;
+        match event.attendees.as_ref() {
+            Some(attendees) => {
+                write!(buffer, "<ol>")?;
+                for attendee in attendees {
+                    write!(buffer, "<li>{} ({})", attendee.name, attendee.count)?;
+                    if include.attendee_comments {
+                        write!(buffer, " {}", attendee.comment)?;
+                    }
+                    write!(buffer, "</li>")?;
+                }
+                write!(buffer, "</ol>")?;
+            }
+            None => {
+                write!(buffer, "{}", strings.none)?;
+            }
+        }
+    } else if include.counts {
+        write!(buffer, "<h3>{}</h3>", strings.attendees)?;
+        match event.attendees {
+            Some(_) => write!(buffer, "{}", event.attendee_count())?,
+            None => write!(buffer, "{}", strings.none)?,
+        }
+    }
+
+    if include.comments {
+        write!(buffer, "<h3>{}</h3>", strings.comments)?;
+        match event.comments.as_ref() {
+            Some(comments) => {
+                write!(buffer, "<ul>")?;
+                for comment in comments {
+                    write!(
+                        buffer,
+                        "<li>{} ({}) {}</li>",
+                        comment.author,
+                        comment.local_date(options.timezone()),
+                        comment.text
+                    )?;
+                }
+                write!(buffer, "</ul>")?;
+            }
+            None => {
+                write!(buffer, "{}", strings.none)?;
+            }
+        }
+    } else if include.counts {
+        write!(buffer, "<h3>{}</h3>", strings.comments)?;
+        match event.comments {
+            Some(ref comments) => write!(buffer, "{}", comments.len())?,
+            None => write!(buffer, "{}", strings.none)?,
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Returns the description without the sync footer.
+///
+/// The footer contains the sync timestamp and therefore changes on every sync.  The footer of any
+/// language is removed.
+fn description_content(description: &str) -> &str {
+    Lang::ALL
+        .iter()
+        .find_map(|lang| description.split_once(lang.strings().footer_separator))
+        .map(|(content, _footer)| content)
+        .unwrap_or(description)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{event, Attendee};
+    use crate::model::{EventTime, Frequency, Recurrence};
+
+    #[test]
+    fn acl_sync_ops() {
         let emails = vec!["user0@example.com", "user1@example.com"];
         let rules = vec![
             api::AclRule {
@@ -523,10 +2889,794 @@ mod test {
                 ..Default::default()
             },
         ];
-        let actual = GCal::acl_sync_ops(&emails, &rules);
+        let actual = GCal::acl_sync_ops(&emails, &[], None, false, &rules);
+        let expected = AclSyncOpsResult {
+            inserts: [AclScope::User("user0@example.com".into())].into(),
+            deletes: [AclScope::User("user2@example.com".into())].into(),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn acl_sync_ops_groups() {
+        let rule = |role: &str, type_: &str, value: &str| api::AclRule {
+            role: Some(role.to_string()),
+            scope: Some(api::AclRuleScope {
+                type_: Some(type_.to_string()),
+                value: Some(value.to_string()),
+            }),
+            ..Default::default()
+        };
+        let rules = vec![
+            rule("reader", "user", "user0@example.com"),
+            rule("reader", "group", "members@example.com"),
+            rule("reader", "group", "alumni@example.com"),
+            rule("reader", "domain", "example.com"),
+            api::AclRule {
+                role: Some("reader".to_string()),
+                scope: Some(api::AclRuleScope {
+                    type_: Some("default".to_string()),
+                    value: None,
+                }),
+                ..Default::default()
+            },
+        ];
+
+        // Group rules are left unchanged without groups
+        let actual = GCal::acl_sync_ops(&["user0@example.com"], &[], None, false, &rules);
+        let expected = AclSyncOpsResult {
+            inserts: HashSet::new(),
+            deletes: HashSet::new(),
+        };
+        assert_eq!(actual, expected);
+
+        let actual = GCal::acl_sync_ops(
+            &[],
+            &["members@example.com", "board@example.com"],
+            None,
+            false,
+            &rules,
+        );
+        let expected = AclSyncOpsResult {
+            inserts: [AclScope::Group("board@example.com".into())].into(),
+            deletes: [
+                AclScope::User("user0@example.com".into()),
+                AclScope::Group("alumni@example.com".into()),
+            ]
+            .into(),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn acl_sync_ops_domain() {
+        let rule = |type_: &str, value: &str| api::AclRule {
+            role: Some("reader".to_string()),
+            scope: Some(api::AclRuleScope {
+                type_: Some(type_.to_string()),
+                value: Some(value.to_string()),
+            }),
+            ..Default::default()
+        };
+        let rules = vec![
+            rule("user", "user0@example.com"),
+            rule("domain", "example.com"),
+        ];
+
+        let actual = GCal::acl_sync_ops(
+            &["user0@example.com"],
+            &[],
+            Some("example.org"),
+            false,
+            &rules,
+        );
+        let expected = AclSyncOpsResult {
+            inserts: [AclScope::Domain("example.org".into())].into(),
+            deletes: [AclScope::Domain("example.com".into())].into(),
+        };
+        assert_eq!(actual, expected);
+
+        let actual = GCal::acl_sync_ops(
+            &["user0@example.com"],
+            &[],
+            Some("example.com"),
+            false,
+            &rules,
+        );
+        let expected = AclSyncOpsResult {
+            inserts: HashSet::new(),
+            deletes: HashSet::new(),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn acl_scope() {
+        let user = AclScope::User("john@example.com".into());
+        let group = AclScope::Group("members@example.com".into());
+        assert_eq!(user.rule_id(), "user:john@example.com");
+        assert_eq!(group.rule_id(), "group:members@example.com");
+        assert_eq!(user.to_pushed(), "john@example.com");
+        assert_eq!(group.to_pushed(), "group:members@example.com");
+        assert_eq!(AclScope::from_pushed(&user.to_pushed()), user);
+        assert_eq!(AclScope::from_pushed(&group.to_pushed()), group);
+        let domain = AclScope::Domain("example.org".into());
+        assert_eq!(domain.to_pushed(), "domain:example.org");
+        assert_eq!(AclScope::from_pushed(&domain.to_pushed()), domain);
+        let public = AclScope::Default;
+        assert_eq!(public.rule_id(), "default");
+        assert_eq!(public.to_api().value, None);
+        assert_eq!(AclScope::from_pushed(&public.to_pushed()), public);
+    }
+
+    #[test]
+    fn acl_sync_ops_public() {
+        let rules = vec![super::reader_rule(&AclScope::User(
+            "user0@example.com".into(),
+        ))];
+        let actual = GCal::acl_sync_ops(&["user0@example.com"], &[], None, true, &rules);
+        let expected = AclSyncOpsResult {
+            inserts: [AclScope::Default].into(),
+            deletes: HashSet::new(),
+        };
+        assert_eq!(actual, expected);
+
+        let rules = vec![super::reader_rule(&AclScope::Default)];
+        let actual = GCal::acl_sync_ops(&[], &[], None, false, &rules);
+        let expected = AclSyncOpsResult {
+            inserts: HashSet::new(),
+            deletes: HashSet::new(),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn public_urls() {
+        let calendar_id = "abc123@group.calendar.google.com";
+        assert_eq!(
+            public_html_url(calendar_id),
+            "https://calendar.google.com/calendar/embed?src=abc123%40group.calendar.google.com"
+        );
+        assert_eq!(
+            public_ics_url(calendar_id),
+            "https://calendar.google.com/calendar/ical/abc123@group.calendar.google.com/public/basic.ics"
+        );
+    }
+
+    #[test]
+    fn batch_url() {
+        assert_eq!(
+            super::batch_url(ROOT_URL),
+            "https://www.googleapis.com/batch/calendar/v3"
+        );
+        assert_eq!(
+            super::batch_url("http://localhost:8080/"),
+            "http://localhost:8080/batch/calendar/v3"
+        );
+    }
+
+    #[test]
+    fn acl_report_entries() {
+        let emails = vec!["user0@example.com", "user1@example.com"];
+        let rule = |role: &str, type_: &str, value: &str| api::AclRule {
+            role: Some(role.to_string()),
+            scope: Some(api::AclRuleScope {
+                type_: Some(type_.to_string()),
+                value: Some(value.to_string()),
+            }),
+            ..Default::default()
+        };
+        let rules = vec![
+            rule("reader", "user", "user1@example.com"),
+            rule("reader", "user", "user2@example.com"),
+            rule("owner", "user", "owner@example.com"),
+            rule("reader", "domain", "example.com"),
+        ];
+        let actual: Vec<(String, Option<String>, AclReportStatus)> =
+            GCal::acl_report_entries(&emails, &rules)
+                .into_iter()
+                .map(|entry| (entry.role, entry.scope_value, entry.status))
+                .collect();
+        let expected = vec![
+            (
+                "owner".to_string(),
+                Some("owner@example.com".to_string()),
+                AclReportStatus::Unknown,
+            ),
+            (
+                "reader".to_string(),
+                Some("example.com".to_string()),
+                AclReportStatus::NotUser,
+            ),
+            (
+                "reader".to_string(),
+                Some("user1@example.com".to_string()),
+                AclReportStatus::Member,
+            ),
+            (
+                "reader".to_string(),
+                Some("user2@example.com".to_string()),
+                AclReportStatus::Unknown,
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn event_guests() {
+        let attendee = |name: &str, count, email: Option<&str>| Attendee {
+            name: name.into(),
+            count,
+            comment: "".into(),
+            email: email.map(str::to_string),
+        };
+        let event = Event {
+            attendees: Some(vec![
+                attendee("Jane Roe", 2, Some("jane@example.com")),
+                attendee("Unknown", 1, None),
+                attendee("John Doe", 1, Some("john@example.com")),
+                attendee("john doe", 1, Some("john@example.com")),
+            ]),
+            ..event("527", "2022-01-14")
+        };
+        let actual: Vec<(Option<String>, Option<i32>)> = super::event_guests(&event)
+            .into_iter()
+            .map(|guest| (guest.email, guest.additional_guests))
+            .collect();
+        let expected = vec![
+            (Some("jane@example.com".to_string()), Some(1)),
+            (Some("john@example.com".to_string()), Some(0)),
+        ];
+        assert_eq!(actual, expected);
+
+        let options = EventOptions::default();
+        let g_event = super::create_api_event(&event, &options).unwrap();
+        assert!(g_event.attendees.is_none());
+        assert_eq!(g_event.guests_can_invite_others, None);
+        assert_eq!(g_event.guests_can_modify, None);
+        assert_eq!(g_event.guests_can_see_other_guests, None);
+
+        let options = EventOptions {
+            attendee_guests: true,
+            ..Default::default()
+        };
+        let g_event = super::create_api_event(&event, &options).unwrap();
+        assert_eq!(g_event.attendees.map(|guests| guests.len()), Some(2));
+
+        let options = EventOptions {
+            guests_can_invite_others: Some(false),
+            guests_can_modify: Some(true),
+            guests_can_see_other_guests: Some(false),
+            ..Default::default()
+        };
+        let g_event = super::create_api_event(&event, &options).unwrap();
+        assert_eq!(g_event.guests_can_invite_others, Some(false));
+        assert_eq!(g_event.guests_can_modify, Some(true));
+        assert_eq!(g_event.guests_can_see_other_guests, Some(false));
+    }
+
+    #[test]
+    fn event_summary() {
+        let mut event = Event {
+            title: "Joshua Tree".into(),
+            ..event("527", "2022-01-14")
+        };
+        let options = EventOptions {
+            attendee_count: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            super::event_summary(&event, &EventOptions::default()),
+            "SCMA: Joshua Tree"
+        );
+        assert_eq!(super::event_summary(&event, &options), "SCMA: Joshua Tree");
+
+        event.attendees = Some(vec![
+            Attendee {
+                name: "Attendee 0".into(),
+                count: 2,
+                comment: "".into(),
+                email: None,
+            },
+            Attendee {
+                name: "Attendee 1".into(),
+                count: 1,
+                comment: "".into(),
+                email: None,
+            },
+        ]);
+        assert_eq!(
+            super::event_summary(&event, &options),
+            "SCMA: Joshua Tree (3 going)"
+        );
+        let options = EventOptions {
+            lang: Lang::Es,
+            ..options
+        };
+        assert_eq!(
+            super::event_summary(&event, &options),
+            "SCMA: Joshua Tree (3 confirmados)"
+        );
+    }
+
+    #[test]
+    fn event_summary_format() {
+        let event = Event {
+            title: "Joshua Tree".into(),
+            location: "Hidden Valley".into(),
+            ..event("527", "2022-01-14")
+        };
+        let summary = |format: &str| {
+            let options = EventOptions {
+                summary_format: Some(format.into()),
+                ..Default::default()
+            };
+            super::event_summary(&event, &options)
+        };
+
+        assert_eq!(
+            summary("{title} @ {location}"),
+            "Joshua Tree @ Hidden Valley"
+        );
+        assert_eq!(
+            summary("{start_date}: {title} ({unknown})"),
+            "2022-01-14: Joshua Tree ({unknown})"
+        );
+        assert_eq!(summary(DEFAULT_SUMMARY_FORMAT), "SCMA: Joshua Tree");
+    }
+
+    #[test]
+    fn event_color() {
+        let mut event = event("527", "2022-01-14");
+        let options = EventOptions {
+            category_colors: serde_yaml::from_str("{ '2': basil, '4': blueberry }").unwrap(),
+            ..Default::default()
+        };
+        let color_id = |event: &Event| super::create_api_event(event, &options).unwrap().color_id;
+
+        assert_eq!(color_id(&event), None);
+        event.category = Some("2".into());
+        assert_eq!(color_id(&event), Some("10".into()));
+        event.category = Some("4".into());
+        assert_eq!(color_id(&event), Some("9".into()));
+        event.category = Some("7".into());
+        assert_eq!(color_id(&event), None);
+    }
+
+    #[test]
+    fn event_reminder() {
+        let reminder = |method, minutes| EventReminder { method, minutes };
+        assert_eq!(
+            "popup:1d".parse(),
+            Ok(reminder(ReminderMethod::Popup, 1440))
+        );
+        assert_eq!(
+            "email:12h".parse(),
+            Ok(reminder(ReminderMethod::Email, 720))
+        );
+        assert_eq!("popup:30".parse(), Ok(reminder(ReminderMethod::Popup, 30)));
+        assert_eq!(
+            "popup:4w".parse(),
+            Ok(reminder(ReminderMethod::Popup, 40320))
+        );
+        assert!("popup:5w".parse::<EventReminder>().is_err());
+        assert!("sms:1d".parse::<EventReminder>().is_err());
+        assert!("popup".parse::<EventReminder>().is_err());
+        assert_eq!(reminder(ReminderMethod::Email, 60).to_string(), "email:60");
+    }
+
+    #[test]
+    fn calendar_route() {
+        let mut event = Event {
+            title: "Board Meeting".into(),
+            end_date: "2022-01-14".parse().unwrap(),
+            location: "Philippe The Original".into(),
+            ..event("527", "2022-01-14")
+        };
+        let route = |yaml: &str| serde_yaml::from_str::<CalendarRoute>(yaml).unwrap();
+
+        assert!(route("keywords: [meeting]").matches(&event));
+        assert!(route("locations: [philippe]").matches(&event));
+        assert!(!route("categories: ['4']").matches(&event));
+        event.category = Some("4".into());
+        assert!(route("categories: ['4']").matches(&event));
+        assert!(!route("{ keywords: [campout], categories: ['2'] }").matches(&event));
+        assert!(!CalendarRoute::default().matches(&event));
+        assert!(serde_yaml::from_str::<CalendarRoute>("keyword: [meeting]").is_err());
+    }
+
+    #[test]
+    fn event_summary_marker() {
+        let event = Event {
+            title: "Joshua Tree Campout".into(),
+            ..event("527", "2022-01-14")
+        };
+        let options = EventOptions {
+            title_markers: [("meeting", "📋"), ("CAMPOUT", "🏕"), ("joshua", "🧗")]
+                .into_iter()
+                .map(|(keyword, marker)| (keyword.to_string(), marker.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            super::event_summary(&event, &options),
+            "🏕 SCMA: Joshua Tree Campout"
+        );
+    }
+
+    #[test]
+    fn event_description_include() {
+        let event = Event {
+            attendees: Some(vec![Attendee {
+                name: "Attendee 0".into(),
+                count: 2,
+                comment: "a comment".into(),
+                email: None,
+            }]),
+            ..event("527", "2022-01-14")
+        };
+        let description = |description_include| {
+            let options = EventOptions {
+                description_include,
+                ..Default::default()
+            };
+            super::event_description(&event, &options).unwrap()
+        };
+
+        assert_eq!(
+            description(DescriptionInclude::default()),
+            "a url<h3>Description</h3>a description<h3>Attendees</h3><ol><li>Attendee 0 (2) a comment</li></ol><h3>Comments</h3>None"
+        );
+        assert_eq!(
+            description(DescriptionInclude {
+                attendee_comments: false,
+                comments: false,
+                ..Default::default()
+            }),
+            "a url<h3>Description</h3>a description<h3>Attendees</h3><ol><li>Attendee 0 (2)</li></ol>"
+        );
+        assert_eq!(
+            description(DescriptionInclude {
+                attendees: false,
+                ..Default::default()
+            }),
+            "a url<h3>Description</h3>a description<h3>Comments</h3>None"
+        );
+        assert_eq!(
+            description(DescriptionInclude {
+                attendees: false,
+                comments: false,
+                counts: true,
+                ..Default::default()
+            }),
+            "a url<h3>Description</h3>a description<h3>Attendees</h3>2<h3>Comments</h3>None"
+        );
+    }
+
+    #[test]
+    fn event_description_template() {
+        let mut event = Event {
+            title: "Joshua Tree".into(),
+            description: "<p>a description</p>".into(),
+            attendees: Some(vec![
+                Attendee {
+                    name: "Attendee 0".into(),
+                    count: 2,
+                    comment: "a comment".into(),
+                    email: None,
+                },
+                Attendee {
+                    name: "Attendee 1".into(),
+                    count: 1,
+                    comment: "".into(),
+                    email: None,
+                },
+            ]),
+            ..event("527", "2022-01-14")
+        };
+        let template = "<b>{{ event.title }}</b> {{ event.start_date }}{{ event.description }}\
+            <ul>{% for attendee in event.attendees %}<li>{{ attendee.name }}</li>{% endfor %}</ul>\
+            {% if event.comments %}comments{% endif %}";
+        let options = EventOptions {
+            description_template: Some(DescriptionTemplate::new(template).unwrap()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::event_description(&event, &options).unwrap(),
+            "<b>Joshua Tree</b> 2022-01-14<p>a description</p><ul><li>Attendee 0</li><li>Attendee 1</li></ul>"
+        );
+
+        event.timestamp = Some("2022-01-11T08:00:00Z".parse().unwrap());
+        let description = super::event_description(&event, &options).unwrap();
+        assert_eq!(
+            super::description_content(&description),
+            "<b>Joshua Tree</b> 2022-01-14<p>a description</p><ul><li>Attendee 0</li><li>Attendee 1</li></ul>"
+        );
+
+        assert!(DescriptionTemplate::new("{{ event.title").is_err());
+    }
+
+    #[test]
+    fn description_content() {
+        let description = "content\n\nLast synced at 2022-01-11T00:00:00-08:00 by scma-gsync.";
+        assert_eq!(super::description_content(description), "content");
+        assert_eq!(super::description_content("content"), "content");
+        let description =
+            "contenido\n\nÚltima sincronización el 2022-01-11T00:00:00-08:00 por scma-gsync.";
+        assert_eq!(super::description_content(description), "contenido");
+    }
+
+    #[test]
+    fn orphan_events() {
+        let event = event("527", "2022-01-14");
+        let g_event = |id: &str, summary: &str, status: &str| api::Event {
+            id: Some(id.into()),
+            summary: Some(summary.into()),
+            status: Some(status.into()),
+            ..Default::default()
+        };
+        let g_events = [
+            g_event("00527", "SCMA: Joshua Tree", "confirmed"),
+            g_event("00528", "SCMA: Red Rocks", "confirmed"),
+            g_event("00529", "CANCELLED: SCMA: Tahquitz", "confirmed"),
+            g_event("00530", "SCMA: Idyllwild", "cancelled"),
+            g_event("abcdef", "Manually added", "confirmed"),
+        ];
+
+        let orphans: Vec<&str> = super::orphan_events(&[event], &g_events, "CANCELLED")
+            .iter()
+            .map(|g_event| g_event.id.as_deref().unwrap())
+            .collect();
+        assert_eq!(orphans, vec!["00528"]);
+    }
+
+    #[test]
+    fn cancel_request() {
+        let req = super::cancel_request("SCMA: Red Rocks", "CANCELLED", OrphanAction::Cancel);
+        assert_eq!(req.summary.as_deref(), Some("CANCELLED: SCMA: Red Rocks"));
+        assert_eq!(req.status, None);
+
+        let req = super::cancel_request("SCMA: Red Rocks", "CANCELLED", OrphanAction::CancelStatus);
+        assert_eq!(req.summary.as_deref(), Some("CANCELLED: SCMA: Red Rocks"));
+        assert_eq!(req.status.as_deref(), Some("cancelled"));
+    }
+
+    #[test]
+    fn instance_event() {
+        let occurrence = |id: &str, date: &str, parent: Option<&str>| Event {
+            title: "Gym Night".into(),
+            recurrence: parent.is_none().then_some(Recurrence {
+                frequency: Frequency::Weekly,
+                interval: 1,
+                until: None,
+                count: Some(3),
+            }),
+            recurring_parent: parent.map(str::to_string),
+            ..event(id, date)
+        };
+        let mut events = [
+            occurrence("527", "2022-07-05", None),
+            occurrence("528", "2022-07-12", Some("527")),
+            occurrence("529", "2022-07-19", Some("527")),
+        ];
+
+        let parents = series_parents(&events);
+        assert!(series_parent(&events[0], &parents).is_none());
+        let parent = series_parent(&events[1], &parents).unwrap();
+        let options = EventOptions::default();
+
+        let g_event = create_api_event(&events[0], &options).unwrap();
+        assert_eq!(
+            g_event.recurrence,
+            Some(vec!["RRULE:FREQ=WEEKLY;INTERVAL=1;COUNT=3".to_string()])
+        );
+
+        let g_event = create_api_event(&events[1], &options).unwrap();
+        let g_event = super::instance_event(g_event, &events[1], parent, DEFAULT_TIMEZONE).unwrap();
+        assert_eq!(g_event.id.as_deref(), Some("00527_20220712"));
+        assert_eq!(g_event.recurring_event_id.as_deref(), Some("00527"));
+        assert_eq!(g_event.recurrence, None);
+
+        // Timed instances are identified by the UTC start time
+        events[0].time = Some(EventTime {
+            start: "19:00:00".parse().unwrap(),
+            end: "21:00:00".parse().unwrap(),
+        });
+        let parents = series_parents(&events);
+        let parent = series_parent(&events[1], &parents).unwrap();
+        let g_event = create_api_event(&events[1], &options).unwrap();
+        let g_event = super::instance_event(g_event, &events[1], parent, DEFAULT_TIMEZONE).unwrap();
+        assert_eq!(g_event.id.as_deref(), Some("00527_20220713T020000Z"));
+
+        // The standalone events of the instances are orphans
+        let g_events = [
+            api::Event {
+                id: Some("00527".into()),
+                ..Default::default()
+            },
+            api::Event {
+                id: Some("00528".into()),
+                ..Default::default()
+            },
+        ];
+        let orphans: Vec<&str> = super::orphan_events(&events, &g_events, "CANCELLED")
+            .iter()
+            .map(|g_event| g_event.id.as_deref().unwrap())
+            .collect();
+        assert_eq!(orphans, vec!["00528"]);
+    }
+
+    #[test]
+    fn event_hash() {
+        let mut event = Event {
+            timestamp: Some("2022-01-11T08:00:00Z".parse().unwrap()),
+            ..event("527", "2022-01-14")
+        };
+        let options = EventOptions::default();
+        let hash =
+            |event: &Event| super::event_hash(&create_api_event(event, &options).unwrap()).unwrap();
+        let original = hash(&event);
+
+        event.timestamp = Some("2022-01-12T08:00:00Z".parse().unwrap());
+        assert_eq!(hash(&event), original);
+
+        event.location = "another location".into();
+        assert_ne!(hash(&event), original);
+
+        let existing = api::Event {
+            extended_properties: Some(hash_properties(&original)),
+            ..Default::default()
+        };
+        assert!(is_unchanged(&existing, &original));
+        assert!(!is_unchanged(&existing, &hash(&event)));
+        assert!(!is_unchanged(&api::Event::default(), &original));
+    }
+
+    #[test]
+    fn drifted_fields() {
+        let event = event("527", "2022-01-14");
+        let mut g_event = create_api_event(&event, &EventOptions::default()).unwrap();
+        assert!(super::drifted_fields(&g_event).is_empty());
+
+        g_event.extended_properties = Some(api::EventExtendedProperties {
+            private: Some([(FIELDS_PROPERTY.to_string(), field_hashes(&g_event))].into()),
+            ..Default::default()
+        });
+        assert!(super::drifted_fields(&g_event).is_empty());
+
+        // A manual edit drifts
+        g_event.location = Some("another location".into());
+        g_event.description = Some("an edited description".into());
+        assert_eq!(
+            super::drifted_fields(&g_event),
+            [EventField::Description, EventField::Location]
+        );
+
+        // A patch by the sync does not
+        let mut patch = api::Event {
+            description: Some("a refreshed description".into()),
+            ..Default::default()
+        };
+        insert_patched_field_hashes(&g_event, &mut patch);
+        g_event.description = patch.description;
+        g_event.extended_properties = patch.extended_properties;
+        assert_eq!(super::drifted_fields(&g_event), [EventField::Location]);
+    }
+
+    #[test]
+    fn is_not_found() {
+        let error = |code: u16| {
+            google_calendar3::Error::BadRequest(serde_json::json!({
+                "error": {"code": code, "message": "an error"}
+            }))
+        };
+
+        assert!(super::is_not_found(&error(404)));
+        assert!(super::is_not_found(&error(410)));
+        assert!(!super::is_not_found(&error(403)));
+        assert!(!super::is_not_found(&error(500)));
+        assert!(!super::is_not_found(
+            &google_calendar3::Error::MissingAPIKey
+        ));
+    }
+
+    #[test]
+    fn is_restored() {
+        let backed_up = api::Event {
+            id: Some("scma00527".into()),
+            status: Some("confirmed".into()),
+            summary: Some("SCMA: Joshua Tree".into()),
+            description: Some(
+                "a description\n\nLast synced at 2024-01-01T00:00:00-08:00 by scma-gsync.".into(),
+            ),
+            start: Some(api::EventDateTime {
+                date: Some("2022-01-14".parse().unwrap()),
+                ..Default::default()
+            }),
+            etag: Some("an etag".into()),
+            ..Default::default()
+        };
+
+        // Google Calendar fields and the sync footer are ignored
+        let mut existing = api::Event {
+            description: Some(
+                "a description\n\nLast synced at 2024-02-01T00:00:00-08:00 by scma-gsync.".into(),
+            ),
+            etag: Some("another etag".into()),
+            ..backed_up.clone()
+        };
+        assert!(super::is_restored(&existing, &backed_up));
+
+        existing.status = Some("cancelled".into());
+        assert!(!super::is_restored(&existing, &backed_up));
+
+        existing.status = backed_up.status.clone();
+        existing.start = None;
+        assert!(!super::is_restored(&existing, &backed_up));
+
+        assert_eq!(super::restored_event(&backed_up).etag, None);
+    }
+
+    #[test]
+    fn event_cache() {
+        let g_event = |id: &str, start_date: &str, end_date: &str, status: &str| api::Event {
+            id: Some(id.into()),
+            status: Some(status.into()),
+            start: Some(api::EventDateTime {
+                date: Some(start_date.parse().unwrap()),
+                ..Default::default()
+            }),
+            end: Some(api::EventDateTime {
+                date: Some(end_date.parse().unwrap()),
+                ..Default::default()
+            }),
+            extended_properties: Some(hash_properties("a hash")),
+            ..Default::default()
+        };
+        let ids = |g_events: Vec<api::Event>| -> Vec<String> {
+            g_events
+                .into_iter()
+                .filter_map(|g_event| g_event.id)
+                .collect()
+        };
+        let mut cache = EventCache::new("a calendar");
+        cache.apply(vec![
+            g_event("00527", "2022-01-14", "2022-01-18", "confirmed"),
+            g_event("00528", "2022-01-21", "2022-01-23", "confirmed"),
+        ]);
+        cache.apply(vec![g_event(
+            "00528",
+            "2022-01-28",
+            "2022-01-30",
+            "cancelled",
+        )]);
+
+        assert_eq!(cache.events["00527"].hash.as_deref(), Some("a hash"));
+        assert_eq!(cache.events["00528"].status.as_deref(), Some("cancelled"));
+        assert_eq!(ids(cache.events(None, None)), vec!["00527", "00528"]);
+        assert_eq!(
+            ids(cache.events(Some("2022-01-18".parse().unwrap()), None)),
+            vec!["00528"]
+        );
+        assert_eq!(
+            ids(cache.events(None, Some("2022-01-27".parse().unwrap()))),
+            vec!["00527"]
+        );
+        assert!(is_unchanged(&cache.events(None, None)[0], "a hash"));
+    }
+
+    #[test]
+    fn reader_rule() {
+        let emails = vec!["user0@example.com", "user1@example.com"];
+        let rules = vec![
+            super::reader_rule(&AclScope::User("user1@example.com".into())),
+            super::reader_rule(&AclScope::User("user2@example.com".into())),
+        ];
+        let actual = GCal::acl_sync_ops(&emails, &[], None, false, &rules);
         let expected = AclSyncOpsResult {
-            inserts: vec!["user0@example.com".to_string()].into_iter().collect(),
-            deletes: vec!["user2@example.com".to_string()].into_iter().collect(),
+            inserts: [AclScope::User("user0@example.com".into())].into(),
+            deletes: [AclScope::User("user2@example.com".into())].into(),
         };
         assert_eq!(actual, expected);
     }
@@ -1,14 +1,23 @@
-use crate::model::User;
-use crate::output::GAuth;
+use crate::model::{TripLeaderStatus, User, DEFAULT_TIMEZONE};
+use crate::output::retry::RetryDelegate;
+use crate::output::{fail_or_continue, GAuth, UserSink};
+use crate::plan::{Plan, PlannedWrite};
 use crate::Connector;
+use crate::Error;
 
+use async_trait::async_trait;
+use chrono::Datelike;
+use chrono_tz::Tz;
 use google_people1::{api, FieldMask, PeopleService};
 use hyper_util::{client::legacy::Client, rt::TokioExecutor};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tap::prelude::*;
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 const SCOPE: api::Scope = api::Scope::Contact;
 
@@ -16,15 +25,34 @@ const CONTACT_GROUPS_GET_MAX_MEMBERS: i32 = 999;
 const PEOPLE_BATCH_CREATE_MAX_CONTACTS: usize = 50;
 const PEOPLE_BATCH_GET_MAX_CONTACTS: usize = 50;
 const PEOPLE_BATCH_UPDATE_MAX_CONTACTS: usize = 50;
+const PEOPLE_BATCH_DELETE_MAX_CONTACTS: usize = 500;
+const CONTACT_GROUPS_MODIFY_MAX_MEMBERS: usize = 1000;
 const GROUP_FIELDS: &[&str] = &["name"];
+const GROUP_FIELDS_MEMBERS: &[&str] = &["name", "memberCount"];
+const CONNECTIONS_LIST_PAGE_SIZE: i32 = 1000;
 const PERSON_FIELDS_GET: &[&str] = &[
     "addresses",
+    "birthdays",
     "emailAddresses",
     "names",
     "phoneNumbers",
     "userDefined",
 ];
-const PERSON_FIELDS_UPDATE: &[&str] = &["addresses", "phoneNumbers", "userDefined"];
+const PERSON_FIELDS_UPDATE: &[&str] = &["addresses", "birthdays", "phoneNumbers", "userDefined"];
+/// The user defined field key of the time of the last update
+const LAST_UPDATED_KEY: &str = "SCMA Last Updated";
+/// The user defined field key of the hash of the last uploaded photo.  Detects photo changes.
+const PHOTO_HASH_KEY: &str = "SCMA Photo Hash";
+/// The user defined field key of the hashes of the synced fields as last written.  Detects manual
+/// edits in Google Contacts (see GPpl::drift_report).
+const FIELD_HASHES_KEY: &str = "SCMA Field Hashes";
+/// The contact fields checked for drift
+const DRIFT_FIELDS: [ContactField; 4] = [
+    ContactField::Name,
+    ContactField::Phone,
+    ContactField::Address,
+    ContactField::Birthday,
+];
 
 /// Synchronizes SCMA members with Google Contacts using the algorithm below.
 ///
@@ -52,21 +80,169 @@ const PERSON_FIELDS_UPDATE: &[&str] = &["addresses", "phoneNumbers", "userDefine
 ///      First, merge SCMA User data with Google People Person obtained by the people.getBatchGet
 ///      API. Then, make one or more calls to people.batchUpdateContacts.
 ///
-///      TODO?: A update is performed whether an update needs to be performed. This could be
-///      improved by only updating Persons that need an update.
+///      Persons whose merged data is unchanged are not updated.
+///
+///    * Remove -- As directed by the StaleContactAction.
 ///
-///    * Remove -- Do nothing.
+///      Persons that exist in the Google People ContactGroup but no longer exist in the SCMA are
+///      ignored (the default), deleted using the people.batchDeleteContacts API method, or moved
+///      to a different ContactGroup (e.g. "SCMA Alumni") using the contactGroups.members.modify
+///      API method.
 ///
-///      Currently, nothing is done for Persons that exist in the Google People ContactGroup that
-///      do not or no longer exist in the SCMA.
+/// 6. Sync the additional ContactGroups (e.g. "SCMA Trip Leaders")
 ///
-///      TODO?: Add an option to delete these contacts using the people.batchDeleteContacts?
-///      TODO?: Move these contacts to a different ContactGroup (e.g. "SCMA Alumni")?
+///    Created persons are added to the additional ContactGroups whose MembershipRule they match.
+///    Other persons of the ContactGroup are added to or removed from the additional ContactGroups
+///    using the contactGroups.members.modify API method.  Persons not in the ContactGroup are not
+///    touched.
 pub struct GPpl {
     hub: PeopleService<Connector>,
+    group_name: String,
     /// The unique identifer for the ContactGroup assigned by the People API
     group_resource_name: String,
     dry_run: bool,
+    /// Records the writes (or the writes that would be made if dry_run)
+    plan: Arc<Plan>,
+    /// Records failed writes in the plan and continues instead of stopping if set
+    continue_on_error: bool,
+    /// Creates contacts for opted-out and blocked users if set
+    include_optedout: bool,
+    stale_contact_action: StaleContactAction,
+    /// Additional ContactGroups for subsets of the members
+    rule_groups: Vec<RuleGroup>,
+    /// A map of SCMA email addresses to Google email aliases
+    email_aliases: HashMap<String, String>,
+    /// The maximum number of stale contacts to delete.  Guards against deleting all contacts
+    /// because of an incomplete SCMA roster.
+    max_contact_deletes: Option<usize>,
+    /// The timezone of the last updated timestamp
+    timezone: Tz,
+}
+
+/// An additional ContactGroup for the members that match a rule.
+struct RuleGroup {
+    name: String,
+    resource_name: String,
+    rule: MembershipRule,
+}
+
+/// Which SCMA members belong to an additional contact group.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MembershipRule {
+    /// Members with any trip leader status
+    TripLeader,
+    /// Members with one of the trip leader statuses
+    TripLeaderStatus(Vec<TripLeaderStatus>),
+    /// Members with a position (e.g. board members)
+    Position,
+}
+
+impl MembershipRule {
+    pub fn matches(&self, user: &User) -> bool {
+        match self {
+            Self::TripLeader => user.trip_leader_status.is_some(),
+            Self::TripLeaderStatus(statuses) => user
+                .trip_leader_status
+                .as_ref()
+                .is_some_and(|status| statuses.contains(status)),
+            Self::Position => user
+                .position
+                .as_deref()
+                .is_some_and(|position| !position.trim().is_empty()),
+        }
+    }
+}
+
+/// What to do with contacts in the group that are no longer SCMA members.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum StaleContactAction {
+    /// Leaves the contacts in the group
+    #[default]
+    Ignore,
+    /// Deletes the contacts
+    Delete,
+    /// Moves the contacts from the group to the named contact group.  The named contact group is
+    /// created if it does not exist.
+    Move(String),
+}
+
+/// A Google Contacts value that differs from the SCMA user data.
+///
+/// Likely the result of a manual edit in Google Contacts that should be made on the SCMA website.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ContactDiscrepancy {
+    /// The SCMA email the contact was matched by
+    pub email: String,
+    pub name: String,
+    pub field: ContactField,
+    pub contacts_value: String,
+    pub scma_value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum ContactField {
+    Name,
+    Email,
+    Phone,
+    Address,
+    Birthday,
+}
+
+impl ContactField {
+    fn key(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Email => "email",
+            Self::Phone => "phone",
+            Self::Address => "address",
+            Self::Birthday => "birthday",
+        }
+    }
+
+    /// Returns the value of the field of the person with the given name.
+    fn get(self, person: &api::Person, name: &str) -> String {
+        match self {
+            Self::Name => name.to_string(),
+            Self::Email => person
+                .email_addresses
+                .iter()
+                .flatten()
+                .find(|email| email.type_.as_deref() == Some("SCMA"))
+                .and_then(|email| email.value.clone())
+                .unwrap_or_default(),
+            Self::Phone => scma_phone(person).cloned().unwrap_or_default(),
+            Self::Address => scma_address(person).cloned().unwrap_or_default(),
+            Self::Birthday => birthday(person)
+                .map(|birthday| format!("{birthday:?}"))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A synced contact that was edited in Google Contacts since it was last synced.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ContactDrift {
+    pub email: String,
+    pub name: String,
+    /// The fields that were edited
+    pub fields: Vec<ContactField>,
+}
+
+/// The members of a contact group as backed up (see GPpl::backup).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactsBackup {
+    pub group_name: String,
+    pub people: Vec<api::Person>,
+}
+
+/// A contact photo to upload
+struct PhotoUpdate {
+    /// None for a contact that was not created because of a dry run
+    resource_name: Option<String>,
+    name: String,
+    email: String,
+    photo: Vec<u8>,
 }
 
 #[derive(Debug)]
@@ -81,19 +257,319 @@ impl GPpl {
         group_name: &str,
         auth: GAuth,
         dry_run: bool,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+        plan: Arc<Plan>,
+    ) -> Result<Self, Error> {
         let hub = Self::create_hub(auth).await?;
         let group_resource_name =
             Self::contact_groups_get_or_create_by_name(&hub, group_name, dry_run).await?;
 
         Ok(Self {
             hub,
+            group_name: group_name.to_string(),
             group_resource_name,
             dry_run,
+            plan,
+            continue_on_error: false,
+            include_optedout: false,
+            stale_contact_action: StaleContactAction::default(),
+            rule_groups: Vec::new(),
+            email_aliases: HashMap::new(),
+            max_contact_deletes: None,
+            timezone: DEFAULT_TIMEZONE,
+        })
+    }
+
+    /// Continues with the remaining photo updates if a photo update fails.
+    ///
+    /// The errors of the failed updates are recorded in the plan (see Plan::failures).  The
+    /// contact writes are batched so a failed batch still stops the sync.
+    pub fn set_continue_on_error(&mut self, continue_on_error: bool) {
+        self.continue_on_error = continue_on_error;
+    }
+
+    pub fn set_stale_contact_action(&mut self, action: StaleContactAction) {
+        self.stale_contact_action = action;
+    }
+
+    /// Creates contacts for opted-out and blocked users (see User::is_optedout_or_blocked).
+    ///
+    /// By default, contacts are not created for these users.  Their existing contacts are updated.
+    pub fn set_include_optedout(&mut self, include_optedout: bool) {
+        self.include_optedout = include_optedout;
+    }
+
+    /// Sets the maximum number of stale contacts to delete.
+    ///
+    /// If more stale contacts are found, none are deleted and an error is returned.  During a dry
+    /// run, a warning is logged instead.
+    pub fn set_max_contact_deletes(&mut self, max: usize) {
+        self.max_contact_deletes = Some(max);
+    }
+
+    /// Sets the map of SCMA email addresses to Google email aliases.
+    ///
+    /// A user is matched to a person by either email so that a contact stored under the alias is
+    /// updated instead of duplicated.
+    pub fn set_email_aliases(&mut self, email_aliases: HashMap<String, String>) {
+        self.email_aliases = email_aliases;
+    }
+
+    /// Sets the timezone of the last updated timestamp.  Defaults to DEFAULT_TIMEZONE.
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = timezone;
+    }
+
+    /// Adds an additional contact group for the members that match the rule.
+    ///
+    /// The contact group is created if it does not exist.
+    pub async fn add_rule_group(
+        &mut self,
+        group_name: &str,
+        rule: MembershipRule,
+    ) -> Result<(), Error> {
+        let resource_name =
+            Self::contact_groups_get_or_create_by_name(&self.hub, group_name, self.dry_run).await?;
+        self.rule_groups.push(RuleGroup {
+            name: group_name.to_string(),
+            resource_name,
+            rule,
+        });
+
+        Ok(())
+    }
+
+    pub async fn people_sync(&self, users: Vec<User>) -> Result<(), Error> {
+        let members = self.group_members().await?;
+        let rule_emails: Vec<HashSet<String>> = self
+            .rule_groups
+            .iter()
+            .map(|group| {
+                users
+                    .iter()
+                    .filter(|user| group.rule.matches(user))
+                    .flat_map(|user| {
+                        std::iter::once(user.email.clone())
+                            .chain(self.email_aliases.get(&user.email).cloned())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        info!(user_count = users.len(), "Determining sync operations");
+        let ops = Self::people_sync_ops(users, members.clone(), &self.email_aliases);
+        info!(
+            inserts = ops.inserts.len(),
+            updates = ops.updates.len(),
+            stale = ops.deletes.len(),
+            "Determined sync operations"
+        );
+        trace!(?ops);
+
+        let (inserts, skipped): (Vec<_>, Vec<_>) = ops
+            .inserts
+            .into_iter()
+            .partition(|user| self.include_optedout || !user.is_optedout_or_blocked());
+        if !skipped.is_empty() {
+            let skipped: Vec<_> = skipped.iter().map(User::name_email).collect();
+            info!(count=%skipped.len(), ?skipped, "Skipped adding opted-out or blocked people");
+        }
+
+        info!(count=%inserts.len(), "Adding people");
+        let mut photos = self.people_batch_create(inserts).await?;
+
+        let matched = ops.updates.len();
+        let (people, mut update_photos) = self.people_batch_update_ops(ops.updates);
+        info!(count=%people.len(), unchanged=%(matched - people.len()), "Updating people");
+        self.people_batch_update(people).await?;
+
+        photos.append(&mut update_photos);
+        info!(count=%photos.len(), "Updating contact photos");
+        self.people_update_photos(photos).await?;
+
+        for (group, emails) in self.rule_groups.iter().zip(&rule_emails) {
+            self.rule_group_sync(group, &members, emails).await?;
+        }
+
+        let stale: Vec<_> = ops.deletes.iter().map(PersonWrapper::name_email).collect();
+        match self.stale_contact_action {
+            StaleContactAction::Ignore => {
+                info!(count=%stale.len(), ?stale, "Ignoring people found in Google Contacts but not a current member of the SCMA");
+            }
+            StaleContactAction::Delete => {
+                info!(count=%stale.len(), ?stale, "Deleting people found in Google Contacts but not a current member of the SCMA");
+                self.check_max_contact_deletes(stale.len())?;
+                self.people_batch_delete(&ops.deletes).await?;
+            }
+            StaleContactAction::Move(ref group_name) => {
+                info!(count=%stale.len(), ?stale, %group_name, "Moving people found in Google Contacts but not a current member of the SCMA");
+                self.people_move(&ops.deletes, group_name).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reports differences between the Google Contacts in the group and the SCMA users.
+    ///
+    /// Read-only.  Contacts are matched to users by email.
+    pub async fn people_report(&self, users: &[User]) -> Result<Vec<ContactDiscrepancy>, Error> {
+        let members = self.group_members().await?;
+        let report = Self::people_report_entries(users, &members, &self.email_aliases);
+        info!(discrepancies=%report.len(), "Determined contact discrepancies");
+
+        Ok(report)
+    }
+
+    /// Deletes the people with the emails from the group.
+    ///
+    /// Returns the number of deleted people.  Emails are matched case-insensitively.
+    pub async fn people_remove(&self, emails: &[&str]) -> Result<usize, Error> {
+        let people: Vec<PersonWrapper> = self
+            .group_members()
+            .await?
+            .into_iter()
+            .filter(|person| {
+                person
+                    .email
+                    .as_ref()
+                    .is_some_and(|email| emails.iter().any(|e| e.eq_ignore_ascii_case(email)))
+            })
+            .collect();
+
+        let names: Vec<_> = people.iter().map(PersonWrapper::name_email).collect();
+        info!(count=%people.len(), ?names, "Removing people");
+        self.people_batch_delete(&people).await?;
+
+        Ok(people.len())
+    }
+
+    /// Reports the contacts in the group that were edited in Google Contacts since they were last
+    /// synced.
+    ///
+    /// Read-only.  Contacts last synced before field hashes were recorded are skipped.
+    pub async fn drift_report(&self) -> Result<Vec<ContactDrift>, Error> {
+        let mut report: Vec<ContactDrift> = self
+            .group_members()
+            .await?
+            .into_iter()
+            .filter_map(|person| {
+                let fields = drifted_fields(&person);
+                (!fields.is_empty()).then(|| ContactDrift {
+                    email: person.email.unwrap_or_default(),
+                    name: person.name,
+                    fields,
+                })
+            })
+            .collect();
+        report.sort();
+        info!(drifted=%report.len(), "Determined contact drift");
+
+        Ok(report)
+    }
+
+    /// Returns every person in the group.
+    ///
+    /// Read-only.
+    pub async fn backup(&self) -> Result<ContactsBackup, Error> {
+        let people: Vec<api::Person> = self
+            .group_members()
+            .await?
+            .into_iter()
+            .map(|person| person.person)
+            .collect();
+        info!(group=%self.group_name, people=%people.len(), "Backed up contact group");
+
+        Ok(ContactsBackup {
+            group_name: self.group_name.clone(),
+            people,
         })
     }
 
-    pub async fn people_sync(&self, users: Vec<User>) -> Result<(), Box<dyn std::error::Error>> {
+    /// Recreates the people of the backup that are missing from the group.
+    ///
+    /// People are matched by email (case-insensitively).  People in the group are left alone.
+    /// Photos are not backed up and therefore not restored.
+    pub async fn restore(&self, backup: &ContactsBackup) -> Result<(), Error> {
+        let emails: HashSet<String> = self
+            .group_members()
+            .await?
+            .into_iter()
+            .filter_map(|person| person.email)
+            .map(|email| email.to_lowercase())
+            .collect();
+        let people: Vec<(PersonWrapper, api::Person)> = backup
+            .people
+            .iter()
+            .filter(|person| person.resource_name.is_some())
+            .map(|person| {
+                let wrapper = PersonWrapper::from(person.clone());
+                (wrapper, restored_person(person, &self.group_resource_name))
+            })
+            .filter(|(wrapper, _)| {
+                wrapper
+                    .email
+                    .as_ref()
+                    .is_some_and(|email| !emails.contains(&email.to_lowercase()))
+            })
+            .collect();
+        info!(group=%self.group_name, people=%people.len(), "Determined people to restore");
+
+        for chunk in people.chunks(PEOPLE_BATCH_CREATE_MAX_CONTACTS) {
+            info!(people=?chunk.iter().map(|(wrapper, _)| wrapper.name_email()).collect::<Vec<String>>(), "Restoring people");
+            let writes: Vec<PlannedWrite> = chunk
+                .iter()
+                .map(|(wrapper, _)| PlannedWrite::ContactCreate {
+                    name: wrapper.name.clone(),
+                    email: wrapper.email.clone().unwrap_or_default(),
+                })
+                .collect();
+            for write in &writes {
+                self.plan.record(write.clone());
+            }
+            if !self.dry_run {
+                let result = self.people_batch_restore(chunk, &writes).await;
+                fail_or_continue(&self.plan, self.continue_on_error, result)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn people_batch_restore(
+        &self,
+        people: &[(PersonWrapper, api::Person)],
+        writes: &[PlannedWrite],
+    ) -> Result<(), Error> {
+        let contacts = people
+            .iter()
+            .map(|(_, person)| api::ContactToCreate {
+                contact_person: Some(person.clone()),
+            })
+            .collect();
+        let req = api::BatchCreateContactsRequest {
+            contacts: Some(contacts),
+            read_mask: Some(FieldMask::new(&["emailAddresses"])),
+            ..Default::default()
+        };
+        let (rsp, batch_create_contacts) = self
+            .hub
+            .people()
+            .batch_create_contacts(req)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp);
+        debug!(?batch_create_contacts);
+        for (write, (wrapper, _)) in writes.iter().zip(people) {
+            self.plan
+                .audit(&self.group_name, write, None, Some(&wrapper.name_email()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn group_members(&self) -> Result<Vec<PersonWrapper>, Error> {
         info!("Getting group member resource names");
         let member_resource_names = self
             .contact_groups_get_member_resource_names(&self.group_resource_name)
@@ -110,44 +586,153 @@ impl GPpl {
         info!(member_count = members.len(), "Got group member details");
         trace!(?members);
 
-        info!(user_count = users.len(), "Determining sync operations");
-        let ops = Self::people_sync_ops(users, members);
-        info!(
-            inserts = ops.inserts.len(),
-            updates = ops.updates.len(),
-            ignores = ops.deletes.len(),
-            "Determined sync operations"
-        );
-        trace!(?ops);
+        Ok(members)
+    }
 
-        info!(count=%ops.inserts.len(), "Adding people");
-        self.people_batch_create(ops.inserts).await?;
+    fn people_report_entries(
+        users: &[User],
+        people: &[PersonWrapper],
+        email_aliases: &HashMap<String, String>,
+    ) -> Vec<ContactDiscrepancy> {
+        let users: HashMap<&str, &User> = users
+            .iter()
+            .map(|user| (resolve_alias(email_aliases, &user.email), user))
+            .collect();
 
-        info!(count=%ops.updates.len(), "Updating people");
-        let people = self.people_batch_update_ops(ops.updates);
-        self.people_batch_update(people).await?;
+        let mut report = Vec::new();
+        for person in people {
+            let Some(user) = person
+                .email
+                .as_deref()
+                .and_then(|email| users.get(resolve_alias(email_aliases, email)))
+            else {
+                continue;
+            };
+            let alias = resolve_alias(email_aliases, &user.email);
+            let mut discrepancy = |field, contacts_value: &str, scma_value: &str| {
+                report.push(ContactDiscrepancy {
+                    email: user.email.clone(),
+                    name: user.name.clone(),
+                    field,
+                    contacts_value: contacts_value.to_string(),
+                    scma_value: scma_value.to_string(),
+                })
+            };
+
+            if normalize_text(&person.name) != normalize_text(&user.name) {
+                discrepancy(ContactField::Name, &person.name, &user.name);
+            }
+
+            for email in person.person.email_addresses.iter().flatten() {
+                let value = email.value.as_deref().unwrap_or_default();
+                if value.to_lowercase() != user.email && value.to_lowercase() != alias {
+                    discrepancy(ContactField::Email, value, &user.email);
+                }
+            }
+
+            let user_phone = user.phone.as_deref().unwrap_or_default();
+            for phone_number in person.person.phone_numbers.iter().flatten() {
+                let value = phone_number.value.as_deref().unwrap_or_default();
+                if phone_digits(value) != phone_digits(user_phone) {
+                    discrepancy(ContactField::Phone, value, user_phone);
+                }
+            }
+
+            let user_address = user.address();
+            for address in person.person.addresses.iter().flatten() {
+                let value = address.formatted_value.as_deref().unwrap_or_default();
+                if normalize_text(value) != normalize_text(&user_address) {
+                    discrepancy(ContactField::Address, value, &user_address);
+                }
+            }
+        }
+        report.sort();
+
+        report
+    }
+
+    /// Returns the updated people that changed and the photos that changed.
+    fn people_batch_update_ops(
+        &self,
+        updates: Vec<(User, PersonWrapper)>,
+    ) -> (Vec<(api::Person, PersonWrapper)>, Vec<PhotoUpdate>) {
+        let mut people = Vec::new();
+        let mut photos = Vec::new();
+        for (mut user, person) in updates {
+            let photo = user.photo.take();
+            let before = person.person.clone();
+            let person = person.update(user, self.timezone);
+            if !is_changed(&before, &person.person) {
+                continue;
+            }
+
+            if let Some(photo) = photo {
+                if photo_hash(&before) != photo_hash(&person.person) {
+                    photos.push(PhotoUpdate {
+                        resource_name: Some(person.resource_name.clone()),
+                        name: person.name.clone(),
+                        email: person.email.clone().unwrap_or_default(),
+                        photo,
+                    });
+                }
+            }
+            people.push((before, person));
+        }
 
-        let ignores: Vec<_> = ops.deletes.iter().map(PersonWrapper::name_email).collect();
-        info!(count=%ignores.len(), ?ignores, "Ignoring people found in Google Contacts but not a current member of the SCMA");
+        (people, photos)
+    }
+
+    async fn people_update_photos(&self, photos: Vec<PhotoUpdate>) -> Result<(), Error> {
+        for photo in photos {
+            let write = PlannedWrite::ContactPhotoUpdate {
+                name: photo.name.clone(),
+                email: photo.email.clone(),
+            };
+            self.plan.record(write.clone());
+            // The resource name of a new contact is not known during a dry run
+            let Some(ref resource_name) = photo.resource_name else {
+                continue;
+            };
+            if !self.dry_run {
+                info!(person=%format!("{} <{}>", photo.name, photo.email), "Updating contact photo");
+                let result = self
+                    .people_update_photo(resource_name, photo.photo)
+                    .await
+                    .and_then(|()| self.plan.audit(&self.group_name, &write, None, None));
+                fail_or_continue(&self.plan, self.continue_on_error, result)?;
+            }
+        }
 
         Ok(())
     }
 
-    fn people_batch_update_ops(&self, updates: Vec<(User, PersonWrapper)>) -> Vec<PersonWrapper> {
-        updates
-            .into_iter()
-            .map(|(user, person)| person.update(user))
-            .collect()
+    async fn people_update_photo(&self, resource_name: &str, photo: Vec<u8>) -> Result<(), Error> {
+        let req = api::UpdateContactPhotoRequest {
+            photo_bytes: Some(photo),
+            ..Default::default()
+        };
+        let (rsp, _) = self
+            .hub
+            .people()
+            .update_contact_photo(req, resource_name)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "people.updateContactPhoto");
+
+        Ok(())
     }
 
+    /// Updates the people.  Each person is paired with the person before the update.
     async fn people_batch_update(
         &self,
-        people: Vec<PersonWrapper>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        people: Vec<(api::Person, PersonWrapper)>,
+    ) -> Result<(), Error> {
         for people_chunk in people.chunks(PEOPLE_BATCH_UPDATE_MAX_CONTACTS) {
             let contacts = people_chunk
                 .iter()
-                .map(|person| (person.resource_name.clone(), person.person.clone()))
+                .map(|(_, person)| (person.resource_name.clone(), person.person.clone()))
                 .collect();
             let req = api::BatchUpdateContactsRequest {
                 contacts: Some(contacts),
@@ -158,40 +743,286 @@ impl GPpl {
 
             info!(
             count=people_chunk.len(),
-            people=?people_chunk.iter().map(PersonWrapper::name_email).collect::<Vec<String>>(),
+            people=?people_chunk.iter().map(|(_, person)| person.name_email()).collect::<Vec<String>>(),
             "Updating contacts"
             );
+            let writes: Vec<PlannedWrite> = people_chunk
+                .iter()
+                .map(|(_, person)| PlannedWrite::ContactUpdate {
+                    name: person.name.clone(),
+                    email: person.email.clone().unwrap_or_default(),
+                })
+                .collect();
+            for write in &writes {
+                self.plan.record(write.clone());
+            }
+            if !self.dry_run {
+                let (rsp, update_response) = self
+                    .hub
+                    .people()
+                    .batch_update_contacts(req)
+                    .add_scope(SCOPE)
+                    .delegate(&mut RetryDelegate::default())
+                    .doit()
+                    .await?;
+                trace!(?rsp, "people.batchUpdateContacts");
+                debug!(?update_response, "people.batchUpdateContacts");
+                for (write, (before, person)) in writes.iter().zip(people_chunk) {
+                    self.plan.audit(
+                        &self.group_name,
+                        write,
+                        Some(&person_changes(before, &person.person)),
+                        Some(&person_changes(&person.person, before)),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn people_batch_delete(&self, people: &[PersonWrapper]) -> Result<(), Error> {
+        for people_chunk in people.chunks(PEOPLE_BATCH_DELETE_MAX_CONTACTS) {
+            let writes: Vec<PlannedWrite> = people_chunk
+                .iter()
+                .map(|person| PlannedWrite::ContactDelete {
+                    name: person.name.clone(),
+                    email: person.email.clone().unwrap_or_default(),
+                })
+                .collect();
+            for write in &writes {
+                self.plan.record(write.clone());
+            }
+            if !self.dry_run {
+                let req = api::BatchDeleteContactsRequest {
+                    resource_names: Some(
+                        people_chunk
+                            .iter()
+                            .map(|person| person.resource_name.clone())
+                            .collect(),
+                    ),
+                };
+                let (rsp, _) = self
+                    .hub
+                    .people()
+                    .batch_delete_contacts(req)
+                    .add_scope(SCOPE)
+                    .delegate(&mut RetryDelegate::default())
+                    .doit()
+                    .await?;
+                trace!(?rsp, "people.batchDeleteContacts");
+                for (write, person) in writes.iter().zip(people_chunk) {
+                    self.plan
+                        .audit(&self.group_name, write, Some(&person.name_email()), None)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the people with the emails to the rule contact group and removes the others.
+    ///
+    /// Only the people of the group are considered.  People created by this sync were added on
+    /// creation.
+    async fn rule_group_sync(
+        &self,
+        group: &RuleGroup,
+        people: &[PersonWrapper],
+        emails: &HashSet<String>,
+    ) -> Result<(), Error> {
+        let group_members: HashSet<String> = self
+            .contact_groups_get_member_resource_names(&group.resource_name)
+            .await?
+            .into_iter()
+            .collect();
+        let (adds, removes) = rule_group_ops(people, &group_members, emails);
+        info!(group=%group.name, adds=%adds.len(), removes=%removes.len(), "Syncing contact group");
+
+        let add_writes: Vec<PlannedWrite> = adds
+            .iter()
+            .map(|person| PlannedWrite::ContactGroupAdd {
+                name: person.name.clone(),
+                email: person.email.clone().unwrap_or_default(),
+                group: group.name.clone(),
+            })
+            .collect();
+        let remove_writes: Vec<PlannedWrite> = removes
+            .iter()
+            .map(|person| PlannedWrite::ContactGroupRemove {
+                name: person.name.clone(),
+                email: person.email.clone().unwrap_or_default(),
+                group: group.name.clone(),
+            })
+            .collect();
+        for write in add_writes.iter().chain(&remove_writes) {
+            self.plan.record(write.clone());
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let add_writes = add_writes.chunks(CONTACT_GROUPS_MODIFY_MAX_MEMBERS);
+        for (adds_chunk, writes) in adds
+            .chunks(CONTACT_GROUPS_MODIFY_MAX_MEMBERS)
+            .zip(add_writes)
+        {
+            self.contact_groups_members_modify(
+                &group.resource_name,
+                api::ModifyContactGroupMembersRequest {
+                    resource_names_to_add: Some(
+                        adds_chunk
+                            .iter()
+                            .map(|person| person.resource_name.clone())
+                            .collect(),
+                    ),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            for write in writes {
+                self.plan.audit(&group.name, write, None, None)?;
+            }
+        }
+        let remove_writes = remove_writes.chunks(CONTACT_GROUPS_MODIFY_MAX_MEMBERS);
+        for (removes_chunk, writes) in removes
+            .chunks(CONTACT_GROUPS_MODIFY_MAX_MEMBERS)
+            .zip(remove_writes)
+        {
+            self.contact_groups_members_modify(
+                &group.resource_name,
+                api::ModifyContactGroupMembersRequest {
+                    resource_names_to_remove: Some(
+                        removes_chunk
+                            .iter()
+                            .map(|person| person.resource_name.clone())
+                            .collect(),
+                    ),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            for write in writes {
+                self.plan.audit(&group.name, write, None, None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_max_contact_deletes(&self, count: usize) -> Result<(), Error> {
+        match self.max_contact_deletes {
+            Some(max) if count > max => {
+                if self.dry_run {
+                    warn!(%count, %max, "Stale contacts exceed the maximum number of deletes");
+                    Ok(())
+                } else {
+                    Err(Error::google(format!(
+                        "refusing to delete {count} stale contacts, more than the maximum of {max}"
+                    )))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Moves the people from the group to the named contact group.
+    async fn people_move(&self, people: &[PersonWrapper], group_name: &str) -> Result<(), Error> {
+        if people.is_empty() {
+            return Ok(());
+        }
+
+        let to_group_resource_name =
+            Self::contact_groups_get_or_create_by_name(&self.hub, group_name, self.dry_run).await?;
+
+        for people_chunk in people.chunks(CONTACT_GROUPS_MODIFY_MAX_MEMBERS) {
+            let writes: Vec<PlannedWrite> = people_chunk
+                .iter()
+                .map(|person| PlannedWrite::ContactMove {
+                    name: person.name.clone(),
+                    email: person.email.clone().unwrap_or_default(),
+                    group: group_name.to_string(),
+                })
+                .collect();
+            for write in &writes {
+                self.plan.record(write.clone());
+            }
             if !self.dry_run {
-                let (rsp, update_response) = self
-                    .hub
-                    .people()
-                    .batch_update_contacts(req)
-                    .add_scope(SCOPE)
-                    .doit()
-                    .await?;
-                trace!(?rsp, "people.batchUpdateContacts");
-                debug!(?update_response, "people.batchUpdateContacts");
+                let resource_names: Vec<String> = people_chunk
+                    .iter()
+                    .map(|person| person.resource_name.clone())
+                    .collect();
+                // Add before remove so that a failure does not leave the contacts in neither group
+                self.contact_groups_members_modify(
+                    &to_group_resource_name,
+                    api::ModifyContactGroupMembersRequest {
+                        resource_names_to_add: Some(resource_names.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                self.contact_groups_members_modify(
+                    &self.group_resource_name,
+                    api::ModifyContactGroupMembersRequest {
+                        resource_names_to_remove: Some(resource_names),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                for write in &writes {
+                    self.plan.audit(
+                        &self.group_name,
+                        write,
+                        Some(&self.group_name),
+                        Some(group_name),
+                    )?;
+                }
             }
         }
 
         Ok(())
     }
 
-    async fn create_hub(
-        gauth: GAuth,
-    ) -> Result<PeopleService<Connector>, Box<dyn std::error::Error>> {
+    async fn contact_groups_members_modify(
+        &self,
+        group_resource_name: &str,
+        req: api::ModifyContactGroupMembersRequest,
+    ) -> Result<(), Error> {
+        let (rsp, modify_response) = self
+            .hub
+            .contact_groups()
+            .members_modify(req, group_resource_name)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "contact_groups.members.modify");
+        debug!(?modify_response, "contact_groups.members.modify");
+
+        Ok(())
+    }
+
+    async fn create_hub(gauth: GAuth) -> Result<PeopleService<Connector>, Error> {
         let scopes = [SCOPE];
-        let token = gauth.auth().token(&scopes).await?;
+        let token = gauth.auth().token(&scopes).await.map_err(Error::auth)?;
         info!(expiration_time=?token.expiration_time(), "Got token");
 
-        let https = hyper_rustls::HttpsConnectorBuilder::new()
-            .with_native_roots()?
-            .https_only()
-            .enable_http1()
-            .build();
+        let builder = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .map_err(Error::google)?;
+        let builder = match gauth.people_root_url() {
+            Some(_) => builder.https_or_http(),
+            None => builder.https_only(),
+        };
+        let https = builder.enable_http1().build();
         let client = Client::builder(TokioExecutor::new()).build(https);
 
-        let hub = PeopleService::new(client, gauth.auth());
+        let mut hub = PeopleService::new(client, gauth.auth());
+        if let Some(root_url) = gauth.people_root_url() {
+            info!(%root_url, "Using Google People API root URL");
+            hub.root_url(root_url.to_string());
+            hub.base_url(root_url.to_string());
+        }
 
         Ok(hub)
     }
@@ -203,13 +1034,14 @@ impl GPpl {
         hub: &PeopleService<Connector>,
         group_name: &str,
         dry_run: bool,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    ) -> Result<String, Error> {
         info!(%group_name, "Finding group");
         let (rsp, list) = hub
             .contact_groups()
             .list()
             .group_fields(FieldMask::new(GROUP_FIELDS))
             .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
             .doit()
             .await?;
         trace!(?rsp, "contact_groups.list");
@@ -230,7 +1062,7 @@ impl GPpl {
                 info!(%group_name, "Contact group not found, creating new contact group");
 
                 let group_resource_name = if dry_run {
-                    return Err("Cannot create contact group during dry run".into());
+                    return Err(Error::google("Cannot create contact group during dry run"));
                 } else {
                     let req = api::CreateContactGroupRequest {
                         contact_group: Some(api::ContactGroup {
@@ -243,6 +1075,7 @@ impl GPpl {
                         .contact_groups()
                         .create(req)
                         .add_scope(SCOPE)
+                        .delegate(&mut RetryDelegate::default())
                         .doit()
                         .await?;
                     trace!(?rsp, "contact_groups.create");
@@ -261,23 +1094,85 @@ impl GPpl {
     }
 
     // Returns all Person.resource_names belonging to the given ContactGroup.resource_name
+    //
+    // The contactGroups.get API method returns at most max_members members and does not paginate.
+    // If the group has more members than were returned, the members are found by paginating the
+    // people.connections.list API method instead.
     async fn contact_groups_get_member_resource_names(
         &self,
         group_resource_name: &str,
-    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<String>, Error> {
         let (rsp, group) = self
             .hub
             .contact_groups()
             .get(group_resource_name)
             .max_members(CONTACT_GROUPS_GET_MAX_MEMBERS)
-            .group_fields(FieldMask::new(GROUP_FIELDS))
+            .group_fields(FieldMask::new(GROUP_FIELDS_MEMBERS))
             .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
             .doit()
             .await?;
         trace!(?rsp);
         debug!(?group);
 
         let member_resource_names = group.member_resource_names.unwrap_or_default();
+        let member_count = group
+            .member_count
+            .and_then(|count| usize::try_from(count).ok())
+            .unwrap_or_default();
+        if member_resource_names.len() >= member_count {
+            return Ok(member_resource_names);
+        }
+
+        info!(
+            %member_count,
+            returned = member_resource_names.len(),
+            "Contact group has more members than returned, listing connections"
+        );
+        self.connections_list_group_members(group_resource_name)
+            .await
+    }
+
+    // Returns all Person.resource_names belonging to the given ContactGroup.resource_name by
+    // paginating the people.connections.list API method
+    async fn connections_list_group_members(
+        &self,
+        group_resource_name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let mut member_resource_names = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut call = self
+                .hub
+                .people()
+                .connections_list("people/me")
+                .person_fields(FieldMask::new(&["memberships"]))
+                .page_size(CONNECTIONS_LIST_PAGE_SIZE);
+            if let Some(ref page_token) = page_token {
+                call = call.page_token(page_token);
+            }
+            let (rsp, list) = call
+                .add_scope(SCOPE)
+                .delegate(&mut RetryDelegate::default())
+                .doit()
+                .await?;
+            trace!(?rsp, "people.connections.list");
+
+            member_resource_names.extend(
+                list.connections
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|person| is_group_member(person, group_resource_name))
+                    .filter_map(|person| person.resource_name),
+            );
+            info!(count=%member_resource_names.len(), "Listed group members");
+
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => break,
+            }
+        }
 
         Ok(member_resource_names)
     }
@@ -286,7 +1181,7 @@ impl GPpl {
     async fn people_batch_get(
         &self,
         resource_names: &[String],
-    ) -> Result<Vec<PersonWrapper>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<PersonWrapper>, Error> {
         let mut people = Vec::new();
         let mut lower = 0;
         let mut upper = PEOPLE_BATCH_GET_MAX_CONTACTS.min(resource_names.len());
@@ -318,7 +1213,7 @@ impl GPpl {
     async fn people_batch_get_page(
         &self,
         resource_names: &[String],
-    ) -> Result<Vec<PersonWrapper>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<PersonWrapper>, Error> {
         let mut builder = self
             .hub
             .people()
@@ -329,7 +1224,11 @@ impl GPpl {
             builder = builder.add_resource_names(resource_name);
         }
 
-        let (rsp, get_people_response) = builder.add_scope(SCOPE).doit().await?;
+        let (rsp, get_people_response) = builder
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
         trace!(?rsp);
         debug!(?get_people_response);
 
@@ -344,52 +1243,101 @@ impl GPpl {
         Ok(people)
     }
 
-    async fn people_batch_create(
-        &self,
-        users: Vec<User>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    /// Returns the photos of the created people.
+    async fn people_batch_create(&self, users: Vec<User>) -> Result<Vec<PhotoUpdate>, Error> {
+        let mut photos = Vec::new();
         for users_chunk in users.chunks(PEOPLE_BATCH_CREATE_MAX_CONTACTS) {
             info!(people=?users_chunk.iter().map(User::name_email).collect::<Vec<String>>(), "Adding people");
             let contacts = users_chunk
                 .iter()
-                .map(|user| create_api_person(user, &self.group_resource_name))
+                .map(|user| {
+                    let group_resource_names: Vec<&str> =
+                        std::iter::once(self.group_resource_name.as_str())
+                            .chain(
+                                self.rule_groups
+                                    .iter()
+                                    .filter(|group| group.rule.matches(user))
+                                    .map(|group| group.resource_name.as_str()),
+                            )
+                            .collect();
+                    create_api_person(user, &group_resource_names, self.timezone)
+                })
                 .map(|person| api::ContactToCreate {
                     contact_person: Some(person),
                 })
                 .collect();
             let req = api::BatchCreateContactsRequest {
                 contacts: Some(contacts),
+                read_mask: Some(FieldMask::new(&["emailAddresses"])),
                 ..Default::default()
             };
+            let writes: Vec<PlannedWrite> = users_chunk
+                .iter()
+                .map(|user| PlannedWrite::ContactCreate {
+                    name: user.name.clone(),
+                    email: user.email.clone(),
+                })
+                .collect();
+            for write in &writes {
+                self.plan.record(write.clone());
+            }
+            let mut resource_names = HashMap::new();
             if !self.dry_run {
                 let (rsp, batch_create_contacts) = self
                     .hub
                     .people()
                     .batch_create_contacts(req)
                     .add_scope(SCOPE)
+                    .delegate(&mut RetryDelegate::default())
                     .doit()
                     .await?;
                 trace!(?rsp);
                 debug!(?batch_create_contacts);
+                for (write, user) in writes.iter().zip(users_chunk) {
+                    self.plan
+                        .audit(&self.group_name, write, None, Some(&user.name_email()))?;
+                }
+
+                resource_names = batch_create_contacts
+                    .created_people
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|person_response| person_response.person)
+                    .map(PersonWrapper::from)
+                    .filter_map(|person| Some((person.email?, person.resource_name)))
+                    .collect();
             }
+
+            photos.extend(users_chunk.iter().filter_map(|user| {
+                Some(PhotoUpdate {
+                    resource_name: resource_names.get(&user.email).cloned(),
+                    name: user.name.clone(),
+                    email: user.email.clone(),
+                    photo: user.photo.clone()?,
+                })
+            }));
         }
 
-        Ok(())
+        Ok(photos)
     }
 
-    /// People w/o an email are ignored.
+    /// People w/o an email are ignored.  Emails are compared after resolving email aliases.
     ///
     /// This effectively performs a diff from People to Users.
-    fn people_sync_ops(users: Vec<User>, people: Vec<PersonWrapper>) -> PersonSyncOpsResult {
+    fn people_sync_ops(
+        users: Vec<User>,
+        people: Vec<PersonWrapper>,
+        email_aliases: &HashMap<String, String>,
+    ) -> PersonSyncOpsResult {
         let mut users: HashMap<String, User> = users
             .into_iter()
-            .map(|user| (user.email.clone(), user))
+            .map(|user| (resolve_alias(email_aliases, &user.email).to_string(), user))
             .collect();
         let mut people: HashMap<String, PersonWrapper> = people
             .into_iter()
             .filter_map(|person| {
                 if let Some(ref email) = person.email {
-                    Some((email.clone(), person))
+                    Some((resolve_alias(email_aliases, email).to_string(), person))
                 } else {
                     None
                 }
@@ -424,6 +1372,13 @@ impl GPpl {
     }
 }
 
+#[async_trait]
+impl UserSink for GPpl {
+    async fn write_users(&self, users: Vec<User>) -> Result<(), Error> {
+        self.people_sync(users).await
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 struct PersonWrapper {
     resource_name: String,
@@ -457,6 +1412,7 @@ impl PersonWrapper {
     ///
     /// * Phone number
     /// * Address
+    /// * Birthday (if the date of birth is set)
     /// * Member status
     /// * Trip leader status
     /// * Position
@@ -476,7 +1432,7 @@ impl PersonWrapper {
     ///
     ///   The person-user pair was matched via their email and therefore the email is already as
     ///   desired.
-    fn update(mut self, user: User) -> Self {
+    fn update(mut self, user: User, timezone: Tz) -> Self {
         let new_phone_number = create_api_phone_number(&user);
         self.person.phone_numbers =
             person_phone_numbers_update_or_insert(new_phone_number, self.person.phone_numbers);
@@ -485,8 +1441,23 @@ impl PersonWrapper {
         self.person.addresses =
             person_addresses_update_or_insert(new_address, self.person.addresses);
 
+        // Google Contacts allows a single birthday
+        if let Some(birthday) = create_api_birthday(&user) {
+            self.person.birthdays = Some(vec![birthday]);
+        }
+
         self.person.user_defined =
-            person_user_defined_update_or_insert(&user, self.person.user_defined);
+            person_user_defined_update_or_insert(&user, self.person.user_defined, timezone);
+
+        // The name is not synced so its hash is kept from the creation of the contact
+        let name_hash = parse_field_hashes(&self.person)
+            .and_then(|hashes| {
+                hashes
+                    .get(ContactField::Name.key())
+                    .map(|hash| hash.to_string())
+            })
+            .unwrap_or_else(|| short_hash(&self.name));
+        insert_field_hashes(&mut self.person, &name_hash);
 
         self
     }
@@ -528,6 +1499,63 @@ impl From<api::Person> for PersonWrapper {
     }
 }
 
+/// Returns true if the person is a member of the contact group.
+fn is_group_member(person: &api::Person, group_resource_name: &str) -> bool {
+    person
+        .memberships
+        .iter()
+        .flatten()
+        .filter_map(|membership| membership.contact_group_membership.as_ref())
+        .any(|membership| {
+            membership.contact_group_resource_name.as_deref() == Some(group_resource_name)
+        })
+}
+
+/// Returns the Google email alias of the email if any, otherwise the email.
+fn resolve_alias<'a>(email_aliases: &'a HashMap<String, String>, email: &'a str) -> &'a str {
+    email_aliases
+        .get(email)
+        .map(String::as_str)
+        .unwrap_or(email)
+}
+
+/// Returns the people to add to and remove from a rule contact group.
+///
+/// The people with the emails should be members.  The others should not.
+fn rule_group_ops<'a>(
+    people: &'a [PersonWrapper],
+    group_members: &HashSet<String>,
+    emails: &HashSet<String>,
+) -> (Vec<&'a PersonWrapper>, Vec<&'a PersonWrapper>) {
+    people
+        .iter()
+        .filter(|person| {
+            let should_be_member = person
+                .email
+                .as_ref()
+                .is_some_and(|email| emails.contains(email));
+            should_be_member != group_members.contains(&person.resource_name)
+        })
+        .partition(|person| !group_members.contains(&person.resource_name))
+}
+
+/// Normalizes case and whitespace for comparison.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the 10 digit US phone number for comparison.
+fn phone_digits(phone: &str) -> String {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    match digits.strip_prefix('1') {
+        Some(rest) if rest.len() == 10 => rest.to_string(),
+        _ => digits,
+    }
+}
+
 fn create_api_address(user: &User) -> api::Address {
     api::Address {
         type_: Some("SCMA".to_string()),
@@ -536,6 +1564,18 @@ fn create_api_address(user: &User) -> api::Address {
     }
 }
 
+fn create_api_birthday(user: &User) -> Option<api::Birthday> {
+    let birthday = user.birthday()?;
+    Some(api::Birthday {
+        date: Some(api::Date {
+            year: Some(birthday.year()),
+            month: Some(birthday.month() as i32),
+            day: Some(birthday.day() as i32),
+        }),
+        ..Default::default()
+    })
+}
+
 fn create_api_phone_number(user: &User) -> api::PhoneNumber {
     api::PhoneNumber {
         type_: Some("SCMA".to_string()),
@@ -544,7 +1584,7 @@ fn create_api_phone_number(user: &User) -> api::PhoneNumber {
     }
 }
 
-fn insert_user_defined(user_defined: &mut IndexMap<String, String>, user: &User) {
+fn insert_user_defined(user_defined: &mut IndexMap<String, String>, user: &User, timezone: Tz) {
     user_defined.insert(
         "SCMA Member Status".to_string(),
         user.member_status.to_string(),
@@ -577,7 +1617,120 @@ fn insert_user_defined(user_defined: &mut IndexMap<String, String>, user: &User)
         "SCMA Last Visit Date".to_string(),
         user.lastvisit_date.clone(),
     );
-    user_defined.insert("SCMA Last Updated".to_string(), user.timestamp());
+    user_defined.insert(LAST_UPDATED_KEY.to_string(), user.timestamp(timezone));
+    // Kept if the photo was not fetched so that the photo is not uploaded again
+    if let Some(ref photo) = user.photo {
+        user_defined.insert(
+            PHOTO_HASH_KEY.to_string(),
+            format!("{:x}", Sha256::digest(photo)),
+        );
+    }
+}
+
+/// Returns the hash of the photo last uploaded for the person if any.
+fn photo_hash(person: &api::Person) -> Option<&String> {
+    person
+        .user_defined
+        .iter()
+        .flatten()
+        .find(|user_defined| user_defined.key.as_deref() == Some(PHOTO_HASH_KEY))
+        .and_then(|user_defined| user_defined.value.as_ref())
+}
+
+fn scma_phone(person: &api::Person) -> Option<&String> {
+    person
+        .phone_numbers
+        .iter()
+        .flatten()
+        .find(|phone_number| phone_number.type_.as_deref() == Some("SCMA"))
+        .and_then(|phone_number| phone_number.value.as_ref())
+}
+
+fn scma_address(person: &api::Person) -> Option<&String> {
+    person
+        .addresses
+        .iter()
+        .flatten()
+        .find(|address| address.type_.as_deref() == Some("SCMA"))
+        .and_then(|address| address.formatted_value.as_ref())
+}
+
+fn birthday(person: &api::Person) -> Option<(Option<i32>, Option<i32>, Option<i32>)> {
+    person
+        .birthdays
+        .iter()
+        .flatten()
+        .find_map(|birthday| birthday.date.as_ref())
+        .map(|date| (date.year, date.month, date.day))
+}
+
+fn user_defined(person: &api::Person) -> HashMap<Option<&String>, Option<&String>> {
+    person
+        .user_defined
+        .iter()
+        .flatten()
+        .filter(|user_defined| {
+            !matches!(
+                user_defined.key.as_deref(),
+                Some(LAST_UPDATED_KEY | FIELD_HASHES_KEY)
+            )
+        })
+        .map(|user_defined| (user_defined.key.as_ref(), user_defined.value.as_ref()))
+        .collect()
+}
+
+/// Returns true if the update changed any of the values written by `PersonWrapper::update`.
+///
+/// The "SCMA Last Updated" user defined field is ignored because it changes on every run.  The
+/// "SCMA Field Hashes" user defined field is ignored because it only changes with the other
+/// fields (or on the first update after the field hashes were introduced).
+fn is_changed(before: &api::Person, after: &api::Person) -> bool {
+    scma_phone(before) != scma_phone(after)
+        || scma_address(before) != scma_address(after)
+        || birthday(before) != birthday(after)
+        || user_defined(before) != user_defined(after)
+}
+
+/// Summarizes the SCMA fields of the person that differ from the other person for the audit log.
+///
+/// E.g. "phone: +15555550100; SCMA Member Status: RM".  Missing fields are empty.
+fn person_changes(person: &api::Person, other: &api::Person) -> String {
+    let text = |value: Option<&String>| value.cloned().unwrap_or_default();
+    let mut changes = Vec::new();
+    if scma_phone(person) != scma_phone(other) {
+        changes.push(format!("phone: {}", text(scma_phone(person))));
+    }
+    if scma_address(person) != scma_address(other) {
+        changes.push(format!("address: {}", text(scma_address(person))));
+    }
+    if birthday(person) != birthday(other) {
+        let date = birthday(person)
+            .map(|(year, month, day)| {
+                let part =
+                    |part: Option<i32>| part.map(|part| part.to_string()).unwrap_or_default();
+                format!("{}-{}-{}", part(year), part(month), part(day))
+            })
+            .unwrap_or_default();
+        changes.push(format!("birthday: {date}"));
+    }
+    let fields = user_defined(person);
+    let other_fields = user_defined(other);
+    let mut keys: Vec<&String> = fields
+        .keys()
+        .chain(other_fields.keys())
+        .flatten()
+        .copied()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        let value = fields.get(&Some(key)).copied().flatten();
+        if value != other_fields.get(&Some(key)).copied().flatten() {
+            changes.push(format!("{key}: {}", text(value)));
+        }
+    }
+
+    changes.join("; ")
 }
 
 fn insert_or_remove_user_defined(
@@ -591,7 +1744,7 @@ fn insert_or_remove_user_defined(
     };
 }
 
-fn create_api_person(user: &User, group_resource_name: &str) -> api::Person {
+fn create_api_person(user: &User, group_resource_names: &[&str], timezone: Tz) -> api::Person {
     let name = api::Name {
         unstructured_name: Some(user.name.clone()),
         ..Default::default()
@@ -603,25 +1756,172 @@ fn create_api_person(user: &User, group_resource_name: &str) -> api::Person {
     };
     let address = create_api_address(user);
     let phone_number = create_api_phone_number(user);
-    let membership = api::Membership {
-        contact_group_membership: Some(api::ContactGroupMembership {
-            contact_group_resource_name: Some(group_resource_name.to_string()),
+    let memberships = group_resource_names
+        .iter()
+        .map(|group_resource_name| api::Membership {
+            contact_group_membership: Some(api::ContactGroupMembership {
+                contact_group_resource_name: Some(group_resource_name.to_string()),
+                ..Default::default()
+            }),
             ..Default::default()
-        }),
-        ..Default::default()
-    };
+        })
+        .collect();
 
-    api::Person {
+    let mut person = api::Person {
         names: Some(vec![name]),
         email_addresses: Some(vec![email_address]),
         addresses: Some(vec![address]),
         phone_numbers: Some(vec![phone_number]),
-        memberships: Some(vec![membership]),
-        user_defined: person_user_defined_update_or_insert(user, None),
+        birthdays: create_api_birthday(user).map(|birthday| vec![birthday]),
+        memberships: Some(memberships),
+        user_defined: person_user_defined_update_or_insert(user, None, timezone),
+        ..Default::default()
+    };
+    insert_field_hashes(&mut person, &short_hash(&user.name));
+
+    person
+}
+
+/// Returns the backed up person without the fields set by Google Contacts as a member of the
+/// group.
+fn restored_person(person: &api::Person, group_resource_name: &str) -> api::Person {
+    let names = person.names.as_ref().map(|names| {
+        names
+            .iter()
+            .map(|name| api::Name {
+                unstructured_name: name
+                    .unstructured_name
+                    .clone()
+                    .or_else(|| name.display_name.clone()),
+                ..Default::default()
+            })
+            .collect()
+    });
+
+    api::Person {
+        names,
+        email_addresses: person.email_addresses.clone().map(|values| {
+            values
+                .into_iter()
+                .map(|value| api::EmailAddress {
+                    metadata: None,
+                    ..value
+                })
+                .collect()
+        }),
+        phone_numbers: person.phone_numbers.clone().map(|values| {
+            values
+                .into_iter()
+                .map(|value| api::PhoneNumber {
+                    metadata: None,
+                    ..value
+                })
+                .collect()
+        }),
+        addresses: person.addresses.clone().map(|values| {
+            values
+                .into_iter()
+                .map(|value| api::Address {
+                    metadata: None,
+                    ..value
+                })
+                .collect()
+        }),
+        birthdays: person.birthdays.clone().map(|values| {
+            values
+                .into_iter()
+                .map(|value| api::Birthday {
+                    metadata: None,
+                    ..value
+                })
+                .collect()
+        }),
+        user_defined: person.user_defined.clone().map(|values| {
+            values
+                .into_iter()
+                .map(|value| api::UserDefined {
+                    metadata: None,
+                    ..value
+                })
+                .collect()
+        }),
+        memberships: Some(vec![api::Membership {
+            contact_group_membership: Some(api::ContactGroupMembership {
+                contact_group_resource_name: Some(group_resource_name.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }]),
         ..Default::default()
     }
 }
 
+/// Records the short hashes of the synced fields in the person (e.g. "name=1a2b3c4d;phone=...").
+fn insert_field_hashes(person: &mut api::Person, name_hash: &str) {
+    let hashes = DRIFT_FIELDS
+        .iter()
+        .map(|field| {
+            let hash = match field {
+                ContactField::Name => name_hash.to_string(),
+                _ => short_hash(&field.get(person, "")),
+            };
+            format!("{}={hash}", field.key())
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let user_defined = person.user_defined.get_or_insert_with(Vec::new);
+    match user_defined
+        .iter_mut()
+        .find(|user_defined| user_defined.key.as_deref() == Some(FIELD_HASHES_KEY))
+    {
+        Some(user_defined) => user_defined.value = Some(hashes),
+        None => user_defined.push(api::UserDefined {
+            key: Some(FIELD_HASHES_KEY.to_string()),
+            value: Some(hashes),
+            ..Default::default()
+        }),
+    }
+}
+
+fn parse_field_hashes(person: &api::Person) -> Option<HashMap<&str, &str>> {
+    let hashes = person
+        .user_defined
+        .iter()
+        .flatten()
+        .find(|user_defined| user_defined.key.as_deref() == Some(FIELD_HASHES_KEY))
+        .and_then(|user_defined| user_defined.value.as_ref())?;
+
+    Some(
+        hashes
+            .split(';')
+            .filter_map(|entry| entry.split_once('='))
+            .collect(),
+    )
+}
+
+/// Returns the synced fields of the person that were edited since the person was last written.
+///
+/// Empty if the person has no field hashes (i.e. it was last written by an older version).
+fn drifted_fields(person: &PersonWrapper) -> Vec<ContactField> {
+    let Some(hashes) = parse_field_hashes(&person.person) else {
+        return Vec::new();
+    };
+
+    DRIFT_FIELDS
+        .into_iter()
+        .filter(|field| {
+            hashes
+                .get(field.key())
+                .is_some_and(|hash| *hash != short_hash(&field.get(&person.person, &person.name)))
+        })
+        .collect()
+}
+
+fn short_hash(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value))[..8].to_string()
+}
+
 fn person_phone_numbers_update_or_insert(
     new_phone_number: api::PhoneNumber,
     phone_numbers: Option<Vec<api::PhoneNumber>>,
@@ -675,6 +1975,7 @@ fn person_addresses_update_or_insert(
 fn person_user_defined_update_or_insert(
     user: &User,
     user_defined: Option<Vec<api::UserDefined>>,
+    timezone: Tz,
 ) -> Option<Vec<api::UserDefined>> {
     let user_defined = match user_defined {
         Some(user_defined) => user_defined
@@ -689,7 +1990,7 @@ fn person_user_defined_update_or_insert(
         None => IndexMap::new(),
     }
     .tap_mut(|user_defined| {
-        insert_user_defined(user_defined, user);
+        insert_user_defined(user_defined, user, timezone);
     })
     .into_iter()
     .map(|(k, v)| api::UserDefined {
@@ -705,6 +2006,7 @@ fn person_user_defined_update_or_insert(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::model::MemberStatus;
 
     impl PartialEq for PersonSyncOpsResult {
         fn eq(&self, other: &Self) -> bool {
@@ -748,7 +2050,7 @@ mod test {
             },
         ];
 
-        let actual = GPpl::people_sync_ops(users, people);
+        let actual = GPpl::people_sync_ops(users, people, &HashMap::new());
         let expected = PersonSyncOpsResult {
             inserts: vec![User {
                 name: "User 0".to_string(),
@@ -775,4 +2077,308 @@ mod test {
         };
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn people_sync_ops_email_aliases() {
+        let user = || User {
+            name: "User 0".to_string(),
+            email: "user0-alias@example.com".to_string(),
+            ..Default::default()
+        };
+        let person = || PersonWrapper {
+            name: "User 0".to_string(),
+            email: Some("user0@example.com".to_string()),
+            ..Default::default()
+        };
+        let email_aliases = HashMap::from([(
+            "user0-alias@example.com".to_string(),
+            "user0@example.com".to_string(),
+        )]);
+
+        let actual = GPpl::people_sync_ops(vec![user()], vec![person()], &email_aliases);
+        let expected = PersonSyncOpsResult {
+            inserts: vec![],
+            updates: vec![(user(), person())],
+            deletes: vec![],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn is_changed() {
+        let user = |phone: &str| User {
+            name: "User 1".to_string(),
+            email: "user1@example.com".to_string(),
+            phone: Some(phone.to_string()),
+            address: "1 Main St".to_string(),
+            timestamp: Some(chrono::Utc::now()),
+            ..Default::default()
+        };
+        let person = PersonWrapper {
+            name: "User 1".to_string(),
+            email: Some("user1@example.com".to_string()),
+            ..Default::default()
+        };
+
+        // A new contact changes
+        let updated = person.update(user("+15555550100"), DEFAULT_TIMEZONE);
+        assert!(super::is_changed(&api::Person::default(), &updated.person));
+
+        // Merging the same user again does not, even with a new timestamp
+        let before = updated.person.clone();
+        let updated = updated.update(user("+15555550100"), DEFAULT_TIMEZONE);
+        assert!(!super::is_changed(&before, &updated.person));
+
+        // A changed phone number does
+        let before = updated.person.clone();
+        let updated = updated.update(user("+15555550199"), DEFAULT_TIMEZONE);
+        assert!(super::is_changed(&before, &updated.person));
+    }
+
+    #[test]
+    fn drifted_fields() {
+        let user = User {
+            name: "User 1".to_string(),
+            email: "user1@example.com".to_string(),
+            phone: Some("+15555550100".to_string()),
+            address: "1 Main St".to_string(),
+            ..Default::default()
+        };
+        let mut person = PersonWrapper {
+            name: "User 1".to_string(),
+            email: Some("user1@example.com".to_string()),
+            person: create_api_person(&user, &[], DEFAULT_TIMEZONE),
+            ..Default::default()
+        };
+        assert!(super::drifted_fields(&person).is_empty());
+
+        // Manual edits drift
+        person.name = "User One".to_string();
+        person.person.phone_numbers.as_mut().unwrap()[0].value = Some("+15555550199".into());
+        assert_eq!(
+            super::drifted_fields(&person),
+            [ContactField::Name, ContactField::Phone]
+        );
+
+        // The sync overwrites the phone but not the name
+        let person = person.update(user, DEFAULT_TIMEZONE);
+        assert_eq!(super::drifted_fields(&person), [ContactField::Name]);
+    }
+
+    #[test]
+    fn restored_person() {
+        let user = User {
+            name: "User 1".to_string(),
+            email: "user1@example.com".to_string(),
+            phone: Some("+15555550100".to_string()),
+            address: "1 Main St".to_string(),
+            ..Default::default()
+        };
+        let mut person = create_api_person(&user, &["contactGroups/old"], DEFAULT_TIMEZONE);
+        person.resource_name = Some("people/c1".to_string());
+        person.names.as_mut().unwrap()[0].display_name = Some("User 1".to_string());
+        person.phone_numbers.as_mut().unwrap()[0].metadata = Some(Default::default());
+
+        let restored = super::restored_person(&person, "contactGroups/new");
+        assert_eq!(restored.resource_name, None);
+        assert_eq!(
+            restored.names.unwrap()[0].unstructured_name.as_deref(),
+            Some("User 1")
+        );
+        assert!(restored.phone_numbers.unwrap()[0].metadata.is_none());
+        assert_eq!(
+            restored.user_defined.map(|values| values.len()),
+            person.user_defined.map(|values| values.len())
+        );
+        assert_eq!(
+            restored.memberships.unwrap()[0]
+                .contact_group_membership
+                .as_ref()
+                .and_then(|membership| membership.contact_group_resource_name.as_deref()),
+            Some("contactGroups/new")
+        );
+    }
+
+    #[test]
+    fn person_changes() {
+        let user = |phone: &str, member_status| User {
+            name: "User 1".to_string(),
+            email: "user1@example.com".to_string(),
+            phone: Some(phone.to_string()),
+            address: "1 Main St".to_string(),
+            member_status,
+            ..Default::default()
+        };
+        let person = PersonWrapper {
+            name: "User 1".to_string(),
+            email: Some("user1@example.com".to_string()),
+            ..Default::default()
+        };
+        let before = person.update(
+            user("+15555550100", MemberStatus::Student),
+            DEFAULT_TIMEZONE,
+        );
+        let after = before
+            .clone()
+            .update(user("+15555550199", MemberStatus::RM), DEFAULT_TIMEZONE);
+
+        assert_eq!(
+            super::person_changes(&before.person, &after.person),
+            "phone: +15555550100; SCMA Member Status: Student"
+        );
+        assert_eq!(
+            super::person_changes(&after.person, &before.person),
+            "phone: +15555550199; SCMA Member Status: RM"
+        );
+    }
+
+    #[test]
+    fn membership_rule_matches() {
+        let user = |trip_leader_status, position: Option<&str>| User {
+            trip_leader_status,
+            position: position.map(str::to_string),
+            ..Default::default()
+        };
+        let leader = user(Some(TripLeaderStatus::S1), None);
+        let board = user(None, Some("Treasurer"));
+        let member = user(None, Some(""));
+
+        assert!(MembershipRule::TripLeader.matches(&leader));
+        assert!(!MembershipRule::TripLeader.matches(&board));
+        assert!(MembershipRule::TripLeaderStatus(vec![TripLeaderStatus::S1]).matches(&leader));
+        assert!(!MembershipRule::TripLeaderStatus(vec![TripLeaderStatus::G]).matches(&leader));
+        assert!(MembershipRule::Position.matches(&board));
+        assert!(!MembershipRule::Position.matches(&member));
+        assert!(!MembershipRule::Position.matches(&leader));
+
+        let rules: IndexMap<String, MembershipRule> = serde_yaml::from_str(
+            "SCMA Trip Leaders: trip-leader\nSCMA Guides: !trip-leader-status [G]\nSCMA Board: position",
+        )
+        .unwrap();
+        assert_eq!(
+            rules.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("SCMA Trip Leaders".to_string(), MembershipRule::TripLeader),
+                (
+                    "SCMA Guides".to_string(),
+                    MembershipRule::TripLeaderStatus(vec![TripLeaderStatus::G])
+                ),
+                ("SCMA Board".to_string(), MembershipRule::Position),
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_group_ops() {
+        let person = |id: &str, email: Option<&str>| PersonWrapper {
+            resource_name: format!("people/{id}"),
+            name: format!("User {id}"),
+            email: email.map(str::to_string),
+            ..Default::default()
+        };
+        let people = vec![
+            person("0", Some("user0@example.com")),
+            person("1", Some("user1@example.com")),
+            person("2", Some("user2@example.com")),
+            person("3", Some("user3@example.com")),
+            person("4", None),
+        ];
+        let group_members = HashSet::from([
+            "people/1".to_string(),
+            "people/2".to_string(),
+            "people/4".to_string(),
+        ]);
+        let emails = HashSet::from([
+            "user0@example.com".to_string(),
+            "user1@example.com".to_string(),
+        ]);
+
+        let (adds, removes) = super::rule_group_ops(&people, &group_members, &emails);
+        assert_eq!(adds, vec![&people[0]]);
+        assert_eq!(removes, vec![&people[2], &people[4]]);
+    }
+
+    #[test]
+    fn is_group_member() {
+        let membership = |group: &str| api::Membership {
+            contact_group_membership: Some(api::ContactGroupMembership {
+                contact_group_resource_name: Some(group.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let person = api::Person {
+            memberships: Some(vec![
+                membership("contactGroups/myContacts"),
+                membership("contactGroups/scma"),
+            ]),
+            ..Default::default()
+        };
+
+        assert!(super::is_group_member(&person, "contactGroups/scma"));
+        assert!(!super::is_group_member(&person, "contactGroups/alumni"));
+        assert!(!super::is_group_member(
+            &api::Person::default(),
+            "contactGroups/scma"
+        ));
+    }
+
+    #[test]
+    fn people_report_entries() {
+        let users = vec![User {
+            name: "User 1".to_string(),
+            email: "user1@example.com".to_string(),
+            phone: Some("+15555550100".to_string()),
+            address: "1 Main St".to_string(),
+            city: "Springfield".to_string(),
+            state: "CA".to_string(),
+            zipcode: "90000".to_string(),
+            ..Default::default()
+        }];
+        let phone_number = |value: &str| api::PhoneNumber {
+            value: Some(value.to_string()),
+            ..Default::default()
+        };
+        let people = vec![
+            PersonWrapper {
+                name: "user 1".to_string(),
+                email: Some("user1@example.com".to_string()),
+                person: api::Person {
+                    email_addresses: Some(vec![api::EmailAddress {
+                        value: Some("User1@example.com".to_string()),
+                        ..Default::default()
+                    }]),
+                    phone_numbers: Some(vec![
+                        phone_number("(555) 555-0100"),
+                        phone_number("555-555-0199"),
+                    ]),
+                    addresses: Some(vec![api::Address {
+                        formatted_value: Some("2 Main St, Springfield, CA 90000".to_string()),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            PersonWrapper {
+                name: "User 2".to_string(),
+                email: Some("user2@example.com".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let actual: Vec<(ContactField, String)> =
+            GPpl::people_report_entries(&users, &people, &HashMap::new())
+                .into_iter()
+                .map(|entry| (entry.field, entry.contacts_value))
+                .collect();
+        let expected = vec![
+            (ContactField::Phone, "555-555-0199".to_string()),
+            (
+                ContactField::Address,
+                "2 Main St, Springfield, CA 90000".to_string(),
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
 }
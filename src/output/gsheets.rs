@@ -0,0 +1,263 @@
+use crate::model::{sort_users, User};
+use crate::output::retry::RetryDelegate;
+use crate::output::UserSink;
+use crate::plan::{Plan, PlannedWrite};
+use crate::Connector;
+use crate::{Error, GAuth};
+
+use async_trait::async_trait;
+use google_sheets4::{api, Sheets};
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use serde_json::Value;
+use tracing::{debug, info, trace};
+
+use std::sync::Arc;
+
+const SCOPE: api::Scope = api::Scope::Spreadsheet;
+/// The header row of the roster
+const ROSTER_HEADER: &[&str] = &[
+    "Name",
+    "Email",
+    "Phone",
+    "Member Status",
+    "Trip Leader Status",
+];
+
+/// Writes the member roster to a tab of a Google Sheets spreadsheet.
+///
+/// The tab is created if it does not exist.  Its contents are replaced on each write so that the
+/// tab always reflects the SCMA users.
+pub struct GSheets {
+    hub: Sheets<Connector>,
+    spreadsheet_id: String,
+    sheet_name: String,
+    dry_run: bool,
+    plan: Arc<Plan>,
+}
+
+impl GSheets {
+    pub async fn new(
+        spreadsheet_id: &str,
+        sheet_name: &str,
+        auth: GAuth,
+        dry_run: bool,
+        plan: Arc<Plan>,
+    ) -> Result<Self, Error> {
+        let hub = Self::create_hub(auth).await?;
+        Self::sheets_get_or_add(&hub, spreadsheet_id, sheet_name, dry_run).await?;
+
+        Ok(Self {
+            hub,
+            spreadsheet_id: spreadsheet_id.to_string(),
+            sheet_name: sheet_name.to_string(),
+            dry_run,
+            plan,
+        })
+    }
+
+    async fn create_hub(gauth: GAuth) -> Result<Sheets<Connector>, Error> {
+        let scopes = [SCOPE];
+        let token = gauth.auth().token(&scopes).await.map_err(Error::auth)?;
+        info!(expiration_time=?token.expiration_time(), "Got token");
+
+        let https = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .map_err(Error::google)?
+            .https_only()
+            .enable_http1()
+            .build();
+        let client = Client::builder(TokioExecutor::new()).build(https);
+
+        let hub = Sheets::new(client, gauth.auth());
+
+        Ok(hub)
+    }
+
+    /// Finds the named sheet (tab) of the spreadsheet.  Adds it if it does not exist.
+    async fn sheets_get_or_add(
+        hub: &Sheets<Connector>,
+        spreadsheet_id: &str,
+        sheet_name: &str,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        info!(%spreadsheet_id, %sheet_name, "Finding sheet");
+        let (rsp, spreadsheet) = hub
+            .spreadsheets()
+            .get(spreadsheet_id)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "spreadsheets.get");
+
+        let exists = spreadsheet
+            .sheets
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|sheet| sheet.properties.as_ref()?.title.as_deref())
+            .any(|title| title == sheet_name);
+        if exists {
+            info!(%spreadsheet_id, %sheet_name, "Found existing sheet");
+            return Ok(());
+        }
+
+        info!(%spreadsheet_id, %sheet_name, "Sheet not found, adding new sheet");
+        if dry_run {
+            return Ok(());
+        }
+
+        let req = api::BatchUpdateSpreadsheetRequest {
+            requests: Some(vec![api::Request {
+                add_sheet: Some(api::AddSheetRequest {
+                    properties: Some(api::SheetProperties {
+                        title: Some(sheet_name.to_string()),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let (rsp, _) = hub
+            .spreadsheets()
+            .batch_update(req, spreadsheet_id)
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "spreadsheets.batchUpdate");
+
+        Ok(())
+    }
+
+    /// Replaces the contents of the sheet with the rows.
+    async fn values_replace(&self, rows: Vec<Vec<Value>>) -> Result<(), Error> {
+        let range = sheet_range(&self.sheet_name);
+        let (rsp, _) = self
+            .hub
+            .spreadsheets()
+            .values_clear(
+                api::ClearValuesRequest::default(),
+                &self.spreadsheet_id,
+                &range,
+            )
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "spreadsheets.values.clear");
+
+        let req = api::ValueRange {
+            values: Some(rows),
+            ..Default::default()
+        };
+        let (rsp, response) = self
+            .hub
+            .spreadsheets()
+            .values_update(req, &self.spreadsheet_id, &format!("{range}!A1"))
+            .value_input_option("RAW")
+            .add_scope(SCOPE)
+            .delegate(&mut RetryDelegate::default())
+            .doit()
+            .await?;
+        trace!(?rsp, "spreadsheets.values.update");
+        debug!(?response, "spreadsheets.values.update");
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserSink for GSheets {
+    async fn write_users(&self, mut users: Vec<User>) -> Result<(), Error> {
+        sort_users(&mut users);
+        let rows = roster_rows(&users);
+
+        self.plan.record(PlannedWrite::SheetWrite {
+            spreadsheet_id: self.spreadsheet_id.clone(),
+            sheet: self.sheet_name.clone(),
+            rows: users.len(),
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+
+        info!(spreadsheet_id=%self.spreadsheet_id, sheet_name=%self.sheet_name, count=%users.len(), "Writing roster");
+        self.values_replace(rows).await
+    }
+}
+
+/// Returns the sheet name in A1 notation.
+///
+/// Quoted so that names with spaces or punctuation are valid.
+fn sheet_range(sheet_name: &str) -> String {
+    format!("'{}'", sheet_name.replace('\'', "''"))
+}
+
+/// Returns the header row and a row per user.
+fn roster_rows(users: &[User]) -> Vec<Vec<Value>> {
+    let header = ROSTER_HEADER
+        .iter()
+        .map(|column| Value::from(*column))
+        .collect();
+    let rows = users.iter().map(|user| {
+        vec![
+            Value::from(user.name.as_str()),
+            Value::from(user.email.as_str()),
+            Value::from(user.phone.as_deref().unwrap_or_default()),
+            Value::from(user.member_status.to_string()),
+            Value::from(
+                user.trip_leader_status
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            ),
+        ]
+    });
+
+    std::iter::once(header).chain(rows).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{MemberStatus, TripLeaderStatus};
+
+    #[test]
+    fn sheet_range() {
+        assert_eq!(super::sheet_range("Roster"), "'Roster'");
+        assert_eq!(super::sheet_range("Bob's Roster"), "'Bob''s Roster'");
+    }
+
+    #[test]
+    fn roster_rows() {
+        let users = vec![User {
+            name: "Jane Doe".into(),
+            email: "jane@example.com".into(),
+            phone: Some("555-1234".into()),
+            member_status: MemberStatus::RM,
+            trip_leader_status: Some(TripLeaderStatus::S1),
+            ..Default::default()
+        }];
+
+        assert_eq!(
+            super::roster_rows(&users),
+            vec![
+                vec![
+                    Value::from("Name"),
+                    "Email".into(),
+                    "Phone".into(),
+                    "Member Status".into(),
+                    "Trip Leader Status".into(),
+                ],
+                vec![
+                    Value::from("Jane Doe"),
+                    "jane@example.com".into(),
+                    "555-1234".into(),
+                    "RM".into(),
+                    "S1".into(),
+                ],
+            ]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+/// The language of the user-visible text of synced Google Calendar events.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+}
+
+impl Lang {
+    pub const ALL: &'static [Lang] = &[Lang::En, Lang::Es];
+
+    pub(crate) fn strings(self) -> &'static Strings {
+        match self {
+            Self::En => &EN,
+            Self::Es => &ES,
+        }
+    }
+}
+
+/// The user-visible strings of synced Google Calendar events.
+pub(crate) struct Strings {
+    pub description: &'static str,
+    pub attendees: &'static str,
+    pub comments: &'static str,
+    /// Used for an empty attendee list or comment thread
+    pub none: &'static str,
+    /// Separates the description content from the sync footer.  Followed by the sync timestamp.
+    pub footer_separator: &'static str,
+    /// Follows the sync timestamp.  Followed by a link to scma-gsync.
+    pub footer_by: &'static str,
+    /// Follows the attendee count in the summary
+    pub going: &'static str,
+    /// Prefixes the summary of events that no longer exist on SCMA
+    pub cancelled: &'static str,
+}
+
+const EN: Strings = Strings {
+    description: "Description",
+    attendees: "Attendees",
+    comments: "Comments",
+    none: "None",
+    footer_separator: "\n\nLast synced at ",
+    footer_by: "by",
+    going: "going",
+    cancelled: "CANCELLED",
+};
+
+const ES: Strings = Strings {
+    description: "Descripción",
+    attendees: "Asistentes",
+    comments: "Comentarios",
+    none: "Ninguno",
+    footer_separator: "\n\nÚltima sincronización el ",
+    footer_by: "por",
+    going: "confirmados",
+    cancelled: "CANCELADO",
+};
@@ -0,0 +1,281 @@
+use crate::model::{Event, User, DEFAULT_TIMEZONE};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use std::fmt::Write;
+
+const PRODID: &str = concat!(
+    "-//",
+    env!("CARGO_PKG_NAME"),
+    "//",
+    env!("CARGO_PKG_VERSION"),
+    "//EN"
+);
+/// Lines longer than this many octets must be folded (RFC 5545 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// The iCalendar METHOD (RFC 5546)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// A published calendar (e.g. a file to subscribe to)
+    Publish,
+    /// An invitation
+    Request,
+}
+
+/// An invitation to render into an event.
+pub struct Invitation<'a> {
+    /// The email address of the organizer
+    pub organizer: &'a str,
+    pub attendees: &'a [&'a User],
+    /// Incremented each time the invitation is revised (RFC 5545 3.8.7.4)
+    pub sequence: u32,
+}
+
+/// Renders events as an iCalendar (RFC 5545) object.
+///
+/// Timed events are rendered with UTC times and other events as all-day VEVENTs.  The UID is derived from the SCMA event ID so that
+/// re-rendering an event updates rather than duplicates it in calendar clients.
+pub struct Calendar {
+    method: Method,
+    /// The display name of the calendar (X-WR-CALNAME)
+    name: Option<String>,
+    /// The timezone of the SCMA event times
+    timezone: Tz,
+    timestamp: DateTime<Utc>,
+    buffer: String,
+}
+
+impl Calendar {
+    pub fn new(method: Method) -> Self {
+        Self {
+            method,
+            name: None,
+            timezone: DEFAULT_TIMEZONE,
+            timestamp: Utc::now(),
+            buffer: String::new(),
+        }
+    }
+
+    /// Sets the name that calendar clients display for the calendar.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Sets the timezone of the SCMA event times.  Defaults to DEFAULT_TIMEZONE.
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Adds an event to the calendar.
+    pub fn event(mut self, event: &Event, invitation: Option<&Invitation>) -> Self {
+        self.line("BEGIN:VEVENT");
+        self.line(&format!("UID:{}", event_uid(event)));
+        self.line(&format!("DTSTAMP:{}", format_datetime(self.timestamp)));
+        if let Some((start, end)) = event.start_end(self.timezone) {
+            self.line(&format!("DTSTART:{}", format_datetime(start)));
+            self.line(&format!("DTEND:{}", format_datetime(end)));
+        } else {
+            self.line(&format!(
+                "DTSTART;VALUE=DATE:{}",
+                format_date(event.start_date)
+            ));
+            // The end date of an all-day event is exclusive
+            self.line(&format!(
+                "DTEND;VALUE=DATE:{}",
+                format_date(event.end_date + Duration::days(1))
+            ));
+        }
+        self.line(&format!("SUMMARY:{}", escape_text(&event.title)));
+        self.line(&format!("LOCATION:{}", escape_text(&event.location)));
+        self.line(&format!(
+            "DESCRIPTION:{}",
+            escape_text(&format!(
+                "{}\n\n{}",
+                event.url,
+                strip_html(&event.description)
+            ))
+        ));
+        self.line(&format!("URL:{}", event.url));
+
+        if let Some(invitation) = invitation {
+            self.line(&format!("SEQUENCE:{}", invitation.sequence));
+            self.line("STATUS:CONFIRMED");
+            self.line(&format!(
+                "ORGANIZER;CN=SCMA:mailto:{}",
+                invitation.organizer
+            ));
+            for attendee in invitation.attendees {
+                self.line(&format!(
+                    "ATTENDEE;CN=\"{}\";ROLE=REQ-PARTICIPANT;PARTSTAT=ACCEPTED;RSVP=FALSE:mailto:{}",
+                    attendee.name.replace('"', "'"),
+                    attendee.email
+                ));
+            }
+        }
+
+        self.line("END:VEVENT");
+        self
+    }
+
+    /// Returns the rendered iCalendar object.
+    pub fn render(self) -> String {
+        let mut buffer = String::with_capacity(self.buffer.len() + 256);
+        push_line(&mut buffer, "BEGIN:VCALENDAR");
+        push_line(&mut buffer, "VERSION:2.0");
+        push_line(&mut buffer, &format!("PRODID:{PRODID}"));
+        push_line(&mut buffer, "CALSCALE:GREGORIAN");
+        match self.method {
+            Method::Publish => push_line(&mut buffer, "METHOD:PUBLISH"),
+            Method::Request => push_line(&mut buffer, "METHOD:REQUEST"),
+        }
+        if let Some(name) = &self.name {
+            push_line(&mut buffer, &format!("X-WR-CALNAME:{}", escape_text(name)));
+        }
+        buffer.push_str(&self.buffer);
+        push_line(&mut buffer, "END:VCALENDAR");
+        buffer
+    }
+
+    fn line(&mut self, line: &str) {
+        push_line(&mut self.buffer, line);
+    }
+}
+
+/// Renders the events as a published iCalendar object for calendar clients to import or subscribe
+/// to.
+pub fn events_to_ics(events: &[Event], name: &str, timezone: Tz) -> String {
+    events
+        .iter()
+        .fold(
+            Calendar::new(Method::Publish).name(name).timezone(timezone),
+            |calendar, event| calendar.event(event, None),
+        )
+        .render()
+}
+
+/// Appends a content line, folding it if necessary.
+pub(crate) fn push_line(buffer: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            buffer.push_str("\r\n ");
+            // The leading space counts toward the line length
+            octets = 1;
+        }
+        buffer.push(c);
+        octets += c.len_utf8();
+    }
+    buffer.push_str("\r\n");
+}
+
+/// Returns a globally unique identifier for the event derived from the SCMA event ID.
+pub fn event_uid(event: &Event) -> String {
+    format!("scma-event-{}@rockclimbing.org", event.id)
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn format_datetime(datetime: DateTime<Utc>) -> String {
+    datetime.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes TEXT property values (RFC 5545 3.3.11).
+pub(crate) fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Converts the SCMA HTML description to plain text.
+pub(crate) fn strip_html(html: &str) -> String {
+    let text = html
+        .replace("<br>", "\n")
+        .replace("<br />", "\n")
+        .replace("</p>", "\n");
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    let stripped = html_escape::decode_html_entities(&stripped);
+
+    let mut lines = String::new();
+    for line in stripped
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        writeln!(lines, "{line}").unwrap();
+    }
+    lines.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::event;
+
+    use chrono::TimeZone;
+
+    #[test]
+    fn request() {
+        let event = Event {
+            title: "Joshua Tree [G]".into(),
+            url: "https://www.rockclimbing.org/index.php/event-list/events-list/joshua-tree-g-36"
+                .into(),
+            end_date: "2022-01-17".parse().unwrap(),
+            location: "Joshua Tree, CA".into(),
+            description: "<p>Trip Leaders: Ming &amp; LeRoy</p>\r\n<p>Bring water; lots.</p>"
+                .into(),
+            ..event("527", "2022-01-14")
+        };
+        let user = User {
+            name: "John Doe".into(),
+            email: "johndoe@example.com".into(),
+            ..Default::default()
+        };
+        let attendees = [&user];
+        let invitation = Invitation {
+            organizer: "scma@example.com",
+            attendees: &attendees,
+            sequence: 1,
+        };
+        let calendar = Calendar {
+            timestamp: Utc.timestamp_opt(0, 0).unwrap(),
+            ..Calendar::new(Method::Request)
+        };
+        let ics = calendar.event(&event, Some(&invitation)).render();
+        insta::assert_snapshot!(ics.replace("\r\n", "\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+    }
+
+    #[test]
+    fn publish() {
+        let calendar = Calendar {
+            timestamp: Utc.timestamp_opt(0, 0).unwrap(),
+            ..Calendar::new(Method::Publish).name("SCMA")
+        };
+        let ics = calendar.event(&event("527", "2022-01-14"), None).render();
+        insta::assert_snapshot!(ics.replace("\r\n", "\n"));
+    }
+}
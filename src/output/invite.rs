@@ -0,0 +1,378 @@
+use crate::fs::write_atomic;
+use crate::model::{Event, EventTime, User, DEFAULT_TIMEZONE};
+use crate::output::ics::{Calendar, Invitation, Method};
+use crate::Error;
+
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use lettre::message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials as SmtpCredentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// SMTP server settings for sending invitations.
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// The sender (e.g. "SCMA <scma@example.com>").  Also used as the organizer of the invitations.
+    pub from: String,
+}
+
+/// The invitations previously sent for an event.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SentInvitation {
+    sequence: u32,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    /// The times of a timed event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<EventTime>,
+    emails: BTreeSet<String>,
+}
+
+/// Emails personal ICS invitations (METHOD:REQUEST) to event attendees.
+///
+/// A record of sent invitations is kept in a YAML file so that each attendee is invited once per
+/// event.  If the dates or times of an event change, the invitation is revised (SEQUENCE
+/// incremented) and re-sent to all attendees.
+pub struct Inviter {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    sent_path: PathBuf,
+    sent: BTreeMap<String, SentInvitation>,
+    dry_run: bool,
+    /// The timezone of SCMA events
+    timezone: Tz,
+}
+
+impl Inviter {
+    pub fn new(config: SmtpConfig, sent_path: &Path, dry_run: bool) -> Result<Self, Error> {
+        let from: Mailbox = config
+            .from
+            .parse()
+            .context(format!("invalid invitation sender `{}`", config.from))
+            .map_err(Error::notify)?;
+
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+            .context(format!("invalid SMTP host `{}`", config.host))
+            .map_err(Error::notify)?
+            .port(config.port);
+        let mailer = if config.username.is_empty() {
+            mailer
+        } else {
+            mailer.credentials(SmtpCredentials::new(config.username, config.password))
+        };
+
+        Ok(Self {
+            mailer: mailer.build(),
+            from,
+            sent_path: sent_path.to_path_buf(),
+            sent: read_sent(sent_path)?,
+            dry_run,
+            timezone: DEFAULT_TIMEZONE,
+        })
+    }
+
+    /// Sets the timezone of SCMA events.  Defaults to DEFAULT_TIMEZONE.
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = timezone;
+    }
+
+    /// Emails invitations for the event to attendees that have not yet been invited.
+    ///
+    /// Past events are skipped.
+    pub async fn invite(&mut self, event: &Event, users: &[User]) -> Result<(), Error> {
+        if event.end_date < Utc::now().with_timezone(&self.timezone).date_naive() {
+            return Ok(());
+        }
+
+        let attendees = event.attendee_users(users);
+        let Some((sequence, recipients)) = plan(self.sent.get(&event.id), event, &attendees) else {
+            debug!(%event, "No invitations to send");
+            return Ok(());
+        };
+
+        let organizer = self.from.email.to_string();
+        let ics = Calendar::new(Method::Request)
+            .timezone(self.timezone)
+            .event(
+                event,
+                Some(&Invitation {
+                    organizer: &organizer,
+                    attendees: &attendees,
+                    sequence,
+                }),
+            )
+            .render();
+
+        for recipient in &recipients {
+            info!(%event, email=%recipient.email, %sequence, "Sending invitation");
+            if self.dry_run {
+                continue;
+            }
+
+            let message = self
+                .message(event, recipient, &ics)
+                .map_err(Error::notify)?;
+            self.mailer
+                .send(message)
+                .await
+                .context(format!(
+                    "unable to send invitation to `{}`",
+                    recipient.email
+                ))
+                .map_err(Error::notify)?;
+
+            // Record each invitation as it is sent so that a failure does not re-send the
+            // invitations already sent
+            let record = self.sent.entry(event.id.clone()).or_default();
+            if record.sequence != sequence {
+                record.emails.clear();
+            }
+            record.sequence = sequence;
+            record.start_date = event.start_date;
+            record.end_date = event.end_date;
+            record.time = event.time;
+            record.emails.insert(recipient.email.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Writes the record of sent invitations.
+    pub fn save(&self) -> Result<(), Error> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        write_sent(&self.sent_path, &self.sent)
+    }
+
+    fn message(&self, event: &Event, recipient: &User, ics: &str) -> anyhow::Result<Message> {
+        let to: Mailbox = recipient
+            .name_email()
+            .parse()
+            .context(format!("invalid attendee email `{}`", recipient.email))?;
+        let text = format!(
+            "You're going to {event}.\n\nDetails: {}\n\nTo cancel, update your RSVP on the SCMA website.",
+            event.url
+        );
+        let calendar_type = ContentType::parse("text/calendar; method=REQUEST; charset=utf-8")?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(format!("Invitation: {event}"))
+            .multipart(
+                MultiPart::mixed()
+                    .multipart(
+                        MultiPart::alternative()
+                            .singlepart(SinglePart::plain(text))
+                            .singlepart(
+                                SinglePart::builder()
+                                    .header(calendar_type.clone())
+                                    .body(ics.to_string()),
+                            ),
+                    )
+                    .singlepart(
+                        Attachment::new("invite.ics".to_string())
+                            .body(ics.to_string(), calendar_type),
+                    ),
+            )?;
+
+        Ok(message)
+    }
+}
+
+/// Removes the email from the record of sent invitations.
+///
+/// Returns the number of events the email was removed from.  The record is not written on a dry
+/// run.
+pub fn forget_invitee(sent_path: &Path, email: &str, dry_run: bool) -> Result<usize, Error> {
+    let mut sent = read_sent(sent_path)?;
+    let count = forget(&mut sent, email);
+    if count > 0 && !dry_run {
+        write_sent(sent_path, &sent)?;
+    }
+
+    Ok(count)
+}
+
+fn forget(sent: &mut BTreeMap<String, SentInvitation>, email: &str) -> usize {
+    sent.values_mut()
+        .map(|record| {
+            let before = record.emails.len();
+            record
+                .emails
+                .retain(|sent| !sent.eq_ignore_ascii_case(email));
+            record.emails.len() < before
+        })
+        .filter(|removed| *removed)
+        .count()
+}
+
+fn read_sent(sent_path: &Path) -> Result<BTreeMap<String, SentInvitation>, Error> {
+    if !sent_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let sent = std::fs::read_to_string(sent_path)
+        .context(format!(
+            "unable to read invitations sent file `{}`",
+            sent_path.display()
+        ))
+        .map_err(Error::file)?;
+    serde_yaml::from_str(&sent)
+        .context(format!(
+            "unable to parse invitations sent file `{}`",
+            sent_path.display()
+        ))
+        .map_err(Error::file)
+}
+
+fn write_sent(sent_path: &Path, sent: &BTreeMap<String, SentInvitation>) -> Result<(), Error> {
+    let sent = serde_yaml::to_string(sent).map_err(Error::file)?;
+    write_atomic(sent_path, &sent)
+        .context(format!(
+            "unable to write invitations sent file `{}`",
+            sent_path.display()
+        ))
+        .map_err(Error::file)
+}
+
+/// Returns the sequence number and recipients of the invitations to send, if any.
+fn plan<'a>(
+    sent: Option<&SentInvitation>,
+    event: &Event,
+    attendees: &[&'a User],
+) -> Option<(u32, Vec<&'a User>)> {
+    let (sequence, recipients): (u32, Vec<&User>) = match sent {
+        None => (0, attendees.to_vec()),
+        Some(sent)
+            if sent.start_date != event.start_date
+                || sent.end_date != event.end_date
+                || sent.time != event.time =>
+        {
+            (sent.sequence + 1, attendees.to_vec())
+        }
+        Some(sent) => (
+            sent.sequence,
+            attendees
+                .iter()
+                .filter(|user| !sent.emails.contains(&user.email))
+                .copied()
+                .collect(),
+        ),
+    };
+
+    if recipients.is_empty() {
+        None
+    } else {
+        Some((sequence, recipients))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::event;
+
+    #[test]
+    fn plan() {
+        let user = |email: &str| User {
+            email: email.into(),
+            ..Default::default()
+        };
+        let john = user("john@example.com");
+        let jane = user("jane@example.com");
+        let attendees = [&john, &jane];
+        fn emails(plan: Option<(u32, Vec<&User>)>) -> Option<(u32, Vec<&str>)> {
+            plan.map(|(sequence, recipients)| {
+                let emails = recipients.iter().map(|u| u.email.as_str()).collect();
+                (sequence, emails)
+            })
+        }
+        let sent = SentInvitation {
+            sequence: 0,
+            start_date: "2022-01-14".parse().unwrap(),
+            end_date: "2022-01-14".parse().unwrap(),
+            time: None,
+            emails: ["john@example.com".to_string()].into(),
+        };
+
+        assert_eq!(
+            emails(super::plan(None, &event("527", "2022-01-14"), &attendees)),
+            Some((0, vec!["john@example.com", "jane@example.com"]))
+        );
+        assert_eq!(
+            emails(super::plan(
+                Some(&sent),
+                &event("527", "2022-01-14"),
+                &attendees
+            )),
+            Some((0, vec!["jane@example.com"]))
+        );
+        assert_eq!(
+            emails(super::plan(
+                Some(&sent),
+                &event("527", "2022-01-14"),
+                &[&john]
+            )),
+            None
+        );
+        assert_eq!(
+            emails(super::plan(
+                Some(&sent),
+                &event("527", "2022-01-15"),
+                &attendees
+            )),
+            Some((1, vec!["john@example.com", "jane@example.com"]))
+        );
+
+        // A time-only change revises the invitation
+        let timed = Event {
+            time: Some(EventTime {
+                start: "08:00:00".parse().unwrap(),
+                end: "17:00:00".parse().unwrap(),
+            }),
+            ..event("527", "2022-01-14")
+        };
+        assert_eq!(
+            emails(super::plan(Some(&sent), &timed, &attendees)),
+            Some((1, vec!["john@example.com", "jane@example.com"]))
+        );
+    }
+
+    #[test]
+    fn forget() {
+        let record = |emails: &[&str]| SentInvitation {
+            emails: emails.iter().map(|email| email.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut sent = BTreeMap::from([
+            (
+                "527".to_string(),
+                record(&["jane@example.com", "john@example.com"]),
+            ),
+            ("531".to_string(), record(&["john@example.com"])),
+            ("533".to_string(), record(&["Jane@Example.com"])),
+        ]);
+
+        assert_eq!(super::forget(&mut sent, "jane@example.com"), 2);
+        assert_eq!(
+            sent,
+            BTreeMap::from([
+                ("527".to_string(), record(&["john@example.com"])),
+                ("531".to_string(), record(&["john@example.com"])),
+                ("533".to_string(), record(&[])),
+            ])
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::model::{MemberStatus, TripLeaderStatus, User};
+use crate::output::batch::encode_path_segment;
+use crate::output::{fail_or_continue, UserSink};
+use crate::plan::{Plan, PlannedWrite};
+use crate::Error;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::{json, Value};
+use tracing::{info, trace};
+
+use std::sync::Arc;
+
+/// The maximum number of concurrent requests.  Mailchimp allows 10 per API key.
+const CONCURRENT_REQUESTS: usize = 5;
+const MEMBER_STATUSES: &[MemberStatus] = &[
+    MemberStatus::Applicant,
+    MemberStatus::Student,
+    MemberStatus::AM,
+    MemberStatus::HM,
+    MemberStatus::RM,
+];
+const TRIP_LEADER_STATUSES: &[TripLeaderStatus] = &[
+    TripLeaderStatus::G,
+    TripLeaderStatus::S1,
+    TripLeaderStatus::S2,
+];
+
+/// Upserts the SCMA users into a Mailchimp audience.
+///
+/// Each member is tagged with their member status (e.g. "Member: RM") and trip leader status
+/// (e.g. "Trip Leader: S1").  The tags of other statuses are removed so that the tags follow
+/// status changes.  New members are subscribed.  Users that have opted out are unsubscribed and
+/// never resubscribed.  Existing members keep their subscription status (e.g. a member that
+/// unsubscribed via Mailchimp stays unsubscribed).
+pub struct Mailchimp {
+    client: reqwest::Client,
+    api_key: String,
+    /// The API root of the data center of the account (e.g. https://us6.api.mailchimp.com/3.0)
+    api_url: String,
+    list_id: String,
+    dry_run: bool,
+    plan: Arc<Plan>,
+    continue_on_error: bool,
+}
+
+impl Mailchimp {
+    pub fn new(
+        api_key: &str,
+        list_id: &str,
+        dry_run: bool,
+        plan: Arc<Plan>,
+    ) -> Result<Self, Error> {
+        let api_url = api_url(api_key)?;
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            api_key: api_key.to_string(),
+            api_url,
+            list_id: list_id.to_string(),
+            dry_run,
+            plan,
+            continue_on_error: false,
+        })
+    }
+
+    /// Continues with the remaining members if a member upsert fails.
+    ///
+    /// The errors of the failed upserts are recorded in the plan (see Plan::failures).
+    pub fn set_continue_on_error(&mut self, continue_on_error: bool) {
+        self.continue_on_error = continue_on_error;
+    }
+
+    async fn members_upsert(&self, user: &User) -> Result<(), Error> {
+        self.plan.record(PlannedWrite::MailingListUpsert {
+            name: user.name.clone(),
+            email: user.email.clone(),
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let member_url = format!(
+            "{}/lists/{}/members/{}",
+            self.api_url,
+            encode_path_segment(&self.list_id),
+            encode_path_segment(&user.email.to_lowercase())
+        );
+        trace!(user=%user.name_email(), "Upserting audience member");
+        self.send(self.client.put(&member_url).json(&member_body(user)))
+            .await
+            .with_context(|| format!("unable to upsert audience member `{}`", user.email))
+            .map_err(Error::notify)?;
+        self.send(
+            self.client
+                .post(format!("{member_url}/tags"))
+                .json(&tags_body(user)),
+        )
+        .await
+        .with_context(|| format!("unable to tag audience member `{}`", user.email))
+        .map_err(Error::notify)?;
+
+        Ok(())
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> anyhow::Result<()> {
+        let rsp = req
+            .basic_auth("scma-gsync", Some(&self.api_key))
+            .send()
+            .await?;
+        if !rsp.status().is_success() {
+            let status = rsp.status();
+            let detail = rsp.text().await.unwrap_or_default();
+            return Err(anyhow!("bad response ({status}): {detail}"));
+        }
+        trace!(?rsp, "mailchimp");
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserSink for Mailchimp {
+    async fn write_users(&self, users: Vec<User>) -> Result<(), Error> {
+        let users = users
+            .into_iter()
+            .filter(|user| !user.email.is_empty())
+            .collect::<Vec<_>>();
+        info!(list_id=%self.list_id, count=%users.len(), "Upserting audience members");
+
+        stream::iter(users)
+            .map(|user| async move {
+                let result = self.members_upsert(&user).await;
+                fail_or_continue(&self.plan, self.continue_on_error, result)
+            })
+            .buffer_unordered(CONCURRENT_REQUESTS)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Returns the API root of the data center of the API key (the suffix after the dash).
+fn api_url(api_key: &str) -> Result<String, Error> {
+    match api_key.rsplit_once('-') {
+        Some((_, dc)) if !dc.is_empty() && dc.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            Ok(format!("https://{dc}.api.mailchimp.com/3.0"))
+        }
+        _ => Err(Error::auth(anyhow!(
+            "invalid Mailchimp API key, expected `<key>-<data center>` (e.g. `abc123-us6`)"
+        ))),
+    }
+}
+
+fn member_body(user: &User) -> Value {
+    let (first_name, last_name) = match user.name.trim().rsplit_once(' ') {
+        Some((first_name, last_name)) => (first_name.trim(), last_name),
+        None => (user.name.trim(), ""),
+    };
+    let mut body = json!({
+        "email_address": user.email,
+        "status_if_new": if user.optedout { "unsubscribed" } else { "subscribed" },
+        "merge_fields": {
+            "FNAME": first_name,
+            "LNAME": last_name,
+        },
+    });
+    if user.optedout {
+        body["status"] = json!("unsubscribed");
+    }
+    body
+}
+
+/// Returns the tags of the member with the tags of the other statuses inactive.
+fn tags_body(user: &User) -> Value {
+    let member_tags = MEMBER_STATUSES.iter().map(|status| {
+        json!({
+            "name": format!("Member: {status}"),
+            "status": if *status == user.member_status { "active" } else { "inactive" },
+        })
+    });
+    let trip_leader_tags = TRIP_LEADER_STATUSES.iter().map(|status| {
+        json!({
+            "name": format!("Trip Leader: {status}"),
+            "status": if Some(status) == user.trip_leader_status.as_ref() { "active" } else { "inactive" },
+        })
+    });
+
+    json!({ "tags": member_tags.chain(trip_leader_tags).collect::<Vec<_>>() })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn user(optedout: bool) -> User {
+        User {
+            name: "Mary Jane Doe".into(),
+            email: "Jane@Example.com".into(),
+            member_status: MemberStatus::RM,
+            trip_leader_status: Some(TripLeaderStatus::S1),
+            optedout,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn api_url() {
+        assert_eq!(
+            super::api_url("abc123-us6").unwrap(),
+            "https://us6.api.mailchimp.com/3.0"
+        );
+        assert!(super::api_url("abc123").is_err());
+        assert!(super::api_url("abc123-us6.evil.com/").is_err());
+    }
+
+    #[test]
+    fn member_body() {
+        assert_eq!(
+            super::member_body(&user(false)),
+            json!({
+                "email_address": "Jane@Example.com",
+                "status_if_new": "subscribed",
+                "merge_fields": {"FNAME": "Mary Jane", "LNAME": "Doe"},
+            })
+        );
+        assert_eq!(
+            super::member_body(&user(true)),
+            json!({
+                "email_address": "Jane@Example.com",
+                "status_if_new": "unsubscribed",
+                "status": "unsubscribed",
+                "merge_fields": {"FNAME": "Mary Jane", "LNAME": "Doe"},
+            })
+        );
+    }
+
+    #[test]
+    fn tags_body() {
+        let tags = super::tags_body(&user(false));
+        let active = tags["tags"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|tag| tag["status"] == "active")
+            .map(|tag| tag["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(active, ["Member: RM", "Trip Leader: S1"]);
+        assert_eq!(tags["tags"].as_array().unwrap().len(), 8);
+    }
+}
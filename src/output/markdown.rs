@@ -0,0 +1,257 @@
+use crate::model::Event;
+use crate::output::strip_html;
+
+use chrono_tz::Tz;
+
+/// Renders the events as a Markdown document for pasting into a newsletter or a wiki.
+///
+/// Each event is a heading linking to the SCMA event page followed by the dates, location,
+/// description, attendees, and comments.  The attendees and comments are omitted if they were not
+/// fetched.  Comment dates are shown in the timezone.
+pub fn events_to_markdown(events: &[Event], timezone: Tz) -> String {
+    let mut buffer = String::from("# SCMA Events\n");
+    for event in events {
+        push_event(&mut buffer, event, timezone);
+    }
+    buffer
+}
+
+fn push_event(buffer: &mut String, event: &Event, timezone: Tz) {
+    buffer.push_str(&format!(
+        "\n## [{}]({})\n\n",
+        escape(&event.title),
+        event.url
+    ));
+
+    let dates = if event.start_date == event.end_date {
+        event.start_date.format("%a %b %-d, %Y").to_string()
+    } else {
+        format!(
+            "{} to {}",
+            event.start_date.format("%a %b %-d"),
+            event.end_date.format("%a %b %-d, %Y")
+        )
+    };
+    let times = event
+        .time
+        .as_ref()
+        .map(|time| {
+            format!(
+                ", {} to {}",
+                time.start.format("%-I:%M %p"),
+                time.end.format("%-I:%M %p")
+            )
+        })
+        .unwrap_or_default();
+    buffer.push_str(&format!("- **When:** {dates}{times}\n"));
+    if !event.location.is_empty() {
+        buffer.push_str(&format!("- **Where:** {}\n", escape(&event.location)));
+    }
+
+    let description = strip_html(&event.description);
+    if !description.is_empty() {
+        buffer.push('\n');
+        for line in description.lines() {
+            buffer.push_str(&format!("{}  \n", escape(line)));
+        }
+    }
+
+    if let Some(ref attendees) = event.attendees {
+        buffer.push_str(&format!("\n### Attendees ({})\n\n", event.attendee_count()));
+        if attendees.is_empty() {
+            buffer.push_str("None\n");
+        }
+        for (index, attendee) in attendees.iter().enumerate() {
+            buffer.push_str(&format!("{}. {}", index + 1, escape(&attendee.name)));
+            if attendee.count > 1 {
+                buffer.push_str(&format!(" (+{})", attendee.count - 1));
+            }
+            if !attendee.comment.is_empty() {
+                buffer.push_str(&format!(" — {}", escape(&attendee.comment)));
+            }
+            buffer.push('\n');
+        }
+    }
+
+    if let Some(ref comments) = event.comments {
+        buffer.push_str("\n### Comments\n\n");
+        if comments.is_empty() {
+            buffer.push_str("None\n");
+        }
+        for comment in comments {
+            buffer.push_str(&format!(
+                "- **{}** ({}): {}\n",
+                escape(&comment.author),
+                comment.local_date(timezone).format("%b %-d, %Y %-I:%M %p"),
+                escape(&comment.text).replace('\n', "  \n  ")
+            ));
+        }
+    }
+}
+
+/// Escapes the Markdown control characters of inline text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{event, Attendee, Comment, EventTime, DEFAULT_TIMEZONE};
+
+    use chrono::{NaiveTime, TimeZone};
+
+    fn markdown(event: &Event) -> String {
+        let mut buffer = String::new();
+        super::push_event(&mut buffer, event, DEFAULT_TIMEZONE);
+        buffer
+    }
+
+    #[test]
+    fn events_to_markdown() {
+        let trip = Event {
+            title: "Joshua Tree, Hidden Valley".into(),
+            url: "https://www.rockclimbing.org/index.php/event-list/events-list/event/527".into(),
+            end_date: "2024-03-10".parse().unwrap(),
+            location: "Joshua Tree, CA".into(),
+            description: "<p>Meet at the *Hidden Valley* campground.</p><p>Bring water.</p>".into(),
+            attendees: Some(vec![
+                Attendee {
+                    name: "Jane Doe".into(),
+                    count: 2,
+                    comment: "Arriving Saturday".into(),
+                    email: None,
+                },
+                Attendee {
+                    name: "John Doe".into(),
+                    count: 1,
+                    comment: String::new(),
+                    email: None,
+                },
+            ]),
+            comments: Some(vec![Comment {
+                author: "Jane Doe".into(),
+                date: DEFAULT_TIMEZONE
+                    .with_ymd_and_hms(2024, 3, 1, 18, 30, 0)
+                    .unwrap()
+                    .fixed_offset(),
+                text: "Who has a rope?".into(),
+            }]),
+            ..event("527", "2024-03-09")
+        };
+        let meeting = Event {
+            title: "Board Meeting".into(),
+            time: Some(EventTime {
+                start: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+            }),
+            ..event("531", "2024-03-09")
+        };
+
+        insta::assert_snapshot!(super::events_to_markdown(
+            &[trip, meeting],
+            DEFAULT_TIMEZONE
+        ));
+    }
+
+    #[test]
+    fn push_event_when() {
+        let when = |event: &Event| {
+            markdown(event)
+                .lines()
+                .find_map(|line| line.strip_prefix("- **When:** "))
+                .map(str::to_string)
+        };
+
+        let day = event("527", "2024-03-09");
+        assert_eq!(when(&day).as_deref(), Some("Sat Mar 9, 2024"));
+
+        let weekend = Event {
+            end_date: "2024-03-10".parse().unwrap(),
+            ..day.clone()
+        };
+        assert_eq!(
+            when(&weekend).as_deref(),
+            Some("Sat Mar 9 to Sun Mar 10, 2024")
+        );
+
+        let evening = Event {
+            time: Some(EventTime {
+                start: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(21, 30, 0).unwrap(),
+            }),
+            ..day
+        };
+        assert_eq!(
+            when(&evening).as_deref(),
+            Some("Sat Mar 9, 2024, 7:00 PM to 9:30 PM")
+        );
+    }
+
+    #[test]
+    fn push_event_sections() {
+        let bare = Event {
+            location: String::new(),
+            description: String::new(),
+            ..event("527", "2024-03-09")
+        };
+        assert_eq!(
+            markdown(&bare),
+            "\n## [a title](a url)\n\n- **When:** Sat Mar 9, 2024\n"
+        );
+
+        // Fetched but empty attendees and comments are listed as none
+        let empty = Event {
+            attendees: Some(Vec::new()),
+            comments: Some(Vec::new()),
+            ..bare
+        };
+        assert_eq!(
+            markdown(&empty),
+            "\n## [a title](a url)\n\n- **When:** Sat Mar 9, 2024\n\
+            \n### Attendees (0)\n\nNone\n\
+            \n### Comments\n\nNone\n"
+        );
+    }
+
+    #[test]
+    fn push_event_escapes() {
+        let event = Event {
+            title: "Crag_Day [G]".into(),
+            location: "Stoney Point #2".into(),
+            description: String::new(),
+            comments: Some(vec![Comment {
+                author: "jane_doe".into(),
+                date: DEFAULT_TIMEZONE
+                    .with_ymd_and_hms(2024, 3, 1, 9, 5, 0)
+                    .unwrap()
+                    .fixed_offset(),
+                text: "Bring a *rope*\nand a rack".into(),
+            }]),
+            ..event("527", "2024-03-09")
+        };
+        assert_eq!(
+            markdown(&event),
+            "\n## [Crag\\_Day \\[G\\]](a url)\n\n\
+            - **When:** Sat Mar 9, 2024\n\
+            - **Where:** Stoney Point \\#2\n\
+            \n### Comments\n\n\
+            - **jane\\_doe** (Mar 1, 2024 9:05 AM): Bring a \\*rope\\*  \n  and a rack\n"
+        );
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(super::escape("a_b *c* [d]"), r"a\_b \*c\* \[d\]");
+    }
+}
@@ -1,7 +1,99 @@
+mod archive;
+mod batch;
+mod export;
 mod gauth;
 mod gcal;
 mod gppl;
+mod gsheets;
+mod i18n;
+mod ics;
+mod invite;
+mod mailchimp;
+mod markdown;
+mod retry;
+mod sms;
+mod vcf;
+mod webhook;
 
-pub use gauth::GAuth;
-pub use gcal::GCal;
-pub use gppl::GPpl;
+pub use archive::Archive;
+pub use export::{events_to_csv, users_to_csv};
+pub use gauth::{GAuth, Secret, TokenStore};
+pub use gcal::{
+    AclReportEntry, AclReportStatus, CachedEvent, CalendarBackup, CalendarRoute, CalendarSelect,
+    DescriptionInclude, DescriptionTemplate, EventCache, EventColor, EventDrift, EventField,
+    EventOptions, EventReminder, GCal, OrphanAction, PushedState, ReminderMethod,
+    DEFAULT_SUMMARY_FORMAT,
+};
+pub use gppl::{
+    ContactDiscrepancy, ContactDrift, ContactField, ContactsBackup, GPpl, MembershipRule,
+    StaleContactAction,
+};
+pub use gsheets::GSheets;
+pub use i18n::Lang;
+pub use ics::events_to_ics;
+pub use invite::{forget_invitee, Inviter, SmtpConfig};
+pub use mailchimp::Mailchimp;
+pub use markdown::events_to_markdown;
+pub use sms::{forget_recipient, Reminder, TwilioConfig};
+pub use vcf::users_to_vcf;
+pub use webhook::{DiscordWebhook, SlackWebhook, TelegramBot};
+
+pub(crate) use ics::strip_html;
+pub(crate) use retry::{
+    api_calls, api_retries, backoff, is_rate_limited, jitter, record_api_calls,
+};
+
+use crate::model::{Event, User};
+use crate::plan::Plan;
+use crate::Error;
+
+use async_trait::async_trait;
+
+/// A destination for SCMA events (e.g. Google Calendar).
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn write_events(&self, events: &[Event]) -> Result<(), Error>;
+}
+
+/// A destination for SCMA users (e.g. the Google Calendar ACL or Google Contacts).
+#[async_trait]
+pub trait UserSink: Send + Sync {
+    async fn write_users(&self, users: Vec<User>) -> Result<(), Error>;
+}
+
+/// Returns the error of a single write unless continue_on_error is set.
+///
+/// If continue_on_error is set, the error is logged and recorded as a failure of the plan instead
+/// so that the remaining writes are made.
+pub(crate) fn fail_or_continue(
+    plan: &Plan,
+    continue_on_error: bool,
+    result: Result<(), Error>,
+) -> Result<(), Error> {
+    match result {
+        Err(err) if continue_on_error => {
+            tracing::error!(%err, "Write failed, continuing");
+            plan.record_failure(&err);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fail_or_continue() {
+        let plan = Plan::new();
+        let err = || Err(Error::google("unable to write event `00527`"));
+
+        assert!(super::fail_or_continue(&plan, false, err()).is_err());
+        assert!(plan.failures().is_empty());
+
+        assert!(super::fail_or_continue(&plan, true, err()).is_ok());
+        assert!(super::fail_or_continue(&plan, true, Ok(())).is_ok());
+        assert_eq!(plan.failures(), vec!["unable to write event `00527`"]);
+    }
+}
@@ -0,0 +1,145 @@
+use google_calendar3::common::{Delegate, MethodInfo, Response, Retry};
+use tracing::warn;
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The maximum number of times to retry a rate limited request
+pub(crate) const MAX_RETRIES: u32 = 6;
+/// The delay before the first retry.  Doubles on each subsequent retry.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(32);
+/// The maximum random delay added to the backoff to spread out concurrent retries
+const MAX_JITTER_MS: u64 = 1000;
+
+/// The number of rate limited requests retried by the process
+static API_RETRIES: AtomicU64 = AtomicU64::new(0);
+/// The number of Google API calls made by the process
+static API_CALLS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of rate limited requests retried since the start of the process.
+pub(crate) fn api_retries() -> u64 {
+    API_RETRIES.load(Ordering::Relaxed)
+}
+
+/// Returns the number of Google API calls made since the start of the process.
+pub(crate) fn api_calls() -> u64 {
+    API_CALLS.load(Ordering::Relaxed)
+}
+
+/// Records Google API calls made without a RetryDelegate (e.g. the calls of a batch request).
+pub(crate) fn record_api_calls(count: usize) {
+    API_CALLS.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Retries Google API requests that fail due to rate limiting.
+///
+/// Rate limited requests (429, or 403 with a rate limit reason) are retried with exponential
+/// backoff and jitter.  The Retry-After header takes precedence over the backoff if present.  Any
+/// other failure is not retried.
+///
+/// Use a new delegate for each request (e.g. `.delegate(&mut RetryDelegate::default())`).
+#[derive(Default)]
+pub(crate) struct RetryDelegate {
+    retries: u32,
+}
+
+impl Delegate for RetryDelegate {
+    fn begin(&mut self, _info: MethodInfo) {
+        record_api_calls(1);
+    }
+
+    fn http_failure(&mut self, response: &Response, error: Option<&serde_json::Value>) -> Retry {
+        let status = response.status().as_u16();
+        if !is_rate_limited(status, error) || self.retries >= MAX_RETRIES {
+            return Retry::Abort;
+        }
+
+        let delay = retry_after(response).unwrap_or_else(|| backoff(self.retries) + jitter());
+        self.retries += 1;
+        API_RETRIES.fetch_add(1, Ordering::Relaxed);
+        warn!(%status, retry=%self.retries, ?delay, "Rate limited, retrying");
+
+        Retry::After(delay)
+    }
+}
+
+/// Returns true if the failure is due to rate limiting.
+///
+/// Google APIs signal rate limiting with 429 or with 403 and a rate limit reason (e.g.
+/// "rateLimitExceeded" or "userRateLimitExceeded").  Other 403s (e.g. permission denied) are not
+/// rate limiting.
+pub(crate) fn is_rate_limited(status: u16, error: Option<&serde_json::Value>) -> bool {
+    match status {
+        429 => true,
+        403 => error
+            .and_then(|error| error["error"]["errors"].as_array())
+            .is_some_and(|errors| {
+                errors.iter().any(|error| {
+                    matches!(
+                        error["reason"].as_str(),
+                        Some("rateLimitExceeded" | "userRateLimitExceeded")
+                    )
+                })
+            }),
+        _ => false,
+    }
+}
+
+/// Returns the delay requested by the Retry-After header in seconds, if any.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(hyper::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+pub(crate) fn backoff(retries: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(retries))
+        .min(MAX_BACKOFF)
+}
+
+pub(crate) fn jitter() -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % MAX_JITTER_MS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn is_rate_limited() {
+        let error = |reason| json!({"error": {"code": 403, "errors": [{"reason": reason}]}});
+
+        assert!(super::is_rate_limited(429, None));
+        assert!(super::is_rate_limited(
+            403,
+            Some(&error("rateLimitExceeded"))
+        ));
+        assert!(super::is_rate_limited(
+            403,
+            Some(&error("userRateLimitExceeded"))
+        ));
+        assert!(!super::is_rate_limited(403, Some(&error("forbidden"))));
+        assert!(!super::is_rate_limited(403, None));
+        assert!(!super::is_rate_limited(404, None));
+    }
+
+    #[test]
+    fn backoff() {
+        assert_eq!(super::backoff(0), Duration::from_secs(1));
+        assert_eq!(super::backoff(3), Duration::from_secs(8));
+        assert_eq!(super::backoff(10), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::fs::write_atomic;
+use crate::model::{Event, User, DEFAULT_TIMEZONE};
+use crate::Error;
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use tracing::{debug, info, trace};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+const TWILIO_API_URL: &str = "https://api.twilio.com/2010-04-01";
+
+/// Twilio account settings for sending text messages.
+pub struct TwilioConfig {
+    pub account_sid: String,
+    pub auth_token: String,
+    /// The Twilio phone number to send from (e.g. "+15555550100")
+    pub from: String,
+}
+
+/// Texts event reminders to attendees via Twilio.
+///
+/// Only attendees that have opted in (by email) and have a valid phone number are texted.  A
+/// reminder is sent once the event is within the lead time of its start.  Timed events start at
+/// their start time and all-day events at midnight, both in the SCMA timezone.  A record of sent
+/// reminders is kept in a YAML file so that each attendee is reminded once per event.
+pub struct Reminder {
+    client: reqwest::Client,
+    config: TwilioConfig,
+    lead: Duration,
+    opt_in: HashSet<String>,
+    sent_path: PathBuf,
+    /// Maps SCMA event IDs to the emails of the reminded attendees
+    sent: BTreeMap<String, BTreeSet<String>>,
+    dry_run: bool,
+    /// The timezone of SCMA events
+    timezone: Tz,
+}
+
+impl Reminder {
+    pub fn new(
+        config: TwilioConfig,
+        lead: Duration,
+        opt_in: impl IntoIterator<Item = String>,
+        sent_path: &Path,
+        dry_run: bool,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            client: reqwest::Client::new(),
+            config,
+            lead,
+            opt_in: opt_in
+                .into_iter()
+                .map(|email| email.to_lowercase())
+                .collect(),
+            sent_path: sent_path.to_path_buf(),
+            sent: read_sent(sent_path)?,
+            dry_run,
+            timezone: DEFAULT_TIMEZONE,
+        })
+    }
+
+    /// Sets the timezone of SCMA events.  Defaults to DEFAULT_TIMEZONE.
+    pub fn set_timezone(&mut self, timezone: Tz) {
+        self.timezone = timezone;
+    }
+
+    /// Texts a reminder for the event to opted-in attendees that have not yet been reminded.
+    ///
+    /// Does nothing if the event is not yet within the lead time or has already started.
+    pub async fn remind(&mut self, event: &Event, users: &[User]) -> Result<(), Error> {
+        if !is_due(event, self.lead, Utc::now().with_timezone(&self.timezone)) {
+            return Ok(());
+        }
+
+        let sent = self.sent.get(&event.id);
+        let recipients: Vec<(&User, &str)> = event
+            .attendee_users(users)
+            .into_iter()
+            .filter(|user| self.opt_in.contains(&user.email))
+            .filter(|user| !sent.is_some_and(|sent| sent.contains(&user.email)))
+            .filter_map(|user| match user.phone.as_deref() {
+                Some(phone) if is_valid_phone_number(phone) => Some((user, phone)),
+                _ => {
+                    debug!(%event, email=%user.email, "Skipping reminder, no valid phone number");
+                    None
+                }
+            })
+            .collect();
+
+        let body = format!(
+            "SCMA reminder: You're going to {} starting {}. Details: {}",
+            event.title,
+            event.start_date.format("%a %b %-d"),
+            event.url
+        );
+
+        for (user, phone) in recipients {
+            info!(%event, email=%user.email, %phone, "Sending reminder");
+            if !self.dry_run {
+                self.send(phone, &body)
+                    .await
+                    .context(format!("unable to send reminder to `{}`", user.email))
+                    .map_err(Error::notify)?;
+                self.sent
+                    .entry(event.id.clone())
+                    .or_default()
+                    .insert(user.email.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the record of sent reminders.
+    pub fn save(&self) -> Result<(), Error> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        write_sent(&self.sent_path, &self.sent)
+    }
+
+    async fn send(&self, to: &str, body: &str) -> anyhow::Result<()> {
+        let url = format!(
+            "{TWILIO_API_URL}/Accounts/{}/Messages.json",
+            self.config.account_sid
+        );
+        let rsp = self
+            .client
+            .post(url)
+            .basic_auth(&self.config.account_sid, Some(&self.config.auth_token))
+            .form(&[("To", to), ("From", &self.config.from), ("Body", body)])
+            .send()
+            .await?
+            .error_for_status()?;
+        trace!(?rsp, "messages.create");
+
+        Ok(())
+    }
+}
+
+/// Removes the email from the record of sent reminders.
+///
+/// Returns the number of events the email was removed from.  The record is not written on a dry
+/// run.
+pub fn forget_recipient(sent_path: &Path, email: &str, dry_run: bool) -> Result<usize, Error> {
+    let mut sent = read_sent(sent_path)?;
+    let count = sent
+        .values_mut()
+        .map(|emails| {
+            let before = emails.len();
+            emails.retain(|sent| !sent.eq_ignore_ascii_case(email));
+            emails.len() < before
+        })
+        .filter(|removed| *removed)
+        .count();
+    if count > 0 && !dry_run {
+        write_sent(sent_path, &sent)?;
+    }
+
+    Ok(count)
+}
+
+fn read_sent(sent_path: &Path) -> Result<BTreeMap<String, BTreeSet<String>>, Error> {
+    if !sent_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let sent = std::fs::read_to_string(sent_path)
+        .context(format!(
+            "unable to read reminders sent file `{}`",
+            sent_path.display()
+        ))
+        .map_err(Error::file)?;
+    serde_yaml::from_str(&sent)
+        .context(format!(
+            "unable to parse reminders sent file `{}`",
+            sent_path.display()
+        ))
+        .map_err(Error::file)
+}
+
+fn write_sent(sent_path: &Path, sent: &BTreeMap<String, BTreeSet<String>>) -> Result<(), Error> {
+    let sent = serde_yaml::to_string(sent).map_err(Error::file)?;
+    write_atomic(sent_path, &sent)
+        .context(format!(
+            "unable to write reminders sent file `{}`",
+            sent_path.display()
+        ))
+        .map_err(Error::file)
+}
+
+/// Returns true if the event starts within the lead time.  The event starts in the timezone of
+/// now.
+fn is_due<T: TimeZone>(event: &Event, lead: Duration, now: DateTime<T>) -> bool {
+    let start = match event.time {
+        Some(time) => event.start_date.and_time(time.start),
+        None => event.start_date.and_time(NaiveTime::MIN),
+    };
+    let Some(start) = now.timezone().from_local_datetime(&start).earliest() else {
+        return false;
+    };
+
+    now < start && now >= start - lead
+}
+
+/// Returns true for a normalized (e.g. "+15555550100") US phone number.
+fn is_valid_phone_number(phone: &str) -> bool {
+    phone.len() == 12 && phone.starts_with("+1")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{event, EventTime};
+
+    #[test]
+    fn is_due() {
+        let event = event("527", "2022-01-14");
+        let lead = Duration::hours(24);
+        let at = |day, hour| {
+            DEFAULT_TIMEZONE
+                .with_ymd_and_hms(2022, 1, day, hour, 0, 0)
+                .unwrap()
+        };
+
+        assert!(!super::is_due(&event, lead, at(12, 23)));
+        assert!(super::is_due(&event, lead, at(13, 0)));
+        assert!(super::is_due(&event, lead, at(13, 23)));
+        assert!(!super::is_due(&event, lead, at(14, 0)));
+
+        // Starts at midnight PST (08:00 UTC)
+        let utc = |day, hour| {
+            Utc.with_ymd_and_hms(2022, 1, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&DEFAULT_TIMEZONE)
+        };
+        assert!(!super::is_due(&event, lead, utc(13, 7)));
+        assert!(super::is_due(&event, lead, utc(13, 8)));
+        assert!(super::is_due(&event, lead, utc(14, 7)));
+
+        // Timed events start at their start time
+        let event = Event {
+            time: Some(EventTime {
+                start: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(21, 0, 0).unwrap(),
+            }),
+            ..event
+        };
+        assert!(!super::is_due(&event, lead, at(13, 18)));
+        assert!(super::is_due(&event, lead, at(13, 19)));
+        assert!(super::is_due(&event, lead, at(14, 18)));
+        assert!(!super::is_due(&event, lead, at(14, 19)));
+    }
+
+    #[test]
+    fn is_valid_phone_number() {
+        assert!(super::is_valid_phone_number("+15555550100"));
+        assert!(!super::is_valid_phone_number("+1"));
+        assert!(!super::is_valid_phone_number("+1555555010"));
+    }
+}
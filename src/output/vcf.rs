@@ -0,0 +1,115 @@
+use crate::model::User;
+use crate::output::ics::{escape_text, push_line};
+
+/// Renders the users as vCard 3.0 (RFC 2426) contacts for contacts apps to import.
+///
+/// The SCMA fields without a standard vCard property (e.g. the member status) are extension
+/// properties.  The UID is derived from the SCMA user ID so that re-importing updates rather than
+/// duplicates a contact in apps that support it.
+pub fn users_to_vcf(users: &[User]) -> String {
+    let mut buffer = String::new();
+    for user in users {
+        push_user(&mut buffer, user);
+    }
+    buffer
+}
+
+fn push_user(buffer: &mut String, user: &User) {
+    push_line(buffer, "BEGIN:VCARD");
+    push_line(buffer, "VERSION:3.0");
+    if !user.id.is_empty() {
+        push_line(
+            buffer,
+            &format!("UID:scma-user-{}@rockclimbing.org", user.id),
+        );
+    }
+    push_line(buffer, &format!("FN:{}", escape_text(&user.name)));
+    let (given, family) = split_name(&user.name);
+    push_line(
+        buffer,
+        &format!("N:{};{};;;", escape_text(family), escape_text(given)),
+    );
+    if !user.email.is_empty() {
+        push_line(
+            buffer,
+            &format!("EMAIL;TYPE=INTERNET:{}", escape_text(&user.email)),
+        );
+    }
+    if let Some(ref phone) = user.phone {
+        push_line(buffer, &format!("TEL:{}", escape_text(phone)));
+    }
+    if [&user.address, &user.city, &user.state, &user.zipcode]
+        .iter()
+        .any(|part| !part.is_empty())
+    {
+        push_line(
+            buffer,
+            &format!(
+                "ADR;TYPE=HOME:;;{};{};{};{};",
+                escape_text(&user.address),
+                escape_text(&user.city),
+                escape_text(&user.state),
+                escape_text(&user.zipcode)
+            ),
+        );
+    }
+    if let Some(ref position) = user.position {
+        push_line(buffer, &format!("TITLE:{}", escape_text(position)));
+    }
+    push_line(buffer, "ORG:SCMA");
+    push_line(
+        buffer,
+        &format!("X-SCMA-MEMBER-STATUS:{}", user.member_status),
+    );
+    if let Some(ref status) = user.trip_leader_status {
+        push_line(buffer, &format!("X-SCMA-TRIP-LEADER-STATUS:{status}"));
+    }
+    if let Some(ref since) = user.membersince {
+        push_line(
+            buffer,
+            &format!("X-SCMA-MEMBER-SINCE:{}", escape_text(since)),
+        );
+    }
+    push_line(buffer, "END:VCARD");
+}
+
+/// Splits a full name into the given names and the family name (the last word).
+fn split_name(name: &str) -> (&str, &str) {
+    let name = name.trim();
+    match name.rsplit_once(' ') {
+        Some((given, family)) => (given.trim(), family),
+        None => (name, ""),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{MemberStatus, TripLeaderStatus};
+
+    #[test]
+    fn users_to_vcf() {
+        let users = vec![User {
+            id: "42".into(),
+            name: "Mary Jane Doe".into(),
+            email: "jane@example.com".into(),
+            phone: Some("555-1234".into()),
+            member_status: MemberStatus::RM,
+            trip_leader_status: Some(TripLeaderStatus::S1),
+            address: "123 Main St, Apt 4".into(),
+            city: "Pasadena".into(),
+            state: "CA".into(),
+            zipcode: "91101".into(),
+            membersince: Some("2015-06-01".into()),
+            ..Default::default()
+        }];
+
+        insta::assert_snapshot!(super::users_to_vcf(&users).replace("\r\n", "\n"));
+    }
+
+    #[test]
+    fn split_name() {
+        assert_eq!(super::split_name("Mary Jane Doe"), ("Mary Jane", "Doe"));
+        assert_eq!(super::split_name("Cher"), ("Cher", ""));
+    }
+}
@@ -0,0 +1,394 @@
+use crate::changelog::Changelog;
+use crate::output::retry::{backoff, jitter, MAX_RETRIES};
+use crate::Error;
+
+use anyhow::{anyhow, Context};
+use serde_json::{json, Value};
+use tracing::{info, trace, warn};
+
+use std::time::Duration;
+
+/// The maximum number of embeds per Discord message
+const DISCORD_MAX_EMBEDS: usize = 10;
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+/// The maximum length of a Telegram message in characters
+const TELEGRAM_MAX_MESSAGE_LEN: usize = 4096;
+/// The delay between the messages of a run.  Telegram allows about 20 messages per minute to a
+/// group chat.
+const TELEGRAM_MESSAGE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Posts the event changes of a run (see Changelog) to a Slack channel via an incoming webhook.
+///
+/// Nothing is posted if there are no changes.
+pub struct SlackWebhook {
+    client: reqwest::Client,
+    url: String,
+    dry_run: bool,
+}
+
+impl SlackWebhook {
+    pub fn new(url: &str, dry_run: bool) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            dry_run,
+        }
+    }
+
+    pub async fn post(&self, changelog: &Changelog) -> Result<(), Error> {
+        if changelog.is_empty() {
+            return Ok(());
+        }
+
+        let message = slack_message(changelog);
+        if self.dry_run {
+            info!(changes=%changelog.0.len(), %message, "Dry run, not posting to Slack");
+            return Ok(());
+        }
+
+        info!(changes=%changelog.0.len(), "Posting changes to Slack");
+        post_json(&self.client, &self.url, &message)
+            .await
+            .context("unable to post to Slack webhook")
+            .map_err(Error::notify)
+    }
+}
+
+/// Posts the event changes of a run (see Changelog) to a Discord channel via a webhook.
+///
+/// Each change is an embed with the event title linking to the SCMA event page, the dates, and
+/// the location.  Nothing is posted if there are no changes.
+pub struct DiscordWebhook {
+    client: reqwest::Client,
+    url: String,
+    dry_run: bool,
+}
+
+impl DiscordWebhook {
+    pub fn new(url: &str, dry_run: bool) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            dry_run,
+        }
+    }
+
+    pub async fn post(&self, changelog: &Changelog) -> Result<(), Error> {
+        if changelog.is_empty() {
+            return Ok(());
+        }
+
+        let messages = discord_messages(changelog);
+        if self.dry_run {
+            info!(changes=%changelog.0.len(), messages=%messages.len(), "Dry run, not posting to Discord");
+            return Ok(());
+        }
+
+        info!(changes=%changelog.0.len(), "Posting changes to Discord");
+        for message in &messages {
+            post_json(&self.client, &self.url, message)
+                .await
+                .context("unable to post to Discord webhook")
+                .map_err(Error::notify)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Posts the event changes of a run (see Changelog) to a Telegram chat via a bot.
+///
+/// Each change is a line with the event title linking to the SCMA event page.  The changes are
+/// split across messages as a message holds a limited number of characters.  The messages are
+/// spaced out and a rate limited message is retried after the delay requested by Telegram.
+/// Nothing is posted if there are no changes.
+pub struct TelegramBot {
+    client: reqwest::Client,
+    token: String,
+    chat_id: String,
+    dry_run: bool,
+}
+
+impl TelegramBot {
+    pub fn new(token: &str, chat_id: &str, dry_run: bool) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            token: token.to_string(),
+            chat_id: chat_id.to_string(),
+            dry_run,
+        }
+    }
+
+    pub async fn post(&self, changelog: &Changelog) -> Result<(), Error> {
+        if changelog.is_empty() {
+            return Ok(());
+        }
+
+        let messages = telegram_messages(changelog);
+        if self.dry_run {
+            info!(changes=%changelog.0.len(), chat_id=%self.chat_id, ?messages, "Dry run, not posting to Telegram");
+            return Ok(());
+        }
+
+        info!(changes=%changelog.0.len(), chat_id=%self.chat_id, "Posting changes to Telegram");
+        for (index, message) in messages.iter().enumerate() {
+            if index > 0 {
+                tokio::time::sleep(TELEGRAM_MESSAGE_INTERVAL).await;
+            }
+            self.send_message(message)
+                .await
+                .context("unable to post to Telegram")
+                .map_err(Error::notify)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the message.  Retries if rate limited.
+    async fn send_message(&self, text: &str) -> anyhow::Result<()> {
+        let url = format!("{TELEGRAM_API_URL}/bot{}/sendMessage", self.token);
+        let body = json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "parse_mode": "HTML",
+            "disable_web_page_preview": true,
+        });
+
+        let mut retries = 0;
+        loop {
+            // The URL holds the bot token so keep it out of errors
+            let rsp = self
+                .client
+                .post(&url)
+                .json(&body)
+                .send()
+                .await
+                .map_err(reqwest::Error::without_url)?;
+            let status = rsp.status();
+            if status.is_success() {
+                trace!(?rsp, "telegram");
+                return Ok(());
+            }
+
+            let detail: Value = rsp.json().await.unwrap_or_default();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS && retries < MAX_RETRIES {
+                let delay = detail["parameters"]["retry_after"]
+                    .as_u64()
+                    .map(Duration::from_secs)
+                    .unwrap_or_else(|| backoff(retries) + jitter());
+                retries += 1;
+                warn!(%status, retry=%retries, ?delay, "Rate limited, retrying");
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            return Err(anyhow!(
+                "bad response ({status}): {}",
+                detail["description"].as_str().unwrap_or_default()
+            ));
+        }
+    }
+}
+
+async fn post_json(client: &reqwest::Client, url: &str, message: &Value) -> reqwest::Result<()> {
+    let rsp = client
+        .post(url)
+        .json(message)
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?
+        .error_for_status()
+        .map_err(reqwest::Error::without_url)?;
+    trace!(status = %rsp.status(), "webhook");
+
+    Ok(())
+}
+
+/// Returns the Slack message of the changes with a line per change linking to the SCMA event.
+fn slack_message(changelog: &Changelog) -> Value {
+    let lines = changelog
+        .0
+        .iter()
+        .map(|entry| {
+            format!(
+                "• {}: <{}|{}> ({})",
+                entry.label(),
+                entry.url,
+                slack_escape(&entry.title),
+                slack_escape(&entry.detail())
+            )
+        })
+        .collect::<Vec<_>>();
+
+    json!({ "text": format!("SCMA event changes\n{}", lines.join("\n")) })
+}
+
+/// Returns the Discord messages of the changes with an embed per change.
+///
+/// The changes are split across messages as a message holds a limited number of embeds.
+fn discord_messages(changelog: &Changelog) -> Vec<Value> {
+    changelog
+        .0
+        .chunks(DISCORD_MAX_EMBEDS)
+        .map(|entries| {
+            let embeds = entries
+                .iter()
+                .map(|entry| {
+                    let mut fields = vec![json!({
+                        "name": "Dates",
+                        "value": format!("{} to {}", entry.start_date, entry.end_date),
+                        "inline": true,
+                    })];
+                    // Discord rejects the whole message if a field value is empty
+                    if !entry.location.trim().is_empty() {
+                        fields.push(json!({
+                            "name": "Location",
+                            "value": entry.location,
+                            "inline": true,
+                        }));
+                    }
+                    json!({
+                        "title": entry.title,
+                        "url": entry.url,
+                        "description": format!("{} ({})", entry.label(), entry.detail()),
+                        "fields": fields,
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({ "content": "SCMA event changes", "embeds": embeds })
+        })
+        .collect()
+}
+
+/// Returns the Telegram messages of the changes with a line per change linking to the SCMA event.
+///
+/// The lines are split across messages as a message holds a limited number of characters.
+fn telegram_messages(changelog: &Changelog) -> Vec<String> {
+    const HEADER: &str = "<b>SCMA event changes</b>";
+
+    let mut messages = Vec::new();
+    let mut message = String::from(HEADER);
+    for entry in &changelog.0 {
+        let line = format!(
+            "• {}: <a href=\"{}\">{}</a> ({})",
+            entry.label(),
+            html_escape::encode_double_quoted_attribute(&entry.url),
+            html_escape::encode_text(&entry.title),
+            html_escape::encode_text(&entry.detail())
+        );
+        if message.chars().count() + 1 + line.chars().count() > TELEGRAM_MAX_MESSAGE_LEN {
+            messages.push(message);
+            message = String::from(HEADER);
+        }
+        message.push('\n');
+        message.push_str(&line);
+    }
+    messages.push(message);
+
+    messages
+}
+
+/// Escapes the control characters of Slack message text.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::changelog::{Change, ChangelogEntry};
+
+    fn entry(id: &str, title: &str, change: Change) -> ChangelogEntry {
+        let date = "2024-03-09".parse().unwrap();
+        ChangelogEntry {
+            id: id.into(),
+            title: title.into(),
+            url: format!("https://example.com/{id}"),
+            start_date: date,
+            end_date: date,
+            location: "Joshua Tree".into(),
+            change,
+        }
+    }
+
+    #[test]
+    fn slack_message() {
+        let changelog = Changelog(vec![
+            entry("1", "Hidden Valley", Change::New),
+            entry(
+                "2",
+                "Rock & Roll",
+                Change::LocationChanged {
+                    old_location: "Red Rocks".into(),
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            super::slack_message(&changelog),
+            json!({
+                "text": "SCMA event changes\n\
+                    • New: <https://example.com/1|Hidden Valley> (2024-03-09/2024-03-09)\n\
+                    • Location changed: <https://example.com/2|Rock &amp; Roll> (Red Rocks -&gt; Joshua Tree)"
+            })
+        );
+    }
+
+    #[test]
+    fn telegram_messages() {
+        let changelog = Changelog(vec![entry("1", "Rock & Roll", Change::New)]);
+        assert_eq!(
+            super::telegram_messages(&changelog),
+            ["<b>SCMA event changes</b>\n\
+                • New: <a href=\"https://example.com/1\">Rock &amp; Roll</a> (2024-03-09/2024-03-09)"]
+        );
+
+        let changelog = Changelog(
+            (0..100)
+                .map(|id| entry(&id.to_string(), &"Hidden Valley ".repeat(5), Change::New))
+                .collect(),
+        );
+        let messages = super::telegram_messages(&changelog);
+        assert_eq!(messages.len(), 4);
+        assert!(messages
+            .iter()
+            .all(|message| message.chars().count() <= TELEGRAM_MAX_MESSAGE_LEN));
+    }
+
+    #[test]
+    fn discord_messages() {
+        let changelog = Changelog(
+            (0..11)
+                .map(|id| entry(&id.to_string(), "Hidden Valley", Change::New))
+                .collect(),
+        );
+
+        let messages = super::discord_messages(&changelog);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["embeds"].as_array().unwrap().len(), 10);
+        assert_eq!(
+            messages[1]["embeds"][0],
+            json!({
+                "title": "Hidden Valley",
+                "url": "https://example.com/10",
+                "description": "New (2024-03-09/2024-03-09)",
+                "fields": [
+                    {"name": "Dates", "value": "2024-03-09 to 2024-03-09", "inline": true},
+                    {"name": "Location", "value": "Joshua Tree", "inline": true},
+                ],
+            })
+        );
+
+        let changelog = Changelog(vec![ChangelogEntry {
+            location: "".into(),
+            ..entry("527", "Hidden Valley", Change::New)
+        }]);
+        let messages = super::discord_messages(&changelog);
+        assert_eq!(
+            messages[0]["embeds"][0]["fields"],
+            json!([{"name": "Dates", "value": "2024-03-09 to 2024-03-09", "inline": true}])
+        );
+    }
+}
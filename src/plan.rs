@@ -0,0 +1,241 @@
+use crate::audit::AuditLog;
+use crate::Error;
+
+use serde::Serialize;
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+/// A write to a Google API.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+pub enum PlannedWrite {
+    EventInsert {
+        event_id: String,
+        summary: String,
+    },
+    EventPatch {
+        event_id: String,
+        summary: String,
+    },
+    EventCancel {
+        event_id: String,
+        summary: String,
+    },
+    EventDelete {
+        event_id: String,
+        summary: String,
+    },
+    CalendarRemindersPatch {
+        calendar_id: String,
+        reminders: String,
+    },
+    AclInsert {
+        email: String,
+        role: String,
+    },
+    AclDelete {
+        email: String,
+    },
+    AclGroupInsert {
+        group: String,
+        role: String,
+    },
+    AclGroupDelete {
+        group: String,
+    },
+    AclDomainInsert {
+        domain: String,
+        role: String,
+    },
+    AclDomainDelete {
+        domain: String,
+    },
+    AclPublicInsert {
+        role: String,
+    },
+    AclPublicDelete,
+    ContactCreate {
+        name: String,
+        email: String,
+    },
+    ContactUpdate {
+        name: String,
+        email: String,
+    },
+    ContactDelete {
+        name: String,
+        email: String,
+    },
+    ContactMove {
+        name: String,
+        email: String,
+        group: String,
+    },
+    ContactPhotoUpdate {
+        name: String,
+        email: String,
+    },
+    ContactGroupAdd {
+        name: String,
+        email: String,
+        group: String,
+    },
+    ContactGroupRemove {
+        name: String,
+        email: String,
+        group: String,
+    },
+    SheetWrite {
+        spreadsheet_id: String,
+        sheet: String,
+        rows: usize,
+    },
+    MailingListUpsert {
+        name: String,
+        email: String,
+    },
+}
+
+impl PlannedWrite {
+    pub fn op(&self) -> &'static str {
+        match self {
+            Self::EventInsert { .. } => "event-insert",
+            Self::EventPatch { .. } => "event-patch",
+            Self::EventCancel { .. } => "event-cancel",
+            Self::EventDelete { .. } => "event-delete",
+            Self::CalendarRemindersPatch { .. } => "calendar-reminders-patch",
+            Self::AclInsert { .. } => "acl-insert",
+            Self::AclDelete { .. } => "acl-delete",
+            Self::AclGroupInsert { .. } => "acl-group-insert",
+            Self::AclGroupDelete { .. } => "acl-group-delete",
+            Self::AclDomainInsert { .. } => "acl-domain-insert",
+            Self::AclDomainDelete { .. } => "acl-domain-delete",
+            Self::AclPublicInsert { .. } => "acl-public-insert",
+            Self::AclPublicDelete => "acl-public-delete",
+            Self::ContactCreate { .. } => "contact-create",
+            Self::ContactUpdate { .. } => "contact-update",
+            Self::ContactDelete { .. } => "contact-delete",
+            Self::ContactMove { .. } => "contact-move",
+            Self::ContactPhotoUpdate { .. } => "contact-photo-update",
+            Self::ContactGroupAdd { .. } => "contact-group-add",
+            Self::ContactGroupRemove { .. } => "contact-group-remove",
+            Self::SheetWrite { .. } => "sheet-write",
+            Self::MailingListUpsert { .. } => "mailing-list-upsert",
+        }
+    }
+}
+
+/// Collects the writes of a run.
+///
+/// Writes are recorded whether or not --dry-run is given so that a dry run reports exactly what a
+/// real run would do.  Can be shared between concurrent operations.
+#[derive(Debug, Default)]
+pub struct Plan {
+    writes: Mutex<Vec<PlannedWrite>>,
+    /// The errors of the writes that failed (see GCal::set_continue_on_error)
+    failures: Mutex<Vec<String>>,
+    audit_log: OnceLock<AuditLog>,
+}
+
+#[derive(Serialize)]
+struct PlanReport {
+    counts: BTreeMap<&'static str, usize>,
+    writes: Vec<PlannedWrite>,
+}
+
+impl Plan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, write: PlannedWrite) {
+        self.writes.lock().unwrap().push(write);
+    }
+
+    /// Returns the recorded writes.
+    ///
+    /// The writes are sorted because concurrent operations record them in a nondeterministic
+    /// order.
+    pub fn writes(&self) -> Vec<PlannedWrite> {
+        let mut writes = self.writes.lock().unwrap().clone();
+        writes.sort();
+        writes
+    }
+
+    /// Logs the writes that are made to the audit log (see Plan::audit).
+    pub fn set_audit_log(&self, audit_log: AuditLog) {
+        let _ = self.audit_log.set(audit_log);
+    }
+
+    /// Appends a write that was made to the audit log, if any.
+    ///
+    /// The resource is the calendar ID or contact group name written to.  The before and after
+    /// are summaries of the written entity.
+    pub fn audit(
+        &self,
+        resource: &str,
+        write: &PlannedWrite,
+        before: Option<&str>,
+        after: Option<&str>,
+    ) -> Result<(), Error> {
+        match self.audit_log.get() {
+            Some(audit_log) => audit_log.append(resource, write, before, after),
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_failure(&self, error: impl ToString) {
+        self.failures.lock().unwrap().push(error.to_string());
+    }
+
+    /// Returns the errors of the writes that failed but did not stop the run.
+    pub fn failures(&self) -> Vec<String> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    fn report(&self) -> PlanReport {
+        let writes = self.writes();
+        let mut counts = BTreeMap::new();
+        for write in &writes {
+            *counts.entry(write.op()).or_default() += 1;
+        }
+
+        PlanReport { counts, writes }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.report())
+    }
+
+    pub fn to_yaml(&self) -> serde_yaml::Result<String> {
+        serde_yaml::to_string(&self.report())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let plan = Plan::new();
+        plan.record(PlannedWrite::AclDelete {
+            email: "jane@example.com".into(),
+        });
+        plan.record(PlannedWrite::EventPatch {
+            event_id: "00531".into(),
+            summary: "SCMA: Red Rocks".into(),
+        });
+        plan.record(PlannedWrite::EventInsert {
+            event_id: "00527".into(),
+            summary: "SCMA: Joshua Tree".into(),
+        });
+        plan.record(PlannedWrite::AclInsert {
+            email: "john@example.com".into(),
+            role: "reader".into(),
+        });
+
+        insta::assert_snapshot!(plan.to_yaml().unwrap());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::model::User;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// The personal information omitted from exported users
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Redact {
+    /// Nothing is omitted
+    #[default]
+    None,
+    /// The contact details (phone, address, date of birth, and sex) are omitted
+    Contact,
+    /// Everything but the name, member status, trip leader status, and position is omitted
+    Full,
+}
+
+/// The User fields omitted by Redact::Contact
+const CONTACT_FIELDS: &[&str] = &["address", "city", "state", "zipcode", "phone", "dob", "sex"];
+/// The User fields kept by Redact::Full
+const FULL_KEPT_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "member_status",
+    "trip_leader_status",
+    "position",
+    "timestamp",
+];
+
+impl Redact {
+    /// Returns true if the User field is kept.
+    fn keeps(self, field: &str) -> bool {
+        match self {
+            Self::None => true,
+            Self::Contact => !CONTACT_FIELDS.contains(&field),
+            Self::Full => FULL_KEPT_FIELDS.contains(&field),
+        }
+    }
+}
+
+/// Serializes a user with the fields of the redaction level omitted.
+///
+/// The kept fields are serialized as by User.
+pub struct RedactedUser<'a> {
+    user: &'a User,
+    redact: Redact,
+}
+
+impl<'a> RedactedUser<'a> {
+    pub fn new(user: &'a User, redact: Redact) -> Self {
+        Self { user, redact }
+    }
+}
+
+impl Serialize for RedactedUser<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let user = self.user;
+        let mut state = serializer.serialize_struct("User", 27)?;
+
+        macro_rules! field {
+            ($($name:ident),*) => {
+                $(
+                    if self.redact.keeps(stringify!($name)) {
+                        state.serialize_field(stringify!($name), &user.$name)?;
+                    } else {
+                        state.skip_field(stringify!($name))?;
+                    }
+                )*
+            };
+        }
+
+        // In the order of the User fields
+        field!(
+            id,
+            name,
+            member_status,
+            trip_leader_status,
+            position,
+            address,
+            city,
+            state,
+            zipcode,
+            phone,
+            email,
+            climbingtypes,
+            lead,
+            follow,
+            favoriteclimbs,
+            referredby,
+            dob,
+            applicantdate,
+            membersince,
+            resignedmembership,
+            sex,
+            caneval,
+            optedout,
+            block,
+            register_date,
+            lastvisit_date,
+            timestamp
+        );
+
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::MemberStatus;
+
+    fn user() -> User {
+        User {
+            id: "42".into(),
+            name: "Jane Doe".into(),
+            member_status: MemberStatus::RM,
+            address: "123 Main St".into(),
+            phone: Some("555-1234".into()),
+            email: "jane@example.com".into(),
+            dob: Some("1990-01-01".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn none() {
+        let user = user();
+        assert_eq!(
+            serde_yaml::to_string(&RedactedUser::new(&user, Redact::None)).unwrap(),
+            serde_yaml::to_string(&user).unwrap()
+        );
+    }
+
+    #[test]
+    fn contact() {
+        insta::assert_yaml_snapshot!(RedactedUser::new(&user(), Redact::Contact));
+    }
+
+    #[test]
+    fn full() {
+        insta::assert_yaml_snapshot!(RedactedUser::new(&user(), Redact::Full));
+    }
+}
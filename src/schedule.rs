@@ -0,0 +1,259 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The number of days to search for the next scheduled time before giving up (e.g. for
+/// "0 0 30 2 *")
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// A cron-like schedule (minute hour day-of-month month day-of-week).
+///
+/// Each field is `*`, a number, a range (`a-b`), a step (`*/n` or `a-b/n`), or a comma separated
+/// list of these.  Day of week 0 and 7 are Sunday.  As with cron, if both day of month and day of
+/// week are restricted (i.e. neither is `*` or `*/n`), a day matching either runs.  Otherwise, a
+/// day must match both.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: Field,
+    hours: Field,
+    days_of_month: Field,
+    months: Field,
+    days_of_week: Field,
+}
+
+/// The allowed values of a schedule field as a bit set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Field {
+    values: u64,
+    /// False if `*` or `*/n`
+    restricted: bool,
+}
+
+impl Field {
+    fn parse(field: &str, min: u32, max: u32) -> Result<Self, String> {
+        let mut values = 0;
+        let mut restricted = false;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    let step: u32 = step
+                        .parse()
+                        .ok()
+                        .filter(|step| *step > 0)
+                        .ok_or_else(|| format!("invalid step `{step}`"))?;
+                    (range, step)
+                }
+                None => (part, 1),
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                range => {
+                    restricted = true;
+                    let (start, end) = range.split_once('-').unwrap_or((range, range));
+                    let parse = |value: &str| -> Result<u32, String> {
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|value| (min..=max).contains(value))
+                            .ok_or_else(|| format!("invalid value `{value}`, expected {min}-{max}"))
+                    };
+                    (parse(start)?, parse(end)?)
+                }
+            };
+            if start > end {
+                return Err(format!("invalid range `{range}`"));
+            }
+            for value in (start..=end).step_by(step as usize) {
+                values |= 1 << value;
+            }
+        }
+
+        Ok(Self { values, restricted })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.values & (1 << value) != 0
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(format!(
+                "invalid schedule `{s}`, expected 5 fields (minute hour day-of-month month day-of-week)"
+            ));
+        };
+        let field = |name, field, min, max| {
+            Field::parse(field, min, max).map_err(|err| format!("invalid {name} in `{s}`: {err}"))
+        };
+
+        let mut days_of_week = field("day of week", days_of_week, 0, 7)?;
+        // Sunday is both 0 and 7
+        if days_of_week.contains(7) {
+            days_of_week.values |= 1;
+        }
+
+        Ok(Self {
+            expression: s.trim().to_string(),
+            minutes: field("minute", minutes, 0, 59)?,
+            hours: field("hour", hours, 0, 23)?,
+            days_of_month: field("day of month", days_of_month, 1, 31)?,
+            months: field("month", months, 1, 12)?,
+            days_of_week,
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+impl Schedule {
+    /// Returns the next scheduled time after the given time.
+    ///
+    /// Returns None if the schedule never runs (e.g. February 30).
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let after_local = after.naive_local();
+        let timezone = after.timezone();
+
+        for day in 0..MAX_SEARCH_DAYS {
+            let date = after_local.date() + Duration::days(day);
+            if !self.is_scheduled_date(date) {
+                continue;
+            }
+
+            for hour in (0..24).filter(|hour| self.hours.contains(*hour)) {
+                for minute in (0..60).filter(|minute| self.minutes.contains(*minute)) {
+                    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+                    let datetime = date.and_time(time);
+                    if datetime <= after_local {
+                        continue;
+                    }
+                    // Skips times that do not exist (e.g. during a daylight saving time gap)
+                    if let Some(datetime) = timezone.from_local_datetime(&datetime).earliest() {
+                        return Some(datetime);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the duration from now until the next scheduled time in the timezone.
+    pub fn until_next<Tz: TimeZone>(&self, timezone: &Tz) -> Option<std::time::Duration> {
+        let now = Utc::now().with_timezone(timezone);
+        let next = self.next_after(&now)?;
+        (next - now).to_std().ok()
+    }
+
+    fn is_scheduled_date(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(date.month()) {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month.contains(date.day());
+        let day_of_week = self
+            .days_of_week
+            .contains(date.weekday().num_days_from_sunday());
+        if self.days_of_month.restricted && self.days_of_week.restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn next(schedule: &str, after: &str) -> String {
+        let schedule: Schedule = schedule.parse().unwrap();
+        let after: DateTime<Utc> = after.parse().unwrap();
+        schedule.next_after(&after).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn next_after() {
+        assert_eq!(
+            next("0 3 * * *", "2022-01-14T02:00:00Z"),
+            "2022-01-14T03:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 3 * * *", "2022-01-14T03:00:00Z"),
+            "2022-01-15T03:00:00+00:00"
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2022-01-14T03:07:00Z"),
+            "2022-01-14T03:15:00+00:00"
+        );
+        // Saturday
+        assert_eq!(
+            next("30 6 * * 6", "2022-01-14T00:00:00Z"),
+            "2022-01-15T06:30:00+00:00"
+        );
+        // Sunday as 7
+        assert_eq!(
+            next("0 0 * * 7", "2022-01-14T00:00:00Z"),
+            "2022-01-16T00:00:00+00:00"
+        );
+        // Day of month or day of week
+        assert_eq!(
+            next("0 0 20 * 0", "2022-01-14T00:00:00Z"),
+            "2022-01-16T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 1 3 *", "2022-01-14T00:00:00Z"),
+            "2022-03-01T00:00:00+00:00"
+        );
+        // A stepped `*` is not restricted so an odd day of month and a Monday
+        assert_eq!(
+            next("0 0 */2 * 1", "2022-01-18T00:00:00Z"),
+            "2022-01-31T00:00:00+00:00"
+        );
+        // In the timezone of the given time
+        let after = "2022-01-14T12:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&chrono_tz::America::Los_Angeles);
+        let schedule: Schedule = "0 3 * * *".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(&after).unwrap().to_rfc3339(),
+            "2022-01-15T03:00:00-08:00"
+        );
+
+        let schedule: Schedule = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(schedule.next_after(&Utc::now()), None);
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            next("0-10/5,30 3 * * 1-5", "2022-01-14T03:01:00Z"),
+            "2022-01-14T03:05:00+00:00"
+        );
+        assert_eq!(
+            next("0-10/5,30 3 * * 1-5", "2022-01-14T03:10:00Z"),
+            "2022-01-14T03:30:00+00:00"
+        );
+        // Friday to Monday
+        assert_eq!(
+            next("0-10/5,30 3 * * 1-5", "2022-01-14T03:30:00Z"),
+            "2022-01-17T03:00:00+00:00"
+        );
+
+        assert!("0 3 * *".parse::<Schedule>().is_err());
+        assert!("60 3 * * *".parse::<Schedule>().is_err());
+        assert!("0 3 0 * *".parse::<Schedule>().is_err());
+        assert!("*/0 3 * * *".parse::<Schedule>().is_err());
+        assert!("5-1 3 * * *".parse::<Schedule>().is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::Error;
+
+use anyhow::{anyhow, Context};
+use tokio::process::Command;
+use tracing::info;
+
+/// A secret stored in a cloud secret manager.
+///
+/// Referenced by URI-style paths:
+///
+/// * `gcp-sm://<project>/<secret>[/<version>]` for GCP Secret Manager (latest version by default)
+/// * `aws-ssm://<parameter-name>` for AWS Systems Manager Parameter Store
+/// * `aws-sm://<secret-id>` for AWS Secrets Manager
+///
+/// Secrets are fetched using the `gcloud` and `aws` CLIs so that the ambient credentials of the
+/// job (e.g. the service account of the instance or the IAM role of the task) are used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecretRef {
+    GcpSecretManager {
+        project: String,
+        secret: String,
+        version: String,
+    },
+    AwsSsm {
+        name: String,
+    },
+    AwsSecretsManager {
+        id: String,
+    },
+}
+
+impl SecretRef {
+    /// Parses a secret manager URI.  Returns None if the path is not a secret manager URI (e.g. a
+    /// file path).
+    pub fn parse(path: &str) -> Result<Option<Self>, Error> {
+        let Some((scheme, rest)) = path.split_once("://") else {
+            return Ok(None);
+        };
+
+        let secret = match scheme {
+            "gcp-sm" => match rest.split('/').collect::<Vec<_>>()[..] {
+                [project, secret] | [project, secret, ""] => Self::GcpSecretManager {
+                    project: project.to_string(),
+                    secret: secret.to_string(),
+                    version: "latest".to_string(),
+                },
+                [project, secret, version] => Self::GcpSecretManager {
+                    project: project.to_string(),
+                    secret: secret.to_string(),
+                    version: version.to_string(),
+                },
+                _ => {
+                    return Err(Error::auth(anyhow!(
+                        "invalid GCP Secret Manager path `{path}`, expected `gcp-sm://<project>/<secret>[/<version>]`"
+                    )))
+                }
+            },
+            "aws-ssm" => Self::AwsSsm {
+                name: rest.to_string(),
+            },
+            "aws-sm" => Self::AwsSecretsManager {
+                id: rest.to_string(),
+            },
+            _ => {
+                return Err(Error::auth(anyhow!(
+                    "unknown secret manager `{scheme}` in `{path}`, expected `gcp-sm`, `aws-ssm`, or `aws-sm`"
+                )))
+            }
+        };
+
+        Ok(Some(secret))
+    }
+
+    /// Fetches the value of the secret.
+    pub async fn fetch(&self) -> Result<String, Error> {
+        let (program, args) = self.command();
+        info!(secret=?self, "Fetching secret");
+
+        let output = Command::new(program)
+            .args(&args)
+            .output()
+            .await
+            .with_context(|| format!("unable to run `{program}` to fetch secret"))
+            .map_err(Error::auth)?;
+        if !output.status.success() {
+            return Err(Error::auth(anyhow!(
+                "unable to fetch secret {self:?}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let value = String::from_utf8(output.stdout)
+            .context("secret is not UTF-8")
+            .map_err(Error::auth)?;
+
+        Ok(value.strip_suffix('\n').unwrap_or(&value).to_string())
+    }
+
+    /// Returns the CLI command that prints the value of the secret.
+    fn command(&self) -> (&'static str, Vec<String>) {
+        match self {
+            Self::GcpSecretManager {
+                project,
+                secret,
+                version,
+            } => (
+                "gcloud",
+                vec![
+                    "secrets".into(),
+                    "versions".into(),
+                    "access".into(),
+                    version.clone(),
+                    format!("--secret={secret}"),
+                    format!("--project={project}"),
+                ],
+            ),
+            Self::AwsSsm { name } => (
+                "aws",
+                vec![
+                    "ssm".into(),
+                    "get-parameter".into(),
+                    "--with-decryption".into(),
+                    "--name".into(),
+                    name.clone(),
+                    "--query".into(),
+                    "Parameter.Value".into(),
+                    "--output".into(),
+                    "text".into(),
+                ],
+            ),
+            Self::AwsSecretsManager { id } => (
+                "aws",
+                vec![
+                    "secretsmanager".into(),
+                    "get-secret-value".into(),
+                    "--secret-id".into(),
+                    id.clone(),
+                    "--query".into(),
+                    "SecretString".into(),
+                    "--output".into(),
+                    "text".into(),
+                ],
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(SecretRef::parse("secret.json").unwrap(), None);
+        assert_eq!(
+            SecretRef::parse("gcp-sm://scma/gsync-key").unwrap(),
+            Some(SecretRef::GcpSecretManager {
+                project: "scma".into(),
+                secret: "gsync-key".into(),
+                version: "latest".into(),
+            })
+        );
+        assert_eq!(
+            SecretRef::parse("gcp-sm://scma/gsync-key/3").unwrap(),
+            Some(SecretRef::GcpSecretManager {
+                project: "scma".into(),
+                secret: "gsync-key".into(),
+                version: "3".into(),
+            })
+        );
+        assert_eq!(
+            SecretRef::parse("aws-ssm:///scma/gsync-key").unwrap(),
+            Some(SecretRef::AwsSsm {
+                name: "/scma/gsync-key".into(),
+            })
+        );
+        assert_eq!(
+            SecretRef::parse("aws-sm://scma-gsync-key").unwrap(),
+            Some(SecretRef::AwsSecretsManager {
+                id: "scma-gsync-key".into(),
+            })
+        );
+        assert!(SecretRef::parse("gcp-sm://scma").is_err());
+        assert!(SecretRef::parse("vault://scma/gsync-key").is_err());
+    }
+
+    #[test]
+    fn command() {
+        let secret = SecretRef::parse("gcp-sm://scma/gsync-key")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            secret.command(),
+            (
+                "gcloud",
+                vec![
+                    "secrets".to_string(),
+                    "versions".into(),
+                    "access".into(),
+                    "latest".into(),
+                    "--secret=gsync-key".into(),
+                    "--project=scma".into(),
+                ]
+            )
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::changelog::EventSnapshot;
+use crate::fs::write_atomic;
+use crate::output::{EventCache, PushedState};
+use crate::Error;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// State persisted between runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// The SCMA events as of the previous run
+    #[serde(default)]
+    pub events: Vec<EventSnapshot>,
+    /// The Google Calendar events for incremental sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar: Option<EventCache>,
+    /// What was last pushed to the calendar for diffing against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pushed: Option<PushedState>,
+    /// The email aliases learned from the calendar ACL (see GCal::learned_aliases)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub email_aliases: BTreeMap<String, String>,
+}
+
+impl State {
+    /// Reads the state from a YAML file.  Returns the default state if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let state = std::fs::read_to_string(path)
+            .context(format!("unable to read state file `{}`", path.display()))
+            .map_err(Error::file)?;
+        serde_yaml::from_str(&state)
+            .context(format!("unable to parse state file `{}`", path.display()))
+            .map_err(Error::file)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let state = serde_yaml::to_string(self).map_err(Error::file)?;
+        write_atomic(path, &state)
+            .context(format!("unable to write state file `{}`", path.display()))
+            .map_err(Error::file)
+    }
+}
@@ -0,0 +1,454 @@
+use crate::filter::DuplicateEvent;
+use crate::output::{api_calls, api_retries};
+use crate::plan::Plan;
+
+use serde::{Serialize, Serializer};
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A timed phase of a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Logging in to the SCMA website
+    Login,
+    /// Reading the events from the source (e.g. the SCMA event list and detail pages)
+    EventRead,
+    /// Fetching the SCMA event list
+    EventListFetch,
+    /// Fetching the SCMA event detail pages
+    EventDetailsFetch,
+    /// Fetching the SCMA users
+    UserFetch,
+    /// Authenticating with Google and finding the calendar or contact group
+    GoogleAuth,
+    /// Inserting and patching Google Calendar events
+    EventWrites,
+    /// Syncing the Google Calendar ACL
+    AclSync,
+    /// Syncing Google Contacts
+    ContactSync,
+    /// Emailing event invitations
+    InviteSend,
+    /// Texting event reminders
+    SmsSend,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Login => write!(f, "login"),
+            Self::EventRead => write!(f, "event-read"),
+            Self::EventListFetch => write!(f, "event-list-fetch"),
+            Self::EventDetailsFetch => write!(f, "event-details-fetch"),
+            Self::UserFetch => write!(f, "user-fetch"),
+            Self::GoogleAuth => write!(f, "google-auth"),
+            Self::EventWrites => write!(f, "event-writes"),
+            Self::AclSync => write!(f, "acl-sync"),
+            Self::ContactSync => write!(f, "contact-sync"),
+            Self::InviteSend => write!(f, "invite-send"),
+            Self::SmsSend => write!(f, "sms-send"),
+        }
+    }
+}
+
+/// The accumulated timing of a phase.
+///
+/// Phases that run concurrently (e.g. event detail fetches) accumulate the duration of each
+/// operation.  Because of this, the total of a phase may exceed the wall time of the run.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct PhaseTiming {
+    /// The number of operations
+    pub count: u32,
+    /// The accumulated duration of all operations
+    #[serde(rename = "total_secs", serialize_with = "serialize_secs")]
+    pub total: Duration,
+    /// The duration of the slowest operation
+    #[serde(rename = "max_secs", serialize_with = "serialize_secs")]
+    pub max: Duration,
+}
+
+fn serialize_secs<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Collects information about a run for the end-of-run summary.
+///
+/// Can be shared between concurrent operations.
+pub struct Summary {
+    start: Instant,
+    timings: Mutex<BTreeMap<Phase, PhaseTiming>>,
+    plan: Arc<Plan>,
+    /// The number of events written to the calendar
+    events_synced: AtomicUsize,
+    /// The number of Google API retries at the start of the run
+    api_retries_start: u64,
+    /// The number of Google API calls at the start of the run
+    api_calls_start: u64,
+    /// The duplicate SCMA events removed by the run
+    duplicates: Mutex<Vec<DuplicateEvent>>,
+    /// The errors of the run
+    errors: Mutex<Vec<String>>,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize)]
+struct SummaryReport {
+    #[serde(serialize_with = "serialize_secs")]
+    wall_time_secs: Duration,
+    phases: BTreeMap<Phase, PhaseTiming>,
+    /// The number of writes by operation (e.g. event-insert or acl-delete)
+    writes: BTreeMap<&'static str, usize>,
+    events_synced: usize,
+    /// The synced events that were skipped because they were unchanged
+    events_unchanged: usize,
+    api_calls: u64,
+    api_retries: u64,
+    /// The duplicate SCMA events that were not synced
+    duplicates: Vec<DuplicateEvent>,
+    errors: Vec<String>,
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            timings: Mutex::new(BTreeMap::new()),
+            plan: Arc::new(Plan::new()),
+            events_synced: AtomicUsize::new(0),
+            api_retries_start: api_retries(),
+            api_calls_start: api_calls(),
+            duplicates: Mutex::new(Vec::new()),
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records an error of the run.
+    pub fn record_error(&self, error: impl ToString) {
+        self.errors.lock().unwrap().push(error.to_string());
+    }
+
+    /// Records the duplicate SCMA events removed by the event filter (see EventFilter::dedup).
+    pub fn record_duplicates(&self, duplicates: Vec<DuplicateEvent>) {
+        self.duplicates.lock().unwrap().extend(duplicates);
+    }
+
+    /// Records the number of events written to the calendar.
+    pub fn record_events_synced(&self, count: usize) {
+        self.events_synced.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Records a single operation of a phase.
+    pub fn record(&self, phase: Phase, duration: Duration) {
+        let mut timings = self.timings.lock().unwrap();
+        let timing = timings.entry(phase).or_default();
+        timing.count += 1;
+        timing.total += duration;
+        timing.max = timing.max.max(duration);
+    }
+
+    /// Awaits the future and records its duration as a single operation of the phase.
+    pub async fn time<F, T>(&self, phase: Phase, future: F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        let start = Instant::now();
+        let output = future.await;
+        self.record(phase, start.elapsed());
+        output
+    }
+
+    pub fn timings(&self) -> BTreeMap<Phase, PhaseTiming> {
+        self.timings.lock().unwrap().clone()
+    }
+
+    /// Returns the writes of the run.
+    ///
+    /// Share with GCal and GPpl (see set_plan()) to record their writes.
+    pub fn plan(&self) -> Arc<Plan> {
+        self.plan.clone()
+    }
+
+    fn report(&self) -> SummaryReport {
+        let mut writes = BTreeMap::new();
+        for write in self.plan.writes() {
+            *writes.entry(write.op()).or_insert(0) += 1;
+        }
+        let events_synced = self.events_synced.load(Ordering::Relaxed);
+        let events_written =
+            writes.get("event-insert").unwrap_or(&0) + writes.get("event-patch").unwrap_or(&0);
+
+        SummaryReport {
+            wall_time_secs: self.start.elapsed(),
+            phases: self.timings(),
+            writes,
+            events_synced,
+            events_unchanged: events_synced.saturating_sub(events_written),
+            api_calls: api_calls() - self.api_calls_start,
+            api_retries: api_retries() - self.api_retries_start,
+            duplicates: self.duplicates.lock().unwrap().clone(),
+            errors: self.errors.lock().unwrap().clone(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.report())
+    }
+
+    /// Returns the metrics of the run in the Prometheus text exposition format.
+    ///
+    /// Intended for the node_exporter textfile collector or a Pushgateway.
+    pub fn to_prometheus(&self, success: bool) -> String {
+        let report = self.report();
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut buffer = String::new();
+
+        let mut metric = |name: &str, type_: &str, help: &str, samples: &[(String, String)]| {
+            writeln!(buffer, "# HELP scma_gsync_{name} {help}").unwrap();
+            writeln!(buffer, "# TYPE scma_gsync_{name} {type_}").unwrap();
+            for (suffix, value) in samples {
+                writeln!(buffer, "scma_gsync_{name}{suffix} {value}").unwrap();
+            }
+        };
+
+        metric(
+            "last_run_timestamp_seconds",
+            "gauge",
+            "The time the run finished.",
+            &[(String::new(), timestamp.as_secs().to_string())],
+        );
+        metric(
+            "last_run_success",
+            "gauge",
+            "Whether the run succeeded (1) or failed (0).",
+            &[(String::new(), u8::from(success).to_string())],
+        );
+        metric(
+            "run_duration_seconds",
+            "gauge",
+            "The wall time of the run.",
+            &[(
+                String::new(),
+                report.wall_time_secs.as_secs_f64().to_string(),
+            )],
+        );
+        metric(
+            "phase_duration_seconds",
+            "summary",
+            "The accumulated duration of the operations of each phase of the run.",
+            &report
+                .phases
+                .iter()
+                .flat_map(|(phase, timing)| {
+                    [
+                        (
+                            format!("_sum{{phase=\"{phase}\"}}"),
+                            timing.total.as_secs_f64().to_string(),
+                        ),
+                        (
+                            format!("_count{{phase=\"{phase}\"}}"),
+                            timing.count.to_string(),
+                        ),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        );
+        metric(
+            "phase_max_duration_seconds",
+            "gauge",
+            "The duration of the slowest operation of each phase of the run.",
+            &report
+                .phases
+                .iter()
+                .map(|(phase, timing)| {
+                    (
+                        format!("{{phase=\"{phase}\"}}"),
+                        timing.max.as_secs_f64().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+        metric(
+            "events_synced",
+            "gauge",
+            "The number of events written to the calendar by the run.",
+            &[(String::new(), report.events_synced.to_string())],
+        );
+        metric(
+            "writes",
+            "gauge",
+            "The number of Google API writes (e.g. event inserts, ACL inserts and deletes) of the run by operation.",
+            &report
+                .writes
+                .iter()
+                .map(|(op, count)| (format!("{{op=\"{op}\"}}"), count.to_string()))
+                .collect::<Vec<_>>(),
+        );
+        metric(
+            "api_retries",
+            "gauge",
+            "The number of rate limited Google API requests retried by the run.",
+            &[(String::new(), report.api_retries.to_string())],
+        );
+        metric(
+            "api_calls",
+            "gauge",
+            "The number of Google API calls made by the run.",
+            &[(String::new(), report.api_calls.to_string())],
+        );
+
+        buffer
+    }
+
+    pub fn to_human(&self) -> String {
+        let report = self.report();
+        let mut buffer = String::new();
+
+        writeln!(buffer, "Summary").unwrap();
+        writeln!(
+            buffer,
+            "  {:<22} {:>6} {:>10} {:>10}",
+            "phase", "count", "total", "max"
+        )
+        .unwrap();
+        for (phase, timing) in &report.phases {
+            writeln!(
+                buffer,
+                "  {:<22} {:>6} {:>9.2}s {:>9.2}s",
+                phase.to_string(),
+                timing.count,
+                timing.total.as_secs_f64(),
+                timing.max.as_secs_f64()
+            )
+            .unwrap();
+        }
+        writeln!(
+            buffer,
+            "  {:<22} {:>6} {:>9.2}s",
+            "wall-time",
+            "",
+            report.wall_time_secs.as_secs_f64()
+        )
+        .unwrap();
+        for (op, count) in &report.writes {
+            writeln!(buffer, "  {op:<22} {count:>6}").unwrap();
+        }
+        for (name, count) in [
+            ("events-synced", report.events_synced as u64),
+            ("events-unchanged", report.events_unchanged as u64),
+            ("api-calls", report.api_calls),
+            ("api-retries", report.api_retries),
+            ("duplicates", report.duplicates.len() as u64),
+            ("errors", report.errors.len() as u64),
+        ] {
+            writeln!(buffer, "  {name:<22} {count:>6}").unwrap();
+        }
+        for duplicate in &report.duplicates {
+            writeln!(
+                buffer,
+                "  duplicate: {} {} (duplicate of {}) {}",
+                duplicate.id, duplicate.title, duplicate.kept_id, duplicate.url
+            )
+            .unwrap();
+        }
+        for error in &report.errors {
+            writeln!(buffer, "  error: {error}").unwrap();
+        }
+        buffer.pop();
+
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::plan::PlannedWrite;
+
+    #[test]
+    fn record() {
+        let summary = Summary::new();
+        summary.record(Phase::EventDetailsFetch, Duration::from_millis(300));
+        summary.record(Phase::EventDetailsFetch, Duration::from_millis(500));
+        summary.record(Phase::Login, Duration::from_millis(100));
+
+        let timings = summary.timings();
+        let details = timings[&Phase::EventDetailsFetch];
+        assert_eq!(details.count, 2);
+        assert_eq!(details.total, Duration::from_millis(800));
+        assert_eq!(details.max, Duration::from_millis(500));
+        assert_eq!(timings[&Phase::Login].count, 1);
+        assert!(!timings.contains_key(&Phase::AclSync));
+    }
+
+    #[test]
+    fn report() {
+        let summary = Summary::new();
+        summary.record_events_synced(3);
+        summary.plan().record(PlannedWrite::EventInsert {
+            event_id: "00527".into(),
+            summary: "SCMA: Joshua Tree".into(),
+        });
+        summary.record_error("unable to write event");
+        let duplicate = DuplicateEvent {
+            id: "528".into(),
+            kept_id: "527".into(),
+            title: "Joshua Tree".into(),
+            url: "a url".into(),
+        };
+        summary.record_duplicates(vec![duplicate.clone()]);
+
+        let report = summary.report();
+        assert_eq!(report.writes, BTreeMap::from([("event-insert", 1)]));
+        assert_eq!(report.events_synced, 3);
+        assert_eq!(report.events_unchanged, 2);
+        assert_eq!(report.duplicates, vec![duplicate]);
+        assert_eq!(report.errors, vec!["unable to write event".to_string()]);
+        assert!(summary
+            .to_human()
+            .contains("duplicate: 528 Joshua Tree (duplicate of 527) a url"));
+    }
+
+    #[test]
+    fn to_prometheus() {
+        let summary = Summary::new();
+        summary.record(Phase::AclSync, Duration::from_millis(1500));
+        summary.record_events_synced(12);
+        summary.plan().record(PlannedWrite::AclInsert {
+            email: "john@example.com".into(),
+            role: "reader".into(),
+        });
+
+        let metrics = summary.to_prometheus(true);
+        let lines: Vec<&str> = metrics.lines().collect();
+        for expected in [
+            "# TYPE scma_gsync_last_run_success gauge",
+            "scma_gsync_last_run_success 1",
+            "scma_gsync_phase_duration_seconds_sum{phase=\"acl-sync\"} 1.5",
+            "scma_gsync_phase_duration_seconds_count{phase=\"acl-sync\"} 1",
+            "scma_gsync_phase_max_duration_seconds{phase=\"acl-sync\"} 1.5",
+            "scma_gsync_events_synced 12",
+            "scma_gsync_writes{op=\"acl-insert\"} 1",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "missing `{expected}` in:\n{metrics}"
+            );
+        }
+    }
+}